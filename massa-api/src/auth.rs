@@ -0,0 +1,102 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Bearer token authentication, used to protect the private API
+//! when its port can be reached by other processes than the node owner's.
+
+use hyper::{
+    header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+    Body, Request, Response, StatusCode,
+};
+use tower_http::auth::AuthorizeRequest;
+
+/// Rejects the requests that do not carry an `Authorization: Bearer <token>` header
+#[derive(Clone)]
+pub(crate) struct BearerTokenAuth {
+    /// expected value of the `Authorization` header
+    expected: Vec<u8>,
+}
+
+impl BearerTokenAuth {
+    /// Accept the requests carrying `token`
+    pub(crate) fn new(token: &str) -> Self {
+        BearerTokenAuth {
+            expected: format!("Bearer {}", token).into_bytes(),
+        }
+    }
+
+    /// Compare the header value in a time that does not depend on the position of the first difference
+    fn is_expected(&self, value: &[u8]) -> bool {
+        value.len() == self.expected.len()
+            && value
+                .iter()
+                .zip(self.expected.iter())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+impl<B> AuthorizeRequest<B> for BearerTokenAuth {
+    type ResponseBody = Body;
+
+    fn authorize(&mut self, request: &mut Request<B>) -> Result<(), Response<Self::ResponseBody>> {
+        match request.headers().get(AUTHORIZATION) {
+            Some(value) if self.is_expected(value.as_bytes()) => Ok(()),
+            _ => {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::UNAUTHORIZED;
+                response
+                    .headers_mut()
+                    .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+                Err(response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BearerTokenAuth;
+    use hyper::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        Body, Request, StatusCode,
+    };
+    use tower_http::auth::AuthorizeRequest;
+
+    fn request(authorization: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder();
+        if let Some(value) = authorization {
+            builder = builder.header(AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_bearer_token_accepted() {
+        let mut auth = BearerTokenAuth::new("s3cr3t");
+        assert!(auth.authorize(&mut request(Some("Bearer s3cr3t"))).is_ok());
+    }
+
+    #[test]
+    fn test_bearer_token_rejected() {
+        let mut auth = BearerTokenAuth::new("s3cr3t");
+        for value in [
+            "Bearer s3cr3u",
+            "Bearer s3cr3",
+            "Bearer s3cr3tt",
+            "s3cr3t",
+            "Basic s3cr3t",
+        ] {
+            let response = auth.authorize(&mut request(Some(value))).unwrap_err();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", value);
+            assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        }
+    }
+
+    #[test]
+    fn test_bearer_token_missing_header() {
+        let mut auth = BearerTokenAuth::new("s3cr3t");
+        let response = auth.authorize(&mut request(None)).unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+}
@@ -1,6 +1,9 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::rate_limit::RateLimiter;
+use crate::tls::TlsConfig;
 use massa_time::MassaTime;
+use num::rational::Ratio;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
@@ -60,4 +63,15 @@ pub struct APIConfig {
     pub t0: MassaTime,
     /// periods per cycle
     pub periods_per_cycle: u64,
+    /// maximum ratio of missed blocks above which rolls are deactivated
+    pub max_miss_ratio: Ratio<u64>,
+    /// bearer token required by the private API, if authentication is enabled
+    pub private_api_token: Option<String>,
+    /// TLS configuration of the public API, served in plain text if `None`
+    pub public_tls: Option<TlsConfig>,
+    /// TLS configuration of the private API, served in plain text if `None`
+    pub private_tls: Option<TlsConfig>,
+    /// per-IP rate limiter of the public API, not limited if `None`
+    #[serde(skip)]
+    pub public_rate_limiter: Option<RateLimiter>,
 }
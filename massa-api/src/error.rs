@@ -10,11 +10,15 @@ use massa_execution_exports::ExecutionError;
 use massa_hash::MassaHashError;
 use massa_models::error::ModelsError;
 use massa_network_exports::NetworkError;
+use massa_pos_exports::PosError;
 use massa_protocol_exports::ProtocolError;
 use massa_time::TimeError;
 use massa_wallet::WalletError;
 use thiserror::Error;
 
+/// JSON-RPC error code of the calls rejected by the rate limiter
+pub(crate) const RATE_LIMITED_ERROR_CODE: i32 = -32021;
+
 #[non_exhaustive]
 #[derive(Display, Error, Debug)]
 pub enum ApiError {
@@ -38,6 +42,8 @@ pub enum ApiError {
     TimeError(#[from] TimeError),
     /// Wallet error: {0}
     WalletError(#[from] WalletError),
+    /// Proof-of-Stake error: {0}
+    PosError(#[from] PosError),
     /// Not found
     NotFound,
     /// Inconsistency error: {0}
@@ -75,6 +81,7 @@ impl From<ApiError> for JsonRpseeError {
             ApiError::MissingCommandSender(_) => -32017,
             ApiError::MissingConfig(_) => -32018,
             ApiError::WrongAPI => -32019,
+            ApiError::PosError(_) => -32020,
         };
 
         CallError::Custom(ErrorObject::owned(code, err.to_string(), None::<()>)).into()
@@ -0,0 +1,588 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Front of the API servers.
+//!
+//! The JSON-RPC server only listens on plain TCP sockets and does not tell its middleware
+//! which IP a request comes from. When TLS or rate limiting is enabled, the front serves the API
+//! in its place: it accepts the connections on the configured address, terminates TLS, and
+//! dispatches the calls to the RPC methods itself, so that no plain-text listener is left for
+//! local processes to bypass TLS or client certificate verification.
+//! HTTP requests and websocket messages are charged to the IP of the client one by one,
+//! and websocket connections are also charged when they are opened.
+//! The notifications of the subscriptions opened by a websocket connection are forwarded
+//! to it until it is closed.
+
+use crate::auth::BearerTokenAuth;
+use crate::error::RATE_LIMITED_ERROR_CODE;
+use crate::rate_limit::{CallCost, RateLimiter};
+use futures::{
+    io::{BufReader, BufWriter},
+    StreamExt,
+};
+use hyper::{
+    body::{Bytes, HttpBody},
+    header::{HeaderValue, CONTENT_TYPE, HOST},
+    server::conn::Http,
+    service::service_fn,
+    upgrade::Upgraded,
+    Body, Method, Request, Response, StatusCode,
+};
+use jsonrpsee::core::{server::rpc_module::MethodKind, Error as JsonRpseeError};
+use jsonrpsee::types::error::{
+    BATCHES_NOT_SUPPORTED_CODE, INTERNAL_ERROR_CODE, INVALID_REQUEST_CODE, OVERSIZED_RESPONSE_CODE,
+    PARSE_ERROR_CODE, TOO_MANY_SUBSCRIPTIONS_CODE,
+};
+use jsonrpsee::Methods;
+use serde_json::{json, Value};
+use soketto::{data::ByteSlice125, handshake::http::Server as WsServer};
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::{mpsc, OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tower::ServiceBuilder;
+use tower_http::{auth::RequireAuthorizationLayer, cors::CorsLayer};
+use tracing::{debug, warn};
+
+/// Method name under which the requests that cannot be parsed are counted
+const INVALID_REQUEST_METHOD: &str = "invalid_request";
+
+/// Method name under which the calls to methods that do not exist are counted
+const UNKNOWN_METHOD: &str = "unknown_method";
+
+/// Method name under which the websocket connections are counted
+const WEBSOCKET_METHOD: &str = "websocket";
+
+/// What the front does with the connections it accepts
+#[derive(Clone)]
+pub(crate) struct FrontConfig {
+    /// terminate TLS with this acceptor, giving up on handshakes lasting longer than the duration
+    pub tls: Option<(TlsAcceptor, Duration)>,
+    /// charge the calls to this rate limiter
+    pub rate_limiter: Option<RateLimiter>,
+    /// CORS policy of the HTTP requests
+    pub cors: CorsLayer,
+    /// authentication required from the clients
+    pub auth: Option<BearerTokenAuth>,
+    /// accepted values of the `Host` header, any if empty
+    pub allow_hosts: Arc<HashSet<String>>,
+    /// maximum number of connections served at once
+    pub max_connections: u32,
+    /// maximum number of subscriptions opened at once by a websocket connection
+    pub max_subscriptions_per_connection: u32,
+    /// maximum size in bytes of a request
+    pub max_request_body_size: u32,
+    /// maximum size in bytes of a response
+    pub max_response_body_size: u32,
+    /// whether batch requests are served
+    pub batch_requests_supported: bool,
+    /// whether HTTP requests are served
+    pub enable_http: bool,
+    /// whether websocket connections are served
+    pub enable_ws: bool,
+    /// interval between two websocket pings
+    pub ping_interval: Duration,
+}
+
+/// Shared by all the connections of the front
+struct FrontContext {
+    config: FrontConfig,
+    /// methods served by the API
+    methods: Methods,
+    /// names of the methods served by the API: the calls to other methods are counted together
+    method_names: HashSet<&'static str>,
+}
+
+/// Subscriptions opened by a websocket connection
+struct ConnectionSubscriptions {
+    /// one permit per subscription the connection may still open
+    slots: Arc<Semaphore>,
+    /// sends the notifications to the client
+    notification_sender: mpsc::UnboundedSender<String>,
+    /// tasks forwarding the notifications of each subscription
+    forwarders: Vec<JoinHandle<()>>,
+}
+
+impl ConnectionSubscriptions {
+    fn new(max_subscriptions: u32, notification_sender: mpsc::UnboundedSender<String>) -> Self {
+        ConnectionSubscriptions {
+            slots: Arc::new(Semaphore::new(max_subscriptions as usize)),
+            notification_sender,
+            forwarders: Vec::new(),
+        }
+    }
+
+    /// Forward the notifications of a subscription until it ends, releasing `slot` afterwards
+    fn forward(
+        &mut self,
+        mut notifications: futures::channel::mpsc::UnboundedReceiver<String>,
+        slot: OwnedSemaphorePermit,
+    ) {
+        self.forwarders.retain(|forwarder| !forwarder.is_finished());
+        let notification_sender = self.notification_sender.clone();
+        self.forwarders.push(tokio::spawn(async move {
+            while let Some(notification) = notifications.next().await {
+                if notification_sender.send(notification).is_err() {
+                    break;
+                }
+            }
+            drop(slot);
+        }));
+    }
+}
+
+impl Drop for ConnectionSubscriptions {
+    /// Stop forwarding when the connection is closed:
+    /// the subscriptions end as soon as their notifications can no longer be delivered
+    fn drop(&mut self) {
+        for forwarder in &self.forwarders {
+            forwarder.abort();
+        }
+    }
+}
+
+/// Serve `methods` on `bind`
+pub(crate) async fn start_front(
+    bind: &SocketAddr,
+    methods: Methods,
+    config: FrontConfig,
+) -> Result<JoinHandle<()>, String> {
+    let listener = TcpListener::bind(bind)
+        .await
+        .map_err(|err| format!("could not bind {}: {}", bind, err))?;
+    let connection_slots = Arc::new(Semaphore::new(config.max_connections as usize));
+    let context = Arc::new(FrontContext {
+        method_names: methods.method_names().collect(),
+        methods,
+        config,
+    });
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, remote_addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!("API front could not accept a connection: {}", err);
+                    continue;
+                }
+            };
+            let permit = match connection_slots.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    debug!(
+                        "API connection from {} refused: too many connections",
+                        remote_addr
+                    );
+                    continue;
+                }
+            };
+            let context = context.clone();
+            tokio::spawn(async move {
+                let result = match &context.config.tls {
+                    Some((acceptor, handshake_timeout)) => {
+                        match tokio::time::timeout(*handshake_timeout, acceptor.accept(stream))
+                            .await
+                        {
+                            Ok(Ok(tls_stream)) => {
+                                serve_stream(tls_stream, remote_addr.ip(), context.clone(), permit)
+                                    .await
+                            }
+                            Ok(Err(err)) => Err(format!("TLS handshake failed: {}", err)),
+                            Err(_) => Err("TLS handshake timed out".to_string()),
+                        }
+                    }
+                    None => serve_stream(stream, remote_addr.ip(), context.clone(), permit).await,
+                };
+                if let Err(err) = result {
+                    debug!("API connection from {} ended: {}", remote_addr, err);
+                }
+            });
+        }
+    }))
+}
+
+/// Serve the HTTP requests of a connection.
+/// The connection slot is released once the connection, and the websocket it may be upgraded to, are closed.
+async fn serve_stream<S>(
+    stream: S,
+    remote_ip: IpAddr,
+    context: Arc<FrontContext>,
+    permit: OwnedSemaphorePermit,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let permit = Arc::new(permit);
+    let service = ServiceBuilder::new()
+        .layer(context.config.cors.clone())
+        .option_layer(
+            context
+                .config
+                .auth
+                .clone()
+                .map(RequireAuthorizationLayer::custom),
+        )
+        .service(service_fn(move |request| {
+            handle_request(request, remote_ip, context.clone(), permit.clone())
+        }));
+    Http::new()
+        .serve_connection(stream, service)
+        .with_upgrades()
+        .await
+        .map_err(|err| err.to_string())
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn json_response(body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// JSON-RPC error answering the call `id`
+fn error_response(id: &Value, code: i32, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": code,
+            "message": message,
+        },
+        "id": id,
+    })
+    .to_string()
+}
+
+/// Calls of a JSON-RPC request body
+struct ParsedRequest {
+    /// method, length of the first parameter if it is a list, and id of each call
+    calls: Vec<(String, u64, Value)>,
+    /// whether the calls were sent as a batch
+    is_batch: bool,
+}
+
+fn parse_request(body: &[u8], method_names: &HashSet<&'static str>) -> ParsedRequest {
+    let parse_call = |call: &Value| {
+        (
+            match call.get("method").and_then(Value::as_str) {
+                Some(method) if method_names.contains(method) => method.to_string(),
+                Some(_) => UNKNOWN_METHOD.to_string(),
+                None => INVALID_REQUEST_METHOD.to_string(),
+            },
+            call.get("params")
+                .and_then(|params| params.get(0))
+                .and_then(Value::as_array)
+                .map_or(1, |items| items.len() as u64),
+            call.get("id").cloned().unwrap_or(Value::Null),
+        )
+    };
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(batch)) if !batch.is_empty() => ParsedRequest {
+            calls: batch.iter().map(parse_call).collect(),
+            is_batch: true,
+        },
+        Ok(call) => ParsedRequest {
+            calls: vec![parse_call(&call)],
+            is_batch: false,
+        },
+        Err(_) => ParsedRequest {
+            calls: vec![(INVALID_REQUEST_METHOD.to_string(), 1, Value::Null)],
+            is_batch: false,
+        },
+    }
+}
+
+/// JSON-RPC answer to rate limited calls
+fn rate_limited_response(request: &ParsedRequest) -> String {
+    let errors: Vec<String> = request
+        .calls
+        .iter()
+        .map(|(_, _, id)| {
+            error_response(
+                id,
+                RATE_LIMITED_ERROR_CODE,
+                "rate limit exceeded, retry later",
+            )
+        })
+        .collect();
+    if request.is_batch {
+        format!("[{}]", errors.join(","))
+    } else {
+        errors.into_iter().next().unwrap_or_default()
+    }
+}
+
+/// Read a request body, failing if it exceeds `max_size` bytes
+async fn read_body(mut body: Body, max_size: u32) -> Result<Bytes, StatusCode> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if bytes.len() + chunk.len() > max_size as usize {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes.into())
+}
+
+impl FrontContext {
+    /// Whether the `Host` header of `request` is allowed
+    fn is_host_allowed(&self, request: &Request<Body>) -> bool {
+        self.config.allow_hosts.is_empty()
+            || request
+                .headers()
+                .get(HOST)
+                .and_then(|host| host.to_str().ok())
+                .map_or(false, |host| self.config.allow_hosts.contains(host))
+    }
+
+    /// Charge the calls of `body` to `remote_ip`.
+    /// Returns the JSON-RPC answer to send instead of calling the methods if they are rate limited.
+    fn charge(&self, body: &[u8], remote_ip: IpAddr) -> Option<String> {
+        let rate_limiter = self.config.rate_limiter.as_ref()?;
+        let parsed_request = parse_request(body, &self.method_names);
+        let calls: Vec<CallCost> = parsed_request
+            .calls
+            .iter()
+            .map(|(method, items, _)| CallCost {
+                method,
+                items: *items,
+            })
+            .collect();
+        if rate_limiter.try_acquire(remote_ip, &calls) {
+            None
+        } else {
+            Some(rate_limited_response(&parsed_request))
+        }
+    }
+
+    /// Answer a JSON-RPC request body, made of a single call or of a batch.
+    /// The subscriptions it opens are added to `subscriptions`, if any.
+    async fn call(
+        &self,
+        body: &[u8],
+        mut subscriptions: Option<&mut ConnectionSubscriptions>,
+    ) -> String {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(batch)) => {
+                if !self.config.batch_requests_supported {
+                    return error_response(
+                        &Value::Null,
+                        BATCHES_NOT_SUPPORTED_CODE,
+                        "batch requests are not supported",
+                    );
+                }
+                if batch.is_empty() {
+                    return error_response(&Value::Null, INVALID_REQUEST_CODE, "empty batch");
+                }
+                let mut responses = Vec::with_capacity(batch.len());
+                for call in &batch {
+                    responses.push(self.call_method(call, subscriptions.as_deref_mut()).await);
+                }
+                format!("[{}]", responses.join(","))
+            }
+            Ok(call) => self.call_method(&call, subscriptions).await,
+            Err(_) => error_response(&Value::Null, PARSE_ERROR_CODE, "parse error"),
+        }
+    }
+
+    /// Answer a single JSON-RPC call
+    async fn call_method(
+        &self,
+        call: &Value,
+        subscriptions: Option<&mut ConnectionSubscriptions>,
+    ) -> String {
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+
+        // a subscription takes one of the slots of the connection until it ends
+        let is_subscription = call
+            .get("method")
+            .and_then(Value::as_str)
+            .and_then(|method| self.methods.method(method))
+            .map_or(false, |method| {
+                matches!(method.inner(), MethodKind::Subscription(_))
+            });
+        let subscription_slot = match (&subscriptions, is_subscription) {
+            (Some(subscriptions), true) => match subscriptions.slots.clone().try_acquire_owned() {
+                Ok(slot) => Some(slot),
+                Err(_) => {
+                    return error_response(
+                        &id,
+                        TOO_MANY_SUBSCRIPTIONS_CODE,
+                        &format!(
+                            "too many subscriptions on this connection, at most {} are allowed",
+                            self.config.max_subscriptions_per_connection
+                        ),
+                    )
+                }
+            },
+            _ => None,
+        };
+
+        let (response, notifications) = match self.methods.raw_json_request(&call.to_string()).await
+        {
+            Ok(answer) => answer,
+            // the call is valid JSON but not a JSON-RPC request
+            Err(JsonRpseeError::ParseError(err)) => {
+                return error_response(&id, INVALID_REQUEST_CODE, &err.to_string())
+            }
+            Err(err) => return error_response(&id, INTERNAL_ERROR_CODE, &err.to_string()),
+        };
+        if let (Some(subscriptions), Some(slot), true) =
+            (subscriptions, subscription_slot, response.success)
+        {
+            subscriptions.forward(notifications, slot);
+        }
+        if response.result.len() > self.config.max_response_body_size as usize {
+            return error_response(&id, OVERSIZED_RESPONSE_CODE, "response too big");
+        }
+        response.result
+    }
+}
+
+async fn handle_request(
+    request: Request<Body>,
+    remote_ip: IpAddr,
+    context: Arc<FrontContext>,
+    permit: Arc<OwnedSemaphorePermit>,
+) -> Result<Response<Body>, Infallible> {
+    if !context.is_host_allowed(&request) {
+        return Ok(status_response(StatusCode::FORBIDDEN));
+    }
+
+    if soketto::handshake::http::is_upgrade_request(&request) {
+        if !context.config.enable_ws {
+            return Ok(status_response(StatusCode::FORBIDDEN));
+        }
+        if let Some(rate_limiter) = &context.config.rate_limiter {
+            let call = CallCost {
+                method: WEBSOCKET_METHOD,
+                items: 1,
+            };
+            if !rate_limiter.try_acquire(remote_ip, &[call]) {
+                return Ok(status_response(StatusCode::TOO_MANY_REQUESTS));
+            }
+        }
+        return Ok(upgrade_websocket(request, remote_ip, context, permit));
+    }
+
+    if !context.config.enable_http || request.method() != Method::POST {
+        return Ok(status_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    let bytes = match read_body(request.into_body(), context.config.max_request_body_size).await {
+        Ok(bytes) => bytes,
+        Err(status) => return Ok(status_response(status)),
+    };
+    if let Some(response) = context.charge(&bytes, remote_ip) {
+        return Ok(json_response(response));
+    }
+    Ok(json_response(context.call(&bytes, None).await))
+}
+
+/// Accept a websocket upgrade request and serve the connection once it is upgraded
+fn upgrade_websocket(
+    request: Request<Body>,
+    remote_ip: IpAddr,
+    context: Arc<FrontContext>,
+    permit: Arc<OwnedSemaphorePermit>,
+) -> Response<Body> {
+    let mut server = WsServer::new();
+    let response = match server.receive_request(&request) {
+        Ok(response) => response,
+        Err(err) => {
+            debug!("API front refused a websocket upgrade: {}", err);
+            return status_response(StatusCode::BAD_REQUEST);
+        }
+    };
+    tokio::spawn(async move {
+        let result = match hyper::upgrade::on(request).await {
+            Ok(upgraded) => serve_websocket(server, upgraded, remote_ip, context).await,
+            Err(err) => Err(err.to_string()),
+        };
+        if let Err(err) = result {
+            debug!("API websocket connection ended: {}", err);
+        }
+        drop(permit);
+    });
+    response.map(|()| Body::empty())
+}
+
+/// Answer the calls sent over a websocket connection until it is closed
+async fn serve_websocket(
+    server: WsServer,
+    upgraded: Upgraded,
+    remote_ip: IpAddr,
+    context: Arc<FrontContext>,
+) -> Result<(), String> {
+    let stream = BufReader::new(BufWriter::new(upgraded.compat()));
+    let mut builder = server.into_builder(stream);
+    builder.set_max_message_size(context.config.max_request_body_size as usize);
+    let (mut sender, mut receiver) = builder.finish();
+
+    // the answers and notifications are written by a single task, which also pings the client
+    let (message_sender, mut message_receiver) = mpsc::unbounded_channel::<String>();
+    let mut subscriptions = ConnectionSubscriptions::new(
+        context.config.max_subscriptions_per_connection,
+        message_sender.clone(),
+    );
+    let ping_interval = context.config.ping_interval;
+    let writer = tokio::spawn(async move {
+        let mut ping_timer = tokio::time::interval(ping_interval);
+        ping_timer.tick().await;
+        loop {
+            let result = tokio::select! {
+                message = message_receiver.recv() => match message {
+                    Some(message) => sender.send_text(&message).await,
+                    None => break,
+                },
+                _ = ping_timer.tick() => {
+                    let empty: &[u8] = &[];
+                    match ByteSlice125::try_from(empty) {
+                        Ok(data) => sender.send_ping(data).await,
+                        Err(_) => Ok(()),
+                    }
+                }
+            };
+            if result.is_err() || sender.flush().await.is_err() {
+                break;
+            }
+        }
+        let _ = sender.close().await;
+    });
+
+    let mut message = Vec::new();
+    let result = loop {
+        message.clear();
+        match receiver.receive_data(&mut message).await {
+            Ok(_) => {}
+            Err(soketto::connection::Error::Closed) => break Ok(()),
+            Err(err) => break Err(err.to_string()),
+        }
+        let response = match context.charge(&message, remote_ip) {
+            Some(response) => response,
+            None => context.call(&message, Some(&mut subscriptions)).await,
+        };
+        if message_sender.send(response).is_err() {
+            break Ok(());
+        }
+    };
+    // stop forwarding the notifications, and writing to the closed connection
+    drop(subscriptions);
+    writer.abort();
+    result
+}
@@ -3,21 +3,25 @@
 #![feature(async_closure)]
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
+use crate::auth::BearerTokenAuth;
 use crate::error::ApiError::WrongAPI;
 use hyper::Method;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::server::{AllowHosts, ServerBuilder, ServerHandle};
+use massa_bootstrap_exports::{BootstrapListController, BootstrapStatusController};
 use massa_consensus_exports::ConsensusController;
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, AddressSelections, BannedPeer, BlockInfo, BlockSummary, BootstrapSessionInfo,
+    ConnectedPeer, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    FinalStateHash, LedgerProof, LedgerProofInput, NodeStatus, OperationInfo, OperationInput,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionsFilter, StakingReport, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::ip_range::IpRange;
 use massa_models::node::NodeId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
@@ -34,21 +38,30 @@ use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::ProtocolCommandSender;
 use massa_storage::Storage;
+use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use serde_json::Value;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use tower_http::auth::RequireAuthorizationLayer;
 use tower_http::cors::{Any, CorsLayer};
 
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::{info, warn};
 
+mod auth;
 mod config;
 mod error;
+mod front;
 mod private;
 mod public;
+mod rate_limit;
+mod tls;
 pub use config::APIConfig;
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use tls::TlsConfig;
 
 /// Public API component
 pub struct Public {
@@ -76,12 +89,16 @@ pub struct Public {
     pub compensation_millis: i64,
     /// our node id
     pub node_id: NodeId,
+    /// bootstrap server status
+    pub bootstrap_server_status: Box<dyn BootstrapStatusController>,
 }
 
 /// Private API content
 pub struct Private {
     /// link to the network component
     pub network_command_sender: NetworkCommandSender,
+    /// link to the protocol component
+    pub protocol_command_sender: ProtocolCommandSender,
     /// link to the execution component
     pub execution_controller: Box<dyn ExecutionController>,
     /// API settings
@@ -90,6 +107,10 @@ pub struct Private {
     pub stop_node_channel: mpsc::Sender<()>,
     /// User wallet
     pub node_wallet: Arc<RwLock<Wallet>>,
+    /// bootstrap whitelist and blacklist
+    pub bootstrap_white_black_list: Box<dyn BootstrapListController>,
+    /// bootstrap server status
+    pub bootstrap_server_status: Box<dyn BootstrapStatusController>,
 }
 
 /// The API wrapper
@@ -110,6 +131,9 @@ async fn serve(
     api: impl MassaRpcServer,
     url: &SocketAddr,
     api_config: &APIConfig,
+    auth: Option<BearerTokenAuth>,
+    tls_config: Option<&TlsConfig>,
+    rate_limiter: Option<RateLimiter>,
 ) -> Result<StopHandle, JsonRpseeError> {
     let allowed_hosts = if api_config.allow_hosts.is_empty() {
         AllowHosts::Any
@@ -143,9 +167,45 @@ async fn serve(
         .allow_methods([Method::POST, Method::OPTIONS])
         // Allow requests from any origin
         .allow_origin(Any)
-        .allow_headers([hyper::header::CONTENT_TYPE]);
+        .allow_headers([hyper::header::CONTENT_TYPE, hyper::header::AUTHORIZATION]);
+    let rpc_module = api.into_rpc();
+
+    // with TLS or rate limiting, the front serves the API in place of the server
+    if tls_config.is_some() || rate_limiter.is_some() {
+        let tls = match tls_config {
+            Some(tls_config) => Some((
+                tls::build_acceptor(tls_config).map_err(JsonRpseeError::Custom)?,
+                tls_config.handshake_timeout.to_duration(),
+            )),
+            None => None,
+        };
+        let front_config = front::FrontConfig {
+            tls,
+            rate_limiter,
+            cors,
+            auth,
+            allow_hosts: Arc::new(api_config.allow_hosts.iter().cloned().collect()),
+            max_connections: api_config.max_connections,
+            max_subscriptions_per_connection: api_config.max_subscriptions_per_connection,
+            max_request_body_size: api_config.max_request_body_size,
+            max_response_body_size: api_config.max_response_body_size,
+            batch_requests_supported: api_config.batch_requests_supported,
+            enable_http: api_config.enable_http,
+            enable_ws: api_config.enable_ws,
+            ping_interval: api_config.ping_interval.to_duration(),
+        };
+        let front = front::start_front(url, rpc_module.into(), front_config)
+            .await
+            .map_err(JsonRpseeError::Custom)?;
+        return Ok(StopHandle {
+            server_handler: None,
+            front: Some(front),
+        });
+    }
 
-    let middleware = tower::ServiceBuilder::new().layer(cors);
+    let middleware = tower::ServiceBuilder::new()
+        .layer(cors)
+        .option_layer(auth.map(RequireAuthorizationLayer::custom));
 
     let server = server_builder
         .set_middleware(middleware)
@@ -153,25 +213,35 @@ async fn serve(
         .await
         .expect("failed to build server");
 
-    let server_handler = server.start(api.into_rpc()).expect("server start failed");
-    let stop_handler = StopHandle { server_handler };
+    let server_handler = server.start(rpc_module).expect("server start failed");
+    let stop_handler = StopHandle {
+        server_handler: Some(server_handler),
+        front: None,
+    };
 
     Ok(stop_handler)
 }
 
 /// Used to be able to stop the API
 pub struct StopHandle {
-    server_handler: ServerHandle,
+    server_handler: Option<ServerHandle>,
+    front: Option<JoinHandle<()>>,
 }
 
 impl StopHandle {
     /// stop the API gracefully
     pub fn stop(self) {
-        match self.server_handler.stop() {
-            Ok(_) => {
-                info!("API finished cleanly");
+        if let Some(front) = self.front {
+            front.abort();
+            info!("API finished cleanly");
+        }
+        if let Some(server_handler) = self.server_handler {
+            match server_handler.stop() {
+                Ok(_) => {
+                    info!("API finished cleanly");
+                }
+                Err(err) => warn!("API thread panicked: {:?}", err),
             }
-            Err(err) => warn!("API thread panicked: {:?}", err),
         }
     }
 }
@@ -216,15 +286,31 @@ pub trait MassaRpc {
     #[method(name = "get_staking_addresses")]
     async fn get_staking_addresses(&self) -> RpcResult<PreHashSet<Address>>;
 
-    /// Bans given IP address(es).
+    /// Bans given IP address(es) for the given duration in milliseconds,
+    /// or until they are unbanned if no duration is given.
     /// No confirmation to expect.
     #[method(name = "node_ban_by_ip")]
-    async fn node_ban_by_ip(&self, arg: Vec<IpAddr>) -> RpcResult<()>;
+    async fn node_ban_by_ip(&self, arg: Vec<IpAddr>, duration: Option<MassaTime>) -> RpcResult<()>;
 
-    /// Bans given node id.
+    /// Bans given node id for the given duration in milliseconds,
+    /// or until they are unbanned if no duration is given.
     /// No confirmation to expect.
     #[method(name = "node_ban_by_id")]
-    async fn node_ban_by_id(&self, arg: Vec<NodeId>) -> RpcResult<()>;
+    async fn node_ban_by_id(&self, arg: Vec<NodeId>, duration: Option<MassaTime>) -> RpcResult<()>;
+
+    /// Get the banned peers with the reason and expiry of their ban.
+    #[method(name = "get_banned_peers")]
+    async fn get_banned_peers(&self) -> RpcResult<Vec<BannedPeer>>;
+
+    /// Get the connected nodes with statistics about their connection:
+    /// version, age, traffic by message type, ask block queue depth, ping and last activity.
+    #[method(name = "get_peers")]
+    async fn get_peers(&self) -> RpcResult<Vec<ConnectedPeer>>;
+
+    /// Close the connections to the given node ids, without banning them.
+    /// No confirmation to expect.
+    #[method(name = "node_disconnect_by_id")]
+    async fn node_disconnect_by_id(&self, arg: Vec<NodeId>) -> RpcResult<()>;
 
     /// whitelist given IP address.
     /// No confirmation to expect.
@@ -238,6 +324,42 @@ pub trait MassaRpc {
     #[method(name = "node_remove_from_whitelist")]
     async fn node_remove_from_whitelist(&self, arg: Vec<IpAddr>) -> RpcResult<()>;
 
+    /// Get the IPs and CIDR ranges allowed to bootstrap on the node.
+    /// Returns null if there is no bootstrap whitelist, every IP being then allowed.
+    #[method(name = "node_bootstrap_whitelist")]
+    async fn node_bootstrap_whitelist(&self) -> RpcResult<Option<Vec<IpRange>>>;
+
+    /// Add IPs and CIDR ranges to the bootstrap whitelist, creating it if it does not exist.
+    /// The whitelist file is updated.
+    #[method(name = "node_add_to_bootstrap_whitelist")]
+    async fn node_add_to_bootstrap_whitelist(&self, arg: Vec<IpRange>) -> RpcResult<()>;
+
+    /// Remove IPs and CIDR ranges from the bootstrap whitelist.
+    /// A listed range containing a removed range is split so that only the removed range leaves the list.
+    /// The whitelist file is updated.
+    #[method(name = "node_remove_from_bootstrap_whitelist")]
+    async fn node_remove_from_bootstrap_whitelist(&self, arg: Vec<IpRange>) -> RpcResult<()>;
+
+    /// Get the IPs and CIDR ranges not allowed to bootstrap on the node.
+    /// Returns null if there is no bootstrap blacklist.
+    #[method(name = "node_bootstrap_blacklist")]
+    async fn node_bootstrap_blacklist(&self) -> RpcResult<Option<Vec<IpRange>>>;
+
+    /// Add IPs and CIDR ranges to the bootstrap blacklist, creating it if it does not exist.
+    /// The blacklist file is updated.
+    #[method(name = "node_add_to_bootstrap_blacklist")]
+    async fn node_add_to_bootstrap_blacklist(&self, arg: Vec<IpRange>) -> RpcResult<()>;
+
+    /// Remove IPs and CIDR ranges from the bootstrap blacklist.
+    /// A listed range containing a removed range is split so that only the removed range leaves the list.
+    /// The blacklist file is updated.
+    #[method(name = "node_remove_from_bootstrap_blacklist")]
+    async fn node_remove_from_bootstrap_blacklist(&self, arg: Vec<IpRange>) -> RpcResult<()>;
+
+    /// Get the sessions currently served by the bootstrap server.
+    #[method(name = "get_bootstrap_sessions")]
+    async fn get_bootstrap_sessions(&self) -> RpcResult<Vec<BootstrapSessionInfo>>;
+
     /// Unban given IP address(es).
     /// No confirmation to expect.
     #[method(name = "node_unban_by_ip")]
@@ -293,6 +415,29 @@ pub trait MassaRpc {
     #[method(name = "get_addresses")]
     async fn get_addresses(&self, arg: Vec<Address>) -> RpcResult<Vec<AddressInfo>>;
 
+    /// Get the upcoming block and endorsement draws of a list of addresses,
+    /// with their estimated timestamps. Optional parameters:
+    /// * start slot (included), defaults to the current slot
+    /// * end slot (excluded), defaults to the end of the last drawn cycle
+    #[method(name = "get_selections")]
+    async fn get_selections(&self, arg: SelectionsFilter) -> RpcResult<Vec<AddressSelections>>;
+
+    /// Get the block production statistics, deactivation risk and rewards of a list of addresses,
+    /// for every cycle kept in the PoS state and the active cycles.
+    /// Rewards are only known for the cycles entirely executed by the node since it started, they are null otherwise.
+    #[method(name = "get_staking_reports")]
+    async fn get_staking_reports(&self, arg: Vec<Address>) -> RpcResult<Vec<StakingReport>>;
+
+    /// Get the hashes of the final state at the output of the given final slots.
+    /// Only the latest final slot and the slots kept in the final state changes history are available.
+    #[method(name = "get_final_state_hash")]
+    async fn get_final_state_hash(&self, arg: Vec<Slot>) -> RpcResult<Vec<FinalStateHash>>;
+
+    /// Get final ledger sub-entries (balance, bytecode or datastore entry) along with their inclusion or exclusion proofs
+    /// against the ledger sparse Merkle root and the latest final state hash.
+    #[method(name = "get_ledger_proof")]
+    async fn get_ledger_proof(&self, arg: Vec<LedgerProofInput>) -> RpcResult<Vec<LedgerProof>>;
+
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
     #[method(name = "send_operations")]
     async fn send_operations(&self, arg: Vec<OperationInput>) -> RpcResult<Vec<OperationId>>;
@@ -1,21 +1,25 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::auth::BearerTokenAuth;
 use crate::config::APIConfig;
 use crate::error::ApiError;
 use crate::{MassaRpcServer, Private, RpcServer, StopHandle, Value, API};
 
 use async_trait::async_trait;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use massa_bootstrap_exports::{BootstrapListController, BootstrapStatusController};
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, AddressSelections, BannedPeer, BlockInfo, BlockSummary, BootstrapSessionInfo,
+    ConnectedPeer, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    FinalStateHash, LedgerProof, LedgerProofInput, NodeStatus, OperationInfo, OperationInput,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionsFilter, StakingReport, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
-use massa_models::node::NodeId;
+use massa_models::ip_range::IpRange;
+use massa_models::node::{BanReason, NodeId};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{
@@ -26,7 +30,9 @@ use massa_models::{
     slot::Slot,
 };
 use massa_network_exports::NetworkCommandSender;
+use massa_protocol_exports::ProtocolCommandSender;
 use massa_signature::KeyPair;
+use massa_time::MassaTime;
 use massa_wallet::Wallet;
 
 use parking_lot::RwLock;
@@ -39,18 +45,24 @@ impl API<Private> {
     /// generate a new private API
     pub fn new(
         network_command_sender: NetworkCommandSender,
+        protocol_command_sender: ProtocolCommandSender,
         execution_controller: Box<dyn ExecutionController>,
         api_settings: APIConfig,
         node_wallet: Arc<RwLock<Wallet>>,
+        bootstrap_white_black_list: Box<dyn BootstrapListController>,
+        bootstrap_server_status: Box<dyn BootstrapStatusController>,
     ) -> (Self, mpsc::Receiver<()>) {
         let (stop_node_channel, rx) = mpsc::channel(1);
         (
             API(Private {
                 network_command_sender,
+                protocol_command_sender,
                 execution_controller,
                 api_settings,
                 stop_node_channel,
                 node_wallet,
+                bootstrap_white_black_list,
+                bootstrap_server_status,
             }),
             rx,
         )
@@ -64,7 +76,19 @@ impl RpcServer for API<Private> {
         url: &SocketAddr,
         settings: &APIConfig,
     ) -> Result<StopHandle, JsonRpseeError> {
-        crate::serve(self, url, settings).await
+        let auth = settings
+            .private_api_token
+            .as_deref()
+            .map(BearerTokenAuth::new);
+        crate::serve(
+            self,
+            url,
+            settings,
+            auth,
+            settings.private_tls.as_ref(),
+            None,
+        )
+        .await
     }
 }
 
@@ -130,22 +154,79 @@ impl MassaRpcServer for API<Private> {
         Ok(addresses_set)
     }
 
-    async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
+    async fn node_ban_by_ip(&self, ips: Vec<IpAddr>, duration: Option<MassaTime>) -> RpcResult<()> {
         let network_command_sender = self.0.network_command_sender.clone();
-        match network_command_sender.node_ban_by_ips(ips).await {
+        match network_command_sender
+            .node_ban_by_ips(ips, duration, BanReason::Manual)
+            .await
+        {
             Ok(()) => return Ok(()),
             Err(e) => return Err(ApiError::from(e).into()),
         };
     }
 
-    async fn node_ban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
+    async fn node_ban_by_id(&self, ids: Vec<NodeId>, duration: Option<MassaTime>) -> RpcResult<()> {
         let network_command_sender = self.0.network_command_sender.clone();
-        match network_command_sender.node_ban_by_ids(ids).await {
+        match network_command_sender
+            .node_ban_by_ids(ids, duration, BanReason::Manual)
+            .await
+        {
             Ok(()) => return Ok(()),
             Err(e) => return Err(ApiError::from(e).into()),
         };
     }
 
+    async fn get_banned_peers(&self) -> RpcResult<Vec<BannedPeer>> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        let peers = match network_command_sender.get_peers().await {
+            Ok(peers) => peers,
+            Err(e) => return Err(ApiError::from(e).into()),
+        };
+        Ok(peers
+            .peers
+            .values()
+            .filter(|peer| peer.peer_info.banned)
+            .filter_map(|peer| {
+                Some(BannedPeer {
+                    ip: peer.peer_info.ip,
+                    reason: peer.peer_info.ban_reason?,
+                    expiry: peer.peer_info.ban_expiry,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_peers(&self) -> RpcResult<Vec<ConnectedPeer>> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        let protocol_command_sender = self.0.protocol_command_sender.clone();
+        let (peers, ask_block_queue_depths) = match tokio::join!(
+            network_command_sender.get_connected_peers(),
+            protocol_command_sender.get_ask_block_queue_depths()
+        ) {
+            (Ok(peers), Ok(depths)) => (peers, depths),
+            (Err(e), _) => return Err(ApiError::from(e).into()),
+            (_, Err(e)) => return Err(ApiError::from(e).into()),
+        };
+        Ok(peers
+            .into_iter()
+            .map(|mut peer| {
+                peer.ask_block_queue_depth = ask_block_queue_depths
+                    .get(&peer.node_id)
+                    .copied()
+                    .unwrap_or_default();
+                peer
+            })
+            .collect())
+    }
+
+    async fn node_disconnect_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        match network_command_sender.node_disconnect_by_ids(ids).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(ApiError::from(e).into()),
+        }
+    }
+
     async fn node_unban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
         let network_command_sender = self.0.network_command_sender.clone();
         match network_command_sender.node_unban_by_ids(ids).await {
@@ -205,6 +286,22 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Vec<AddressInfo>>()
     }
 
+    async fn get_selections(&self, _: SelectionsFilter) -> RpcResult<Vec<AddressSelections>> {
+        crate::wrong_api::<Vec<AddressSelections>>()
+    }
+
+    async fn get_staking_reports(&self, _: Vec<Address>) -> RpcResult<Vec<StakingReport>> {
+        crate::wrong_api::<Vec<StakingReport>>()
+    }
+
+    async fn get_final_state_hash(&self, _: Vec<Slot>) -> RpcResult<Vec<FinalStateHash>> {
+        crate::wrong_api::<Vec<FinalStateHash>>()
+    }
+
+    async fn get_ledger_proof(&self, _: Vec<LedgerProofInput>) -> RpcResult<Vec<LedgerProof>> {
+        crate::wrong_api::<Vec<LedgerProof>>()
+    }
+
     async fn send_operations(&self, _: Vec<OperationInput>) -> RpcResult<Vec<OperationId>> {
         crate::wrong_api::<Vec<OperationId>>()
     }
@@ -229,6 +326,46 @@ impl MassaRpcServer for API<Private> {
         };
     }
 
+    async fn node_bootstrap_whitelist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        Ok(self.0.bootstrap_white_black_list.get_whitelist())
+    }
+
+    async fn node_add_to_bootstrap_whitelist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.0
+            .bootstrap_white_black_list
+            .add_to_whitelist(ranges)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()).into())
+    }
+
+    async fn node_remove_from_bootstrap_whitelist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.0
+            .bootstrap_white_black_list
+            .remove_from_whitelist(ranges)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()).into())
+    }
+
+    async fn node_bootstrap_blacklist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        Ok(self.0.bootstrap_white_black_list.get_blacklist())
+    }
+
+    async fn node_add_to_bootstrap_blacklist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.0
+            .bootstrap_white_black_list
+            .add_to_blacklist(ranges)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()).into())
+    }
+
+    async fn node_remove_from_bootstrap_blacklist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.0
+            .bootstrap_white_black_list
+            .remove_from_blacklist(ranges)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()).into())
+    }
+
+    async fn get_bootstrap_sessions(&self) -> RpcResult<Vec<BootstrapSessionInfo>> {
+        Ok(self.0.bootstrap_server_status.get_sessions())
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         crate::wrong_api::<Value>()
     }
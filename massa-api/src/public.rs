@@ -6,33 +6,40 @@ use crate::error::ApiError;
 use crate::{MassaRpcServer, Public, RpcServer, StopHandle, Value, API};
 use async_trait::async_trait;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use massa_bootstrap_exports::BootstrapStatusController;
 use massa_consensus_exports::block_status::DiscardReason;
 use massa_consensus_exports::ConsensusController;
 use massa_execution_exports::{
     ExecutionController, ExecutionStackElement, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
+use massa_models::address::ExecutionAddressCycleInfo;
 use massa_models::api::{
-    BlockGraphStatus, DatastoreEntryInput, DatastoreEntryOutput, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, SlotAmount,
+    AddressSelections, BannedPeer, BlockDrawInfo, BlockGraphStatus, BootstrapSessionInfo,
+    ConnectedPeer, CycleProductionReport, DatastoreEntryInput, DatastoreEntryOutput,
+    EndorsementDrawInfo, LedgerProof, LedgerProofInput, OperationInput, ReadOnlyBytecodeExecution,
+    ReadOnlyCall, SelectionsFilter, SlotAmount, StakingReport,
 };
 use massa_models::execution::ReadOnlyResult;
-use massa_models::operation::OperationDeserializer;
-use massa_models::wrapped::WrappedDeserializer;
+use massa_models::ip_range::IpRange;
+use massa_models::operation::{Operation, OperationDeserializer};
+use massa_models::wrapped::{WrappedContent, WrappedDeserializer};
 use massa_models::{
     block::Block, endorsement::WrappedEndorsement, error::ModelsError, operation::WrappedOperation,
     timeslots,
 };
-use massa_pos_exports::SelectorController;
+use massa_pos_exports::{ProductionStats, SelectorController};
 use massa_protocol_exports::ProtocolCommandSender;
 use massa_serialization::{DeserializeError, Deserializer};
+use num::rational::Ratio;
 
 use itertools::{izip, Itertools};
 use massa_models::datastore::DatastoreDeserializer;
 use massa_models::{
     address::Address,
+    amount::Amount,
     api::{
         AddressInfo, BlockInfo, BlockInfoContent, BlockSummary, EndorsementInfo, EventFilter,
-        NodeStatus, OperationInfo, TimeInterval,
+        FinalStateHash, NodeStatus, OperationInfo, TimeInterval,
     },
     block::BlockId,
     clique::Clique,
@@ -45,7 +52,9 @@ use massa_models::{
     output_event::SCOutputEvent,
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
-    timeslots::{get_latest_block_slot_at_timestamp, time_range_to_slot_range},
+    timeslots::{
+        get_block_slot_timestamp, get_latest_block_slot_at_timestamp, time_range_to_slot_range,
+    },
     version::Version,
 };
 use massa_network_exports::{NetworkCommandSender, NetworkConfig};
@@ -71,6 +80,7 @@ impl API<Public> {
         compensation_millis: i64,
         node_id: NodeId,
         storage: Storage,
+        bootstrap_server_status: Box<dyn BootstrapStatusController>,
     ) -> Self {
         API(Public {
             consensus_controller,
@@ -85,6 +95,7 @@ impl API<Public> {
             execution_controller,
             selector_controller,
             storage,
+            bootstrap_server_status,
         })
     }
 }
@@ -96,7 +107,15 @@ impl RpcServer for API<Public> {
         url: &SocketAddr,
         api_config: &APIConfig,
     ) -> Result<StopHandle, JsonRpseeError> {
-        crate::serve(self, url, api_config).await
+        crate::serve(
+            self,
+            url,
+            api_config,
+            None,
+            api_config.public_tls.as_ref(),
+            api_config.public_rate_limiter.clone(),
+        )
+        .await
     }
 }
 
@@ -281,11 +300,23 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<PreHashSet<Address>>()
     }
 
-    async fn node_ban_by_ip(&self, _: Vec<IpAddr>) -> RpcResult<()> {
+    async fn node_ban_by_ip(&self, _: Vec<IpAddr>, _: Option<MassaTime>) -> RpcResult<()> {
         crate::wrong_api::<()>()
     }
 
-    async fn node_ban_by_id(&self, _: Vec<NodeId>) -> RpcResult<()> {
+    async fn node_ban_by_id(&self, _: Vec<NodeId>, _: Option<MassaTime>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn get_banned_peers(&self) -> RpcResult<Vec<BannedPeer>> {
+        crate::wrong_api::<Vec<BannedPeer>>()
+    }
+
+    async fn get_peers(&self) -> RpcResult<Vec<ConnectedPeer>> {
+        crate::wrong_api::<Vec<ConnectedPeer>>()
+    }
+
+    async fn node_disconnect_by_id(&self, _: Vec<NodeId>) -> RpcResult<()> {
         crate::wrong_api::<()>()
     }
 
@@ -379,6 +410,8 @@ impl MassaRpcServer for API<Public> {
             last_slot,
             next_slot,
             execution_stats,
+            bootstrap_stats: self.0.bootstrap_server_status.get_stats(),
+            final_state_hash: execution_controller.get_final_state_hash(),
             consensus_stats,
             network_stats,
             pool_stats,
@@ -850,6 +883,131 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
+    async fn get_selections(&self, filter: SelectionsFilter) -> RpcResult<Vec<AddressSelections>> {
+        let api_settings = self.0.api_settings.clone();
+        if filter.addresses.len() as u64 > api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+
+        // only look into the cycles that were already drawn by the selector
+        let (first_cycle, last_cycle) = match self.0.selector_controller.get_drawn_cycle_range() {
+            Ok(Some(range)) => range,
+            Ok(None) => {
+                return Ok(filter
+                    .addresses
+                    .into_iter()
+                    .map(|address| AddressSelections {
+                        address,
+                        block_draws: Vec::new(),
+                        endorsement_draws: Vec::new(),
+                    })
+                    .collect())
+            }
+            Err(e) => return Err(ApiError::from(e).into()),
+        };
+        let drawn_start = Slot::new_first_of_cycle(first_cycle, api_settings.periods_per_cycle)
+            .map_err(ApiError::from)?;
+        let drawn_end =
+            Slot::new_first_of_cycle(last_cycle.saturating_add(1), api_settings.periods_per_cycle)
+                .map_err(ApiError::from)?;
+
+        // compute the effective slot range
+        let start = match filter.start {
+            Some(start) => start,
+            None => timeslots::get_current_latest_block_slot(
+                api_settings.thread_count,
+                api_settings.t0,
+                api_settings.genesis_timestamp,
+                self.0.compensation_millis,
+            )
+            .map_err(ApiError::from)?
+            .unwrap_or_else(|| Slot::new(0, 0)),
+        }
+        .max(drawn_start);
+        let end = filter.end.unwrap_or(drawn_end).min(drawn_end);
+
+        let slot_timestamp = |slot: Slot| {
+            get_block_slot_timestamp(
+                api_settings.thread_count,
+                api_settings.t0,
+                api_settings.genesis_timestamp,
+                slot,
+            )
+            .map_err(ApiError::from)
+        };
+
+        let mut res = Vec::with_capacity(filter.addresses.len());
+        for address in filter.addresses {
+            let (block_slots, endorsement_slots) = self
+                .0
+                .selector_controller
+                .get_address_selections(&address, start, end)
+                .map_err(ApiError::from)?;
+            let block_draws = block_slots
+                .into_iter()
+                .map(|slot| {
+                    Ok(BlockDrawInfo {
+                        slot,
+                        timestamp: slot_timestamp(slot)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, ApiError>>()?;
+            let endorsement_draws = endorsement_slots
+                .into_iter()
+                .map(|indexed_slot| {
+                    Ok(EndorsementDrawInfo {
+                        slot: indexed_slot.slot,
+                        index: indexed_slot.index,
+                        timestamp: slot_timestamp(indexed_slot.slot)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, ApiError>>()?;
+            res.push(AddressSelections {
+                address,
+                block_draws,
+                endorsement_draws,
+            });
+        }
+
+        Ok(res)
+    }
+
+    async fn get_staking_reports(&self, addresses: Vec<Address>) -> RpcResult<Vec<StakingReport>> {
+        if addresses.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+
+        let max_miss_ratio = self.0.api_settings.max_miss_ratio;
+
+        // cycle infos cover the final cycles of the PoS state and the active ones
+        let execution_infos = self.0.execution_controller.get_addresses_infos(&addresses);
+
+        let mut res = Vec::with_capacity(addresses.len());
+        for (address, execution_info) in addresses.into_iter().zip(execution_infos.into_iter()) {
+            res.push(build_staking_report(
+                address,
+                &max_miss_ratio,
+                execution_info.cycle_infos,
+            ));
+        }
+
+        Ok(res)
+    }
+
+    async fn get_final_state_hash(&self, slots: Vec<Slot>) -> RpcResult<Vec<FinalStateHash>> {
+        if slots.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        Ok(self.0.execution_controller.get_final_state_hashes(&slots))
+    }
+
+    async fn get_ledger_proof(&self, inputs: Vec<LedgerProofInput>) -> RpcResult<Vec<LedgerProof>> {
+        if inputs.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        Ok(self.0.execution_controller.get_ledger_proofs(&inputs))
+    }
+
     async fn send_operations(&self, ops: Vec<OperationInput>) -> RpcResult<Vec<OperationId>> {
         let mut cmd_sender = self.0.pool_command_sender.clone();
         let mut protocol_sender = self.0.protocol_command_sender.clone();
@@ -871,9 +1029,14 @@ impl MassaRpcServer for API<Public> {
             .into_iter()
             .map(|op_input| {
                 let mut op_serialized = Vec::new();
-                op_serialized.extend(op_input.signature.to_bytes());
-                op_serialized.extend(op_input.creator_public_key.to_bytes());
-                op_serialized.extend(op_input.serialized_content);
+                <Operation as WrappedContent>::serialize(
+                    &op_input.signature,
+                    &op_input.creator_public_key,
+                    op_input.multisig.as_ref(),
+                    &op_input.serialized_content,
+                    &mut op_serialized,
+                )
+                .map_err(|err| ApiError::ModelsError(err.into()))?;
                 let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
                     .deserialize::<DeserializeError>(&op_serialized)
                     .map_err(|err| {
@@ -939,6 +1102,34 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_bootstrap_whitelist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        crate::wrong_api::<Option<Vec<IpRange>>>()
+    }
+
+    async fn node_add_to_bootstrap_whitelist(&self, _: Vec<IpRange>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_remove_from_bootstrap_whitelist(&self, _: Vec<IpRange>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_bootstrap_blacklist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        crate::wrong_api::<Option<Vec<IpRange>>>()
+    }
+
+    async fn node_add_to_bootstrap_blacklist(&self, _: Vec<IpRange>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_remove_from_bootstrap_blacklist(&self, _: Vec<IpRange>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn get_bootstrap_sessions(&self) -> RpcResult<Vec<BootstrapSessionInfo>> {
+        crate::wrong_api::<Vec<BootstrapSessionInfo>>()
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         let openrpc_spec_path = self.0.api_settings.openrpc_spec_path.clone();
         let openrpc: RpcResult<Value> = std::fs::read_to_string(openrpc_spec_path)
@@ -962,3 +1153,382 @@ impl MassaRpcServer for API<Public> {
         openrpc
     }
 }
+
+/// Builds the staking report of an address from its per-cycle execution infos.
+/// Cycles with unknown rewards are left out of the reward totals.
+fn build_staking_report(
+    address: Address,
+    max_miss_ratio: &Ratio<u64>,
+    cycle_infos: Vec<ExecutionAddressCycleInfo>,
+) -> StakingReport {
+    let mut total_block_rewards = Amount::zero();
+    let mut total_endorsement_rewards = Amount::zero();
+    let cycles = cycle_infos
+        .into_iter()
+        .map(|cycle_info| {
+            if let Some(block_rewards) = cycle_info.block_rewards {
+                total_block_rewards = total_block_rewards.saturating_add(block_rewards);
+            }
+            if let Some(endorsement_rewards) = cycle_info.endorsement_rewards {
+                total_endorsement_rewards =
+                    total_endorsement_rewards.saturating_add(endorsement_rewards);
+            }
+            let stats = ProductionStats {
+                block_success_count: cycle_info.ok_count,
+                block_failure_count: cycle_info.nok_count,
+                ..Default::default()
+            };
+            let opportunities_count = cycle_info.ok_count.saturating_add(cycle_info.nok_count);
+            CycleProductionReport {
+                cycle: cycle_info.cycle,
+                is_final: cycle_info.is_final,
+                active_rolls: cycle_info.active_rolls,
+                ok_count: cycle_info.ok_count,
+                nok_count: cycle_info.nok_count,
+                miss_ratio: if opportunities_count == 0 {
+                    0.0
+                } else {
+                    cycle_info.nok_count as f64 / opportunities_count as f64
+                },
+                deactivation_risk: !stats.is_satisfying(max_miss_ratio),
+                block_rewards: cycle_info.block_rewards,
+                endorsement_rewards: cycle_info.endorsement_rewards,
+            }
+        })
+        .collect();
+    StakingReport {
+        address,
+        max_miss_ratio: *max_miss_ratio.numer() as f64 / *max_miss_ratio.denom() as f64,
+        cycles,
+        total_block_rewards,
+        total_endorsement_rewards,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::error::CallError;
+    use massa_consensus_exports::test_exports::MockConsensusController;
+    use massa_execution_exports::test_exports::MockExecutionController;
+    use massa_models::api::IndexedSlot;
+    use massa_models::config::{
+        MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        MAX_PARAMETERS_SIZE,
+    };
+    use massa_models::stats::BootstrapStats;
+    use massa_pool_exports::test_exports::MockPoolController;
+    use massa_pos_exports::test_exports::{MockSelectorController, MockSelectorControllerMessage};
+    use std::str::FromStr;
+
+    fn cycle_info(
+        cycle: u64,
+        ok_count: u64,
+        nok_count: u64,
+        rewards: Option<(&str, &str)>,
+    ) -> ExecutionAddressCycleInfo {
+        ExecutionAddressCycleInfo {
+            cycle,
+            is_final: true,
+            ok_count,
+            nok_count,
+            active_rolls: Some(10),
+            block_rewards: rewards.map(|(block, _)| Amount::from_str(block).unwrap()),
+            endorsement_rewards: rewards
+                .map(|(_, endorsement)| Amount::from_str(endorsement).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_staking_report() {
+        let address = Address::from_bytes(&[1; 32]);
+        let report = build_staking_report(
+            address,
+            &Ratio::new(1, 3),
+            vec![
+                // executed before the node started: rewards unknown
+                cycle_info(4, 3, 0, None),
+                cycle_info(5, 2, 1, Some(("1.5", "0.25"))),
+                cycle_info(6, 1, 1, Some(("0.5", "0"))),
+                cycle_info(7, 0, 0, Some(("0", "0.5"))),
+            ],
+        );
+
+        assert_eq!(report.address, address);
+        assert!((report.max_miss_ratio - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            report.cycles.iter().map(|c| c.cycle).collect::<Vec<_>>(),
+            vec![4, 5, 6, 7]
+        );
+
+        // rewards of the cycles not executed by the node are unknown and not totaled
+        assert_eq!(report.cycles[0].block_rewards, None);
+        assert_eq!(report.cycles[0].endorsement_rewards, None);
+        assert_eq!(
+            report.cycles[1].block_rewards,
+            Some(Amount::from_str("1.5").unwrap())
+        );
+        assert_eq!(report.total_block_rewards, Amount::from_str("2").unwrap());
+        assert_eq!(
+            report.total_endorsement_rewards,
+            Amount::from_str("0.75").unwrap()
+        );
+
+        // the deactivation risk follows the maximum miss ratio, inclusive
+        let risks: Vec<bool> = report.cycles.iter().map(|c| c.deactivation_risk).collect();
+        assert_eq!(risks, vec![false, false, true, false]);
+        assert_eq!(report.cycles[2].miss_ratio, 0.5);
+        assert_eq!(report.cycles[3].miss_ratio, 0.0);
+
+        // unknown rewards are serialized as null
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json["cycles"][0]["block_rewards"].is_null());
+        assert_eq!(json["cycles"][1]["block_rewards"], "1.5");
+    }
+
+    /// Bootstrap server status of a node that is not serving any bootstrap
+    #[derive(Clone)]
+    struct IdleBootstrapStatus;
+
+    impl BootstrapStatusController for IdleBootstrapStatus {
+        fn get_sessions(&self) -> Vec<BootstrapSessionInfo> {
+            Vec::new()
+        }
+
+        fn get_stats(&self) -> BootstrapStats {
+            BootstrapStats::default()
+        }
+
+        fn clone_box(&self) -> Box<dyn BootstrapStatusController> {
+            Box::new(self.clone())
+        }
+    }
+
+    /// Starts a mock selector that has drawn `drawn_cycle_range` and selects each address at the start of the requested range.
+    /// Returns the selector and a receiver of the `(address, start, end)` selections requested to it.
+    fn start_mock_selector(
+        drawn_cycle_range: Option<(u64, u64)>,
+    ) -> (
+        Box<dyn SelectorController>,
+        std::sync::mpsc::Receiver<(Address, Slot, Slot)>,
+    ) {
+        let (selector_controller, selector_receiver) = MockSelectorController::new_with_receiver();
+        let (request_tx, request_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for message in selector_receiver.iter() {
+                match message {
+                    MockSelectorControllerMessage::GetDrawnCycleRange { response_tx } => {
+                        response_tx.send(Ok(drawn_cycle_range)).unwrap();
+                    }
+                    MockSelectorControllerMessage::GetAddressSelections {
+                        address,
+                        start,
+                        end,
+                        response_tx,
+                    } => {
+                        request_tx.send((address, start, end)).unwrap();
+                        response_tx
+                            .send(Ok((
+                                vec![start],
+                                vec![IndexedSlot {
+                                    slot: start,
+                                    index: 1,
+                                }],
+                            )))
+                            .unwrap();
+                    }
+                    other => panic!("unexpected selector call: {:?}", other),
+                }
+            }
+        });
+        (selector_controller, request_rx)
+    }
+
+    fn get_public_api(
+        selector_controller: Box<dyn SelectorController>,
+        genesis_timestamp: MassaTime,
+    ) -> API<Public> {
+        let api_settings = APIConfig {
+            draw_lookahead_period_count: 10,
+            bind_private: "127.0.0.1:0".parse().unwrap(),
+            bind_public: "127.0.0.1:0".parse().unwrap(),
+            max_arguments: 2,
+            openrpc_spec_path: "../massa-node/base_config/openrpc.json".into(),
+            max_request_body_size: 52428800,
+            max_response_body_size: 52428800,
+            max_connections: 100,
+            max_subscriptions_per_connection: 1024,
+            max_log_length: 1024,
+            allow_hosts: Vec::new(),
+            batch_requests_supported: true,
+            ping_interval: 60000.into(),
+            enable_http: true,
+            enable_ws: false,
+            max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
+            max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+            max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
+            max_parameter_size: MAX_PARAMETERS_SIZE,
+            thread_count: 2,
+            genesis_timestamp,
+            t0: T0,
+            periods_per_cycle: 4,
+            max_miss_ratio: Ratio::new(7, 10),
+            private_api_token: None,
+            public_tls: None,
+            private_tls: None,
+            public_rate_limiter: None,
+        };
+        let (consensus_controller, _) = MockConsensusController::new_with_receiver();
+        let (execution_controller, _) = MockExecutionController::new_with_receiver();
+        let (pool_controller, _) = MockPoolController::new_with_receiver();
+        API::<Public>::new(
+            consensus_controller,
+            execution_controller,
+            api_settings,
+            selector_controller,
+            pool_controller,
+            ProtocolCommandSender(tokio::sync::mpsc::channel(1).0),
+            NetworkConfig::default(),
+            Version::from_str("TEST.1.0").unwrap(),
+            NetworkCommandSender(tokio::sync::mpsc::channel(1).0),
+            0,
+            NodeId(KeyPair::generate().get_public_key()),
+            Storage::create_root(),
+            Box::new(IdleBootstrapStatus),
+        )
+    }
+
+    const T0: MassaTime = MassaTime::from_millis(1_000_000);
+
+    fn get_addresses(count: u8) -> Vec<Address> {
+        (1..=count).map(|i| Address::from_bytes(&[i; 32])).collect()
+    }
+
+    #[tokio::test]
+    async fn test_get_selections_default_range() {
+        // the current slot is (9, 1), in the middle of the drawn cycles 2 and 3
+        let genesis_timestamp = MassaTime::now(0)
+            .unwrap()
+            .saturating_sub(T0.checked_mul(39).unwrap().checked_div_u64(4).unwrap());
+        let (selector_controller, requests) = start_mock_selector(Some((2, 3)));
+        let api = get_public_api(selector_controller, genesis_timestamp);
+        let addresses = get_addresses(2);
+
+        // by default, from the current slot to the end of the last drawn cycle
+        let selections = api
+            .get_selections(SelectionsFilter {
+                addresses: addresses.clone(),
+                start: None,
+                end: None,
+            })
+            .await
+            .unwrap();
+        let expected_start = Slot::new(9, 1);
+        assert_eq!(
+            requests.try_iter().collect::<Vec<_>>(),
+            addresses
+                .iter()
+                .map(|address| (*address, expected_start, Slot::new(16, 0)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(selections.len(), 2);
+        for (selection, address) in selections.iter().zip(addresses.iter()) {
+            assert_eq!(selection.address, *address);
+            assert_eq!(
+                selection.block_draws,
+                vec![BlockDrawInfo {
+                    slot: expected_start,
+                    timestamp: get_block_slot_timestamp(2, T0, genesis_timestamp, expected_start)
+                        .unwrap(),
+                }]
+            );
+            assert_eq!(selection.endorsement_draws.len(), 1);
+            assert_eq!(selection.endorsement_draws[0].index, 1);
+        }
+
+        // given slots are kept inside the drawn cycles
+        api.get_selections(SelectionsFilter {
+            addresses: addresses[..1].to_vec(),
+            start: Some(Slot::new(1, 0)),
+            end: Some(Slot::new(20, 0)),
+        })
+        .await
+        .unwrap();
+        api.get_selections(SelectionsFilter {
+            addresses: addresses[..1].to_vec(),
+            start: Some(Slot::new(10, 1)),
+            end: Some(Slot::new(12, 0)),
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            requests.try_iter().collect::<Vec<_>>(),
+            vec![
+                (addresses[0], Slot::new(8, 0), Slot::new(16, 0)),
+                (addresses[0], Slot::new(10, 1), Slot::new(12, 0)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_selections_before_genesis_and_draws() {
+        // before genesis, the draws start at the first drawn cycle
+        let genesis_timestamp = MassaTime::now(0).unwrap().saturating_add(T0);
+        let (selector_controller, requests) = start_mock_selector(Some((2, 3)));
+        let api = get_public_api(selector_controller, genesis_timestamp);
+        let addresses = get_addresses(1);
+        api.get_selections(SelectionsFilter {
+            addresses: addresses.clone(),
+            start: None,
+            end: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            requests.try_iter().collect::<Vec<_>>(),
+            vec![(addresses[0], Slot::new(8, 0), Slot::new(16, 0))]
+        );
+
+        // nothing is drawn yet: no draws
+        let (selector_controller, requests) = start_mock_selector(None);
+        let api = get_public_api(selector_controller, genesis_timestamp);
+        let selections = api
+            .get_selections(SelectionsFilter {
+                addresses: addresses.clone(),
+                start: None,
+                end: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].address, addresses[0]);
+        assert!(selections[0].block_draws.is_empty());
+        assert!(selections[0].endorsement_draws.is_empty());
+        assert!(requests.try_iter().next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_selections_too_many_arguments() {
+        let (selector_controller, requests) = start_mock_selector(Some((2, 3)));
+        let api = get_public_api(selector_controller, MassaTime::now(0).unwrap());
+        let err = api
+            .get_selections(SelectionsFilter {
+                addresses: get_addresses(3),
+                start: None,
+                end: None,
+            })
+            .await
+            .unwrap_err();
+        match err {
+            JsonRpseeError::Call(CallError::Custom(error)) => {
+                assert_eq!(error.code(), -32000);
+                assert!(error.message().contains("too many arguments"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(requests.try_iter().next().is_none());
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Per-IP token-bucket rate limiting of the API calls.
+//!
+//! Each client IP, or /64 prefix for IPv6 clients, owns a bucket of `capacity` tokens refilled at `refill_per_second`.
+//! A call costs the weight of its method, multiplied by the length of its first parameter
+//! when it is a list, so that asking for a thousand addresses costs as much as a thousand calls.
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    net::{IpAddr, Ipv6Addr},
+    sync::Arc,
+    time::Instant,
+};
+
+/// Rate limiting configuration
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    /// maximum number of tokens of a bucket, i.e. the allowed burst
+    pub capacity: u64,
+    /// tokens given back to each bucket every second
+    pub refill_per_second: u64,
+    /// weight of the methods that are not listed in `method_weights`
+    pub default_weight: u64,
+    /// weight of specific methods
+    pub method_weights: HashMap<String, u64>,
+    /// maximum number of clients tracked at once, the least recently seen is forgotten beyond
+    pub max_tracked_ips: usize,
+}
+
+/// Token bucket of a client
+struct Bucket {
+    /// available tokens
+    tokens: f64,
+    /// last time `tokens` was refilled
+    last_refill: Instant,
+}
+
+struct RateLimiterState {
+    buckets: HashMap<IpAddr, Bucket>,
+    /// clients by last refill of their bucket, to forget the least recently seen first
+    last_refills: BTreeSet<(Instant, IpAddr)>,
+    rejected_calls: BTreeMap<String, u64>,
+}
+
+/// Shared per-IP rate limiter
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    state: Arc<Mutex<RateLimiterState>>,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// A call as seen by the rate limiter
+pub(crate) struct CallCost<'a> {
+    /// called method
+    pub method: &'a str,
+    /// length of the first parameter if it is a list, 1 otherwise
+    pub items: u64,
+}
+
+/// Key of the bucket of `ip`.
+/// IPv6 clients are keyed by their /64 prefix, as a single host usually owns a whole /64.
+fn bucket_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => {
+                let segments = ipv6.segments();
+                IpAddr::V6(Ipv6Addr::new(
+                    segments[0],
+                    segments[1],
+                    segments[2],
+                    segments[3],
+                    0,
+                    0,
+                    0,
+                    0,
+                ))
+            }
+        },
+    }
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter tracking no IP yet
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config: Arc::new(config),
+            state: Arc::new(Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                last_refills: BTreeSet::new(),
+                rejected_calls: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Number of rejected calls since the start, by method
+    pub fn get_rejected_calls(&self) -> BTreeMap<String, u64> {
+        self.state.lock().rejected_calls.clone()
+    }
+
+    fn cost(&self, call: &CallCost) -> f64 {
+        let weight = self
+            .config
+            .method_weights
+            .get(call.method)
+            .copied()
+            .unwrap_or(self.config.default_weight);
+        weight.saturating_mul(call.items.max(1)) as f64
+    }
+
+    /// Charge `calls`, made together by `ip`, to its bucket.
+    /// Returns false, and charges nothing, if the bucket does not hold enough tokens.
+    pub(crate) fn try_acquire(&self, ip: IpAddr, calls: &[CallCost]) -> bool {
+        self.try_acquire_at(ip, calls, Instant::now())
+    }
+
+    fn try_acquire_at(&self, ip: IpAddr, calls: &[CallCost], now: Instant) -> bool {
+        let cost: f64 = calls.iter().map(|call| self.cost(call)).sum();
+        let key = bucket_key(ip);
+        let capacity = self.config.capacity as f64;
+        let refill_per_second = self.config.refill_per_second as f64;
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        let bucket = match state.buckets.get_mut(&key) {
+            Some(bucket) => {
+                state.last_refills.remove(&(bucket.last_refill, key));
+                bucket
+            }
+            None => {
+                // forget the least recently seen client to make room
+                if state.buckets.len() >= self.config.max_tracked_ips {
+                    if let Some((_, forgotten)) = state.last_refills.pop_first() {
+                        state.buckets.remove(&forgotten);
+                    }
+                }
+                state.buckets.entry(key).or_insert(Bucket {
+                    tokens: capacity,
+                    last_refill: now,
+                })
+            }
+        };
+        bucket.tokens = (bucket.tokens
+            + now
+                .saturating_duration_since(bucket.last_refill)
+                .as_secs_f64()
+                * refill_per_second)
+            .min(capacity);
+        bucket.last_refill = std::cmp::max(bucket.last_refill, now);
+        state.last_refills.insert((bucket.last_refill, key));
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            return true;
+        }
+        for call in calls {
+            *state
+                .rejected_calls
+                .entry(call.method.to_string())
+                .or_default() += 1;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CallCost, RateLimitConfig, RateLimiter};
+    use std::{
+        collections::HashMap,
+        net::IpAddr,
+        time::{Duration, Instant},
+    };
+
+    fn rate_limiter(max_tracked_ips: usize) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            capacity: 10,
+            refill_per_second: 2,
+            default_weight: 1,
+            method_weights: HashMap::from([("heavy".to_string(), 5)]),
+            max_tracked_ips,
+        })
+    }
+
+    fn call(method: &str, items: u64) -> CallCost {
+        CallCost { method, items }
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rate_limiter_refill() {
+        let limiter = rate_limiter(10);
+        let start = Instant::now();
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("heavy", 2)], start));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 1)], start));
+        // 2 tokens per second
+        let later = start + Duration::from_millis(1500);
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 3)], later));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 1)], later));
+        // the bucket does not grow above its capacity
+        let much_later = later + Duration::from_secs(3600);
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("heavy", 2)], much_later));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 1)], much_later));
+        assert_eq!(limiter.get_rejected_calls().get("light"), Some(&3));
+    }
+
+    #[test]
+    fn test_rate_limiter_cost() {
+        let limiter = rate_limiter(10);
+        let now = Instant::now();
+        // weight 5 times 3 items is more than the capacity: nothing is charged
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &[call("heavy", 3)], now));
+        assert_eq!(limiter.get_rejected_calls().get("heavy"), Some(&1));
+        // an empty list costs as much as a single item
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("heavy", 0)], now));
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("unknown", 5)], now));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &[call("unknown", 1)], now));
+        // other clients have their own bucket
+        assert!(limiter.try_acquire_at(ip("1.2.3.5"), &[call("heavy", 2)], now));
+    }
+
+    #[test]
+    fn test_rate_limiter_batch() {
+        let limiter = rate_limiter(10);
+        let now = Instant::now();
+        let batch = [call("heavy", 1), call("light", 4), call("light", 1)];
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &batch, now));
+        // the whole batch is rejected if its total cost is not available
+        let batch = [call("light", 1), call("heavy", 1)];
+        assert!(!limiter.try_acquire_at(ip("1.2.3.4"), &batch, now));
+        let rejected_calls = limiter.get_rejected_calls();
+        assert_eq!(rejected_calls.get("light"), Some(&1));
+        assert_eq!(rejected_calls.get("heavy"), Some(&1));
+        let later = now + Duration::from_secs(3);
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &batch, later));
+    }
+
+    #[test]
+    fn test_rate_limiter_cap() {
+        let limiter = rate_limiter(2);
+        let start = Instant::now();
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 10)], start));
+        let later = start + Duration::from_millis(100);
+        assert!(limiter.try_acquire_at(ip("1.2.3.5"), &[call("light", 10)], later));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.5"), &[call("light", 1)], later));
+        // a third client makes the least recently seen one forgotten
+        assert!(limiter.try_acquire_at(ip("1.2.3.6"), &[call("light", 1)], later));
+        assert_eq!(limiter.state.lock().buckets.len(), 2);
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 10)], later));
+        assert!(!limiter.try_acquire_at(ip("1.2.3.6"), &[call("light", 10)], later));
+        assert_eq!(limiter.state.lock().buckets.len(), 2);
+        assert_eq!(limiter.state.lock().last_refills.len(), 2);
+    }
+
+    #[test]
+    fn test_rate_limiter_ipv6_prefix() {
+        let limiter = rate_limiter(10);
+        let now = Instant::now();
+        assert!(limiter.try_acquire_at(ip("2001:db8:1:2::1"), &[call("light", 10)], now));
+        // same /64
+        assert!(!limiter.try_acquire_at(ip("2001:db8:1:2:ffff::2"), &[call("light", 1)], now));
+        // other /64
+        assert!(limiter.try_acquire_at(ip("2001:db8:1:3::1"), &[call("light", 1)], now));
+        // IPv4-mapped addresses share the bucket of the IPv4 address
+        assert!(limiter.try_acquire_at(ip("1.2.3.4"), &[call("light", 10)], now));
+        assert!(!limiter.try_acquire_at(ip("::ffff:1.2.3.4"), &[call("light", 1)], now));
+    }
+}
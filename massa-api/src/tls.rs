@@ -0,0 +1,200 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! TLS configuration of the API servers.
+//! The TLS connections are terminated by the front, see `front.rs`.
+
+use massa_time::MassaTime;
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio_rustls::{
+    rustls::{
+        server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+/// TLS configuration of an API server
+#[derive(Debug, Deserialize, Clone)]
+pub struct TlsConfig {
+    /// PEM file holding the certificate chain of the server
+    pub cert_path: PathBuf,
+    /// PEM file holding the private key of the server
+    pub key_path: PathBuf,
+    /// PEM file holding the certificate authorities that sign the accepted client certificates.
+    /// Client certificates are not requested if `None`.
+    pub client_ca_path: Option<PathBuf>,
+    /// connections that do not complete the TLS handshake within this delay are closed
+    pub handshake_timeout: MassaTime,
+}
+
+/// Load all the certificates of a PEM file
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    if certificates.is_empty() {
+        return Err(format!("{}: no certificate found", path.display()));
+    }
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+/// Load the first private key of a PEM file
+fn load_private_key(path: &Path) -> Result<PrivateKey, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let mut reader = BufReader::new(file);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|err| format!("{}: {}", path.display(), err))?
+        {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(format!("{}: no private key found", path.display())),
+        }
+    }
+}
+
+/// Build the TLS acceptor described by `config`
+pub(crate) fn build_acceptor(config: &TlsConfig) -> Result<TlsAcceptor, String> {
+    let certificates = load_certificates(&config.cert_path)?;
+    let key = load_private_key(&config.key_path)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in load_certificates(client_ca_path)? {
+                roots
+                    .add(&certificate)
+                    .map_err(|err| format!("{}: {}", client_ca_path.display(), err))?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config = builder
+        .with_single_cert(certificates, key)
+        .map_err(|err| format!("invalid TLS certificate or key: {}", err))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::{
+        rustls::{ClientConfig, ServerName},
+        TlsConnector,
+    };
+
+    /// Certificates signed by `ca.pem`: `server.pem` is valid for `localhost`, `client.pem` for client authentication
+    fn cert_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/tests/certs")
+            .join(name)
+    }
+
+    fn server_config(client_ca: bool) -> TlsConfig {
+        TlsConfig {
+            cert_path: cert_path("server.pem"),
+            key_path: cert_path("server.key"),
+            client_ca_path: client_ca.then(|| cert_path("ca.pem")),
+            handshake_timeout: MassaTime::from_millis(1000),
+        }
+    }
+
+    fn client_config(client_certificate: bool) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        for certificate in load_certificates(&cert_path("ca.pem")).unwrap() {
+            roots.add(&certificate).unwrap();
+        }
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        if client_certificate {
+            builder
+                .with_single_cert(
+                    load_certificates(&cert_path("client.pem")).unwrap(),
+                    load_private_key(&cert_path("client.key")).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        }
+    }
+
+    /// Send a message through a TLS connection to an echo server using `acceptor`
+    async fn round_trip(
+        acceptor: TlsAcceptor,
+        client_config: ClientConfig,
+    ) -> std::io::Result<Vec<u8>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let mut stream = acceptor.accept(stream).await?;
+            let mut message = [0u8; 4];
+            stream.read_exact(&mut message).await?;
+            stream.write_all(&message).await?;
+            stream.flush().await?;
+            Ok::<(), std::io::Error>(())
+        });
+
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        stream.write_all(b"ping").await?;
+        stream.flush().await?;
+        let mut answer = vec![0u8; 4];
+        stream.read_exact(&mut answer).await?;
+        Ok(answer)
+    }
+
+    #[tokio::test]
+    async fn test_tls_round_trip() {
+        let acceptor = build_acceptor(&server_config(false)).unwrap();
+        assert_eq!(
+            round_trip(acceptor, client_config(false)).await.unwrap(),
+            b"ping"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tls_client_certificate() {
+        // a client certificate signed by the configured authority is accepted
+        let acceptor = build_acceptor(&server_config(true)).unwrap();
+        assert_eq!(
+            round_trip(acceptor, client_config(true)).await.unwrap(),
+            b"ping"
+        );
+
+        // a client without certificate is refused
+        let acceptor = build_acceptor(&server_config(true)).unwrap();
+        assert!(round_trip(acceptor, client_config(false)).await.is_err());
+    }
+
+    #[test]
+    fn test_tls_invalid_files() {
+        let mut config = server_config(false);
+        config.key_path = cert_path("server.pem");
+        assert!(build_acceptor(&config)
+            .err()
+            .unwrap()
+            .contains("no private key found"));
+
+        let mut config = server_config(false);
+        config.cert_path = cert_path("server.key");
+        assert!(build_acceptor(&config)
+            .err()
+            .unwrap()
+            .contains("no certificate found"));
+    }
+}
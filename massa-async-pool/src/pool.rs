@@ -50,6 +50,16 @@ impl AsyncPool {
         }
     }
 
+    /// Number of messages currently in the pool
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns true if the pool holds no message
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
     /// Applies pre-compiled `AsyncPoolChanges` to the pool without checking for overflows.
     /// This function is used when applying pre-compiled `AsyncPoolChanges` to an `AsyncPool`.
     ///
@@ -0,0 +1,64 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module exports generic traits representing interfaces for interacting
+//! with the bootstrap server.
+
+use massa_models::{api::BootstrapSessionInfo, ip_range::IpRange, stats::BootstrapStats};
+
+use crate::BootstrapListError;
+
+/// interface to read and modify the bootstrap whitelist and blacklist
+pub trait BootstrapListController: Send + Sync {
+    /// Gets the whitelist, `None` if every IP is allowed
+    fn get_whitelist(&self) -> Option<Vec<IpRange>>;
+
+    /// Gets the blacklist, `None` if there is none
+    fn get_blacklist(&self) -> Option<Vec<IpRange>>;
+
+    /// Adds IP ranges to the whitelist. If there was no whitelist, only those ranges become allowed.
+    fn add_to_whitelist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError>;
+
+    /// Removes IP ranges from the whitelist.
+    /// The listed ranges containing a removed range are split so that only the removed range leaves the list.
+    fn remove_from_whitelist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError>;
+
+    /// Adds IP ranges to the blacklist
+    fn add_to_blacklist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError>;
+
+    /// Removes IP ranges from the blacklist.
+    /// The listed ranges containing a removed range are split so that only the removed range leaves the list.
+    fn remove_from_blacklist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError>;
+
+    /// Returns a boxed clone of self.
+    /// Useful to allow cloning `Box<dyn BootstrapListController>`.
+    fn clone_box(&self) -> Box<dyn BootstrapListController>;
+}
+
+/// Allow cloning `Box<dyn BootstrapListController>`
+/// Uses `BootstrapListController::clone_box` internally
+impl Clone for Box<dyn BootstrapListController> {
+    fn clone(&self) -> Box<dyn BootstrapListController> {
+        self.clone_box()
+    }
+}
+
+/// interface to read the status of the bootstrap server
+pub trait BootstrapStatusController: Send + Sync {
+    /// Gets the active sessions, oldest first
+    fn get_sessions(&self) -> Vec<BootstrapSessionInfo>;
+
+    /// Gets the session counters
+    fn get_stats(&self) -> BootstrapStats;
+
+    /// Returns a boxed clone of self.
+    /// Useful to allow cloning `Box<dyn BootstrapStatusController>`.
+    fn clone_box(&self) -> Box<dyn BootstrapStatusController>;
+}
+
+/// Allow cloning `Box<dyn BootstrapStatusController>`
+/// Uses `BootstrapStatusController::clone_box` internally
+impl Clone for Box<dyn BootstrapStatusController> {
+    fn clone(&self) -> Box<dyn BootstrapStatusController> {
+        self.clone_box()
+    }
+}
@@ -0,0 +1,14 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Bootstrap whitelist and blacklist error
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum BootstrapListError {
+    /// IO error: {0}
+    IOError(#[from] std::io::Error),
+    /// invalid bootstrap list file: {0}
+    InvalidListFile(String),
+}
@@ -0,0 +1,11 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+//! Definition and exports of the traits and errors used to interact with the bootstrap server
+//! from the other components, without depending on the bootstrap worker.
+
+#![warn(missing_docs)]
+
+mod controller_traits;
+mod error;
+
+pub use controller_traits::{BootstrapListController, BootstrapStatusController};
+pub use error::*;
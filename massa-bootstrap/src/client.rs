@@ -1,9 +1,17 @@
+use futures::future::{join_all, try_join_all};
 use humantime::format_duration;
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use massa_final_state::FinalState;
+use massa_hash::Hash;
+use massa_ledger_exports::LedgerChanges;
 use massa_logging::massa_trace;
-use massa_models::{streaming_step::StreamingStep, version::Version};
+use massa_models::{
+    address::{Address, ADDRESS_SIZE_BYTES},
+    slot::Slot,
+    streaming_step::StreamingStep,
+    version::Version,
+};
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
@@ -16,19 +24,232 @@ use tracing::{debug, info, warn};
 
 use crate::{
     client_binder::BootstrapClientBinder,
+    compression::BootstrapCompression,
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
+    resume::{without_ledger_data, BootstrapResumeJournal, LedgerCheckpoint},
+    types::Duplex,
     BootstrapConfig, Establisher, GlobalBootstrapState,
 };
 
+/// Applies a `BootstrapServerMessage::BootstrapPart` to the global bootstrap state.
+/// Returns the `BootstrapClientMessage::AskBootstrapPart` to send to the server to get the following part.
+///
+/// `replayed_ledger_step` is set when replaying a part of the resume journal:
+/// its ledger data is already in the disk ledger and only the cursor is restored.
+fn apply_bootstrap_part(
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    part: BootstrapServerMessage,
+    replayed_ledger_step: Option<StreamingStep<Vec<u8>>>,
+) -> Result<BootstrapClientMessage, BootstrapError> {
+    let (
+        slot,
+        ledger_part,
+        async_pool_part,
+        pos_cycle_part,
+        pos_credits_part,
+        exec_ops_part,
+        final_state_changes,
+        consensus_part,
+        consensus_outdated_ids,
+    ) = match part {
+        BootstrapServerMessage::BootstrapPart {
+            slot,
+            ledger_part,
+            async_pool_part,
+            pos_cycle_part,
+            pos_credits_part,
+            exec_ops_part,
+            final_state_changes,
+            consensus_part,
+            consensus_outdated_ids,
+        } => (
+            slot,
+            ledger_part,
+            async_pool_part,
+            pos_cycle_part,
+            pos_credits_part,
+            exec_ops_part,
+            final_state_changes,
+            consensus_part,
+            consensus_outdated_ids,
+        ),
+        other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+    };
+
+    // Set final state
+    let replayed = replayed_ledger_step.is_some();
+    let mut write_final_state = global_bootstrap_state.final_state.write();
+    let last_ledger_step = match replayed_ledger_step {
+        Some(step) => step,
+        None => write_final_state.ledger.set_ledger_part(ledger_part)?,
+    };
+    let last_pool_step = write_final_state.async_pool.set_pool_part(async_pool_part);
+    let last_cycle_step = write_final_state
+        .pos_state
+        .set_cycle_history_part(pos_cycle_part);
+    let last_credits_step = write_final_state
+        .pos_state
+        .set_deferred_credits_part(pos_credits_part);
+    let last_ops_step = write_final_state
+        .executed_ops
+        .set_executed_ops_part(exec_ops_part);
+    for (changes_slot, changes) in final_state_changes.iter() {
+        if !replayed {
+            write_final_state
+                .ledger
+                .apply_changes(changes.ledger_changes.clone(), *changes_slot);
+        }
+        write_final_state
+            .async_pool
+            .apply_changes_unchecked(&changes.async_pool_changes);
+        if !changes.pos_changes.is_empty() {
+            write_final_state.pos_state.apply_changes(
+                changes.pos_changes.clone(),
+                *changes_slot,
+                false,
+            )?;
+        }
+        if !changes.executed_ops_changes.is_empty() {
+            write_final_state
+                .executed_ops
+                .apply_changes(changes.executed_ops_changes.clone(), *changes_slot);
+        }
+    }
+    write_final_state.slot = slot;
+    drop(write_final_state);
+
+    // Set consensus blocks
+    if let Some(graph) = global_bootstrap_state.graph.as_mut() {
+        // Extend the final blocks with the received part
+        graph.final_blocks.extend(consensus_part.final_blocks);
+        // Remove every outdated block
+        graph
+            .final_blocks
+            .retain(|block_export| !consensus_outdated_ids.contains(&block_export.block.id));
+    } else {
+        global_bootstrap_state.graph = Some(consensus_part);
+    }
+    let last_consensus_step = StreamingStep::Ongoing(
+        // Note that this unwrap call is safe because of the above conditional statement
+        global_bootstrap_state
+            .graph
+            .as_ref()
+            .unwrap()
+            .final_blocks
+            .iter()
+            .map(|b_export| b_export.block.id)
+            .collect(),
+    );
+
+    debug!(
+        "client final state slot changes length: {}",
+        final_state_changes.len()
+    );
+
+    Ok(BootstrapClientMessage::AskBootstrapPart {
+        last_slot: Some(slot),
+        last_ledger_step,
+        last_pool_step,
+        last_cycle_step,
+        last_credits_step,
+        last_ops_step,
+        last_consensus_step,
+    })
+}
+
+/// Replays the parts stored in the resume journal into the global bootstrap state,
+/// and checks that the disk ledger is in the state recorded by the last part.
+/// Returns the message to send to resume the bootstrap from the last stored part,
+/// or `None` if there is nothing to resume.
+fn resume_from_journal(
+    resume_journal: &BootstrapResumeJournal,
+    global_bootstrap_state: &mut GlobalBootstrapState,
+) -> Result<Option<BootstrapClientMessage>, BootstrapError> {
+    let mut next_bootstrap_message = None;
+    let last_checkpoint = resume_journal.load(|part, checkpoint| {
+        next_bootstrap_message = Some(apply_bootstrap_part(
+            global_bootstrap_state,
+            part,
+            Some(checkpoint.last_ledger_step.clone()),
+        )?);
+        Ok(())
+    })?;
+    if let Some(checkpoint) = last_checkpoint {
+        let ledger_hash = global_bootstrap_state
+            .final_state
+            .read()
+            .ledger
+            .get_ledger_hash();
+        if ledger_hash != checkpoint.ledger_hash {
+            return Err(BootstrapError::GeneralError(format!(
+                "the disk ledger hash {} does not match the hash {} recorded by the resume journal",
+                ledger_hash, checkpoint.ledger_hash
+            )));
+        }
+    }
+    Ok(next_bootstrap_message)
+}
+
+/// First message of a bootstrap started from scratch
+fn initial_bootstrap_message() -> BootstrapClientMessage {
+    BootstrapClientMessage::AskBootstrapPart {
+        last_slot: None,
+        last_ledger_step: StreamingStep::Started,
+        last_pool_step: StreamingStep::Started,
+        last_cycle_step: StreamingStep::Started,
+        last_credits_step: StreamingStep::Started,
+        last_ops_step: StreamingStep::Started,
+        last_consensus_step: StreamingStep::Started,
+    }
+}
+
+/// Clears the resume journal and everything bootstrapped so far, including the disk ledger.
+/// Returns the message to send to restart the bootstrap from scratch.
+fn reset_bootstrap(
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    resume_journal: &mut BootstrapResumeJournal,
+) -> BootstrapClientMessage {
+    resume_journal.clear();
+    global_bootstrap_state.final_state.write().reset();
+    *global_bootstrap_state = GlobalBootstrapState::new(global_bootstrap_state.final_state.clone());
+    initial_bootstrap_message()
+}
+
+/// Checks that the bootstrapped final state matches the final state hash sent by the server at the end of the bootstrap.
+/// On success, the hash is kept in the final state.
+fn check_final_state_hash(
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    slot: Slot,
+    final_state_hash: Hash,
+) -> Result<(), BootstrapError> {
+    let mut write_final_state = global_bootstrap_state.final_state.write();
+    if write_final_state.slot != slot {
+        return Err(BootstrapError::FinalStateHashMismatch(format!(
+            "bootstrapped state is at slot {} but the server state is at slot {}",
+            write_final_state.slot, slot
+        )));
+    }
+    let computed_hash = write_final_state.compute_state_hash();
+    if computed_hash != final_state_hash {
+        return Err(BootstrapError::FinalStateHashMismatch(format!(
+            "bootstrapped state hash at slot {} is {} but the server state hash is {}",
+            slot, computed_hash, final_state_hash
+        )));
+    }
+    write_final_state.final_state_hash = computed_hash;
+    Ok(())
+}
+
 /// This function will send the starting point to receive a stream of the ledger and will receive and process each part until receive a `BootstrapServerMessage::FinalStateFinished` message from the server.
 /// `next_bootstrap_message` passed as parameter must be `BootstrapClientMessage::AskFinalStatePart` enum variant.
 /// `next_bootstrap_message` will be updated after receiving each part so that in case of connection lost we can restart from the last message we processed.
-async fn stream_final_state_and_consensus(
+pub(crate) async fn stream_final_state_and_consensus(
     cfg: &BootstrapConfig,
     client: &mut BootstrapClientBinder,
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
+    resume_journal: &mut BootstrapResumeJournal,
 ) -> Result<(), BootstrapError> {
     if let BootstrapClientMessage::AskBootstrapPart { .. } = &next_bootstrap_message {
         match tokio::time::timeout(
@@ -58,98 +279,64 @@ async fn stream_final_state_and_consensus(
                 Ok(Ok(msg)) => msg,
             };
             match msg {
-                BootstrapServerMessage::BootstrapPart {
-                    slot,
-                    ledger_part,
-                    async_pool_part,
-                    pos_cycle_part,
-                    pos_credits_part,
-                    exec_ops_part,
-                    final_state_changes,
-                    consensus_part,
-                    consensus_outdated_ids,
-                } => {
-                    // Set final state
-                    let mut write_final_state = global_bootstrap_state.final_state.write();
-                    let last_ledger_step = write_final_state.ledger.set_ledger_part(ledger_part)?;
-                    let last_pool_step =
-                        write_final_state.async_pool.set_pool_part(async_pool_part);
-                    let last_cycle_step = write_final_state
-                        .pos_state
-                        .set_cycle_history_part(pos_cycle_part);
-                    let last_credits_step = write_final_state
-                        .pos_state
-                        .set_deferred_credits_part(pos_credits_part);
-                    let last_ops_step = write_final_state
-                        .executed_ops
-                        .set_executed_ops_part(exec_ops_part);
-                    for (changes_slot, changes) in final_state_changes.iter() {
-                        write_final_state
-                            .ledger
-                            .apply_changes(changes.ledger_changes.clone(), *changes_slot);
-                        write_final_state
-                            .async_pool
-                            .apply_changes_unchecked(&changes.async_pool_changes);
-                        if !changes.pos_changes.is_empty() {
-                            write_final_state.pos_state.apply_changes(
-                                changes.pos_changes.clone(),
-                                *changes_slot,
-                                false,
-                            )?;
-                        }
-                        if !changes.executed_ops_changes.is_empty() {
-                            write_final_state
-                                .executed_ops
-                                .apply_changes(changes.executed_ops_changes.clone(), *changes_slot);
-                        }
-                    }
-                    write_final_state.slot = slot;
-
-                    // Set consensus blocks
-                    if let Some(graph) = global_bootstrap_state.graph.as_mut() {
-                        // Extend the final blocks with the received part
-                        graph.final_blocks.extend(consensus_part.final_blocks);
-                        // Remove every outdated block
-                        graph.final_blocks.retain(|block_export| {
-                            !consensus_outdated_ids.contains(&block_export.block.id)
-                        });
+                BootstrapServerMessage::BootstrapPart { .. } => {
+                    // The ledger data of the part is persisted by the disk ledger, the rest is journaled
+                    let journal_part = if resume_journal.is_enabled() {
+                        without_ledger_data(&msg)
                     } else {
-                        global_bootstrap_state.graph = Some(consensus_part);
-                    }
-                    let last_consensus_step = StreamingStep::Ongoing(
-                        // Note that this unwrap call is safe because of the above conditional statement
-                        global_bootstrap_state
-                            .graph
-                            .as_ref()
-                            .unwrap()
-                            .final_blocks
-                            .iter()
-                            .map(|b_export| b_export.block.id)
-                            .collect(),
-                    );
-
-                    // Set new message in case of disconnection
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
-                        last_slot: Some(slot),
-                        last_ledger_step,
-                        last_pool_step,
-                        last_cycle_step,
-                        last_credits_step,
-                        last_ops_step,
-                        last_consensus_step,
+                        None
                     };
+                    // Set new message in case of disconnection
+                    *next_bootstrap_message =
+                        apply_bootstrap_part(global_bootstrap_state, msg, None)?;
+                    // Record the applied part so that the bootstrap can be resumed from it
+                    if let (
+                        Some(part),
+                        BootstrapClientMessage::AskBootstrapPart {
+                            last_ledger_step, ..
+                        },
+                    ) = (journal_part, &*next_bootstrap_message)
+                    {
+                        let checkpoint = LedgerCheckpoint {
+                            last_ledger_step: last_ledger_step.clone(),
+                            ledger_hash: global_bootstrap_state
+                                .final_state
+                                .read()
+                                .ledger
+                                .get_ledger_hash(),
+                        };
+                        resume_journal.append(&part, &checkpoint);
+                    }
 
                     // Logs for an easier diagnostic if needed
                     debug!(
                         "client final state bootstrap cursors: {:?}",
                         next_bootstrap_message
                     );
-                    debug!(
-                        "client final state slot changes length: {}",
-                        final_state_changes.len()
-                    );
                 }
                 BootstrapServerMessage::BootstrapFinished => {
+                    // servers older than `final_state_hash_version` do not send their state hash
+                    warn!("bootstrap server did not send its final state hash, the bootstrapped state cannot be checked");
+                    let mut write_final_state = global_bootstrap_state.final_state.write();
+                    write_final_state.final_state_hash = write_final_state.compute_state_hash();
+                    drop(write_final_state);
+                    info!("State bootstrap complete");
+                    // Set next bootstrap message
+                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
+                    return Ok(());
+                }
+                BootstrapServerMessage::BootstrapFinishedWithHash {
+                    slot,
+                    final_state_hash,
+                } => {
+                    // Check that the rebuilt state matches the one of the server
+                    if let Err(err) =
+                        check_final_state_hash(global_bootstrap_state, slot, final_state_hash)
+                    {
+                        *next_bootstrap_message =
+                            reset_bootstrap(global_bootstrap_state, resume_journal);
+                        return Err(err);
+                    }
                     info!("State bootstrap complete");
                     // Set next bootstrap message
                     *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
@@ -157,15 +344,8 @@ async fn stream_final_state_and_consensus(
                 }
                 BootstrapServerMessage::SlotTooOld => {
                     info!("Slot is too old retry bootstrap from scratch");
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
-                        last_slot: None,
-                        last_ledger_step: StreamingStep::Started,
-                        last_pool_step: StreamingStep::Started,
-                        last_cycle_step: StreamingStep::Started,
-                        last_credits_step: StreamingStep::Started,
-                        last_ops_step: StreamingStep::Started,
-                        last_consensus_step: StreamingStep::Started,
-                    };
+                    *next_bootstrap_message =
+                        reset_bootstrap(global_bootstrap_state, resume_journal);
                     return Err(BootstrapError::GeneralError(String::from("Slot too old")));
                 }
                 BootstrapServerMessage::BootstrapError { error } => {
@@ -188,17 +368,13 @@ async fn stream_final_state_and_consensus(
     }
 }
 
-/// Gets the state from a bootstrap server (internal private function)
-/// needs to be CANCELLABLE
-async fn bootstrap_from_server(
+/// Reads the error possibly sent by the server at connection, then performs the handshake and syncs the clocks.
+/// Returns the clock compensation in milliseconds.
+async fn handshake_with_server(
     cfg: &BootstrapConfig,
     client: &mut BootstrapClientBinder,
-    next_bootstrap_message: &mut BootstrapClientMessage,
-    global_bootstrap_state: &mut GlobalBootstrapState,
     our_version: Version,
-) -> Result<(), BootstrapError> {
-    massa_trace!("bootstrap.lib.bootstrap_from_server", {});
-
+) -> Result<i64, BootstrapError> {
     // read error (if sent by the server)
     // client.next() is not cancel-safe but we drop the whole client object if cancelled => it's OK
     match tokio::time::timeout(cfg.read_error_timeout.into(), client.next()).await {
@@ -240,32 +416,33 @@ async fn bootstrap_from_server(
 
     // First, clock and version.
     // client.next() is not cancel-safe but we drop the whole client object if cancelled => it's OK
-    let server_time = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
-        Err(_) => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::TimedOut,
-                "bootstrap clock sync read timed out",
-            )
-            .into())
-        }
-        Ok(Err(e)) => return Err(e),
-        Ok(Ok(BootstrapServerMessage::BootstrapTime {
-            server_time,
-            version,
-        })) => {
-            if !our_version.is_compatible(&version) {
-                return Err(BootstrapError::IncompatibleVersionError(format!(
-                    "remote is running incompatible version: {} (local node version: {})",
-                    version, our_version
-                )));
+    let (server_time, server_version) =
+        match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap clock sync read timed out",
+                )
+                .into())
             }
-            server_time
-        }
-        Ok(Ok(BootstrapServerMessage::BootstrapError { error })) => {
-            return Err(BootstrapError::ReceivedError(error))
-        }
-        Ok(Ok(msg)) => return Err(BootstrapError::UnexpectedServerMessage(msg)),
-    };
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(BootstrapServerMessage::BootstrapTime {
+                server_time,
+                version,
+            })) => {
+                if !our_version.is_compatible(&version) {
+                    return Err(BootstrapError::IncompatibleVersionError(format!(
+                        "remote is running incompatible version: {} (local node version: {})",
+                        version, our_version
+                    )));
+                }
+                (server_time, version)
+            }
+            Ok(Ok(BootstrapServerMessage::BootstrapError { error })) => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            Ok(Ok(msg)) => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+        };
 
     let recv_time_uncompensated = MassaTime::now(0)?;
 
@@ -299,8 +476,74 @@ async fn bootstrap_from_server(
         0
     };
 
-    global_bootstrap_state.compensation_millis = compensation_millis;
+    // negotiate the compression of the next server messages, older servers not knowing the request
+    if cfg.bootstrap_compression != BootstrapCompression::None
+        && server_version.is_at_least(&cfg.compression_version)
+    {
+        match send_client_message(
+            &BootstrapClientMessage::AskBootstrapCompression {
+                compression: cfg.bootstrap_compression,
+            },
+            client,
+            cfg.write_timeout.into(),
+            cfg.read_timeout.into(),
+            "bootstrap compression negotiation timed out",
+        )
+        .await?
+        {
+            BootstrapServerMessage::BootstrapCompression { compression } => {
+                if compression != BootstrapCompression::None
+                    && compression != cfg.bootstrap_compression
+                {
+                    return Err(BootstrapError::GeneralError(format!(
+                        "server chose compression {:?} while {:?} was requested",
+                        compression, cfg.bootstrap_compression
+                    )));
+                }
+                client.set_compression(compression);
+            }
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            msg => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+        }
+    }
+    Ok(compensation_millis)
+}
 
+/// Gets the state from a bootstrap server (internal private function)
+/// needs to be CANCELLABLE
+async fn bootstrap_from_server(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    next_bootstrap_message: &mut BootstrapClientMessage,
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    resume_journal: &mut BootstrapResumeJournal,
+    our_version: Version,
+) -> Result<(), BootstrapError> {
+    massa_trace!("bootstrap.lib.bootstrap_from_server", {});
+
+    global_bootstrap_state.compensation_millis =
+        handshake_with_server(cfg, client, our_version).await?;
+
+    exchange_bootstrap_messages(
+        cfg,
+        client,
+        next_bootstrap_message,
+        global_bootstrap_state,
+        resume_journal,
+    )
+    .await
+}
+
+/// Asks the server for the data to bootstrap depending on the last message we sent, until the bootstrap succeeds
+async fn exchange_bootstrap_messages(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    next_bootstrap_message: &mut BootstrapClientMessage,
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    resume_journal: &mut BootstrapResumeJournal,
+) -> Result<(), BootstrapError> {
     let write_timeout: std::time::Duration = cfg.write_timeout.into();
     // Loop to ask data to the server depending on the last message we sent
     loop {
@@ -311,6 +554,7 @@ async fn bootstrap_from_server(
                     client,
                     next_bootstrap_message,
                     global_bootstrap_state,
+                    resume_journal,
                 )
                 .await?;
             }
@@ -349,6 +593,13 @@ async fn bootstrap_from_server(
             BootstrapClientMessage::BootstrapError { error: _ } => {
                 panic!("The next message to send shouldn't be BootstrapError");
             }
+            BootstrapClientMessage::AskLedgerRangePart { .. }
+            | BootstrapClientMessage::AskFinalStateHash { .. } => {
+                panic!("The next message to send shouldn't be a parallel bootstrap message");
+            }
+            BootstrapClientMessage::AskBootstrapCompression { .. } => {
+                panic!("The next message to send shouldn't be a compression request");
+            }
         };
     }
     info!("Successful bootstrap");
@@ -385,7 +636,15 @@ async fn connect_to_server(
         .get_connector(bootstrap_config.connect_timeout)
         .await?; // cancellable
     let socket = connector.connect(*addr).await?; // cancellable
-    Ok(BootstrapClientBinder::new(
+    Ok(new_client_binder(bootstrap_config, socket, pub_key))
+}
+
+fn new_client_binder(
+    bootstrap_config: &BootstrapConfig,
+    socket: Duplex,
+    pub_key: &PublicKey,
+) -> BootstrapClientBinder {
+    BootstrapClientBinder::new(
         socket,
         *pub_key,
         bootstrap_config.max_bytes_read_write,
@@ -411,7 +670,353 @@ async fn connect_to_server(
         bootstrap_config.max_credits_length,
         bootstrap_config.max_executed_ops_length,
         bootstrap_config.max_ops_changes_length,
+    )
+}
+
+/// Splits the ledger into `count` disjoint ranges of addresses `[start, end)`, according to the first byte of the addresses.
+/// `count` must be lower than or equal to 256.
+fn ledger_ranges(count: usize) -> Vec<(Option<Address>, Option<Address>)> {
+    let bounds: Vec<Address> = (1..count)
+        .map(|index| {
+            let mut bytes = [0u8; ADDRESS_SIZE_BYTES];
+            bytes[0] = (256 * index / count) as u8;
+            Address::from_bytes(&bytes)
+        })
+        .collect();
+    (0..count)
+        .map(|index| {
+            (
+                index.checked_sub(1).map(|previous| bounds[previous]),
+                bounds.get(index).copied(),
+            )
+        })
+        .collect()
+}
+
+/// Bounds `[start, end)` of a bootstrapped ledger range, with the slot it is attached to
+pub(crate) type LedgerRangeSlot = (Option<Address>, Option<Address>, Slot);
+
+/// Keeps the ledger changes of `changes_slot` that the range of their address does not contain yet.
+/// A range streamed at a later slot already holds them, and they must not be applied twice:
+/// setting an existing entry again would add its hash to the ledger hash a second time.
+pub(crate) fn filter_reconciled_changes(
+    range_slots: &[LedgerRangeSlot],
+    changes_slot: Slot,
+    mut ledger_changes: LedgerChanges,
+) -> LedgerChanges {
+    ledger_changes.0.retain(|address, _| {
+        range_slots.iter().any(|(start, end, range_slot)| {
+            start.map_or(true, |start| *address >= start)
+                && end.map_or(true, |end| *address < end)
+                && changes_slot > *range_slot
+        })
+    });
+    ledger_changes
+}
+
+/// Applies a `BootstrapServerMessage::BootstrapPart` received while streaming a ledger range.
+/// The changes sent along with the part only concern the ledger entries of the range.
+/// When reconciling the ranges, `range_slots` holds the slot of each range, and only the changes
+/// following it are applied to the range.
+/// Returns the last received ledger key.
+fn apply_ledger_range_part(
+    final_state: &Arc<RwLock<FinalState>>,
+    part: BootstrapServerMessage,
+    range_slots: Option<&[LedgerRangeSlot]>,
+) -> Result<StreamingStep<Vec<u8>>, BootstrapError> {
+    let (ledger_part, final_state_changes) = match part {
+        BootstrapServerMessage::BootstrapPart {
+            ledger_part,
+            final_state_changes,
+            ..
+        } => (ledger_part, final_state_changes),
+        other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+    };
+    let mut write_final_state = final_state.write();
+    let last_ledger_step = write_final_state.ledger.set_ledger_part(ledger_part)?;
+    for (changes_slot, changes) in final_state_changes {
+        let ledger_changes = match range_slots {
+            Some(range_slots) => {
+                filter_reconciled_changes(range_slots, changes_slot, changes.ledger_changes)
+            }
+            None => changes.ledger_changes,
+        };
+        write_final_state
+            .ledger
+            .apply_changes(ledger_changes, changes_slot);
+    }
+    Ok(last_ledger_step)
+}
+
+/// Streams the ledger entries of the addresses in `[start, end)` from a server,
+/// applying the ledger changes of the range that happened after `last_slot`,
+/// or after the slot of their range in `range_slots` when reconciling the ranges.
+/// Returns the slot the bootstrapped range is attached to.
+#[allow(clippy::too_many_arguments)]
+async fn bootstrap_ledger_range(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    final_state: Arc<RwLock<FinalState>>,
+    last_slot: Option<Slot>,
+    mut last_ledger_step: StreamingStep<Vec<u8>>,
+    start: Option<Address>,
+    end: Option<Address>,
+    range_slots: Option<&[LedgerRangeSlot]>,
+) -> Result<Slot, BootstrapError> {
+    match tokio::time::timeout(
+        cfg.write_timeout.into(),
+        client.send(&BootstrapClientMessage::AskLedgerRangePart {
+            last_slot,
+            last_ledger_step: last_ledger_step.clone(),
+            start,
+            end,
+        }),
+    )
+    .await
+    {
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "bootstrap ask ledger range part send timed out",
+        )
+        .into()),
+        Ok(Err(e)) => Err(e),
+        Ok(Ok(_)) => Ok(()),
+    }?;
+    loop {
+        let msg = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "ledger range bootstrap read timed out",
+                )
+                .into());
+            }
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(msg)) => msg,
+        };
+        match msg {
+            BootstrapServerMessage::BootstrapPart { .. } => {
+                last_ledger_step = apply_ledger_range_part(&final_state, msg, range_slots)?;
+                debug!(
+                    "client ledger range bootstrap cursor: {:?}",
+                    last_ledger_step
+                );
+            }
+            BootstrapServerMessage::BootstrapFinishedWithHash { slot, .. } => return Ok(slot),
+            BootstrapServerMessage::SlotTooOld => {
+                return Err(BootstrapError::GeneralError(String::from("Slot too old")))
+            }
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+        }
+    }
+}
+
+/// Gets the state from several bootstrap servers in parallel:
+/// * the servers are connected concurrently, the ones failing to connect or to handshake being left out
+/// * each remaining server streams a disjoint range of the ledger
+/// * the server whose range is attached to the most recent slot then streams the ledger changes
+///   bringing every range to a common slot, followed by the rest of the final state and the consensus graph
+/// * the hash of the bootstrapped final state must be confirmed by a majority of the servers answering with a hash
+async fn parallel_bootstrap(
+    cfg: &BootstrapConfig,
+    establisher: &mut Establisher,
+    servers: &[(SocketAddr, PublicKey)],
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    resume_journal: &mut BootstrapResumeJournal,
+    our_version: Version,
+) -> Result<(), BootstrapError> {
+    massa_trace!("bootstrap.lib.parallel_bootstrap", {});
+    let mut connectors = Vec::with_capacity(servers.len());
+    for _ in servers {
+        connectors.push(establisher.get_connector(cfg.connect_timeout).await?);
+    }
+    let connections = join_all(servers.iter().zip(connectors).map(
+        |((addr, pub_key), mut connector)| async move {
+            let socket = connector.connect(*addr).await?; // cancellable
+            let mut client = new_client_binder(cfg, socket, pub_key);
+            let compensation = handshake_with_server(cfg, &mut client, our_version).await?;
+            Ok::<_, BootstrapError>((client, compensation))
+        },
+    ))
+    .await;
+    let mut addrs = Vec::with_capacity(servers.len());
+    let mut clients = Vec::with_capacity(servers.len());
+    let mut compensations = Vec::with_capacity(servers.len());
+    for ((addr, _), connection) in servers.iter().zip(connections) {
+        match connection {
+            Ok((client, compensation)) => {
+                info!("Start bootstrapping a ledger range from {}", addr);
+                addrs.push(*addr);
+                clients.push(client);
+                compensations.push(compensation);
+            }
+            Err(err) => warn!(
+                "Could not connect to bootstrap server {}: {}. Bootstrapping without it.",
+                addr, err
+            ),
+        }
+    }
+    if clients.is_empty() {
+        return Err(BootstrapError::GeneralError(
+            "no bootstrap server could be connected".into(),
+        ));
+    }
+
+    // Stream the ledger ranges in parallel
+    let ranges = ledger_ranges(clients.len());
+    let range_slots = try_join_all(clients.iter_mut().zip(ranges.iter()).map(
+        |(client, (start, end))| {
+            bootstrap_ledger_range(
+                cfg,
+                client,
+                global_bootstrap_state.final_state.clone(),
+                None,
+                StreamingStep::Started,
+                *start,
+                *end,
+                None,
+            )
+        },
     ))
+    .await?;
+    let (main_index, min_slot) = match (
+        range_slots
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, slot)| **slot),
+        range_slots.iter().min(),
+    ) {
+        (Some((main_index, _)), Some(min_slot)) => (main_index, *min_slot),
+        _ => {
+            return Err(BootstrapError::GeneralError(
+                "no ledger range was bootstrapped".into(),
+            ))
+        }
+    };
+    global_bootstrap_state.compensation_millis = compensations[main_index];
+
+    // Bring every range to a common slot with the ledger changes of the most advanced server,
+    // each range only getting the changes following its own slot
+    let range_slots: Vec<LedgerRangeSlot> = ranges
+        .into_iter()
+        .zip(range_slots)
+        .map(|((start, end), slot)| (start, end, slot))
+        .collect();
+    let ledger_slot = bootstrap_ledger_range(
+        cfg,
+        &mut clients[main_index],
+        global_bootstrap_state.final_state.clone(),
+        Some(min_slot),
+        StreamingStep::Finished(None),
+        None,
+        None,
+        Some(&range_slots),
+    )
+    .await?;
+    info!("Ledger ranges reconciled at slot {}", ledger_slot);
+
+    // Stream the rest of the final state and the consensus graph.
+    // The parts do not contain the ledger and can't be replayed on their own: they are not journaled.
+    resume_journal.disable();
+    let mut next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
+        last_slot: Some(ledger_slot),
+        last_ledger_step: StreamingStep::Finished(None),
+        last_pool_step: StreamingStep::Started,
+        last_cycle_step: StreamingStep::Started,
+        last_credits_step: StreamingStep::Started,
+        last_ops_step: StreamingStep::Started,
+        last_consensus_step: StreamingStep::Started,
+    };
+    stream_final_state_and_consensus(
+        cfg,
+        &mut clients[main_index],
+        &mut next_bootstrap_message,
+        global_bootstrap_state,
+        resume_journal,
+    )
+    .await?;
+
+    // Check that a majority of the servers answering with a hash agree on the bootstrapped final state
+    let (slot, final_state_hash) = {
+        let final_state_read = global_bootstrap_state.final_state.read();
+        (final_state_read.slot, final_state_read.final_state_hash)
+    };
+    let mut agreeing_servers = 1;
+    let mut responsive_servers = 1;
+    for (index, client) in clients.iter_mut().enumerate() {
+        if index == main_index {
+            continue;
+        }
+        match send_client_message(
+            &BootstrapClientMessage::AskFinalStateHash { slot },
+            client,
+            cfg.write_timeout.into(),
+            cfg.read_timeout.into(),
+            "ask final state hash timed out",
+        )
+        .await
+        {
+            Ok(BootstrapServerMessage::FinalStateHash {
+                final_state_hash: Some(hash),
+                ..
+            }) => {
+                responsive_servers += 1;
+                if hash == final_state_hash {
+                    agreeing_servers += 1;
+                } else {
+                    warn!(
+                        "Bootstrap server {} does not confirm the final state hash at slot {}",
+                        addrs[index], slot
+                    );
+                }
+            }
+            Ok(BootstrapServerMessage::FinalStateHash {
+                final_state_hash: None,
+                ..
+            }) => warn!(
+                "Bootstrap server {} does not know its final state hash at slot {}",
+                addrs[index], slot
+            ),
+            Ok(other) => warn!(
+                "Unexpected answer of bootstrap server {} to the final state hash request: {:?}",
+                addrs[index], other
+            ),
+            Err(err) => warn!(
+                "Could not get the final state hash of bootstrap server {}: {}",
+                addrs[index], err
+            ),
+        }
+    }
+    if agreeing_servers * 2 <= responsive_servers {
+        return Err(BootstrapError::FinalStateHashMismatch(format!(
+            "only {} of the {} responsive bootstrap servers confirm the final state hash {} at slot {}",
+            agreeing_servers, responsive_servers, final_state_hash, slot
+        )));
+    }
+
+    // Get the peers and end the bootstrap sessions
+    exchange_bootstrap_messages(
+        cfg,
+        &mut clients[main_index],
+        &mut next_bootstrap_message,
+        global_bootstrap_state,
+        resume_journal,
+    )
+    .await?;
+    for (index, client) in clients.iter_mut().enumerate() {
+        if index != main_index {
+            // We allow unused result because the bootstrap already succeeded.
+            let _ = tokio::time::timeout(
+                cfg.write_timeout.into(),
+                client.send(&BootstrapClientMessage::BootstrapSuccess),
+            )
+            .await;
+        }
+    }
+    Ok(())
 }
 
 /// Gets the state from a bootstrap server
@@ -441,6 +1046,7 @@ pub async fn get_state(
                 })?;
             // create the initial cycle of PoS cycle_history
             final_state_guard.pos_state.create_initial_cycle();
+            final_state_guard.final_state_hash = final_state_guard.compute_state_hash();
         }
         return Ok(GlobalBootstrapState::new(final_state));
     }
@@ -453,17 +1059,68 @@ pub async fn get_state(
     }
     let mut shuffled_list = bootstrap_config.bootstrap_list.clone();
     shuffled_list.shuffle(&mut StdRng::from_entropy());
-    let mut next_bootstrap_message: BootstrapClientMessage =
-        BootstrapClientMessage::AskBootstrapPart {
-            last_slot: None,
-            last_ledger_step: StreamingStep::Started,
-            last_pool_step: StreamingStep::Started,
-            last_cycle_step: StreamingStep::Started,
-            last_credits_step: StreamingStep::Started,
-            last_ops_step: StreamingStep::Started,
-            last_consensus_step: StreamingStep::Started,
-        };
+    let mut resume_journal = BootstrapResumeJournal::new(bootstrap_config);
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state.clone());
+    let mut next_bootstrap_message = match resume_from_journal(
+        &resume_journal,
+        &mut global_bootstrap_state,
+    ) {
+        Ok(Some(resume_message)) => {
+            info!("Resuming interrupted bootstrap from the local resume journal");
+            resume_message
+        }
+        Ok(None) => initial_bootstrap_message(),
+        Err(err) => {
+            warn!(
+                "Could not resume bootstrap from the local resume journal: {}. Bootstrapping from scratch.",
+                err
+            );
+            reset_bootstrap(&mut global_bootstrap_state, &mut resume_journal)
+        }
+    };
+    let parallel_count = bootstrap_config
+        .parallel_bootstrap_servers
+        .min(shuffled_list.len())
+        .min(256);
+    if parallel_count > 1
+        && matches!(
+            next_bootstrap_message,
+            BootstrapClientMessage::AskBootstrapPart {
+                last_slot: None,
+                ..
+            }
+        )
+    {
+        info!(
+            "Start bootstrapping from {} servers in parallel",
+            parallel_count
+        );
+        match parallel_bootstrap(
+            bootstrap_config,
+            &mut establisher,
+            &shuffled_list[..parallel_count],
+            &mut global_bootstrap_state,
+            &mut resume_journal,
+            version,
+        )
+        .await
+        {
+            Ok(()) => {
+                resume_journal.clear();
+                return Ok(global_bootstrap_state);
+            }
+            Err(e) => {
+                warn!(
+                    "Error while bootstrapping in parallel: {}. Bootstrapping from one server at a time.",
+                    e
+                );
+                // the parallel bootstrap disabled the journal and left ledger ranges on disk
+                resume_journal = BootstrapResumeJournal::new(bootstrap_config);
+                next_bootstrap_message =
+                    reset_bootstrap(&mut global_bootstrap_state, &mut resume_journal);
+            }
+        }
+    }
     loop {
         for (addr, pub_key) in shuffled_list.iter() {
             if let Some(end) = end_timestamp {
@@ -474,7 +1131,7 @@ pub async fn get_state(
             info!("Start bootstrapping from {}", addr);
             match connect_to_server(&mut establisher, bootstrap_config, addr, pub_key).await {
                 Ok(mut client) => {
-                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state,version)
+                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state, &mut resume_journal, version)
                     .await  // cancellable
                     {
                         Err(BootstrapError::ReceivedError(error)) => warn!("Error received from bootstrap server: {}", error),
@@ -484,6 +1141,7 @@ pub async fn get_state(
                             let _ = tokio::time::timeout(bootstrap_config.write_error_timeout.into(), client.send(&BootstrapClientMessage::BootstrapError { error: e.to_string() })).await;
                         }
                         Ok(()) => {
+                            resume_journal.clear();
                             return Ok(global_bootstrap_state)
                         }
                     }
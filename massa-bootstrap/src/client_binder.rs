@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::compression::BootstrapCompression;
 use crate::error::BootstrapError;
 use crate::establisher::types::Duplex;
 use crate::messages::{
@@ -25,6 +26,7 @@ pub struct BootstrapClientBinder {
     duplex: Resource<Duplex, StandardClock>,
     prev_message: Option<Hash>,
     version_serializer: VersionSerializer,
+    compression: BootstrapCompression,
     endorsement_count: u32,
     max_advertise_length: u32,
     max_bootstrap_blocks: u32,
@@ -90,6 +92,7 @@ impl BootstrapClientBinder {
             duplex: <Limiter>::new(limit).limit(duplex),
             prev_message: None,
             version_serializer: VersionSerializer::new(),
+            compression: BootstrapCompression::None,
             endorsement_count,
             max_advertise_length,
             max_bootstrap_blocks,
@@ -116,6 +119,14 @@ impl BootstrapClientBinder {
 }
 
 impl BootstrapClientBinder {
+    /// Sets the compression of the next messages received,
+    /// to be called once the server announced it in `BootstrapServerMessage::BootstrapCompression`
+    pub fn set_compression(&mut self, compression: BootstrapCompression) {
+        self.compression = compression;
+        self.size_field_len =
+            u32::be_bytes_min_length(compression.max_frame_size(self.max_bootstrap_message_size));
+    }
+
     /// Performs a handshake. Should be called after connection
     /// NOT cancel-safe
     pub async fn handshake(&mut self, version: Version) -> Result<(), BootstrapError> {
@@ -150,7 +161,12 @@ impl BootstrapClientBinder {
         let msg_len = {
             let mut msg_len_bytes = vec![0u8; self.size_field_len];
             self.duplex.read_exact(&mut msg_len_bytes[..]).await?;
-            u32::from_be_bytes_min(&msg_len_bytes, self.max_bootstrap_message_size)?.0
+            u32::from_be_bytes_min(
+                &msg_len_bytes,
+                self.compression
+                    .max_frame_size(self.max_bootstrap_message_size),
+            )?
+            .0
         };
 
         // read message, check signature and check signature of the message sent just before then deserialize it
@@ -177,13 +193,17 @@ impl BootstrapClientBinder {
             self.max_ops_changes_length,
         );
         let message = {
+            let mut received_bytes = vec![0u8; msg_len as usize];
+            self.duplex.read_exact(&mut received_bytes[..]).await?;
+            // the signature covers the uncompressed bytes
+            let msg_bytes = self
+                .compression
+                .decompress(received_bytes, self.max_bootstrap_message_size)?;
             if let Some(prev_message) = self.prev_message {
                 self.prev_message = Some(Hash::compute_from(&sig.to_bytes()));
-                let mut sig_msg_bytes = vec![0u8; HASH_SIZE_BYTES + (msg_len as usize)];
-                sig_msg_bytes[..HASH_SIZE_BYTES].copy_from_slice(prev_message.to_bytes());
-                self.duplex
-                    .read_exact(&mut sig_msg_bytes[HASH_SIZE_BYTES..])
-                    .await?;
+                let mut sig_msg_bytes = Vec::with_capacity(HASH_SIZE_BYTES + msg_bytes.len());
+                sig_msg_bytes.extend(prev_message.to_bytes());
+                sig_msg_bytes.extend(&msg_bytes);
                 let msg_hash = Hash::compute_from(&sig_msg_bytes);
                 self.remote_pubkey.verify_signature(&msg_hash, &sig)?;
                 let (_, msg) = message_deserializer
@@ -192,12 +212,10 @@ impl BootstrapClientBinder {
                 msg
             } else {
                 self.prev_message = Some(Hash::compute_from(&sig.to_bytes()));
-                let mut sig_msg_bytes = vec![0u8; msg_len as usize];
-                self.duplex.read_exact(&mut sig_msg_bytes[..]).await?;
-                let msg_hash = Hash::compute_from(&sig_msg_bytes);
+                let msg_hash = Hash::compute_from(&msg_bytes);
                 self.remote_pubkey.verify_signature(&msg_hash, &sig)?;
                 let (_, msg) = message_deserializer
-                    .deserialize::<DeserializeError>(&sig_msg_bytes[..])
+                    .deserialize::<DeserializeError>(&msg_bytes[..])
                     .map_err(|err| BootstrapError::GeneralError(format!("{}", err)))?;
                 msg
             }
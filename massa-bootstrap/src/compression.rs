@@ -0,0 +1,76 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Compression of the messages sent by the bootstrap server.
+//!
+//! Once the clocks are synchronized, a client talking to a server running at least `BootstrapConfig::compression_version`
+//! requests a compression algorithm with `BootstrapClientMessage::AskBootstrapCompression`,
+//! and the server answers with the algorithm it will actually use in `BootstrapServerMessage::BootstrapCompression`.
+//! All the server messages following that answer are then compressed by the binders.
+//! Signatures are always computed on the uncompressed message bytes,
+//! and the maximum message size applies to the uncompressed bytes: a compressed frame may be slightly larger.
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::Deserialize;
+
+use crate::error::BootstrapError;
+
+/// Compression algorithm applied to the bootstrap server messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+pub enum BootstrapCompression {
+    /// messages are sent as is
+    None = 0u8,
+    /// messages are compressed with LZ4
+    Lz4 = 1u8,
+}
+
+impl BootstrapCompression {
+    /// Maximal size of a frame carrying a message of at most `max_message_size` uncompressed bytes
+    pub(crate) fn max_frame_size(&self, max_message_size: u32) -> u32 {
+        match self {
+            BootstrapCompression::None => max_message_size,
+            // the compressed block is prepended with the uncompressed size, as a u32
+            BootstrapCompression::Lz4 => {
+                lz4_flex::block::get_maximum_output_size(max_message_size as usize)
+                    .saturating_add(std::mem::size_of::<u32>())
+                    .try_into()
+                    .unwrap_or(u32::MAX)
+            }
+        }
+    }
+
+    /// Compresses serialized message bytes
+    pub(crate) fn compress(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            BootstrapCompression::None => bytes.to_vec(),
+            BootstrapCompression::Lz4 => lz4_flex::compress_prepend_size(bytes),
+        }
+    }
+
+    /// Decompresses received message bytes
+    ///
+    /// # Arguments
+    /// * `max_size`: maximal size of the decompressed bytes, checked before decompressing
+    pub(crate) fn decompress(
+        &self,
+        bytes: Vec<u8>,
+        max_size: u32,
+    ) -> Result<Vec<u8>, BootstrapError> {
+        match self {
+            BootstrapCompression::None => Ok(bytes),
+            BootstrapCompression::Lz4 => {
+                let (size, compressed) = lz4_flex::block::uncompressed_size(&bytes)
+                    .map_err(|err| BootstrapError::DecompressionError(err.to_string()))?;
+                if size > max_size as usize {
+                    return Err(BootstrapError::DecompressionError(format!(
+                        "decompressed message size {} exceeds the maximum of {}",
+                        size, max_size
+                    )));
+                }
+                lz4_flex::block::decompress(compressed, size)
+                    .map_err(|err| BootstrapError::DecompressionError(err.to_string()))
+            }
+        }
+    }
+}
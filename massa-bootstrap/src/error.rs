@@ -50,4 +50,12 @@ pub enum BootstrapError {
     IncompatibleVersionError(String),
     /// Received error: {0}
     ReceivedError(String),
+    /// final state hash mismatch: {0}
+    FinalStateHashMismatch(String),
+    /// IP {0} is blacklisted
+    BlackListed(String),
+    /// a whitelist exists and IP {0} is not whitelisted
+    WhiteListed(String),
+    /// decompression error: {0}
+    DecompressionError(String),
 }
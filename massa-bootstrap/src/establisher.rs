@@ -14,13 +14,9 @@ pub mod types {
 #[cfg(not(test))]
 /// Connection types
 pub mod types {
-    use crate::tools::normalize_ip;
+    use crate::white_black_list::SharedWhiteBlackList;
     use massa_time::MassaTime;
-    use std::{
-        collections::HashSet,
-        io,
-        net::{IpAddr, SocketAddr},
-    };
+    use std::{io, net::SocketAddr};
     use tokio::{
         net::{TcpListener, TcpStream},
         time::timeout,
@@ -42,21 +38,13 @@ pub mod types {
         /// Accepts a new incoming connection from this listener.
         pub async fn accept(
             &mut self,
-            whitelist: &Option<HashSet<IpAddr>>,
-            blacklist: &Option<HashSet<IpAddr>>,
+            white_black_list: &SharedWhiteBlackList,
         ) -> io::Result<(Duplex, SocketAddr)> {
             // accept
             let (sock, mut remote_addr) = self.0.accept().await?;
-            let ip = normalize_ip(remote_addr.ip());
-            if let Some(blacklist) = blacklist && blacklist.contains(&ip) {
-                return Err(io::Error::new(io::ErrorKind::Other, "IP is blacklisted"));
-            }
-            if let Some(whitelist) = whitelist && !whitelist.contains(&ip) {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "A whitelist exists and the IP is not whitelisted",
-                ));
-            }
+            white_black_list
+                .is_ip_allowed(&remote_addr.ip())
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
             // normalize address
             remote_addr.set_ip(remote_addr.ip().to_canonical());
             Ok((sock, remote_addr))
@@ -3,6 +3,8 @@
 //!
 //! At start up, if now is after genesis timestamp,
 //! the node will bootstrap from one of the provided bootstrap servers.
+//! When `parallel_bootstrap_servers` is greater than 1, disjoint ranges of the ledger
+//! are streamed from several servers in parallel, falling back to a single server on failure.
 //!
 //! On server side, the server will query consensus for the graph and the ledger,
 //! execution for execution related data and network for the peer list.
@@ -22,21 +24,28 @@ use std::sync::Arc;
 
 mod client;
 mod client_binder;
+mod compression;
 mod error;
 mod establisher;
 mod messages;
+mod resume;
 mod server;
 mod server_binder;
+mod server_status;
 mod settings;
 mod tools;
+mod white_black_list;
 pub use client::get_state;
+pub use compression::BootstrapCompression;
 pub use establisher::types;
 pub use messages::{
     BootstrapClientMessage, BootstrapClientMessageDeserializer, BootstrapClientMessageSerializer,
     BootstrapServerMessage, BootstrapServerMessageDeserializer, BootstrapServerMessageSerializer,
 };
 pub use server::{start_bootstrap_server, BootstrapManager};
+pub use server_status::SharedBootstrapServerStatus;
 pub use settings::BootstrapConfig;
+pub use white_black_list::SharedWhiteBlackList;
 
 #[cfg(test)]
 pub mod tests;
@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::compression::BootstrapCompression;
 use massa_async_pool::{
     AsyncMessage, AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer,
     AsyncPoolDeserializer, AsyncPoolSerializer,
@@ -9,7 +10,9 @@ use massa_consensus_exports::bootstrapable_graph::{
 };
 use massa_executed_ops::{ExecutedOpsDeserializer, ExecutedOpsSerializer};
 use massa_final_state::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
 use massa_ledger_exports::{KeyDeserializer, KeySerializer};
+use massa_models::address::{Address, AddressDeserializer, AddressSerializer};
 use massa_models::block::{BlockId, BlockIdDeserializer, BlockIdSerializer};
 use massa_models::operation::OperationId;
 use massa_models::prehash::PreHashSet;
@@ -81,8 +84,15 @@ pub enum BootstrapServerMessage {
         /// Outdated block ids in the current consensus graph bootstrap
         consensus_outdated_ids: PreHashSet<BlockId>,
     },
-    /// Message sent when the final state and consensus bootstrap are finished
+    /// Message sent when the final state and consensus bootstrap are finished, to clients older than `BootstrapConfig::final_state_hash_version`
     BootstrapFinished,
+    /// Message sent when the final state and consensus bootstrap are finished
+    BootstrapFinishedWithHash {
+        /// Slot of the bootstrapped final state
+        slot: Slot,
+        /// Hash of the final state at the output of `slot`, used by the client to check the bootstrapped state
+        final_state_hash: Hash,
+    },
     /// Slot sent to get state changes is too old
     SlotTooOld,
     /// Bootstrap error
@@ -90,6 +100,18 @@ pub enum BootstrapServerMessage {
         /// Error message
         error: String,
     },
+    /// Final state hash of the server at a given slot
+    FinalStateHash {
+        /// Slot of the final state
+        slot: Slot,
+        /// Hash of the final state at the output of `slot`, `None` if the server does not know it
+        final_state_hash: Option<Hash>,
+    },
+    /// Answer to `BootstrapClientMessage::AskBootstrapCompression`
+    BootstrapCompression {
+        /// The compression applied by the server to the following messages
+        compression: BootstrapCompression,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -101,6 +123,9 @@ enum MessageServerTypeId {
     FinalStateFinished = 3u32,
     SlotTooOld = 4u32,
     BootstrapError = 5u32,
+    FinalStateHash = 6u32,
+    FinalStateFinishedWithHash = 7u32,
+    BootstrapCompression = 8u32,
 }
 
 /// Serializer for `BootstrapServerMessage`
@@ -119,6 +144,8 @@ pub struct BootstrapServerMessageSerializer {
     opt_pos_cycle_serializer: OptionSerializer<CycleInfo, CycleInfoSerializer>,
     pos_credits_serializer: DeferredCreditsSerializer,
     exec_ops_serializer: ExecutedOpsSerializer,
+    hash_serializer: HashSerializer,
+    opt_hash_serializer: OptionSerializer<Hash, HashSerializer>,
 }
 
 impl Default for BootstrapServerMessageSerializer {
@@ -145,6 +172,8 @@ impl BootstrapServerMessageSerializer {
             opt_pos_cycle_serializer: OptionSerializer::new(CycleInfoSerializer::new()),
             pos_credits_serializer: DeferredCreditsSerializer::new(),
             exec_ops_serializer: ExecutedOpsSerializer::new(),
+            hash_serializer: HashSerializer::new(),
+            opt_hash_serializer: OptionSerializer::new(HashSerializer::new()),
         }
     }
 }
@@ -235,6 +264,17 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
                 self.u32_serializer
                     .serialize(&u32::from(MessageServerTypeId::FinalStateFinished), buffer)?;
             }
+            BootstrapServerMessage::BootstrapFinishedWithHash {
+                slot,
+                final_state_hash,
+            } => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageServerTypeId::FinalStateFinishedWithHash),
+                    buffer,
+                )?;
+                self.slot_serializer.serialize(slot, buffer)?;
+                self.hash_serializer.serialize(final_state_hash, buffer)?;
+            }
             BootstrapServerMessage::SlotTooOld => {
                 self.u32_serializer
                     .serialize(&u32::from(MessageServerTypeId::SlotTooOld), buffer)?;
@@ -250,6 +290,23 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
                 )?;
                 buffer.extend(error.as_bytes())
             }
+            BootstrapServerMessage::FinalStateHash {
+                slot,
+                final_state_hash,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageServerTypeId::FinalStateHash), buffer)?;
+                self.slot_serializer.serialize(slot, buffer)?;
+                self.opt_hash_serializer
+                    .serialize(final_state_hash, buffer)?;
+            }
+            BootstrapServerMessage::BootstrapCompression { compression } => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageServerTypeId::BootstrapCompression),
+                    buffer,
+                )?;
+                buffer.push(u8::from(*compression));
+            }
         }
         Ok(())
     }
@@ -272,6 +329,8 @@ pub struct BootstrapServerMessageDeserializer {
     opt_pos_cycle_deserializer: OptionDeserializer<CycleInfo, CycleInfoDeserializer>,
     pos_credits_deserializer: DeferredCreditsDeserializer,
     exec_ops_deserializer: ExecutedOpsDeserializer,
+    hash_deserializer: HashDeserializer,
+    opt_hash_deserializer: OptionDeserializer<Hash, HashDeserializer>,
 }
 
 impl BootstrapServerMessageDeserializer {
@@ -365,6 +424,8 @@ impl BootstrapServerMessageDeserializer {
                 max_executed_ops_length,
                 max_operations_per_block as u64,
             ),
+            hash_deserializer: HashDeserializer::new(),
+            opt_hash_deserializer: OptionDeserializer::new(HashDeserializer::new()),
         }
     }
 }
@@ -504,6 +565,24 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
                 MessageServerTypeId::FinalStateFinished => {
                     Ok((input, BootstrapServerMessage::BootstrapFinished))
                 }
+                MessageServerTypeId::FinalStateFinishedWithHash => context(
+                    "Failed BootstrapFinishedWithHash deserialization",
+                    tuple((
+                        context("Failed slot deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed final_state_hash deserialization", |input| {
+                            self.hash_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(
+                    |(slot, final_state_hash)| BootstrapServerMessage::BootstrapFinishedWithHash {
+                        slot,
+                        final_state_hash,
+                    },
+                )
+                .parse(input),
                 MessageServerTypeId::SlotTooOld => Ok((input, BootstrapServerMessage::SlotTooOld)),
                 MessageServerTypeId::BootstrapError => context(
                     "Failed BootstrapError deserialization",
@@ -515,6 +594,30 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
                     error: String::from_utf8_lossy(error).into_owned(),
                 })
                 .parse(input),
+                MessageServerTypeId::FinalStateHash => context(
+                    "Failed FinalStateHash deserialization",
+                    tuple((
+                        context("Failed slot deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed final_state_hash deserialization", |input| {
+                            self.opt_hash_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(
+                    |(slot, final_state_hash)| BootstrapServerMessage::FinalStateHash {
+                        slot,
+                        final_state_hash,
+                    },
+                )
+                .parse(input),
+                MessageServerTypeId::BootstrapCompression => context(
+                    "Failed BootstrapCompression deserialization",
+                    deserialize_compression,
+                )
+                .map(|compression| BootstrapServerMessage::BootstrapCompression { compression })
+                .parse(input),
             }
         })
         .parse(buffer)
@@ -550,6 +653,28 @@ pub enum BootstrapClientMessage {
     },
     /// Bootstrap succeed
     BootstrapSuccess,
+    /// Ask for a part of the ledger restricted to the addresses in `[start, end)`
+    AskLedgerRangePart {
+        /// Slot we are attached to for changes
+        last_slot: Option<Slot>,
+        /// Last received ledger key
+        last_ledger_step: StreamingStep<Vec<u8>>,
+        /// First address of the range, from the beginning of the ledger if `None`
+        start: Option<Address>,
+        /// Excluded last address of the range, until the end of the ledger if `None`
+        end: Option<Address>,
+    },
+    /// Ask for the final state hash of the server at a given slot
+    AskFinalStateHash {
+        /// Slot of the final state
+        slot: Slot,
+    },
+    /// Ask the server to compress the following messages, sent right after `BootstrapServerMessage::BootstrapTime`
+    /// to servers running at least `BootstrapConfig::compression_version`
+    AskBootstrapCompression {
+        /// Requested compression
+        compression: BootstrapCompression,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -559,12 +684,17 @@ enum MessageClientTypeId {
     AskFinalStatePart = 1u32,
     BootstrapError = 2u32,
     BootstrapSuccess = 3u32,
+    AskLedgerRangePart = 4u32,
+    AskFinalStateHash = 5u32,
+    AskBootstrapCompression = 6u32,
 }
 
 /// Serializer for `BootstrapClientMessage`
 pub struct BootstrapClientMessageSerializer {
     u32_serializer: U32VarIntSerializer,
     slot_serializer: SlotSerializer,
+    opt_slot_serializer: OptionSerializer<Slot, SlotSerializer>,
+    opt_address_serializer: OptionSerializer<Address, AddressSerializer>,
     ledger_step_serializer: StreamingStepSerializer<Vec<u8>, KeySerializer>,
     pool_step_serializer: StreamingStepSerializer<AsyncMessageId, AsyncMessageIdSerializer>,
     cycle_step_serializer: StreamingStepSerializer<u64, U64VarIntSerializer>,
@@ -581,6 +711,8 @@ impl BootstrapClientMessageSerializer {
         Self {
             u32_serializer: U32VarIntSerializer::new(),
             slot_serializer: SlotSerializer::new(),
+            opt_slot_serializer: OptionSerializer::new(SlotSerializer::new()),
+            opt_address_serializer: OptionSerializer::new(AddressSerializer::new()),
             ledger_step_serializer: StreamingStepSerializer::new(KeySerializer::new()),
             pool_step_serializer: StreamingStepSerializer::new(AsyncMessageIdSerializer::new()),
             cycle_step_serializer: StreamingStepSerializer::new(U64VarIntSerializer::new()),
@@ -663,6 +795,32 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                 self.u32_serializer
                     .serialize(&u32::from(MessageClientTypeId::BootstrapSuccess), buffer)?;
             }
+            BootstrapClientMessage::AskLedgerRangePart {
+                last_slot,
+                last_ledger_step,
+                start,
+                end,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::AskLedgerRangePart), buffer)?;
+                self.opt_slot_serializer.serialize(last_slot, buffer)?;
+                self.ledger_step_serializer
+                    .serialize(last_ledger_step, buffer)?;
+                self.opt_address_serializer.serialize(start, buffer)?;
+                self.opt_address_serializer.serialize(end, buffer)?;
+            }
+            BootstrapClientMessage::AskFinalStateHash { slot } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::AskFinalStateHash), buffer)?;
+                self.slot_serializer.serialize(slot, buffer)?;
+            }
+            BootstrapClientMessage::AskBootstrapCompression { compression } => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageClientTypeId::AskBootstrapCompression),
+                    buffer,
+                )?;
+                buffer.push(u8::from(*compression));
+            }
         }
         Ok(())
     }
@@ -673,6 +831,8 @@ pub struct BootstrapClientMessageDeserializer {
     id_deserializer: U32VarIntDeserializer,
     length_error_deserializer: U32VarIntDeserializer,
     slot_deserializer: SlotDeserializer,
+    opt_slot_deserializer: OptionDeserializer<Slot, SlotDeserializer>,
+    opt_address_deserializer: OptionDeserializer<Address, AddressDeserializer>,
     ledger_step_deserializer: StreamingStepDeserializer<Vec<u8>, KeyDeserializer>,
     pool_step_deserializer: StreamingStepDeserializer<AsyncMessageId, AsyncMessageIdDeserializer>,
     cycle_step_deserializer: StreamingStepDeserializer<u64, U64VarIntDeserializer>,
@@ -697,6 +857,11 @@ impl BootstrapClientMessageDeserializer {
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
             ),
+            opt_slot_deserializer: OptionDeserializer::new(SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            )),
+            opt_address_deserializer: OptionDeserializer::new(AddressDeserializer::new()),
             ledger_step_deserializer: StreamingStepDeserializer::new(KeyDeserializer::new(
                 max_datastore_key_length,
             )),
@@ -839,8 +1004,61 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                 MessageClientTypeId::BootstrapSuccess => {
                     Ok((input, BootstrapClientMessage::BootstrapSuccess))
                 }
+                MessageClientTypeId::AskLedgerRangePart => context(
+                    "Failed AskLedgerRangePart deserialization",
+                    tuple((
+                        context("Failed last_slot deserialization", |input| {
+                            self.opt_slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed last_ledger_step deserialization", |input| {
+                            self.ledger_step_deserializer.deserialize(input)
+                        }),
+                        context("Failed start deserialization", |input| {
+                            self.opt_address_deserializer.deserialize(input)
+                        }),
+                        context("Failed end deserialization", |input| {
+                            self.opt_address_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(|(last_slot, last_ledger_step, start, end)| {
+                    BootstrapClientMessage::AskLedgerRangePart {
+                        last_slot,
+                        last_ledger_step,
+                        start,
+                        end,
+                    }
+                })
+                .parse(input),
+                MessageClientTypeId::AskFinalStateHash => {
+                    context("Failed AskFinalStateHash deserialization", |input| {
+                        self.slot_deserializer.deserialize(input)
+                    })
+                    .map(|slot| BootstrapClientMessage::AskFinalStateHash { slot })
+                    .parse(input)
+                }
+                MessageClientTypeId::AskBootstrapCompression => context(
+                    "Failed AskBootstrapCompression deserialization",
+                    deserialize_compression,
+                )
+                .map(|compression| BootstrapClientMessage::AskBootstrapCompression { compression })
+                .parse(input),
             }
         })
         .parse(buffer)
     }
 }
+
+/// Deserializes the byte identifying a `BootstrapCompression`
+fn deserialize_compression<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], BootstrapCompression, E> {
+    let (rest, id) = nom::number::complete::u8(input)?;
+    let compression = BootstrapCompression::try_from(id).map_err(|_| {
+        nom::Err::Error(ParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::Eof,
+        ))
+    })?;
+    Ok((rest, compression))
+}
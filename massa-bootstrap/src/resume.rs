@@ -0,0 +1,333 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Local journal of the bootstrap parts applied by the client.
+//!
+//! The ledger parts are written to the disk ledger, which is kept across restarts while a journal exists.
+//! The journal only records, after each applied `BootstrapServerMessage::BootstrapPart`,
+//! the ledger cursor and the resulting disk ledger hash,
+//! along with the part stripped of its ledger data (async pool, PoS, executed operations and consensus parts live in memory).
+//! On resume, the stripped parts are replayed and the disk ledger hash is checked against the last recorded one,
+//! so that an interrupted bootstrap can be resumed from the last cursors, even after a node restart, with any bootstrap server.
+//!
+//! Each record is synchronized to the disk as soon as it is appended, right after the ledger batch of its part was written,
+//! so that the last record never lags behind the disk ledger it describes.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, ErrorKind, Read, Write},
+    path::PathBuf,
+};
+
+use massa_final_state::StateChanges;
+use massa_hash::{Hash, HashDeserializer};
+use massa_ledger_exports::{KeyDeserializer, KeySerializer};
+use massa_models::streaming_step::{
+    StreamingStep, StreamingStepDeserializer, StreamingStepSerializer,
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use tracing::{info, warn};
+
+use crate::{
+    error::BootstrapError,
+    messages::{
+        BootstrapServerMessage, BootstrapServerMessageDeserializer,
+        BootstrapServerMessageSerializer,
+    },
+    BootstrapConfig,
+};
+
+/// Size of the length field preceding each journal record
+const RECORD_LEN_SIZE: usize = 4;
+
+/// State of the disk ledger after a bootstrap part was applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LedgerCheckpoint {
+    /// cursor of the ledger stream
+    pub last_ledger_step: StreamingStep<Vec<u8>>,
+    /// hash of the disk ledger
+    pub ledger_hash: Hash,
+}
+
+/// Append-only journal of the applied bootstrap parts
+pub(crate) struct BootstrapResumeJournal {
+    path: PathBuf,
+    /// false once a write failed: the journal would have a gap and must not be used anymore
+    enabled: bool,
+    /// journal file opened for appending
+    file: Option<File>,
+    serializer: BootstrapServerMessageSerializer,
+    deserializer: BootstrapServerMessageDeserializer,
+    ledger_step_serializer: StreamingStepSerializer<Vec<u8>, KeySerializer>,
+    ledger_step_deserializer: StreamingStepDeserializer<Vec<u8>, KeyDeserializer>,
+}
+
+impl BootstrapResumeJournal {
+    /// Creates a new journal stored at `cfg.bootstrap_resume_file`
+    pub fn new(cfg: &BootstrapConfig) -> Self {
+        Self {
+            path: cfg.bootstrap_resume_file.clone(),
+            enabled: true,
+            file: None,
+            serializer: BootstrapServerMessageSerializer::new(),
+            deserializer: BootstrapServerMessageDeserializer::new(
+                cfg.thread_count,
+                cfg.endorsement_count,
+                cfg.max_advertise_length,
+                cfg.max_bootstrap_blocks_length,
+                cfg.max_operations_per_block,
+                cfg.max_bootstrap_final_state_parts_size,
+                cfg.max_async_pool_changes,
+                cfg.max_async_pool_length,
+                cfg.max_async_message_data,
+                cfg.max_ledger_changes_count,
+                cfg.max_datastore_key_length,
+                cfg.max_datastore_value_length,
+                cfg.max_datastore_entry_count,
+                cfg.max_bootstrap_error_length,
+                cfg.max_changes_slot_count,
+                cfg.max_rolls_length,
+                cfg.max_production_stats_length,
+                cfg.max_credits_length,
+                cfg.max_executed_ops_length,
+                cfg.max_ops_changes_length,
+            ),
+            ledger_step_serializer: StreamingStepSerializer::new(KeySerializer::new()),
+            ledger_step_deserializer: StreamingStepDeserializer::new(KeyDeserializer::new(
+                cfg.max_datastore_key_length,
+            )),
+        }
+    }
+
+    /// Returns true if the applied parts are still journaled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Reads the journal records one at a time, in application order, and passes them to `replay`.
+    /// Returns the checkpoint of the last record, or `None` if the journal is empty.
+    ///
+    /// A truncated last record (interrupted write) is dropped from the file.
+    /// Any other inconsistency results in an error, the journal should then be cleared.
+    pub fn load<F>(&self, mut replay: F) -> Result<Option<LedgerCheckpoint>, BootstrapError>
+    where
+        F: FnMut(BootstrapServerMessage, &LedgerCheckpoint) -> Result<(), BootstrapError>,
+    {
+        let mut reader = match File::open(&self.path) {
+            Ok(file) => BufReader::new(file),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut last_checkpoint = None;
+        let mut record_start = 0u64;
+        let mut record = Vec::new();
+        loop {
+            let mut len_bytes = [0u8; RECORD_LEN_SIZE];
+            match read_exact_or_eof(&mut reader, &mut len_bytes)? {
+                ReadOutcome::Complete => {}
+                ReadOutcome::Empty => break,
+                ReadOutcome::Truncated => {
+                    self.truncate(record_start)?;
+                    break;
+                }
+            }
+            let record_len = u32::from_be_bytes(len_bytes) as usize;
+            record.resize(record_len, 0);
+            if !matches!(
+                read_exact_or_eof(&mut reader, &mut record)?,
+                ReadOutcome::Complete
+            ) {
+                self.truncate(record_start)?;
+                break;
+            }
+            record_start += (RECORD_LEN_SIZE + record_len) as u64;
+
+            let (part, checkpoint) = self.deserialize_record(&record)?;
+            replay(part, &checkpoint)?;
+            last_checkpoint = Some(checkpoint);
+        }
+        Ok(last_checkpoint)
+    }
+
+    fn deserialize_record(
+        &self,
+        record: &[u8],
+    ) -> Result<(BootstrapServerMessage, LedgerCheckpoint), BootstrapError> {
+        let corrupted = |err: nom::Err<DeserializeError>| {
+            BootstrapError::GeneralError(format!(
+                "could not deserialize bootstrap resume journal record: {}",
+                err
+            ))
+        };
+        let (rest, ledger_hash) = HashDeserializer::new()
+            .deserialize::<DeserializeError>(record)
+            .map_err(corrupted)?;
+        let (rest, last_ledger_step) = self
+            .ledger_step_deserializer
+            .deserialize::<DeserializeError>(rest)
+            .map_err(corrupted)?;
+        let (rest, part) = self
+            .deserializer
+            .deserialize::<DeserializeError>(rest)
+            .map_err(corrupted)?;
+        if !rest.is_empty() {
+            return Err(BootstrapError::GeneralError(
+                "bootstrap resume journal record has trailing bytes".into(),
+            ));
+        }
+        if !matches!(part, BootstrapServerMessage::BootstrapPart { .. }) {
+            return Err(BootstrapError::UnexpectedServerMessage(part));
+        }
+        Ok((
+            part,
+            LedgerCheckpoint {
+                last_ledger_step,
+                ledger_hash,
+            },
+        ))
+    }
+
+    /// Appends an applied part, previously stripped with `without_ledger_data`, to the journal.
+    ///
+    /// The journal is best-effort: on failure it is cleared and disabled
+    /// so that it never contains a gap, and the bootstrap goes on without it.
+    pub fn append(&mut self, part: &BootstrapServerMessage, checkpoint: &LedgerCheckpoint) {
+        if !self.enabled {
+            return;
+        }
+        if let Err(err) = self.try_append(part, checkpoint) {
+            warn!(
+                "could not write bootstrap resume journal {}: {}. The bootstrap will not be resumable after a restart.",
+                self.path.display(),
+                err
+            );
+            self.disable();
+            self.clear();
+        }
+    }
+
+    fn try_append(
+        &mut self,
+        part: &BootstrapServerMessage,
+        checkpoint: &LedgerCheckpoint,
+    ) -> Result<(), BootstrapError> {
+        let mut record = vec![0u8; RECORD_LEN_SIZE];
+        record.extend(checkpoint.ledger_hash.to_bytes());
+        self.ledger_step_serializer
+            .serialize(&checkpoint.last_ledger_step, &mut record)?;
+        self.serializer.serialize(part, &mut record)?;
+        let record_len: u32 = (record.len() - RECORD_LEN_SIZE).try_into().map_err(|_| {
+            BootstrapError::GeneralError("bootstrap part too large for the resume journal".into())
+        })?;
+        record[..RECORD_LEN_SIZE].copy_from_slice(&record_len.to_be_bytes());
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.file = Some(file);
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(&record)?;
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Stops journaling the applied parts
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.file = None;
+    }
+
+    /// Removes the journal file, to be called once the bootstrap succeeded or must restart from scratch
+    pub fn clear(&mut self) {
+        self.file = None;
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => info!("bootstrap resume journal cleared"),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => warn!(
+                "could not remove bootstrap resume journal {}: {}",
+                self.path.display(),
+                err
+            ),
+        }
+    }
+
+    fn truncate(&self, len: u64) -> Result<(), BootstrapError> {
+        warn!(
+            "dropping the truncated last record of the bootstrap resume journal {}",
+            self.path.display()
+        );
+        OpenOptions::new()
+            .write(true)
+            .open(&self.path)?
+            .set_len(len)?;
+        Ok(())
+    }
+}
+
+/// Copy of a bootstrap part without its ledger part and ledger changes, which are persisted by the disk ledger
+pub(crate) fn without_ledger_data(part: &BootstrapServerMessage) -> Option<BootstrapServerMessage> {
+    match part {
+        BootstrapServerMessage::BootstrapPart {
+            slot,
+            async_pool_part,
+            pos_cycle_part,
+            pos_credits_part,
+            exec_ops_part,
+            final_state_changes,
+            consensus_part,
+            consensus_outdated_ids,
+            ..
+        } => Some(BootstrapServerMessage::BootstrapPart {
+            slot: *slot,
+            ledger_part: Vec::new(),
+            async_pool_part: async_pool_part.clone(),
+            pos_cycle_part: pos_cycle_part.clone(),
+            pos_credits_part: pos_credits_part.clone(),
+            exec_ops_part: exec_ops_part.clone(),
+            final_state_changes: final_state_changes
+                .iter()
+                .map(|(changes_slot, changes)| {
+                    (
+                        *changes_slot,
+                        StateChanges {
+                            ledger_changes: Default::default(),
+                            async_pool_changes: changes.async_pool_changes.clone(),
+                            pos_changes: changes.pos_changes.clone(),
+                            executed_ops_changes: changes.executed_ops_changes.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            consensus_part: consensus_part.clone(),
+            consensus_outdated_ids: consensus_outdated_ids.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Outcome of a read that may hit the end of the file
+enum ReadOutcome {
+    /// the buffer was filled
+    Complete,
+    /// the end of the file was reached before reading anything
+    Empty,
+    /// the end of the file was reached in the middle of the buffer
+    Truncated,
+}
+
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<ReadOutcome> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(ReadOutcome::Empty),
+            Ok(0) => return Ok(ReadOutcome::Truncated),
+            Ok(n) => read += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(ReadOutcome::Complete)
+}
@@ -3,18 +3,20 @@ use futures::StreamExt;
 use humantime::format_duration;
 use massa_async_pool::AsyncMessageId;
 use massa_consensus_exports::{bootstrapable_graph::BootstrapableGraph, ConsensusController};
-use massa_final_state::{FinalState, FinalStateError};
+use massa_final_state::{FinalState, FinalStateError, StateChanges};
+use massa_ledger_exports::{get_address_from_key, LedgerChanges};
 use massa_logging::massa_trace;
 use massa_models::{
-    block::BlockId, prehash::PreHashSet, slot::Slot, streaming_step::StreamingStep,
-    version::Version,
+    address::Address, block::BlockId, prehash::PreHashSet, slot::Slot,
+    streaming_step::StreamingStep, version::Version,
 };
 use massa_network_exports::NetworkCommandSender;
+use massa_pos_exports::DeferredCredits;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
 use std::{
-    collections::{hash_map, HashMap, HashSet},
+    collections::{hash_map, BTreeMap, HashMap},
     net::{IpAddr, SocketAddr},
     sync::Arc,
     time::{Duration, Instant},
@@ -23,10 +25,12 @@ use tokio::{sync::mpsc, task::JoinHandle};
 use tracing::{debug, info, warn};
 
 use crate::{
+    compression::BootstrapCompression,
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
     server_binder::BootstrapServerBinder,
-    tools::normalize_ip,
+    server_status::{BootstrapSessionHandle, SharedBootstrapServerStatus},
+    white_black_list::{SharedWhiteBlackList, LIST_RELOAD_INTERVAL},
     BootstrapConfig, Establisher,
 };
 
@@ -62,47 +66,13 @@ pub async fn start_bootstrap_server(
     keypair: KeyPair,
     compensation_millis: i64,
     version: Version,
+    white_black_list: SharedWhiteBlackList,
+    status: SharedBootstrapServerStatus,
 ) -> Result<Option<BootstrapManager>, BootstrapError> {
     massa_trace!("bootstrap.lib.start_bootstrap_server", {});
     if let Some(bind) = bootstrap_config.bind {
         let (manager_tx, manager_rx) = mpsc::channel::<()>(1);
 
-        let whitelist = if let Ok(whitelist) =
-            std::fs::read_to_string(&bootstrap_config.bootstrap_whitelist_file)
-        {
-            Some(
-                serde_json::from_str::<HashSet<IpAddr>>(whitelist.as_str())
-                    .map_err(|_| {
-                        BootstrapError::GeneralError(String::from(
-                            "Failed to parse bootstrap whitelist",
-                        ))
-                    })?
-                    .into_iter()
-                    .map(normalize_ip)
-                    .collect(),
-            )
-        } else {
-            None
-        };
-
-        let blacklist = if let Ok(blacklist) =
-            std::fs::read_to_string(&bootstrap_config.bootstrap_blacklist_file)
-        {
-            Some(
-                serde_json::from_str::<HashSet<IpAddr>>(blacklist.as_str())
-                    .map_err(|_| {
-                        BootstrapError::GeneralError(String::from(
-                            "Failed to parse bootstrap blacklist",
-                        ))
-                    })?
-                    .into_iter()
-                    .map(normalize_ip)
-                    .collect(),
-            )
-        } else {
-            None
-        };
-
         let join_handle = tokio::spawn(async move {
             BootstrapServer {
                 consensus_controller,
@@ -114,8 +84,8 @@ pub async fn start_bootstrap_server(
                 keypair,
                 compensation_millis,
                 version,
-                whitelist,
-                blacklist,
+                white_black_list,
+                status,
                 ip_hist_map: HashMap::with_capacity(bootstrap_config.ip_list_max_size),
                 bootstrap_config,
             }
@@ -142,8 +112,8 @@ struct BootstrapServer {
     bootstrap_config: BootstrapConfig,
     compensation_millis: i64,
     version: Version,
-    blacklist: Option<HashSet<IpAddr>>,
-    whitelist: Option<HashSet<IpAddr>>,
+    white_black_list: SharedWhiteBlackList,
+    status: SharedBootstrapServerStatus,
     ip_hist_map: HashMap<IpAddr, Instant>,
 }
 
@@ -161,6 +131,7 @@ impl BootstrapServer {
         // )> = None;
         // let cache_timer = sleep(cache_timeout);
         let per_ip_min_interval = self.bootstrap_config.per_ip_min_interval.to_duration();
+        let mut list_reload_interval = tokio::time::interval(LIST_RELOAD_INTERVAL);
         // tokio::pin!(cache_timer);
         /*
             select! without the "biased" modifier will randomly select the 1st branch to check,
@@ -168,6 +139,7 @@ impl BootstrapServer {
             We choose this order:
                 * manager commands to avoid waiting too long to stop in case of contention
                 * cache timeout to avoid skipping timeouts cleanup tasks (they are relatively rare)
+                * whitelist and blacklist files reload (rare)
                 * bootstrap sessions (rare)
                 * listener: most frequent => last
         */
@@ -186,13 +158,19 @@ impl BootstrapServer {
                 //     bootstrap_data = None;
                 // }
 
+                // whitelist and blacklist files reload
+                _ = list_reload_interval.tick() => {
+                    massa_trace!("bootstrap.lib.run.select.list_reload", {});
+                    self.white_black_list.reload_if_changed();
+                }
+
                 // bootstrap session finished
                 Some(_) = bootstrap_sessions.next() => {
                     massa_trace!("bootstrap.session.finished", {"active_count": bootstrap_sessions.len()});
                 }
 
                 // listener
-                res_connection = listener.accept(&self.whitelist, &self.blacklist) => {
+                res_connection = listener.accept(&self.white_black_list) => {
                     let (dplx, remote_addr) = if res_connection.is_ok() {
                         res_connection.unwrap()
                     } else {
@@ -227,6 +205,7 @@ impl BootstrapServer {
                                         Ok(Ok(_)) => Ok(()),
                                     };
                                     // in list, non-expired => refuse
+                                    self.status.count_refused_session();
                                     massa_trace!("bootstrap.lib.run.select.accept.refuse_limit", {"remote_addr": remote_addr});
                                     continue;
                                 } else {
@@ -261,21 +240,27 @@ impl BootstrapServer {
                         let network_command_sender = self.network_command_sender.clone();
                         let keypair = self.keypair.clone();
                         let config = self.bootstrap_config.clone();
+                        let session = self.status.start_session(remote_addr.ip(), compensation_millis);
 
                         bootstrap_sessions.push(async move {
                             let mut server = BootstrapServerBinder::new(dplx, keypair, config.max_bytes_read_write, config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
-                            match manage_bootstrap(&config, &mut server, data_execution, compensation_millis, version, consensus_command_sender, network_command_sender).await {
+                            let success = match manage_bootstrap(&config, &mut server, data_execution, compensation_millis, version, consensus_command_sender, network_command_sender, &session).await {
                                 Ok(_) => {
-                                    info!("bootstrapped peer {}", remote_addr)
+                                    info!("bootstrapped peer {}", remote_addr);
+                                    true
+                                },
+                                Err(BootstrapError::ReceivedError(error)) => {
+                                    debug!("bootstrap serving error received from peer {}: {}", remote_addr, error);
+                                    false
                                 },
-                                Err(BootstrapError::ReceivedError(error)) => debug!("bootstrap serving error received from peer {}: {}", remote_addr, error),
                                 Err(err) => {
                                     debug!("bootstrap serving error for peer {}: {}", remote_addr, err);
                                     // We allow unused result because we don't care if an error is thrown when sending the error message to the server we will close the socket anyway.
                                     let _ = tokio::time::timeout(config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError { error: err.to_string() })).await;
+                                    false
                                 },
-                            }
-
+                            };
+                            session.end(success);
                         });
                         massa_trace!("bootstrap.session.started", {"active_count": bootstrap_sessions.len()});
                     } else {
@@ -288,6 +273,7 @@ impl BootstrapServer {
                             Ok(Err(e)) => Err(e),
                             Ok(Ok(_)) => Ok(()),
                         };
+                        self.status.count_no_slot_session();
                         debug!("did not bootstrap {}: no available slots", remote_addr);
                     }
                 }
@@ -301,7 +287,7 @@ impl BootstrapServer {
 }
 
 #[allow(clippy::too_many_arguments)]
-pub async fn stream_bootstrap_information(
+pub(crate) async fn stream_bootstrap_information(
     server: &mut BootstrapServerBinder,
     final_state: Arc<RwLock<FinalState>>,
     consensus_controller: Box<dyn ConsensusController>,
@@ -313,6 +299,8 @@ pub async fn stream_bootstrap_information(
     mut last_ops_step: StreamingStep<Slot>,
     mut last_consensus_step: StreamingStep<PreHashSet<BlockId>>,
     write_timeout: Duration,
+    send_final_state_hash: bool,
+    session: &BootstrapSessionHandle,
 ) -> Result<(), BootstrapError> {
     loop {
         #[cfg(test)]
@@ -461,12 +449,26 @@ pub async fn stream_bootstrap_information(
             && final_state_changes_step.finished()
             && last_consensus_step.finished()
         {
-            match tokio::time::timeout(
-                write_timeout,
-                server.send(BootstrapServerMessage::BootstrapFinished),
-            )
-            .await
-            {
+            // clients older than `final_state_hash_version` only know the unhashed message
+            let finished_msg = if send_final_state_hash {
+                let final_state_hash =
+                    final_state
+                        .read()
+                        .get_state_hash(current_slot)
+                        .ok_or_else(|| {
+                            BootstrapError::GeneralError(format!(
+                                "final state hash of slot {} is not in the history anymore",
+                                current_slot
+                            ))
+                        })?;
+                BootstrapServerMessage::BootstrapFinishedWithHash {
+                    slot: current_slot,
+                    final_state_hash,
+                }
+            } else {
+                BootstrapServerMessage::BootstrapFinished
+            };
+            match tokio::time::timeout(write_timeout, server.send(finished_msg)).await {
                 Err(_) => Err(std::io::Error::new(
                     std::io::ErrorKind::TimedOut,
                     "bootstrap ask ledger part send timed out",
@@ -503,6 +505,206 @@ pub async fn stream_bootstrap_information(
             Ok(Err(e)) => Err(e),
             Ok(Ok(_)) => Ok(()),
         }?;
+
+        // Update the session progress
+        let bytes_sent = server.bytes_sent();
+        session.update(|info| {
+            info.bytes_sent = bytes_sent;
+            info.slot = Some(current_slot);
+            info.ledger_step = ledger_step_address(&last_ledger_step);
+            info.async_pool_step = last_pool_step.map(|id| id.1);
+            info.cycle_step = last_cycle_step;
+            info.credits_step = last_credits_step;
+            info.executed_ops_step = last_ops_step;
+            info.consensus_step = last_consensus_step.clone().map(|ids| ids.len());
+        });
+    }
+    Ok(())
+}
+
+/// Maps a ledger streaming step to the address of its key, for the session progress
+fn ledger_step_address(step: &StreamingStep<Vec<u8>>) -> StreamingStep<Address> {
+    match step {
+        StreamingStep::Started => StreamingStep::Started,
+        StreamingStep::Ongoing(key) => match get_address_from_key(key) {
+            Some(address) => StreamingStep::Ongoing(address),
+            None => StreamingStep::Started,
+        },
+        StreamingStep::Finished(key) => {
+            StreamingStep::Finished(key.as_ref().and_then(|key| get_address_from_key(key)))
+        }
+    }
+}
+
+/// Streams the part of the ledger restricted to the addresses in `[start, end)`,
+/// along with the ledger changes of the already streamed addresses of the range.
+/// Used by the clients bootstrapping disjoint ledger ranges from several servers in parallel.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn stream_ledger_range(
+    server: &mut BootstrapServerBinder,
+    final_state: Arc<RwLock<FinalState>>,
+    mut last_slot: Option<Slot>,
+    mut last_ledger_step: StreamingStep<Vec<u8>>,
+    start: Option<Address>,
+    end: Option<Address>,
+    write_timeout: Duration,
+    session: &BootstrapSessionHandle,
+) -> Result<(), BootstrapError> {
+    loop {
+        let current_slot;
+        let ledger_part;
+        let mut final_state_changes = Vec::new();
+
+        let mut slot_too_old = false;
+
+        // Scope of the final state read
+        {
+            let final_state_read = final_state.read();
+            let (data, new_ledger_step) = final_state_read.ledger.get_ledger_range_part(
+                last_ledger_step.clone(),
+                start.as_ref(),
+                end.as_ref(),
+            )?;
+            ledger_part = data;
+
+            if let Some(slot) = last_slot && slot != final_state_read.slot {
+                if slot > final_state_read.slot {
+                    return Err(BootstrapError::GeneralError(
+                        "Bootstrap cursor set to future slot".to_string(),
+                    ));
+                }
+                match final_state_read.get_state_changes_part(
+                    slot,
+                    new_ledger_step.clone(),
+                    StreamingStep::Started,
+                    StreamingStep::Started,
+                    StreamingStep::Started,
+                    StreamingStep::Started,
+                ) {
+                    Ok(data) => {
+                        // Only keep the ledger changes of the range
+                        final_state_changes = data
+                            .into_iter()
+                            .map(|(changes_slot, changes)| {
+                                let ledger_changes = LedgerChanges(
+                                    changes
+                                        .ledger_changes
+                                        .0
+                                        .into_iter()
+                                        .filter(|(address, _)| {
+                                            start.map_or(true, |start| *address >= start)
+                                                && end.map_or(true, |end| *address < end)
+                                        })
+                                        .collect(),
+                                );
+                                (
+                                    changes_slot,
+                                    StateChanges {
+                                        ledger_changes,
+                                        ..Default::default()
+                                    },
+                                )
+                            })
+                            .filter(|(_, changes)| !changes.ledger_changes.0.is_empty())
+                            .collect();
+                    }
+                    Err(err) if matches!(err, FinalStateError::InvalidSlot(_)) => {
+                        slot_too_old = true;
+                    }
+                    Err(err) => return Err(BootstrapError::FinalStateError(err)),
+                };
+            }
+
+            // Update cursors for next turn
+            last_ledger_step = new_ledger_step;
+            last_slot = Some(final_state_read.slot);
+            current_slot = final_state_read.slot;
+        }
+
+        if slot_too_old {
+            match tokio::time::timeout(
+                write_timeout,
+                server.send(BootstrapServerMessage::SlotTooOld),
+            )
+            .await
+            {
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "SlotTooOld message send timed out",
+                )
+                .into()),
+                Ok(Err(e)) => Err(e),
+                Ok(Ok(_)) => Ok(()),
+            }?;
+            return Ok(());
+        }
+
+        // The range is streamed and attached to the current slot
+        if last_ledger_step.finished() && final_state_changes.is_empty() {
+            let final_state_hash =
+                final_state
+                    .read()
+                    .get_state_hash(current_slot)
+                    .ok_or_else(|| {
+                        BootstrapError::GeneralError(format!(
+                            "final state hash of slot {} is not in the history anymore",
+                            current_slot
+                        ))
+                    })?;
+            match tokio::time::timeout(
+                write_timeout,
+                server.send(BootstrapServerMessage::BootstrapFinishedWithHash {
+                    slot: current_slot,
+                    final_state_hash,
+                }),
+            )
+            .await
+            {
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap ledger range finished send timed out",
+                )
+                .into()),
+                Ok(Err(e)) => Err(e),
+                Ok(Ok(_)) => Ok(()),
+            }?;
+            break;
+        }
+
+        match tokio::time::timeout(
+            write_timeout,
+            server.send(BootstrapServerMessage::BootstrapPart {
+                slot: current_slot,
+                ledger_part,
+                async_pool_part: BTreeMap::new(),
+                pos_cycle_part: None,
+                pos_credits_part: DeferredCredits::default(),
+                exec_ops_part: BTreeMap::new(),
+                final_state_changes,
+                consensus_part: BootstrapableGraph {
+                    final_blocks: Default::default(),
+                },
+                consensus_outdated_ids: PreHashSet::default(),
+            }),
+        )
+        .await
+        {
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "bootstrap ledger range part send timed out",
+            )
+            .into()),
+            Ok(Err(e)) => Err(e),
+            Ok(Ok(_)) => Ok(()),
+        }?;
+
+        // Update the session progress
+        let bytes_sent = server.bytes_sent();
+        session.update(|info| {
+            info.bytes_sent = bytes_sent;
+            info.slot = Some(current_slot);
+            info.ledger_step = ledger_step_address(&last_ledger_step);
+        });
     }
     Ok(())
 }
@@ -517,11 +719,12 @@ async fn manage_bootstrap(
     version: Version,
     consensus_controller: Box<dyn ConsensusController>,
     network_command_sender: NetworkCommandSender,
+    session: &BootstrapSessionHandle,
 ) -> Result<(), BootstrapError> {
     massa_trace!("bootstrap.lib.manage_bootstrap", {});
     let read_error_timeout: std::time::Duration = bootstrap_config.read_error_timeout.into();
 
-    match tokio::time::timeout(
+    let client_version = match tokio::time::timeout(
         bootstrap_config.read_timeout.into(),
         server.handshake(version),
     )
@@ -535,8 +738,10 @@ async fn manage_bootstrap(
             .into())
         }
         Ok(Err(e)) => return Err(e),
-        Ok(Ok(_)) => (),
+        Ok(Ok(client_version)) => client_version,
     };
+    let send_final_state_hash =
+        client_version.is_at_least(&bootstrap_config.final_state_hash_version);
 
     match tokio::time::timeout(read_error_timeout, server.next()).await {
         Err(_) => (),
@@ -614,9 +819,85 @@ async fn manage_bootstrap(
                         last_ops_step,
                         last_consensus_step,
                         write_timeout,
+                        send_final_state_hash,
+                        session,
+                    )
+                    .await?;
+                }
+                BootstrapClientMessage::AskLedgerRangePart {
+                    last_slot,
+                    last_ledger_step,
+                    start,
+                    end,
+                } => {
+                    stream_ledger_range(
+                        server,
+                        final_state.clone(),
+                        last_slot,
+                        last_ledger_step,
+                        start,
+                        end,
+                        write_timeout,
+                        session,
                     )
                     .await?;
                 }
+                BootstrapClientMessage::AskFinalStateHash { slot } => {
+                    // Give our final state a chance to reach the requested slot,
+                    // answering with what we have once the timeout is reached
+                    let mut slot_receiver = final_state.read().subscribe_slot();
+                    let _ = tokio::time::timeout(write_timeout, async {
+                        while *slot_receiver.borrow_and_update() < slot {
+                            if slot_receiver.changed().await.is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .await;
+                    let final_state_hash = final_state.read().get_state_hash(slot);
+                    match tokio::time::timeout(
+                        write_timeout,
+                        server.send(BootstrapServerMessage::FinalStateHash {
+                            slot,
+                            final_state_hash,
+                        }),
+                    )
+                    .await
+                    {
+                        Err(_) => Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            "bootstrap final state hash send timed out",
+                        )
+                        .into()),
+                        Ok(Err(e)) => Err(e),
+                        Ok(Ok(_)) => Ok(()),
+                    }?;
+                }
+                BootstrapClientMessage::AskBootstrapCompression {
+                    compression: requested_compression,
+                } => {
+                    // compress the stream as requested by the client, unless compression is disabled on our side
+                    let compression = match bootstrap_config.bootstrap_compression {
+                        BootstrapCompression::None => BootstrapCompression::None,
+                        _ => requested_compression,
+                    };
+                    // the answer itself is sent uncompressed
+                    match tokio::time::timeout(
+                        write_timeout,
+                        server.send(BootstrapServerMessage::BootstrapCompression { compression }),
+                    )
+                    .await
+                    {
+                        Err(_) => Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            "bootstrap compression send timed out",
+                        )
+                        .into()),
+                        Ok(Err(e)) => Err(e),
+                        Ok(Ok(_)) => Ok(()),
+                    }?;
+                    server.set_compression(compression);
+                }
                 BootstrapClientMessage::BootstrapSuccess => break Ok(()),
                 BootstrapClientMessage::BootstrapError { error } => {
                     break Err(BootstrapError::ReceivedError(error));
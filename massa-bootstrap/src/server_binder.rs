@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::compression::BootstrapCompression;
 use crate::error::BootstrapError;
 use crate::establisher::types::Duplex;
 use crate::messages::{
@@ -30,6 +31,8 @@ pub struct BootstrapServerBinder {
     prev_message: Option<Hash>,
     version_serializer: VersionSerializer,
     version_deserializer: VersionDeserializer,
+    compression: BootstrapCompression,
+    bytes_sent: u64,
 }
 
 impl BootstrapServerBinder {
@@ -63,17 +66,31 @@ impl BootstrapServerBinder {
             randomness_size_bytes,
             version_serializer: VersionSerializer::new(),
             version_deserializer: VersionDeserializer::new(),
+            compression: BootstrapCompression::None,
+            bytes_sent: 0,
         }
     }
+
+    /// Number of bytes sent so far
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Sets the compression of the next messages sent
+    pub fn set_compression(&mut self, compression: BootstrapCompression) {
+        self.compression = compression;
+    }
 }
 
 impl BootstrapServerBinder {
     /// Performs a handshake. Should be called after connection
     /// NOT cancel-safe
     /// MUST always be followed by a send of the `BootstrapMessage::BootstrapTime`
-    pub async fn handshake(&mut self, version: Version) -> Result<(), BootstrapError> {
+    ///
+    /// Returns the client version
+    pub async fn handshake(&mut self, version: Version) -> Result<Version, BootstrapError> {
         // read version and random bytes, send signature
-        let msg_hash = {
+        let (msg_hash, received_version) = {
             let mut version_bytes = Vec::new();
             self.version_serializer
                 .serialize(&version, &mut version_bytes)?;
@@ -86,13 +103,13 @@ impl BootstrapServerBinder {
             if !received_version.is_compatible(&version) {
                 return Err(BootstrapError::IncompatibleVersionError(format!("Received a bad incompatible version in handshake. (excepted: {}, received: {})", version, received_version)));
             }
-            Hash::compute_from(&msg_bytes)
+            (Hash::compute_from(&msg_bytes), received_version)
         };
 
         // save prev sig
         self.prev_message = Some(msg_hash);
 
-        Ok(())
+        Ok(received_version)
     }
 
     /// Writes the next message. NOT cancel-safe
@@ -103,6 +120,13 @@ impl BootstrapServerBinder {
         let msg_len: u32 = msg_bytes.len().try_into().map_err(|e| {
             BootstrapError::GeneralError(format!("bootstrap message too large to encode: {}", e))
         })?;
+        // the limit applies to the uncompressed message, which is what the client decompresses
+        if msg_len > self.max_bootstrap_message_size {
+            return Err(BootstrapError::GeneralError(format!(
+                "bootstrap message size {} exceeds the maximum of {}",
+                msg_len, self.max_bootstrap_message_size
+            )));
+        }
 
         // compute signature
         let sig = {
@@ -120,16 +144,25 @@ impl BootstrapServerBinder {
         };
 
         // send signature
-        self.duplex.write_all(&sig.to_bytes()).await?;
+        let sig_bytes = sig.to_bytes();
+        self.duplex.write_all(&sig_bytes).await?;
 
-        // send message length
-        {
-            let msg_len_bytes = msg_len.to_be_bytes_min(self.max_bootstrap_message_size)?;
-            self.duplex.write_all(&msg_len_bytes).await?;
-        }
+        // compress message, the signature covering the uncompressed bytes
+        let sent_bytes = self.compression.compress(&msg_bytes);
+        let sent_len: u32 = sent_bytes.len().try_into().map_err(|e| {
+            BootstrapError::GeneralError(format!("bootstrap message too large to encode: {}", e))
+        })?;
+
+        // send message length, the compressed bytes may exceed the maximum message size
+        let sent_len_bytes = sent_len.to_be_bytes_min(
+            self.compression
+                .max_frame_size(self.max_bootstrap_message_size),
+        )?;
+        self.duplex.write_all(&sent_len_bytes).await?;
 
         // send message
-        self.duplex.write_all(&msg_bytes).await?;
+        self.duplex.write_all(&sent_bytes).await?;
+        self.bytes_sent += (sig_bytes.len() + sent_len_bytes.len() + sent_bytes.len()) as u64;
 
         // save prev sig
         self.prev_message = Some(Hash::compute_from(&sig.to_bytes()));
@@ -0,0 +1,133 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Status of the bootstrap server, shared with the API: active sessions and session counters.
+
+use std::{collections::BTreeMap, net::IpAddr, sync::Arc};
+
+use massa_bootstrap_exports::BootstrapStatusController;
+use massa_models::{
+    api::BootstrapSessionInfo, stats::BootstrapStats, streaming_step::StreamingStep,
+};
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+
+#[derive(Default)]
+struct BootstrapServerStatus {
+    next_session_id: u64,
+    sessions: BTreeMap<u64, BootstrapSessionInfo>,
+    stats: BootstrapStats,
+}
+
+/// Status of the bootstrap server, cheap to clone
+#[derive(Clone, Default)]
+pub struct SharedBootstrapServerStatus(Arc<RwLock<BootstrapServerStatus>>);
+
+impl SharedBootstrapServerStatus {
+    /// Creates an empty status
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a new active session
+    pub(crate) fn start_session(
+        &self,
+        ip: IpAddr,
+        compensation_millis: i64,
+    ) -> BootstrapSessionHandle {
+        let mut status = self.0.write();
+        let id = status.next_session_id;
+        status.next_session_id += 1;
+        status.sessions.insert(
+            id,
+            BootstrapSessionInfo {
+                ip,
+                start_time: MassaTime::now(compensation_millis)
+                    .unwrap_or_else(|_| MassaTime::from_millis(0)),
+                bytes_sent: 0,
+                slot: None,
+                ledger_step: StreamingStep::Started,
+                async_pool_step: StreamingStep::Started,
+                cycle_step: StreamingStep::Started,
+                credits_step: StreamingStep::Started,
+                executed_ops_step: StreamingStep::Started,
+                consensus_step: StreamingStep::Started,
+            },
+        );
+        BootstrapSessionHandle {
+            status: self.clone(),
+            id,
+            ended: false,
+        }
+    }
+
+    /// Counts a session refused because the peer already bootstrapped less than `per_ip_min_interval` ago
+    pub(crate) fn count_refused_session(&self) {
+        self.0.write().stats.refused_session_count += 1;
+    }
+
+    /// Counts a session refused because `max_simultaneous_bootstraps` was reached
+    pub(crate) fn count_no_slot_session(&self) {
+        self.0.write().stats.no_slot_session_count += 1;
+    }
+}
+
+impl BootstrapStatusController for SharedBootstrapServerStatus {
+    fn get_sessions(&self) -> Vec<BootstrapSessionInfo> {
+        self.0.read().sessions.values().cloned().collect()
+    }
+
+    fn get_stats(&self) -> BootstrapStats {
+        let status = self.0.read();
+        BootstrapStats {
+            active_session_count: status.sessions.len() as u64,
+            ..status.stats.clone()
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn BootstrapStatusController> {
+        Box::new(self.clone())
+    }
+}
+
+/// Handle on an active session, used to update its progress.
+/// A session dropped without being ended, for example when the server stops, counts as failed.
+pub(crate) struct BootstrapSessionHandle {
+    status: SharedBootstrapServerStatus,
+    id: u64,
+    /// true once the session was removed from the active ones
+    ended: bool,
+}
+
+impl BootstrapSessionHandle {
+    /// Updates the session information
+    pub fn update<F: FnOnce(&mut BootstrapSessionInfo)>(&self, f: F) {
+        if let Some(info) = self.status.0.write().sessions.get_mut(&self.id) {
+            f(info);
+        }
+    }
+
+    /// Removes the session from the active ones and counts it as completed or failed
+    pub fn end(mut self, success: bool) {
+        self.finish(success);
+    }
+
+    fn finish(&mut self, success: bool) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+        let mut status = self.status.0.write();
+        status.sessions.remove(&self.id);
+        if success {
+            status.stats.completed_session_count += 1;
+        } else {
+            status.stats.failed_session_count += 1;
+        }
+    }
+}
+
+impl Drop for BootstrapSessionHandle {
+    fn drop(&mut self) {
+        self.finish(false);
+    }
+}
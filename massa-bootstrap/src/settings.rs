@@ -1,5 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::compression::BootstrapCompression;
+use massa_models::version::Version;
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use serde::Deserialize;
@@ -14,6 +16,8 @@ pub struct BootstrapConfig {
     pub bootstrap_whitelist_file: std::path::PathBuf,
     /// Path to the bootstrap blacklist file. This whitelist define IPs that will not be able to bootstrap on your node. This list is optional.
     pub bootstrap_blacklist_file: std::path::PathBuf,
+    /// Path to the local journal of the received bootstrap parts, used to resume an interrupted bootstrap.
+    pub bootstrap_resume_file: std::path::PathBuf,
     /// Port to listen if we choose to allow other nodes to use us as bootstrap node.
     pub bind: Option<SocketAddr>,
     /// connection timeout
@@ -44,6 +48,14 @@ pub struct BootstrapConfig {
     pub max_bytes_read_write: f64,
     /// max bootstrap message size in bytes
     pub max_bootstrap_message_size: u32,
+    /// Compression requested to the bootstrap servers, and allowed when serving bootstrap
+    pub bootstrap_compression: BootstrapCompression,
+    /// First version of the servers to which the compression is requested
+    pub compression_version: Version,
+    /// First version of the clients to which the final state hash is sent at the end of the bootstrap
+    pub final_state_hash_version: Version,
+    /// Number of servers of the bootstrap list to stream disjoint ledger ranges from in parallel, 1 to bootstrap from one server at a time
+    pub parallel_bootstrap_servers: usize,
     /// thread count
     pub thread_count: u8,
     /// period per cycle
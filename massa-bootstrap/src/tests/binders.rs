@@ -1,6 +1,9 @@
 use std::str::FromStr;
 
-use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
+use crate::compression::BootstrapCompression;
+use crate::messages::{
+    BootstrapClientMessage, BootstrapServerMessage, BootstrapServerMessageSerializer,
+};
 use crate::types::Duplex;
 use crate::BootstrapConfig;
 use crate::{
@@ -17,7 +20,9 @@ use massa_models::config::{
     MAX_OPERATIONS_PER_BLOCK, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, THREAD_COUNT,
 };
 use massa_models::version::Version;
+use massa_serialization::Serializer;
 use massa_signature::{KeyPair, PublicKey};
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
 use serial_test::serial;
 use tokio::io::duplex;
 
@@ -30,11 +35,23 @@ lazy_static::lazy_static! {
 
 impl BootstrapClientBinder {
     pub fn test_default(client_duplex: Duplex, remote_pubkey: PublicKey) -> Self {
+        BootstrapClientBinder::test_with_max_message_size(
+            client_duplex,
+            remote_pubkey,
+            MAX_BOOTSTRAP_MESSAGE_SIZE,
+        )
+    }
+
+    pub fn test_with_max_message_size(
+        client_duplex: Duplex,
+        remote_pubkey: PublicKey,
+        max_bootstrap_message_size: u32,
+    ) -> Self {
         BootstrapClientBinder::new(
             client_duplex,
             remote_pubkey,
             f64::INFINITY,
-            MAX_BOOTSTRAP_MESSAGE_SIZE,
+            max_bootstrap_message_size,
             ENDORSEMENT_COUNT,
             MAX_ADVERTISE_LENGTH,
             MAX_BOOTSTRAP_BLOCKS,
@@ -324,3 +341,159 @@ async fn test_binders_try_double_send_client_works() {
     server_thread.await.unwrap();
     client_thread.await.unwrap();
 }
+
+/// The client requests a compression once the handshake is done, then the server sends compressed messages
+#[tokio::test]
+#[serial]
+async fn test_binders_compression() {
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+
+    let (client, server) = duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server,
+        server_keypair.clone(),
+        f64::INFINITY,
+        MAX_BOOTSTRAP_MESSAGE_SIZE,
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        CONSENSUS_BOOTSTRAP_PART_SIZE,
+    );
+    let mut client =
+        BootstrapClientBinder::test_default(client, bootstrap_config.bootstrap_list[0].1);
+    let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip(); 100];
+
+    let server_peers = vector_peers.clone();
+    let server_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        server.handshake(version).await.unwrap();
+        let compression = match server.next().await.unwrap() {
+            BootstrapClientMessage::AskBootstrapCompression { compression } => compression,
+            _ => panic!("Bad message receive: Expected a compression request"),
+        };
+        assert_eq!(compression, BootstrapCompression::Lz4);
+        server
+            .send(BootstrapServerMessage::BootstrapCompression { compression })
+            .await
+            .unwrap();
+        server.set_compression(compression);
+        let test_peers_message = BootstrapServerMessage::BootstrapPeers {
+            peers: BootstrapPeers(server_peers),
+        };
+        let negotiation_bytes_sent = server.bytes_sent();
+        server.send(test_peers_message.clone()).await.unwrap();
+        let compressed_bytes_sent = server.bytes_sent() - negotiation_bytes_sent;
+        server.set_compression(BootstrapCompression::None);
+        server.send(test_peers_message).await.unwrap();
+        // the repeated peers compress well
+        assert!(
+            compressed_bytes_sent
+                < server.bytes_sent() - negotiation_bytes_sent - compressed_bytes_sent
+        );
+    });
+
+    let client_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        client.handshake(version).await.unwrap();
+        client
+            .send(&BootstrapClientMessage::AskBootstrapCompression {
+                compression: BootstrapCompression::Lz4,
+            })
+            .await
+            .unwrap();
+        match client.next().await.unwrap() {
+            BootstrapServerMessage::BootstrapCompression { compression } => {
+                assert_eq!(compression, BootstrapCompression::Lz4);
+                client.set_compression(compression);
+            }
+            _ => panic!("Bad message receive: Expected a compression answer"),
+        }
+        match client.next().await.unwrap() {
+            BootstrapServerMessage::BootstrapPeers { peers } => {
+                assert_eq!(vector_peers, peers.0);
+            }
+            _ => panic!("Bad message receive: Expected a peers list message"),
+        }
+        client.set_compression(BootstrapCompression::None);
+        match client.next().await.unwrap() {
+            BootstrapServerMessage::BootstrapPeers { peers } => {
+                assert_eq!(vector_peers, peers.0);
+            }
+            _ => panic!("Bad message receive: Expected a peers list message"),
+        }
+    });
+
+    server_thread.await.unwrap();
+    client_thread.await.unwrap();
+}
+
+/// The maximum message size applies to the uncompressed messages:
+/// a message that LZ4 cannot compress is still sent, and a larger one is refused
+#[tokio::test]
+#[serial]
+async fn test_binders_compression_size_limit() {
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+
+    // random characters do not compress
+    let error: String = StdRng::seed_from_u64(35)
+        .sample_iter(&Alphanumeric)
+        .take(200)
+        .map(char::from)
+        .collect();
+    let message = BootstrapServerMessage::BootstrapError { error };
+    let mut message_bytes = Vec::new();
+    BootstrapServerMessageSerializer::new()
+        .serialize(&message, &mut message_bytes)
+        .unwrap();
+    let max_message_size = message_bytes.len() as u32;
+    assert!(BootstrapCompression::Lz4.compress(&message_bytes).len() > max_message_size as usize);
+
+    let (client, server) = duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server,
+        server_keypair.clone(),
+        f64::INFINITY,
+        max_message_size,
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        CONSENSUS_BOOTSTRAP_PART_SIZE,
+    );
+    let mut client = BootstrapClientBinder::test_with_max_message_size(
+        client,
+        bootstrap_config.bootstrap_list[0].1,
+        max_message_size,
+    );
+
+    let server_message = message.clone();
+    let server_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        server.handshake(version).await.unwrap();
+        server.set_compression(BootstrapCompression::Lz4);
+        server.send(server_message).await.unwrap();
+        let too_large_message = BootstrapServerMessage::BootstrapError {
+            error: "x".repeat(max_message_size as usize),
+        };
+        assert!(server.send(too_large_message).await.is_err());
+    });
+
+    let client_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        client.handshake(version).await.unwrap();
+        client.set_compression(BootstrapCompression::Lz4);
+        match (client.next().await.unwrap(), message) {
+            (
+                BootstrapServerMessage::BootstrapError { error },
+                BootstrapServerMessage::BootstrapError { error: sent_error },
+            ) => assert_eq!(error, sent_error),
+            _ => panic!("Bad message receive: Expected an error message"),
+        }
+    });
+
+    server_thread.await.unwrap();
+    client_thread.await.unwrap();
+}
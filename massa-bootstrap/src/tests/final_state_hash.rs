@@ -0,0 +1,420 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bitvec::prelude::*;
+use massa_async_pool::{AsyncPool, AsyncPoolConfig};
+use massa_consensus_exports::bootstrapable_graph::BootstrapableGraph;
+use massa_executed_ops::{ExecutedOps, ExecutedOpsConfig};
+use massa_final_state::{
+    test_exports::create_final_state, FinalState, FinalStateConfig, StateChanges,
+};
+use massa_hash::Hash;
+use massa_ledger_exports::{
+    LedgerChanges, LedgerConfig, LedgerEntry, LedgerEntryUpdate, SetOrKeep, SetUpdateOrDelete,
+};
+use massa_ledger_worker::FinalLedger;
+use massa_models::config::{
+    BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CONSENSUS_BOOTSTRAP_PART_SIZE, MAX_ASYNC_MESSAGE_DATA,
+    MAX_ASYNC_POOL_LENGTH, MAX_BOOTSTRAP_MESSAGE_SIZE, MAX_DATASTORE_KEY_LENGTH, POS_SAVED_CYCLES,
+};
+use massa_models::{
+    address::Address, amount::Amount, slot::Slot, streaming_step::StreamingStep, version::Version,
+};
+use massa_pos_exports::{
+    test_exports::MockSelectorController, DeferredCredits, PoSChanges, PoSConfig, PoSFinalState,
+    ProductionStats,
+};
+use massa_signature::KeyPair;
+use parking_lot::RwLock;
+use tempfile::TempDir;
+use tokio::io::duplex;
+
+use crate::client::stream_final_state_and_consensus;
+use crate::client_binder::BootstrapClientBinder;
+use crate::error::BootstrapError;
+use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
+use crate::resume::{BootstrapResumeJournal, LedgerCheckpoint};
+use crate::server_binder::BootstrapServerBinder;
+use crate::tests::tools::{get_bootstrap_config, get_random_address};
+use crate::GlobalBootstrapState;
+
+const THREAD_COUNT: u8 = 2;
+const PERIODS_PER_CYCLE: u64 = 10;
+
+fn get_final_state_config(temp_dir: &TempDir) -> FinalStateConfig {
+    FinalStateConfig {
+        ledger_config: LedgerConfig {
+            thread_count: THREAD_COUNT,
+            initial_ledger_path: "".into(),
+            disk_ledger_path: temp_dir.path().to_path_buf(),
+            max_key_length: MAX_DATASTORE_KEY_LENGTH,
+            max_ledger_part_size: 100_000,
+        },
+        async_pool_config: AsyncPoolConfig {
+            thread_count: THREAD_COUNT,
+            max_length: MAX_ASYNC_POOL_LENGTH,
+            max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
+            bootstrap_part_size: 100,
+        },
+        pos_config: PoSConfig {
+            periods_per_cycle: PERIODS_PER_CYCLE,
+            thread_count: THREAD_COUNT,
+            cycle_history_length: POS_SAVED_CYCLES,
+            credits_bootstrap_part_size: 100,
+        },
+        executed_ops_config: ExecutedOpsConfig {
+            thread_count: THREAD_COUNT,
+            bootstrap_part_size: 10,
+        },
+        final_history_length: 2,
+        initial_seed_string: "".into(),
+        initial_rolls_path: "".into(),
+        thread_count: THREAD_COUNT,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+    }
+}
+
+/// Creates an empty final state at the last genesis slot, with its initial cycle
+fn get_genesis_final_state(temp_dir: &TempDir) -> FinalState {
+    let config = get_final_state_config(temp_dir);
+    let (selector_controller, _) = MockSelectorController::new_with_receiver();
+    let mut pos_state = PoSFinalState::new(
+        config.pos_config.clone(),
+        "",
+        &"../massa-node/base_config/initial_rolls.json".into(),
+        selector_controller,
+    )
+    .unwrap();
+    pos_state.create_initial_cycle();
+    create_final_state(
+        config.clone(),
+        Slot::new(0, THREAD_COUNT - 1),
+        Box::new(FinalLedger::new(config.ledger_config.clone())),
+        AsyncPool::new(config.async_pool_config.clone()),
+        VecDeque::new(),
+        pos_state,
+        ExecutedOps::new(config.executed_ops_config),
+    )
+}
+
+fn get_ledger_entry(balance: &str) -> LedgerEntry {
+    LedgerEntry {
+        balance: Amount::from_str(balance).unwrap(),
+        bytecode: Vec::default(),
+        datastore: BTreeMap::default(),
+    }
+}
+
+fn get_pos_changes(seed_bit: bool, production_stats: Vec<(Address, u64)>) -> PoSChanges {
+    PoSChanges {
+        seed_bits: bitvec![u8, Lsb0; seed_bit as u8],
+        roll_changes: Default::default(),
+        production_stats: production_stats
+            .into_iter()
+            .map(|(addr, block_success_count)| {
+                (
+                    addr,
+                    ProductionStats {
+                        block_success_count,
+                        block_failure_count: 0,
+                    },
+                )
+            })
+            .collect(),
+        staking_delegate_changes: Default::default(),
+        deferred_credits: DeferredCredits::default(),
+    }
+}
+
+/// The final state hash only depends on the state it is computed from,
+/// and the hashes of the previous slots are kept in the history.
+#[test]
+fn test_final_state_hash_composition_and_history() {
+    let addr_x = get_random_address();
+    let addr_y = get_random_address();
+    let (slot_a, slot_b) = (Slot::new(1, 0), Slot::new(1, 1));
+
+    // first path: the entries and the production stats are built across two slots
+    let temp_dir_1 = TempDir::new().unwrap();
+    let mut final_state_1 = get_genesis_final_state(&temp_dir_1);
+    let mut ledger_changes = LedgerChanges::default();
+    ledger_changes
+        .0
+        .insert(addr_x, SetUpdateOrDelete::Set(get_ledger_entry("10")));
+    final_state_1.finalize(
+        slot_a,
+        StateChanges {
+            ledger_changes,
+            pos_changes: get_pos_changes(true, vec![(addr_x, 1)]),
+            ..Default::default()
+        },
+    );
+    let mut ledger_changes = LedgerChanges::default();
+    ledger_changes.0.insert(
+        addr_x,
+        SetUpdateOrDelete::Update(LedgerEntryUpdate {
+            balance: SetOrKeep::Set(Amount::from_str("20").unwrap()),
+            ..Default::default()
+        }),
+    );
+    ledger_changes
+        .0
+        .insert(addr_y, SetUpdateOrDelete::Set(get_ledger_entry("5")));
+    final_state_1.finalize(
+        slot_b,
+        StateChanges {
+            ledger_changes,
+            pos_changes: get_pos_changes(false, vec![(addr_x, 1)]),
+            ..Default::default()
+        },
+    );
+
+    // second path: the same entries and production stats are set at once
+    let temp_dir_2 = TempDir::new().unwrap();
+    let mut final_state_2 = get_genesis_final_state(&temp_dir_2);
+    let mut ledger_changes = LedgerChanges::default();
+    ledger_changes
+        .0
+        .insert(addr_x, SetUpdateOrDelete::Set(get_ledger_entry("20")));
+    ledger_changes
+        .0
+        .insert(addr_y, SetUpdateOrDelete::Set(get_ledger_entry("5")));
+    final_state_2.finalize(
+        slot_a,
+        StateChanges {
+            ledger_changes,
+            pos_changes: get_pos_changes(true, vec![(addr_x, 2)]),
+            ..Default::default()
+        },
+    );
+    final_state_2.finalize(
+        slot_b,
+        StateChanges {
+            pos_changes: get_pos_changes(false, Vec::new()),
+            ..Default::default()
+        },
+    );
+
+    // identical states have the same hash
+    let state_hash = final_state_1.compute_state_hash();
+    assert_eq!(state_hash, final_state_2.compute_state_hash());
+    assert_eq!(final_state_1.final_state_hash, state_hash);
+    assert_eq!(final_state_2.final_state_hash, state_hash);
+
+    // the hash commits to the ledger root followed by the component hashes
+    let mut hash_concat = final_state_1.ledger.get_ledger_root().to_bytes().to_vec();
+    for hash in final_state_1.get_state_hash_components() {
+        hash_concat.extend(hash.to_bytes());
+    }
+    assert_eq!(Hash::compute_from(&hash_concat), state_hash);
+
+    // history lookup
+    for final_state in [&final_state_1, &final_state_2] {
+        assert_eq!(final_state.get_state_hash(slot_b), Some(state_hash));
+        assert_eq!(
+            final_state.final_state_hash_history.back(),
+            Some(&(slot_b, state_hash))
+        );
+        assert!(final_state.get_state_hash(Slot::new(0, 0)).is_none());
+        assert!(final_state.get_state_hash(Slot::new(2, 0)).is_none());
+    }
+    let hash_1_at_slot_a = final_state_1.get_state_hash(slot_a).unwrap();
+    let hash_2_at_slot_a = final_state_2.get_state_hash(slot_a).unwrap();
+    assert_ne!(
+        hash_1_at_slot_a, hash_2_at_slot_a,
+        "the states at the output of the first slot differ"
+    );
+    assert_ne!(hash_1_at_slot_a, state_hash);
+
+    // the history is limited to `final_history_length` slots
+    final_state_1.finalize(
+        Slot::new(2, 0),
+        StateChanges {
+            pos_changes: get_pos_changes(false, Vec::new()),
+            ..Default::default()
+        },
+    );
+    assert!(final_state_1.get_state_hash(slot_a).is_none());
+    assert_eq!(final_state_1.get_state_hash(slot_b), Some(state_hash));
+}
+
+/// Streams a bootstrap ending with the given server state hash to a client holding `final_state`.
+/// Returns the result of the stream and the message the client would send next.
+async fn stream_final_state_with_hash(
+    final_state: Arc<RwLock<FinalState>>,
+    resume_journal: &mut BootstrapResumeJournal,
+    server_slot: Slot,
+    server_hash: Hash,
+) -> (Result<(), BootstrapError>, BootstrapClientMessage) {
+    let server_keypair = KeyPair::generate();
+    let cfg = get_bootstrap_config(server_keypair.get_public_key());
+    let version = Version::from_str("TEST.1.10").unwrap();
+    let (client_duplex, server_duplex) = duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server_duplex,
+        server_keypair.clone(),
+        f64::INFINITY,
+        MAX_BOOTSTRAP_MESSAGE_SIZE,
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        CONSENSUS_BOOTSTRAP_PART_SIZE,
+    );
+    let mut client =
+        BootstrapClientBinder::test_default(client_duplex, server_keypair.get_public_key());
+
+    let server_thread = tokio::spawn(async move {
+        server.handshake(version).await.unwrap();
+        match server.next().await.unwrap() {
+            BootstrapClientMessage::AskBootstrapPart { .. } => {}
+            other => panic!("unexpected client message: {:?}", other),
+        }
+        server
+            .send(BootstrapServerMessage::BootstrapFinishedWithHash {
+                slot: server_slot,
+                final_state_hash: server_hash,
+            })
+            .await
+            .unwrap();
+    });
+
+    client.handshake(version).await.unwrap();
+    let mut global_bootstrap_state = GlobalBootstrapState::new(final_state);
+    let mut next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
+        last_slot: Some(server_slot),
+        last_ledger_step: StreamingStep::Finished(None),
+        last_pool_step: StreamingStep::Finished(None),
+        last_cycle_step: StreamingStep::Finished(None),
+        last_credits_step: StreamingStep::Finished(None),
+        last_ops_step: StreamingStep::Finished(None),
+        last_consensus_step: StreamingStep::Finished(None),
+    };
+    let result = stream_final_state_and_consensus(
+        &cfg,
+        &mut client,
+        &mut next_bootstrap_message,
+        &mut global_bootstrap_state,
+        resume_journal,
+    )
+    .await;
+    server_thread.await.unwrap();
+    (result, next_bootstrap_message)
+}
+
+/// Creates a client final state holding one ledger entry, with a resume journal on disk
+fn get_bootstrapped_client(
+    temp_dir: &TempDir,
+) -> (Arc<RwLock<FinalState>>, BootstrapResumeJournal, Address) {
+    let addr = get_random_address();
+    let mut final_state = get_genesis_final_state(temp_dir);
+    let mut ledger_changes = LedgerChanges::default();
+    ledger_changes
+        .0
+        .insert(addr, SetUpdateOrDelete::Set(get_ledger_entry("10")));
+    final_state.finalize(
+        Slot::new(1, 0),
+        StateChanges {
+            ledger_changes,
+            pos_changes: get_pos_changes(true, Vec::new()),
+            ..Default::default()
+        },
+    );
+
+    let mut cfg = get_bootstrap_config(KeyPair::generate().get_public_key());
+    cfg.bootstrap_resume_file = temp_dir.path().join("bootstrap_resume.bin");
+    let mut resume_journal = BootstrapResumeJournal::new(&cfg);
+    resume_journal.append(
+        &BootstrapServerMessage::BootstrapPart {
+            slot: Slot::new(1, 0),
+            ledger_part: Vec::new(),
+            async_pool_part: Default::default(),
+            pos_cycle_part: None,
+            pos_credits_part: DeferredCredits::default(),
+            exec_ops_part: Default::default(),
+            final_state_changes: Vec::new(),
+            consensus_part: BootstrapableGraph {
+                final_blocks: Vec::new(),
+            },
+            consensus_outdated_ids: Default::default(),
+        },
+        &LedgerCheckpoint {
+            last_ledger_step: StreamingStep::Finished(None),
+            ledger_hash: final_state.ledger.get_ledger_hash(),
+        },
+    );
+    assert!(cfg.bootstrap_resume_file.exists());
+    (Arc::new(RwLock::new(final_state)), resume_journal, addr)
+}
+
+/// A final state hash mismatch at the end of the stream resets the bootstrap
+#[tokio::test]
+async fn test_final_state_hash_mismatch_resets_bootstrap() {
+    let temp_dir = TempDir::new().unwrap();
+    let resume_file = temp_dir.path().join("bootstrap_resume.bin");
+    let (final_state, mut resume_journal, addr) = get_bootstrapped_client(&temp_dir);
+    let slot = final_state.read().slot;
+
+    let (result, next_bootstrap_message) = stream_final_state_with_hash(
+        final_state.clone(),
+        &mut resume_journal,
+        slot,
+        Hash::compute_from(b"not the client state"),
+    )
+    .await;
+
+    assert!(
+        matches!(result, Err(BootstrapError::FinalStateHashMismatch(_))),
+        "unexpected stream result: {:?}",
+        result
+    );
+    assert!(
+        matches!(
+            next_bootstrap_message,
+            BootstrapClientMessage::AskBootstrapPart {
+                last_slot: None,
+                last_ledger_step: StreamingStep::Started,
+                last_pool_step: StreamingStep::Started,
+                last_cycle_step: StreamingStep::Started,
+                last_credits_step: StreamingStep::Started,
+                last_ops_step: StreamingStep::Started,
+                last_consensus_step: StreamingStep::Started,
+            }
+        ),
+        "the bootstrap was not restarted from scratch: {:?}",
+        next_bootstrap_message
+    );
+    let read_final_state = final_state.read();
+    assert_eq!(read_final_state.slot, Slot::new(0, THREAD_COUNT - 1));
+    assert!(!read_final_state.ledger.entry_exists(&addr));
+    assert!(read_final_state.pos_state.cycle_history.is_empty());
+    assert!(read_final_state.final_state_hash_history.is_empty());
+    assert!(!resume_file.exists(), "the resume journal was not cleared");
+}
+
+/// A matching final state hash ends the stream and keeps the bootstrapped state
+#[tokio::test]
+async fn test_final_state_hash_match_keeps_bootstrap() {
+    let temp_dir = TempDir::new().unwrap();
+    let (final_state, mut resume_journal, addr) = get_bootstrapped_client(&temp_dir);
+    let (slot, state_hash) = {
+        let read_final_state = final_state.read();
+        (read_final_state.slot, read_final_state.compute_state_hash())
+    };
+
+    let (result, next_bootstrap_message) =
+        stream_final_state_with_hash(final_state.clone(), &mut resume_journal, slot, state_hash)
+            .await;
+
+    result.unwrap();
+    assert!(matches!(
+        next_bootstrap_message,
+        BootstrapClientMessage::AskBootstrapPeers
+    ));
+    let read_final_state = final_state.read();
+    assert_eq!(read_final_state.slot, slot);
+    assert_eq!(read_final_state.final_state_hash, state_hash);
+    assert!(read_final_state.ledger.entry_exists(&addr));
+}
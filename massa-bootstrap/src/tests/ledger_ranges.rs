@@ -0,0 +1,101 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use massa_ledger_exports::{
+    LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, SetUpdateOrDelete,
+};
+use massa_ledger_worker::test_exports::create_final_ledger;
+use massa_models::{
+    address::{Address, ADDRESS_SIZE_BYTES},
+    amount::Amount,
+    config::{MAX_DATASTORE_KEY_LENGTH, THREAD_COUNT},
+    slot::Slot,
+};
+
+use crate::client::{filter_reconciled_changes, LedgerRangeSlot};
+
+fn address(first_byte: u8) -> Address {
+    let mut bytes = [1u8; ADDRESS_SIZE_BYTES];
+    bytes[0] = first_byte;
+    Address::from_bytes(&bytes)
+}
+
+fn entry(balance: &str) -> LedgerEntry {
+    LedgerEntry {
+        balance: Amount::from_str(balance).unwrap(),
+        ..Default::default()
+    }
+}
+
+fn ledger_config() -> LedgerConfig {
+    LedgerConfig {
+        thread_count: THREAD_COUNT,
+        initial_ledger_path: "".into(),
+        disk_ledger_path: "".into(),
+        max_key_length: MAX_DATASTORE_KEY_LENGTH,
+        max_ledger_part_size: 100_000,
+    }
+}
+
+#[test]
+fn test_reconcile_address_created_in_window() {
+    let low_address = address(0x10);
+    let high_address = address(0x90);
+    let creation_slot = Slot::new(2, 0);
+    let creations = || {
+        LedgerChanges(
+            [
+                (low_address, SetUpdateOrDelete::Set(entry("10"))),
+                (high_address, SetUpdateOrDelete::Set(entry("20"))),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    };
+
+    // ledger of a server, where both addresses were created once
+    let mut expected_ledger = create_final_ledger(ledger_config(), HashMap::new());
+    expected_ledger.apply_changes(creations(), creation_slot);
+
+    // the low range was streamed before the creations, the high one after them
+    let range_slots: Vec<LedgerRangeSlot> = vec![
+        (None, Some(address(0x80)), Slot::new(1, 0)),
+        (Some(address(0x80)), None, Slot::new(3, 0)),
+    ];
+    let mut bootstrapped_ledger = create_final_ledger(ledger_config(), HashMap::new());
+    bootstrapped_ledger.apply_changes(
+        LedgerChanges(
+            [(high_address, SetUpdateOrDelete::Set(entry("20")))]
+                .into_iter()
+                .collect(),
+        ),
+        creation_slot,
+    );
+
+    // the reconciliation replays the creations from the slot of the low range
+    let reconciled = filter_reconciled_changes(&range_slots, creation_slot, creations());
+    assert_eq!(reconciled.0.len(), 1);
+    assert!(reconciled.0.contains_key(&low_address));
+    bootstrapped_ledger.apply_changes(reconciled, creation_slot);
+    assert_eq!(
+        bootstrapped_ledger.get_ledger_hash(),
+        expected_ledger.get_ledger_hash()
+    );
+    assert_eq!(
+        bootstrapped_ledger.get_ledger_root(),
+        expected_ledger.get_ledger_root()
+    );
+
+    // the changes following every range slot go to all the ranges
+    let later_slot = Slot::new(4, 0);
+    let reconciled = filter_reconciled_changes(&range_slots, later_slot, creations());
+    assert_eq!(reconciled.0.len(), 2);
+    bootstrapped_ledger.apply_changes(reconciled, later_slot);
+    expected_ledger.apply_changes(creations(), later_slot);
+    assert_eq!(
+        bootstrapped_ledger.get_ledger_hash(),
+        expected_ledger.get_ledger_hash()
+    );
+}
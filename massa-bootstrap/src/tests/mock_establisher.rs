@@ -1,10 +1,10 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::white_black_list::SharedWhiteBlackList;
 use massa_models::config::{CHANNEL_SIZE, MAX_DUPLEX_BUFFER_SIZE};
 use massa_time::MassaTime;
-use std::collections::HashSet;
 use std::io;
-use std::net::{IpAddr, SocketAddr};
+use std::net::SocketAddr;
 use tokio::io::DuplexStream;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::timeout;
@@ -38,8 +38,7 @@ pub struct MockListener {
 impl MockListener {
     pub async fn accept(
         &mut self,
-        _whitelist: &Option<HashSet<IpAddr>>,
-        _blacklist: &Option<HashSet<IpAddr>>,
+        _white_black_list: &SharedWhiteBlackList,
     ) -> std::io::Result<(Duplex, SocketAddr)> {
         let (addr, sender) = self.connection_listener_rx.recv().await.ok_or_else(|| {
             io::Error::new(
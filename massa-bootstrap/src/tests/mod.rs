@@ -1,6 +1,11 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 mod binders;
+mod final_state_hash;
+mod ledger_ranges;
 pub mod mock_establisher;
+mod resume;
 mod scenarios;
+mod server_status;
 pub mod tools;
+mod white_black_list;
@@ -0,0 +1,103 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::io::Write;
+
+use massa_consensus_exports::bootstrapable_graph::BootstrapableGraph;
+use massa_hash::Hash;
+use massa_models::{slot::Slot, streaming_step::StreamingStep};
+use massa_pos_exports::DeferredCredits;
+use massa_signature::KeyPair;
+
+use crate::messages::BootstrapServerMessage;
+use crate::resume::{without_ledger_data, BootstrapResumeJournal, LedgerCheckpoint};
+use crate::tests::tools::get_bootstrap_config;
+
+fn get_bootstrap_part(slot: Slot, ledger_part: Vec<u8>) -> BootstrapServerMessage {
+    BootstrapServerMessage::BootstrapPart {
+        slot,
+        ledger_part,
+        async_pool_part: Default::default(),
+        pos_cycle_part: None,
+        pos_credits_part: DeferredCredits::default(),
+        exec_ops_part: Default::default(),
+        final_state_changes: Vec::new(),
+        consensus_part: BootstrapableGraph {
+            final_blocks: Vec::new(),
+        },
+        consensus_outdated_ids: Default::default(),
+    }
+}
+
+#[test]
+fn test_resume_journal() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut cfg = get_bootstrap_config(KeyPair::generate().get_public_key());
+    cfg.bootstrap_resume_file = temp_dir.path().join("bootstrap_resume.bin");
+    let mut journal = BootstrapResumeJournal::new(&cfg);
+
+    // nothing to resume
+    assert_eq!(journal.load(|_, _| Ok(())).unwrap(), None);
+    assert!(!cfg.bootstrap_resume_file.exists());
+
+    let checkpoints = [
+        LedgerCheckpoint {
+            last_ledger_step: StreamingStep::Ongoing(vec![1, 2, 3]),
+            ledger_hash: Hash::compute_from(b"first"),
+        },
+        LedgerCheckpoint {
+            last_ledger_step: StreamingStep::Finished(None),
+            ledger_hash: Hash::compute_from(b"second"),
+        },
+    ];
+    for (slot, checkpoint) in [Slot::new(1, 0), Slot::new(1, 1)].iter().zip(&checkpoints) {
+        let part = without_ledger_data(&get_bootstrap_part(*slot, vec![7; 100])).unwrap();
+        journal.append(&part, checkpoint);
+    }
+    journal.disable();
+    let journal_len = std::fs::metadata(&cfg.bootstrap_resume_file).unwrap().len();
+
+    // simulate a write interrupted by a node shutdown
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&cfg.bootstrap_resume_file)
+        .unwrap()
+        .write_all(&[0, 0, 1])
+        .unwrap();
+
+    let mut replayed = Vec::new();
+    let last_checkpoint = journal
+        .load(|part, checkpoint| {
+            replayed.push((part, checkpoint.clone()));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(last_checkpoint.as_ref(), checkpoints.last());
+    assert_eq!(replayed.len(), 2);
+    for ((part, checkpoint), (expected_slot, expected_checkpoint)) in replayed
+        .into_iter()
+        .zip([Slot::new(1, 0), Slot::new(1, 1)].iter().zip(&checkpoints))
+    {
+        assert_eq!(&checkpoint, expected_checkpoint);
+        match part {
+            BootstrapServerMessage::BootstrapPart {
+                slot, ledger_part, ..
+            } => {
+                assert_eq!(slot, *expected_slot);
+                assert!(
+                    ledger_part.is_empty(),
+                    "the ledger data must be left to the disk ledger"
+                );
+            }
+            other => panic!("unexpected journal record: {:?}", other),
+        }
+    }
+    assert_eq!(
+        std::fs::metadata(&cfg.bootstrap_resume_file).unwrap().len(),
+        journal_len,
+        "the truncated record was not dropped"
+    );
+
+    journal.clear();
+    assert!(!cfg.bootstrap_resume_file.exists());
+    assert_eq!(journal.load(|_, _| Ok(())).unwrap(), None);
+}
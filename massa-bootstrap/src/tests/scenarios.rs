@@ -14,6 +14,7 @@ use crate::BootstrapConfig;
 use crate::{
     get_state, start_bootstrap_server,
     tests::tools::{assert_eq_bootstrap_graph, get_bootstrap_config},
+    SharedBootstrapServerStatus, SharedWhiteBlackList,
 };
 use massa_async_pool::AsyncPoolConfig;
 use massa_consensus_exports::{
@@ -150,6 +151,12 @@ async fn test_bootstrap_server() {
         keypair.clone(),
         0,
         Version::from_str("TEST.1.10").unwrap(),
+        SharedWhiteBlackList::new(
+            bootstrap_config.bootstrap_whitelist_file.clone(),
+            bootstrap_config.bootstrap_blacklist_file.clone(),
+        )
+        .unwrap(),
+        SharedBootstrapServerStatus::new(),
     )
     .await
     .unwrap()
@@ -262,8 +269,6 @@ async fn test_bootstrap_server() {
     });
 
     // launch the modifier thread
-    let list_changes: Arc<RwLock<Vec<(Slot, StateChanges)>>> = Arc::new(RwLock::new(Vec::new()));
-    let list_changes_clone = list_changes.clone();
     std::thread::spawn(move || {
         for _ in 0..10 {
             std::thread::sleep(Duration::from_millis(500));
@@ -276,11 +281,26 @@ async fn test_bootstrap_server() {
                 async_pool_changes: get_random_async_pool_changes(10),
                 executed_ops_changes: get_random_executed_ops_changes(10),
             };
+            // apply the changes to the server state like a slot finalization would
+            final_write
+                .pos_state
+                .apply_changes(changes.pos_changes.clone(), next, false)
+                .unwrap();
+            final_write
+                .ledger
+                .apply_changes(changes.ledger_changes.clone(), next);
+            final_write
+                .async_pool
+                .apply_changes_unchecked(&changes.async_pool_changes);
             final_write
-                .changes_history
-                .push_back((next, changes.clone()));
-            let mut list_changes_write = list_changes_clone.write();
-            list_changes_write.push((next, changes));
+                .executed_ops
+                .apply_changes(changes.executed_ops_changes.clone(), next);
+            final_write.changes_history.push_back((next, changes));
+            let final_state_hash = final_write.compute_state_hash();
+            final_write.final_state_hash = final_state_hash;
+            final_write
+                .final_state_hash_history
+                .push_back((next, final_state_hash));
         }
     });
 
@@ -295,28 +315,6 @@ async fn test_bootstrap_server() {
     // wait for bridge
     bridge.await.expect("bridge join failed");
 
-    // apply the changes to the server state before matching with the client
-    {
-        let mut final_state_server_write = final_state_server.write();
-        let list_changes_read = list_changes.read().clone();
-        // note: skip the first change to match the update loop behaviour
-        for (slot, change) in list_changes_read.iter().skip(1) {
-            final_state_server_write
-                .pos_state
-                .apply_changes(change.pos_changes.clone(), *slot, false)
-                .unwrap();
-            final_state_server_write
-                .ledger
-                .apply_changes(change.ledger_changes.clone(), *slot);
-            final_state_server_write
-                .async_pool
-                .apply_changes_unchecked(&change.async_pool_changes);
-            final_state_server_write
-                .executed_ops
-                .apply_changes(change.executed_ops_changes.clone(), *slot);
-        }
-    }
-
     // check final states
     assert_eq_final_state(&final_state_server.read(), &final_state_client.read());
     assert_eq_final_state_hash(&final_state_server.read(), &final_state_client.read());
@@ -0,0 +1,28 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_bootstrap_exports::BootstrapStatusController;
+
+use crate::SharedBootstrapServerStatus;
+
+#[test]
+fn test_session_accounting() {
+    let status = SharedBootstrapServerStatus::new();
+    let ip = "10.0.0.1".parse().unwrap();
+
+    let completed = status.start_session(ip, 0);
+    let failed = status.start_session(ip, 0);
+    let dropped = status.start_session(ip, 0);
+    assert_eq!(status.get_sessions().len(), 3);
+    assert_eq!(status.get_stats().active_session_count, 3);
+
+    completed.end(true);
+    failed.end(false);
+    // a session dropped without being ended, for example when the server stops, counts as failed
+    drop(dropped);
+
+    let stats = status.get_stats();
+    assert!(status.get_sessions().is_empty());
+    assert_eq!(stats.active_session_count, 0);
+    assert_eq!(stats.completed_session_count, 1);
+    assert_eq!(stats.failed_session_count, 2);
+}
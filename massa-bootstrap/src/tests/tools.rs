@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use super::mock_establisher::Duplex;
+use crate::compression::BootstrapCompression;
 use crate::settings::BootstrapConfig;
 use bitvec::vec::BitVec;
 use massa_async_pool::test_exports::{create_async_pool, get_random_message};
@@ -38,6 +39,7 @@ use massa_models::{
     operation::OperationId,
     prehash::PreHashMap,
     slot::Slot,
+    version::Version,
     wrapped::Id,
     wrapped::WrappedContent,
 };
@@ -132,6 +134,17 @@ fn get_random_pos_cycles_info(
     (roll_counts, production_stats, rng_seed)
 }
 
+/// generates random staking delegates for some of the given roll owners
+fn get_random_staking_delegates(
+    roll_counts: &BTreeMap<Address, u64>,
+) -> BTreeMap<Address, Address> {
+    roll_counts
+        .keys()
+        .step_by(2)
+        .map(|owner| (*owner, get_random_address()))
+        .collect()
+}
+
 /// generates random PoS deferred credits
 fn get_random_deferred_credits(r_limit: u64) -> DeferredCredits {
     let mut deferred_credits = DeferredCredits::default();
@@ -156,12 +169,14 @@ fn get_random_deferred_credits(r_limit: u64) -> DeferredCredits {
 fn get_random_pos_state(r_limit: u64, pos: PoSFinalState) -> PoSFinalState {
     let mut cycle_history = VecDeque::new();
     let (roll_counts, production_stats, rng_seed) = get_random_pos_cycles_info(r_limit, true);
+    let staking_delegates = get_random_staking_delegates(&roll_counts);
     cycle_history.push_back(CycleInfo::new_with_hash(
         0,
         false,
         roll_counts,
         rng_seed,
         production_stats,
+        staking_delegates,
     ));
     let mut deferred_credits = DeferredCredits::default();
     deferred_credits.final_nested_extend(get_random_deferred_credits(r_limit));
@@ -176,10 +191,15 @@ fn get_random_pos_state(r_limit: u64, pos: PoSFinalState) -> PoSFinalState {
 pub fn get_random_pos_changes(r_limit: u64) -> PoSChanges {
     let deferred_credits = get_random_deferred_credits(r_limit);
     let (roll_counts, production_stats, seed_bits) = get_random_pos_cycles_info(r_limit, false);
+    let staking_delegate_changes = get_random_staking_delegates(&roll_counts)
+        .into_iter()
+        .map(|(owner, delegate)| (owner, Some(delegate)))
+        .collect();
     PoSChanges {
         seed_bits,
         roll_changes: roll_counts.into_iter().collect(),
         production_stats,
+        staking_delegate_changes,
         deferred_credits,
     }
 }
@@ -292,6 +312,7 @@ pub fn get_bootstrap_config(bootstrap_public_key: PublicKey) -> BootstrapConfig
         bootstrap_blacklist_file: std::path::PathBuf::from(
             "../massa-node/base_config/bootstrap_blacklist.json",
         ),
+        bootstrap_resume_file: tempfile::NamedTempFile::new().unwrap().path().to_path_buf(),
         enable_clock_synchronization: true,
         cache_duration: 10000.into(),
         max_simultaneous_bootstraps: 2,
@@ -299,6 +320,10 @@ pub fn get_bootstrap_config(bootstrap_public_key: PublicKey) -> BootstrapConfig
         per_ip_min_interval: 10000.into(),
         max_bytes_read_write: std::f64::INFINITY,
         max_bootstrap_message_size: MAX_BOOTSTRAP_MESSAGE_SIZE,
+        bootstrap_compression: BootstrapCompression::Lz4,
+        compression_version: Version::from_str("TEST.1.0").unwrap(),
+        final_state_hash_version: Version::from_str("TEST.1.0").unwrap(),
+        parallel_bootstrap_servers: 1,
         max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
         randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
         thread_count: THREAD_COUNT,
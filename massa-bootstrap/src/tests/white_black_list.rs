@@ -0,0 +1,57 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::str::FromStr;
+
+use massa_bootstrap_exports::BootstrapListController;
+use massa_models::ip_range::IpRange;
+use tempfile::TempDir;
+
+use crate::SharedWhiteBlackList;
+
+#[test]
+fn test_white_black_list() {
+    let temp_dir = TempDir::new().unwrap();
+    let whitelist_path = temp_dir.path().join("bootstrap_whitelist.json");
+    let blacklist_path = temp_dir.path().join("bootstrap_blacklist.json");
+    std::fs::write(&blacklist_path, r#"["10.0.0.1"]"#).unwrap();
+    let lists = SharedWhiteBlackList::new(whitelist_path.clone(), blacklist_path.clone()).unwrap();
+
+    // no whitelist: every IP that is not blacklisted is allowed
+    assert!(lists.get_whitelist().is_none());
+    assert!(lists.is_ip_allowed(&"10.0.0.2".parse().unwrap()).is_ok());
+    assert!(lists.is_ip_allowed(&"10.0.0.1".parse().unwrap()).is_err());
+    assert!(lists
+        .is_ip_allowed(&"::ffff:10.0.0.1".parse().unwrap())
+        .is_err());
+
+    // whitelist a range and unblacklist an IP
+    lists
+        .add_to_whitelist(vec![IpRange::from_str("10.0.0.0/24").unwrap()])
+        .unwrap();
+    lists
+        .remove_from_blacklist(vec![IpRange::from_str("10.0.0.1").unwrap()])
+        .unwrap();
+    assert!(lists.is_ip_allowed(&"10.0.0.1".parse().unwrap()).is_ok());
+    assert!(lists.is_ip_allowed(&"10.0.1.1".parse().unwrap()).is_err());
+
+    // removing an IP of a whitelisted range splits the range
+    lists
+        .remove_from_whitelist(vec![IpRange::from_str("10.0.0.7").unwrap()])
+        .unwrap();
+    assert!(lists.is_ip_allowed(&"10.0.0.7".parse().unwrap()).is_err());
+    assert!(lists.is_ip_allowed(&"10.0.0.6".parse().unwrap()).is_ok());
+    assert!(lists.is_ip_allowed(&"10.0.0.200".parse().unwrap()).is_ok());
+    assert_eq!(lists.get_whitelist().unwrap().len(), 8);
+
+    // the changes are persisted
+    let reloaded = SharedWhiteBlackList::new(whitelist_path, blacklist_path.clone()).unwrap();
+    assert_eq!(reloaded.get_whitelist(), lists.get_whitelist());
+    assert_eq!(reloaded.get_blacklist(), Some(Vec::new()));
+
+    // external modifications of the files are picked up by the periodic reload
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    std::fs::write(&blacklist_path, r#"["10.0.0.0/30"]"#).unwrap();
+    assert!(lists.is_ip_allowed(&"10.0.0.1".parse().unwrap()).is_ok());
+    lists.reload_if_changed();
+    assert!(lists.is_ip_allowed(&"10.0.0.1".parse().unwrap()).is_err());
+}
@@ -0,0 +1,213 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Bootstrap whitelist and blacklist, shared between the bootstrap server and the private API.
+//!
+//! Each list is stored as a JSON array of IPs and CIDR ranges in its file.
+//! Runtime modifications are written back to the files,
+//! and the bootstrap server reloads the files every `LIST_RELOAD_INTERVAL` if their modification time changed.
+//! A missing whitelist file means that every IP is allowed to bootstrap.
+
+use std::{
+    collections::BTreeSet,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use massa_bootstrap_exports::{BootstrapListController, BootstrapListError};
+use massa_models::ip_range::IpRange;
+use parking_lot::RwLock;
+use tracing::{info, warn};
+
+use crate::{error::BootstrapError, tools::normalize_ip};
+
+/// Interval between two checks of the modification time of the list files
+pub(crate) const LIST_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One of the lists along with its file
+struct ListFile {
+    path: PathBuf,
+    /// `None` if the file does not exist
+    ranges: Option<BTreeSet<IpRange>>,
+    /// modification time of the file when it was last read or written
+    modified: Option<SystemTime>,
+}
+
+/// Gets the modification time of a file, `None` if it does not exist
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl ListFile {
+    /// Loads the list from its file
+    fn load(path: PathBuf) -> Result<Self, BootstrapListError> {
+        let modified = modified_time(&path);
+        let ranges = ListFile::read(&path)?;
+        Ok(ListFile {
+            path,
+            ranges,
+            modified,
+        })
+    }
+
+    fn read(path: &Path) -> Result<Option<BTreeSet<IpRange>>, BootstrapListError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(
+                serde_json::from_str::<BTreeSet<IpRange>>(&content).map_err(|err| {
+                    BootstrapListError::InvalidListFile(format!(
+                        "failed to parse {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?,
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Reloads the list if its file was modified since it was last read or written.
+    /// An invalid file is ignored and the previous list is kept.
+    fn reload_if_changed(&mut self) {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        match ListFile::read(&self.path) {
+            Ok(ranges) => {
+                info!("bootstrap list {} reloaded", self.path.display());
+                self.ranges = ranges;
+            }
+            Err(err) => warn!("{}, keeping the previous list", err),
+        }
+    }
+
+    fn contains(&self, ip: &IpAddr) -> Option<bool> {
+        self.ranges
+            .as_ref()
+            .map(|ranges| ranges.iter().any(|range| range.contains(ip)))
+    }
+
+    /// Adds ranges to the list, creating it if it does not exist, and writes it to its file
+    fn add(&mut self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.reload_if_changed();
+        self.ranges.get_or_insert_with(BTreeSet::new).extend(ranges);
+        self.write()
+    }
+
+    /// Removes ranges from the list and writes it to its file.
+    /// A listed range containing a removed range is replaced by the parts of it that are not removed.
+    fn remove(&mut self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.reload_if_changed();
+        if let Some(list) = self.ranges.as_mut() {
+            for removed in ranges.iter() {
+                *list = list
+                    .iter()
+                    .flat_map(|range| range.subtract(removed))
+                    .collect();
+            }
+            self.write()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self) -> Result<(), BootstrapListError> {
+        let content = serde_json::to_string_pretty(&self.ranges).map_err(|err| {
+            BootstrapListError::InvalidListFile(format!(
+                "failed to serialize {}: {}",
+                self.path.display(),
+                err
+            ))
+        })?;
+        std::fs::write(&self.path, content)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+}
+
+struct WhiteBlackList {
+    whitelist: ListFile,
+    blacklist: ListFile,
+}
+
+/// Bootstrap whitelist and blacklist, cheap to clone
+#[derive(Clone)]
+pub struct SharedWhiteBlackList(Arc<RwLock<WhiteBlackList>>);
+
+impl SharedWhiteBlackList {
+    /// Loads the lists from their files
+    pub fn new(
+        whitelist_path: PathBuf,
+        blacklist_path: PathBuf,
+    ) -> Result<Self, BootstrapListError> {
+        Ok(SharedWhiteBlackList(Arc::new(RwLock::new(
+            WhiteBlackList {
+                whitelist: ListFile::load(whitelist_path)?,
+                blacklist: ListFile::load(blacklist_path)?,
+            },
+        ))))
+    }
+
+    /// Reloads the list files modified since they were last read or written
+    pub(crate) fn reload_if_changed(&self) {
+        let mut lists = self.0.write();
+        lists.whitelist.reload_if_changed();
+        lists.blacklist.reload_if_changed();
+    }
+
+    /// Checks that an IP is allowed to bootstrap
+    pub fn is_ip_allowed(&self, ip: &IpAddr) -> Result<(), BootstrapError> {
+        let ip = normalize_ip(*ip);
+        let lists = self.0.read();
+        if lists.blacklist.contains(&ip) == Some(true) {
+            return Err(BootstrapError::BlackListed(ip.to_string()));
+        }
+        if lists.whitelist.contains(&ip) == Some(false) {
+            return Err(BootstrapError::WhiteListed(ip.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl BootstrapListController for SharedWhiteBlackList {
+    fn get_whitelist(&self) -> Option<Vec<IpRange>> {
+        self.0
+            .read()
+            .whitelist
+            .ranges
+            .as_ref()
+            .map(|ranges| ranges.iter().copied().collect())
+    }
+
+    fn get_blacklist(&self) -> Option<Vec<IpRange>> {
+        self.0
+            .read()
+            .blacklist
+            .ranges
+            .as_ref()
+            .map(|ranges| ranges.iter().copied().collect())
+    }
+
+    fn add_to_whitelist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.0.write().whitelist.add(ranges)
+    }
+
+    fn remove_from_whitelist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.0.write().whitelist.remove(ranges)
+    }
+
+    fn add_to_blacklist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.0.write().blacklist.add(ranges)
+    }
+
+    fn remove_from_blacklist(&self, ranges: Vec<IpRange>) -> Result<(), BootstrapListError> {
+        self.0.write().blacklist.remove(ranges)
+    }
+
+    fn clone_box(&self) -> Box<dyn BootstrapListController> {
+        Box::new(self.clone())
+    }
+}
@@ -6,8 +6,14 @@
 
 use pbkdf2::Params;
 
-/// Cipher version
-pub const VERSION: u32 = 0;
+/// Cipher version of the files whose key is derived with `PBKDF2`
+pub const PBKDF2_VERSION: u32 = 0;
+
+/// Cipher version of the files whose key is derived with `Argon2id`
+pub const ARGON2_VERSION: u32 = 1;
+
+/// Cipher version of the newly encrypted files
+pub const VERSION: u32 = ARGON2_VERSION;
 
 /// AES-GCM-SIV nonce size.
 ///
@@ -23,3 +29,29 @@ pub const HASH_PARAMS: Params = Params {
     rounds: 10_000,
     output_length: 32,
 };
+
+/// `Argon2id` salt size.
+pub const ARGON2_SALT_SIZE: usize = 16;
+
+/// Size of the AES-GCM key derived by `Argon2id`.
+pub const KEY_SIZE: usize = 32;
+
+/// Default `Argon2id` memory cost, in KiB.
+pub const DEFAULT_MEMORY_COST: u32 = 64 * 1024;
+
+/// Default `Argon2id` time cost, in passes over the memory.
+pub const DEFAULT_TIME_COST: u32 = 3;
+
+/// Default `Argon2id` parallelism.
+pub const DEFAULT_PARALLELISM: u32 = 1;
+
+/// Highest `Argon2id` memory cost accepted in a file header, in KiB (1 GiB).
+///
+/// A corrupted or forged header must not make the decryption exhaust the memory.
+pub const MAX_MEMORY_COST: u32 = 1024 * 1024;
+
+/// Highest `Argon2id` time cost accepted in a file header.
+pub const MAX_TIME_COST: u32 = 64;
+
+/// Highest `Argon2id` parallelism accepted in a file header.
+pub const MAX_PARALLELISM: u32 = 16;
@@ -11,25 +11,49 @@ use pbkdf2::{
     Pbkdf2,
 };
 
-use crate::constants::{HASH_PARAMS, NONCE_SIZE, SALT_SIZE};
+use crate::constants::{ARGON2_SALT_SIZE, HASH_PARAMS, NONCE_SIZE, SALT_SIZE};
 use crate::error::CipherError;
-use massa_serialization::{DeserializeError, Deserializer, U32VarIntDeserializer};
-
-use std::ops::Bound::Included;
+use crate::params::CipherHeader;
 
 /// Decryption function using AES-GCM cipher.
+/// Reads the files of every cipher version, and returns their header with the decrypted data.
 ///
 /// Read `lib.rs` module documentation for more information.
-pub fn decrypt(password: &str, data: &[u8]) -> Result<(u32, Vec<u8>), CipherError> {
-    // parse cipher version
-    let (rest, version) = U32VarIntDeserializer::new(Included(0), Included(u32::MAX))
-        .deserialize::<DeserializeError>(data)
-        .map_err(|_| {
-            CipherError::DecryptionError(
-                "wallet file truncated: version missing or incomplete".to_string(),
+pub fn decrypt(password: &str, data: &[u8]) -> Result<(CipherHeader, Vec<u8>), CipherError> {
+    let (rest, header) = CipherHeader::parse(data)?;
+    let (key, rest) = match header.argon2_params {
+        Some(params) => {
+            // parse Argon2id salt
+            let salt = rest.get(..ARGON2_SALT_SIZE).ok_or_else(|| {
+                CipherError::DecryptionError(
+                    "wallet file truncated: salt missing or incomplete".to_string(),
+                )
+            })?;
+            (
+                params.derive_key(password, salt)?.to_vec(),
+                &rest[ARGON2_SALT_SIZE..],
             )
-        })?;
+        }
+        None => (pbkdf2_key(password, rest)?, &rest[SALT_SIZE..]),
+    };
+
+    // parse AES-GCM nonce
+    let nonce = Nonce::from_slice(rest.get(..NONCE_SIZE).ok_or_else(|| {
+        CipherError::DecryptionError(
+            "wallet file truncated: nonce missing or incomplete".to_string(),
+        )
+    })?);
+
+    // decrypt the data
+    let cipher = Aes256Gcm::new_from_slice(&key).expect("invalid size key");
+    let decrypted_bytes = cipher.decrypt(nonce, &rest[NONCE_SIZE..]).map_err(|_| {
+        CipherError::DecryptionError("wrong password or corrupted data".to_string())
+    })?;
+    Ok((header, decrypted_bytes))
+}
 
+/// Key of the version 0 files, derived with `PBKDF2` from the salt at the start of `rest`
+fn pbkdf2_key(password: &str, rest: &[u8]) -> Result<Vec<u8>, CipherError> {
     // parse PBKDF2 salt
     let salt_data = rest.get(..SALT_SIZE).ok_or_else(|| {
         CipherError::DecryptionError(
@@ -45,28 +69,77 @@ pub fn decrypt(password: &str, data: &[u8]) -> Result<(u32, Vec<u8>), CipherErro
         .map_err(|e| CipherError::DecryptionError(e.to_string()))?
         .hash
         .expect("content is missing after a successful hash");
+    Ok(password_hash.as_bytes().to_vec())
+}
 
-    // parse AES-GCM nonce
-    let nonce_end_index = SALT_SIZE + NONCE_SIZE;
-    let nonce = Nonce::from_slice(rest.get(SALT_SIZE..nonce_end_index).ok_or_else(|| {
-        CipherError::DecryptionError(
-            "wallet file truncated: nonce missing or incomplete".to_string(),
-        )
-    })?);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{
+        ARGON2_VERSION, MAX_MEMORY_COST, MAX_PARALLELISM, MAX_TIME_COST, PBKDF2_VERSION, VERSION,
+    };
+    use crate::encrypt::encrypt_with_params;
+    use crate::params::{read_header, Argon2Params};
+    use massa_serialization::{Serializer, U32VarIntSerializer};
 
-    // decrypt the data
-    let cipher = Aes256Gcm::new_from_slice(password_hash.as_bytes()).expect("invalid size key");
-    let decrypted_bytes = cipher
-        .decrypt(
-            nonce,
-            rest.get(nonce_end_index..).ok_or_else(|| {
-                CipherError::DecryptionError(
-                    "wallet file truncated: encrypted data missing or incomplete".to_string(),
-                )
-            })?,
-        )
-        .map_err(|_| {
-            CipherError::DecryptionError("wrong password or corrupted data".to_string())
-        })?;
-    Ok((version, decrypted_bytes))
+    /// "massa cipher v0 fixture" encrypted by the version 0 cipher with the password "v0 password"
+    const V0_FIXTURE: [u8; 64] = [
+        0x00, 0x4d, 0x61, 0x35, 0x35, 0x61, 0x57, 0x34, 0x4c, 0x4c, 0x65, 0x74, 0x30, 0x01, 0x02,
+        0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x49, 0x8d, 0x98, 0x27, 0x1a,
+        0xda, 0x5f, 0xc0, 0x88, 0x14, 0x52, 0x09, 0xb5, 0xf5, 0x0d, 0x0a, 0x02, 0xd1, 0x62, 0x2b,
+        0xc1, 0x25, 0x40, 0xd2, 0x6b, 0x9d, 0xaa, 0xd2, 0xa8, 0x11, 0xfe, 0xc6, 0xce, 0x0e, 0x94,
+        0x44, 0xdc, 0x74, 0xcc,
+    ];
+
+    #[test]
+    fn test_decrypt_v0_fixture() {
+        let (header, decrypted) = decrypt("v0 password", &V0_FIXTURE).unwrap();
+        assert_eq!(header.version, PBKDF2_VERSION);
+        assert_eq!(header.argon2_params, None);
+        assert_eq!(decrypted, b"massa cipher v0 fixture");
+        assert!(decrypt("wrong password", &V0_FIXTURE).is_err());
+    }
+
+    #[test]
+    fn test_v1_roundtrip_custom_params() {
+        let params = Argon2Params {
+            memory_cost: 1024,
+            time_cost: 1,
+            parallelism: 2,
+        };
+        let encrypted = encrypt_with_params("password", b"content", &params).unwrap();
+        assert_eq!(read_header(&encrypted).unwrap().argon2_params, Some(params));
+        let (header, decrypted) = decrypt("password", &encrypted).unwrap();
+        assert_eq!(header.version, VERSION);
+        assert_eq!(header.argon2_params, Some(params));
+        assert_eq!(decrypted, b"content");
+        assert!(decrypt("wrong password", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_reject_out_of_range_params() {
+        for (memory_cost, time_cost, parallelism) in [
+            (0, 1, 1),
+            (MAX_MEMORY_COST + 1, 1, 1),
+            (1024, 0, 1),
+            (1024, MAX_TIME_COST + 1, 1),
+            (1024, 1, 0),
+            (1024, 1, MAX_PARALLELISM + 1),
+        ] {
+            let mut data = Vec::new();
+            let serializer = U32VarIntSerializer::new();
+            for value in [ARGON2_VERSION, memory_cost, time_cost, parallelism] {
+                serializer.serialize(&value, &mut data).unwrap();
+            }
+            // salt, nonce and authentication tag
+            data.extend([0u8; 44]);
+            assert!(read_header(&data).is_err());
+            match decrypt("password", &data) {
+                Err(CipherError::DecryptionError(err)) => {
+                    assert!(err.contains("key derivation parameters"))
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
 }
@@ -12,6 +12,10 @@ pub enum CipherError {
     EncryptionError(String),
     /// Decryption error: {0}
     DecryptionError(String),
+    /// unsupported cipher version {0}
+    UnsupportedVersion(u32),
+    /// invalid key derivation parameters: {0}
+    InvalidParameters(String),
     /// `Utf8` error: {0}
     Utf8Error(#[from] std::str::Utf8Error),
 }
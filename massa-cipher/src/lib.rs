@@ -7,8 +7,14 @@
 //! AES-GCM is a state-of-the-art high-performance Authenticated Encryption with Associated Data (AEAD)
 //! that provides confidentiality and authenticity.
 //!
-//! To hash the password before using it as a cipher key, we use the `PBKDF2` key derivation function
-//! as specified in [RFC 2898](https://datatracker.ietf.org/doc/html/rfc2898).
+//! To hash the password before using it as a cipher key, we use the `Argon2id` key derivation function
+//! as specified in [RFC 9106](https://datatracker.ietf.org/doc/html/rfc9106). Its memory cost,
+//! time cost and parallelism are stored in the header of the encrypted data, after the cipher version.
+//!
+//! The files of cipher version 0 used the `PBKDF2` key derivation function
+//! as specified in [RFC 2898](https://datatracker.ietf.org/doc/html/rfc2898), with 10 000 rounds.
+//! They can still be decrypted, and `decrypt` returns their header so that they can be encrypted
+//! again with the current version.
 //!
 //! The AES-GCM crate we use has received one security audit by NCC Group, with no significant findings.
 
@@ -16,7 +22,10 @@ mod constants;
 mod decrypt;
 mod encrypt;
 mod error;
+mod params;
 
+pub use constants::VERSION;
 pub use decrypt::decrypt;
-pub use encrypt::encrypt;
+pub use encrypt::{encrypt, encrypt_with_params};
 pub use error::CipherError;
+pub use params::{read_header, Argon2Params, CipherHeader};
@@ -0,0 +1,147 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! massa-cipher key derivation parameters.
+//!
+//! Read `lib.rs` module documentation for more information.
+
+use argon2::{Algorithm, Argon2, Version};
+use massa_serialization::{
+    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
+    U32VarIntSerializer,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::Bound::Included;
+
+use crate::constants::{
+    ARGON2_VERSION, DEFAULT_MEMORY_COST, DEFAULT_PARALLELISM, DEFAULT_TIME_COST, KEY_SIZE,
+    MAX_MEMORY_COST, MAX_PARALLELISM, MAX_TIME_COST, PBKDF2_VERSION,
+};
+use crate::error::CipherError;
+
+/// `Argon2id` cost parameters, stored in the header of the encrypted files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Argon2Params {
+    /// memory used, in KiB
+    pub memory_cost: u32,
+    /// number of passes over the memory
+    pub time_cost: u32,
+    /// number of lanes computed in parallel
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Argon2Params {
+            memory_cost: DEFAULT_MEMORY_COST,
+            time_cost: DEFAULT_TIME_COST,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Derive the AES-GCM key from `password` and `salt`
+    pub(crate) fn derive_key(
+        &self,
+        password: &str,
+        salt: &[u8],
+    ) -> Result<[u8; KEY_SIZE], CipherError> {
+        let params = argon2::Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|e| CipherError::InvalidParameters(e.to_string()))?;
+        let mut key = [0u8; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| CipherError::InvalidParameters(e.to_string()))?;
+        Ok(key)
+    }
+
+    /// Serialize the parameters in a file header
+    pub(crate) fn serialize(&self, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        let serializer = U32VarIntSerializer::new();
+        serializer.serialize(&self.memory_cost, buffer)?;
+        serializer.serialize(&self.time_cost, buffer)?;
+        serializer.serialize(&self.parallelism, buffer)
+    }
+
+    /// Deserialize the parameters from a file header, rejecting excessive costs
+    pub(crate) fn deserialize(data: &[u8]) -> Result<(&[u8], Argon2Params), CipherError> {
+        let (rest, memory_cost) =
+            U32VarIntDeserializer::new(Included(1), Included(MAX_MEMORY_COST))
+                .deserialize::<DeserializeError>(data)
+                .map_err(truncated)?;
+        let (rest, time_cost) = U32VarIntDeserializer::new(Included(1), Included(MAX_TIME_COST))
+            .deserialize::<DeserializeError>(rest)
+            .map_err(truncated)?;
+        let (rest, parallelism) =
+            U32VarIntDeserializer::new(Included(1), Included(MAX_PARALLELISM))
+                .deserialize::<DeserializeError>(rest)
+                .map_err(truncated)?;
+        Ok((
+            rest,
+            Argon2Params {
+                memory_cost,
+                time_cost,
+                parallelism,
+            },
+        ))
+    }
+}
+
+/// Error of the key derivation parameters that cannot be read
+fn truncated<E>(_err: E) -> CipherError {
+    CipherError::DecryptionError(
+        "wallet file truncated or corrupted: key derivation parameters".to_string(),
+    )
+}
+
+/// Header of an encrypted file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CipherHeader {
+    /// cipher version
+    pub version: u32,
+    /// `Argon2id` parameters, `None` for the files whose key is derived with `PBKDF2`
+    pub argon2_params: Option<Argon2Params>,
+}
+
+impl CipherHeader {
+    /// Parse the header of `data`, returns the rest of the data with the header
+    pub(crate) fn parse(data: &[u8]) -> Result<(&[u8], CipherHeader), CipherError> {
+        let (rest, version) = U32VarIntDeserializer::new(Included(0), Included(u32::MAX))
+            .deserialize::<DeserializeError>(data)
+            .map_err(|_| {
+                CipherError::DecryptionError(
+                    "wallet file truncated: version missing or incomplete".to_string(),
+                )
+            })?;
+        match version {
+            PBKDF2_VERSION => Ok((
+                rest,
+                CipherHeader {
+                    version,
+                    argon2_params: None,
+                },
+            )),
+            ARGON2_VERSION => {
+                let (rest, params) = Argon2Params::deserialize(rest)?;
+                Ok((
+                    rest,
+                    CipherHeader {
+                        version,
+                        argon2_params: Some(params),
+                    },
+                ))
+            }
+            _ => Err(CipherError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// Read the header of encrypted data, without decrypting it
+pub fn read_header(data: &[u8]) -> Result<CipherHeader, CipherError> {
+    Ok(CipherHeader::parse(data)?.1)
+}
@@ -4,30 +4,36 @@ use crate::repl::Output;
 use anyhow::{anyhow, bail, Result};
 use console::style;
 use massa_models::api::{
-    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, OperationInput,
+    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, LedgerProofInput,
+    LedgerProofSubEntry, SelectionsFilter,
 };
 use massa_models::api::{ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::ip_range::IpRange;
 use massa_models::node::NodeId;
 use massa_models::prehash::PreHashMap;
-use massa_models::timeslots::get_current_latest_block_slot;
 use massa_models::{
     address::Address,
     amount::Amount,
     block::BlockId,
     endorsement::EndorsementId,
-    operation::{Operation, OperationId, OperationType},
+    multisig::MultisigPolicy,
+    operation::{OperationId, OperationType},
     slot::Slot,
 };
-use massa_sdk::Client;
-use massa_signature::KeyPair;
+use massa_sdk::{
+    decode_operation_to_sign, decode_signed_operation, encode_signed_operation, Client,
+    OperationBuilder, OperationToSign, PartiallySignedOperation, SdkError, UnsignedOperation,
+};
+use massa_signature::{KeyPair, PublicKey};
 use massa_time::MassaTime;
-use massa_wallet::Wallet;
+use massa_wallet::hd::DEFAULT_MNEMONIC_WORD_COUNT;
+use massa_wallet::{Argon2Params, Wallet, WalletError};
 use serde::Serialize;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write as _;
 use std::fmt::{Debug, Display};
 use std::net::IpAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumProperty, EnumString};
 
@@ -56,8 +62,8 @@ pub enum Command {
 
     #[strum(
         ascii_case_insensitive,
-        props(args = "IpAddr1 IpAddr2 ..."),
-        message = "ban given IP address(es)"
+        props(args = "IpAddr1 IpAddr2 ... [DurationInSeconds]"),
+        message = "ban given IP address(es), for the given duration if any (until unbanned otherwise)"
     )]
     node_ban_by_ip,
 
@@ -68,6 +74,25 @@ pub enum Command {
     )]
     node_ban_by_id,
 
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the banned peers with the reason and expiry of their ban"
+    )]
+    node_get_banned_peers,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the connected nodes with statistics about their connection"
+    )]
+    node_get_peers,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Id1 Id2 ..."),
+        message = "close the connections to given id(s) without banning them"
+    )]
+    node_disconnect_by_id,
+
     #[strum(ascii_case_insensitive, message = "stops the node")]
     node_stop,
 
@@ -109,6 +134,26 @@ pub enum Command {
     )]
     node_remove_from_whitelist,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[add|remove IpAddr1 IpRange2 ...]"),
+        message = "show the IPs allowed to bootstrap on the node, or add/remove IPs and CIDR ranges (e.g. 192.168.0.0/16) to/from the bootstrap whitelist"
+    )]
+    bootstrap_whitelist,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[add|remove IpAddr1 IpRange2 ...]"),
+        message = "show the IPs not allowed to bootstrap on the node, or add/remove IPs and CIDR ranges (e.g. 192.168.0.0/16) to/from the bootstrap blacklist"
+    )]
+    bootstrap_blacklist,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the sessions currently served by the bootstrap server of the node (peer IP, start time, bytes sent, progress)"
+    )]
+    get_bootstrap_sessions,
+
     #[strum(
         ascii_case_insensitive,
         message = "show the status of the node (reachable? number of peers connected, consensus, version, config parameter summary...)"
@@ -122,6 +167,27 @@ pub enum Command {
     )]
     get_addresses,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Count Address1 Address2 ..."),
+        message = "show the next Count block and endorsement draws of the given addresses (all the wallet addresses if none is given), with estimated timestamps"
+    )]
+    get_next_draws,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address1 Address2 ..."),
+        message = "show the block production statistics, deactivation risk and rewards of the given addresses for every cycle kept by the node (all the wallet addresses if none is given)"
+    )]
+    get_staking_report,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Slot1 Slot2 ..."),
+        message = "show the final state hash at the given final slots (slot format: period,thread), or at the latest final slot if none is given"
+    )]
+    get_final_state_hash,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address Key"),
@@ -129,6 +195,13 @@ pub enum Command {
     )]
     get_datastore_entry,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address [balance|bytecode|datastore Key]"),
+        message = "get a final ledger sub-entry (balance if not specified, datastore key must be UTF-8) with its proof against the final state hash, and check the proof"
+    )]
+    get_ledger_proof,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "BlockId"),
@@ -185,6 +258,41 @@ pub enum Command {
     )]
     wallet_remove_addresses,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[WordCount] [passphrase=Passphrase]"),
+        message = "generate a mnemonic seed phrase (24 words by default), use it as the seed of the wallet and derive its first address"
+    )]
+    wallet_generate_mnemonic,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "AccountCount Word1 Word2 ... [passphrase=Passphrase]"),
+        message = "use a mnemonic seed phrase as the seed of the wallet and derive its first AccountCount addresses"
+    )]
+    wallet_restore_mnemonic,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[AccountIndex]"),
+        message = "derive the address of the given account index (the next one by default) from the wallet seed and add it to the wallet"
+    )]
+    wallet_derive_address,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Threshold PublicKey1 PublicKey2 ..."),
+        message = "add the multisig address whose operations need Threshold signatures among the given public keys to the wallet. It stakes through set_staking_delegate"
+    )]
+    wallet_add_multisig_address,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[MemoryCostKiB TimeCost Parallelism]"),
+        message = "encrypt the wallet file again with the current cipher version, and the given Argon2id key derivation costs (the current ones by default). Wallet files of older versions are also upgraded when they are opened"
+    )]
+    wallet_upgrade_encryption,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address string"),
@@ -192,6 +300,20 @@ pub enum Command {
     )]
     wallet_sign,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "PathToUnsignedOperation PathToSignedOperation"),
+        message = "sign an operation exported by export_unsigned_operation with the key of its creator, which must be in the wallet. For a multisig creator added with wallet_add_multisig_address, the operation can also be partially signed, and all the keys of the wallet that belong to it sign. The node is not contacted. The encoded operation can be given instead of its path as data=EncodedOperation"
+    )]
+    wallet_sign_operation,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "PathToMergedOperation PathToSignedOperation1 PathToSignedOperation2 ..."),
+        message = "merge the signatures collected in parallel by wallet_sign_operation for the same multisig operation. The encoded operations can be given instead of their paths as data=EncodedOperation"
+    )]
+    wallet_merge_signatures,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address RollCount Fee"),
@@ -206,6 +328,13 @@ pub enum Command {
     )]
     sell_rolls,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address DelegateAddress Fee"),
+        message = "let DelegateAddress create blocks and endorsements with the rolls of a wallet address (rewards still go to Address), use \"none\" as DelegateAddress to revoke"
+    )]
+    set_staking_delegate,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "SenderAddress ReceiverAddress Amount Fee"),
@@ -234,6 +363,20 @@ pub enum Command {
     )]
     read_only_smart_contract,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "PathToUnsignedOperation OperationCommand Parameters ..."),
+        message = "prepare the operation of buy_rolls, sell_rolls, set_staking_delegate, send_transaction, send_smart_contract or call_smart_contract without signing it, and write it to a file"
+    )]
+    export_unsigned_operation,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "PathToSignedOperation"),
+        message = "send an operation signed by wallet_sign_operation. The encoded operation can be given instead of its path as data=EncodedOperation"
+    )]
+    send_signed_operation,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "TargetAddress TargetFunction Parameter MaxGas SenderAddress",),
@@ -368,6 +511,50 @@ impl Command {
         wallet: &mut Wallet,
         parameters: &[String],
         json: bool,
+    ) -> Result<Box<dyn Output>> {
+        match self {
+            Command::export_unsigned_operation => {
+                if parameters.len() < 2 {
+                    bail!("wrong number of parameters");
+                }
+                let path = parameters[0].parse::<PathBuf>()?;
+                let command = parameters[1].parse::<Command>()?;
+                if !command.creates_operation() {
+                    bail!("{} does not create an operation", parameters[1]);
+                }
+                command
+                    .run_command(client, wallet, &parameters[2..], json, Some(&path))
+                    .await
+            }
+            _ => {
+                self.run_command(client, wallet, parameters, json, None)
+                    .await
+            }
+        }
+    }
+
+    /// whether the command creates and sends an operation
+    fn creates_operation(&self) -> bool {
+        matches!(
+            self,
+            Command::buy_rolls
+                | Command::sell_rolls
+                | Command::set_staking_delegate
+                | Command::send_transaction
+                | Command::send_smart_contract
+                | Command::call_smart_contract
+        )
+    }
+
+    /// run a given command, the operation it creates is written unsigned
+    /// to `export_path` instead of being sent if it is set
+    async fn run_command(
+        &self,
+        client: &Client,
+        wallet: &mut Wallet,
+        parameters: &[String],
+        json: bool,
+        export_path: Option<&Path>,
     ) -> Result<Box<dyn Output>> {
         match self {
             Command::help => {
@@ -415,8 +602,18 @@ impl Command {
             }
 
             Command::node_ban_by_ip => {
-                let ips = parse_vec::<IpAddr>(parameters)?;
-                match client.private.node_ban_by_ip(ips).await {
+                // an optional ban duration in seconds can follow the IPs
+                let (ips, duration) = match parameters.split_last() {
+                    Some((last, ips)) => match last.parse::<u64>() {
+                        Ok(seconds) => (
+                            parse_vec::<IpAddr>(ips)?,
+                            Some(MassaTime::from_millis(seconds.saturating_mul(1000))),
+                        ),
+                        Err(_) => (parse_vec::<IpAddr>(parameters)?, None),
+                    },
+                    None => (Vec::new(), None),
+                };
+                match client.private.node_ban_by_ip(ips, duration).await {
                     Ok(()) => {
                         if !json {
                             println!("Request of banning successfully sent!")
@@ -429,7 +626,7 @@ impl Command {
 
             Command::node_ban_by_id => {
                 let ids = parse_vec::<NodeId>(parameters)?;
-                match client.private.node_ban_by_id(ids).await {
+                match client.private.node_ban_by_id(ids, None).await {
                     Ok(()) => {
                         if !json {
                             println!("Request of banning successfully sent!")
@@ -440,6 +637,39 @@ impl Command {
                 Ok(Box::new(()))
             }
 
+            Command::node_get_banned_peers => match client.private.get_banned_peers().await {
+                Ok(peers) => {
+                    if peers.is_empty() && !json {
+                        println!("No banned peer");
+                    }
+                    Ok(Box::new(peers))
+                }
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::node_get_peers => match client.private.get_peers().await {
+                Ok(peers) => {
+                    if peers.is_empty() && !json {
+                        println!("No connected peer");
+                    }
+                    Ok(Box::new(peers))
+                }
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::node_disconnect_by_id => {
+                let ids = parse_vec::<NodeId>(parameters)?;
+                match client.private.node_disconnect_by_id(ids).await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Request of disconnection successfully sent!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+
             Command::node_stop => {
                 match client.private.stop_node().await {
                     Ok(()) => {
@@ -532,6 +762,73 @@ impl Command {
                 }
             }
 
+            Command::get_next_draws => {
+                if parameters.is_empty() {
+                    bail!("wrong number of parameters");
+                }
+                let count = parameters[0].parse::<usize>()?;
+                let addresses = if parameters.len() > 1 {
+                    parse_vec::<Address>(&parameters[1..])?
+                } else {
+                    wallet.get_full_wallet().keys().copied().collect()
+                };
+                if addresses.is_empty() {
+                    bail!("no address given and the wallet is empty");
+                }
+                match client
+                    .public
+                    .get_selections(SelectionsFilter {
+                        addresses,
+                        start: None,
+                        end: None,
+                    })
+                    .await
+                {
+                    Ok(mut selections) => {
+                        for selection in selections.iter_mut() {
+                            selection.block_draws.truncate(count);
+                            selection.endorsement_draws.truncate(count);
+                        }
+                        Ok(Box::new(selections))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_staking_report => {
+                let addresses = if parameters.is_empty() {
+                    wallet.get_full_wallet().keys().copied().collect()
+                } else {
+                    parse_vec::<Address>(parameters)?
+                };
+                if addresses.is_empty() {
+                    bail!("no address given and the wallet is empty");
+                }
+                match client.public.get_staking_reports(addresses).await {
+                    Ok(reports) => Ok(Box::new(reports)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_final_state_hash => {
+                if parameters.is_empty() {
+                    return match client.public.get_status().await {
+                        Ok(node_status) => Ok(Box::new(vec![node_status.final_state_hash])),
+                        Err(e) => rpc_error!(e),
+                    };
+                }
+                let slots = parse_vec::<Slot>(parameters)?;
+                match client.public.get_final_state_hash(slots).await {
+                    Ok(hashes) => {
+                        if hashes.is_empty() {
+                            client_warning!("none of the given slots is available on this node");
+                        }
+                        Ok(Box::new(hashes))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_datastore_entry => {
                 if parameters.len() != 2 {
                     bail!("invalid number of parameters");
@@ -548,6 +845,35 @@ impl Command {
                 }
             }
 
+            Command::get_ledger_proof => {
+                let address = match parameters.first() {
+                    Some(address) => address.parse::<Address>()?,
+                    None => bail!("wrong param numbers"),
+                };
+                let sub_entry = match &parameters[1..] {
+                    [] => LedgerProofSubEntry::Balance,
+                    [sub_entry] if sub_entry == "balance" => LedgerProofSubEntry::Balance,
+                    [sub_entry] if sub_entry == "bytecode" => LedgerProofSubEntry::Bytecode,
+                    [sub_entry, key] if sub_entry == "datastore" => {
+                        LedgerProofSubEntry::Datastore(key.as_bytes().to_vec())
+                    }
+                    _ => bail!("invalid sub-entry, expected balance, bytecode or datastore Key"),
+                };
+                match client
+                    .public
+                    .get_ledger_proof(vec![LedgerProofInput { address, sub_entry }])
+                    .await
+                {
+                    Ok(proofs) => {
+                        if proofs.iter().any(|proof| proof.verify().is_err()) {
+                            client_warning!("the ledger proof returned by the node is invalid");
+                        }
+                        Ok(Box::new(proofs))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_block => {
                 if parameters.len() != 1 {
                     bail!("wrong param numbers")
@@ -666,6 +992,206 @@ impl Command {
                 Ok(Box::new(()))
             }
 
+            Command::wallet_generate_mnemonic => {
+                let (passphrase, parameters) = split_passphrase(parameters);
+                let word_count = match parameters {
+                    [] => DEFAULT_MNEMONIC_WORD_COUNT,
+                    [word_count] => word_count.parse::<usize>()?,
+                    _ => bail!("wrong number of parameters"),
+                };
+                let phrase = wallet.generate_mnemonic(word_count, &passphrase)?;
+                if json {
+                    Ok(Box::new(phrase))
+                } else {
+                    client_warning!("write down this mnemonic phrase and keep it secret: it is not stored and it is the only way to restore the derived addresses");
+                    println!("{}", phrase);
+                    if let Some(address) = wallet.hd_accounts.get(&0) {
+                        println!("Derived address {} and added it to the wallet", address);
+                    }
+                    Ok(Box::new(()))
+                }
+            }
+
+            Command::wallet_restore_mnemonic => {
+                let (passphrase, parameters) = split_passphrase(parameters);
+                if parameters.len() < 2 {
+                    bail!("wrong number of parameters");
+                }
+                let account_count = parameters[0].parse::<u32>()?;
+                let addresses = wallet.restore_mnemonic(
+                    &parameters[1..].join(" "),
+                    &passphrase,
+                    account_count,
+                )?;
+                if json {
+                    return Ok(Box::new(addresses));
+                } else {
+                    for address in addresses {
+                        println!("Derived and added address {} to the wallet.", address);
+                    }
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::wallet_derive_address => {
+                let index = match parameters {
+                    [] => None,
+                    [index] => Some(index.parse::<u32>()?),
+                    _ => bail!("wrong number of parameters"),
+                };
+                let (index, address) = wallet.derive_address(index)?;
+                if json {
+                    Ok(Box::new(address.to_string()))
+                } else {
+                    println!(
+                        "Derived account {} address {} and added it to the wallet",
+                        index, address
+                    );
+                    Ok(Box::new(()))
+                }
+            }
+
+            Command::wallet_add_multisig_address => {
+                if parameters.len() < 2 {
+                    bail!("wrong number of parameters");
+                }
+                let threshold = parameters[0].parse::<u32>()?;
+                let public_keys = parse_vec::<PublicKey>(&parameters[1..])?;
+                let address =
+                    wallet.add_multisig_policy(MultisigPolicy::new(threshold, public_keys)?)?;
+                if json {
+                    Ok(Box::new(address.to_string()))
+                } else {
+                    println!("Added multisig address {} to the wallet.", address);
+                    println!("Prepare its operations with export_unsigned_operation, and have them signed with wallet_sign_operation.");
+                    Ok(Box::new(()))
+                }
+            }
+
+            Command::wallet_upgrade_encryption => {
+                let cipher_params = match parameters {
+                    [] => None,
+                    [memory_cost, time_cost, parallelism] => Some(Argon2Params {
+                        memory_cost: memory_cost.parse::<u32>()?,
+                        time_cost: time_cost.parse::<u32>()?,
+                        parallelism: parallelism.parse::<u32>()?,
+                    }),
+                    _ => bail!("wrong number of parameters"),
+                };
+                let previous_version = wallet.upgrade_encryption(cipher_params)?;
+                if !json {
+                    println!(
+                        "Wallet file of cipher version {} encrypted again with Argon2id ({} KiB of memory, {} passes, {} lanes)",
+                        previous_version,
+                        wallet.cipher_params.memory_cost,
+                        wallet.cipher_params.time_cost,
+                        wallet.cipher_params.parallelism
+                    );
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::wallet_sign_operation => {
+                if parameters.len() != 2 {
+                    bail!("wrong number of parameters");
+                }
+                let encoded_operation = read_encoded_operation(&parameters[0]).await?;
+                let mut multisig_operation = match decode_operation_to_sign(&encoded_operation)? {
+                    OperationToSign::Unsigned(unsigned_operation) => {
+                        let creator_address = unsigned_operation.creator_address;
+                        match wallet.find_multisig_policy(&creator_address) {
+                            Some(policy) => unsigned_operation.into_multisig(policy.clone())?,
+                            None => {
+                                let keypair = wallet
+                                    .find_associated_keypair(&creator_address)
+                                    .ok_or(WalletError::MissingKeyError(creator_address))?;
+                                if !json {
+                                    println!(
+                                        "Signing the operation of {}:\n{}",
+                                        creator_address, unsigned_operation.operation
+                                    );
+                                }
+                                let signed_operation =
+                                    encode_signed_operation(&unsigned_operation.sign(keypair)?)?;
+                                tokio::fs::write(
+                                    parameters[1].parse::<PathBuf>()?,
+                                    &signed_operation,
+                                )
+                                .await?;
+                                return Ok(Box::new(signed_operation));
+                            }
+                        }
+                    }
+                    OperationToSign::Multisig(multisig_operation) => multisig_operation,
+                };
+                let creator_address = multisig_operation.creator_address();
+                let signers = wallet.multisig_signers(&multisig_operation.signatures.policy);
+                if signers.is_empty() {
+                    bail!(
+                        "the wallet has none of the keys of the multisig address {}",
+                        creator_address
+                    );
+                }
+                if !json {
+                    println!(
+                        "Signing the operation of the multisig address {} with {} key(s):\n{}",
+                        creator_address,
+                        signers.len(),
+                        multisig_operation.operation
+                    );
+                }
+                for keypair in signers {
+                    multisig_operation.sign(keypair)?;
+                }
+                let signed_operation = multisig_operation.to_bs58_check()?;
+                tokio::fs::write(parameters[1].parse::<PathBuf>()?, &signed_operation).await?;
+                if !json {
+                    print_multisig_progress(&multisig_operation);
+                }
+                Ok(Box::new(signed_operation))
+            }
+
+            Command::wallet_merge_signatures => {
+                if parameters.len() < 3 {
+                    bail!("wrong number of parameters");
+                }
+                let mut merged_operation = PartiallySignedOperation::from_bs58_check(
+                    &read_encoded_operation(&parameters[1]).await?,
+                )?;
+                for parameter in &parameters[2..] {
+                    merged_operation.merge(PartiallySignedOperation::from_bs58_check(
+                        &read_encoded_operation(parameter).await?,
+                    )?)?;
+                }
+                let merged = merged_operation.to_bs58_check()?;
+                tokio::fs::write(parameters[0].parse::<PathBuf>()?, &merged).await?;
+                if !json {
+                    print_multisig_progress(&merged_operation);
+                }
+                Ok(Box::new(merged))
+            }
+
+            Command::export_unsigned_operation => {
+                bail!("export_unsigned_operation cannot be nested")
+            }
+
+            Command::send_signed_operation => {
+                if parameters.len() != 1 {
+                    bail!("wrong number of parameters");
+                }
+                let operation =
+                    decode_signed_operation(&read_encoded_operation(&parameters[0]).await?)?;
+                match client.public.send_operations(vec![operation]).await {
+                    Ok(operation_ids) => {
+                        if !json {
+                            println!("Sent operation IDs:");
+                        }
+                        Ok(Box::new(operation_ids))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::buy_rolls => {
                 if parameters.len() != 3 {
                     bail!("wrong number of parameters");
@@ -716,6 +1242,7 @@ impl Command {
                     fee,
                     addr,
                     json,
+                    export_path,
                 )
                 .await
             }
@@ -750,6 +1277,47 @@ impl Command {
                     fee,
                     addr,
                     json,
+                    export_path,
+                )
+                .await
+            }
+
+            Command::set_staking_delegate => {
+                if parameters.len() != 3 {
+                    bail!("wrong number of parameters");
+                }
+                let addr = parameters[0].parse::<Address>()?;
+                let delegate = if parameters[1].eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(parameters[1].parse::<Address>()?)
+                };
+                let fee = parameters[2].parse::<Amount>()?;
+
+                if !json {
+                    if let Ok(addresses_info) = client.public.get_addresses(vec![addr]).await {
+                        match addresses_info.get(0) {
+                            Some(info) => {
+                                if info.candidate_balance < fee {
+                                    client_warning!("this operation may be rejected due to insufficient balance");
+                                }
+                                if info.candidate_roll_count == 0 {
+                                    client_warning!("this address has no rolls to delegate yet");
+                                }
+                            }
+                            None => client_warning!(format!("address {} not found", addr)),
+                        }
+                    }
+                }
+
+                send_operation(
+                    client,
+                    wallet,
+                    OperationType::SetStakingDelegate { delegate },
+                    fee,
+                    addr,
+                    json,
+                    export_path,
                 )
                 .await
             }
@@ -788,6 +1356,7 @@ impl Command {
                     fee,
                     addr,
                     json,
+                    export_path,
                 )
                 .await
             }
@@ -863,6 +1432,7 @@ impl Command {
                     fee,
                     addr,
                     json,
+                    export_path,
                 )
                 .await
             }
@@ -916,6 +1486,7 @@ impl Command {
                     fee,
                     addr,
                     json,
+                    export_path,
                 )
                 .await
             }
@@ -1011,6 +1582,87 @@ impl Command {
                 }
                 Ok(Box::new(()))
             }
+            Command::bootstrap_whitelist => {
+                if parameters.is_empty() {
+                    return match client.private.node_bootstrap_whitelist().await {
+                        Ok(Some(ranges)) => Ok(Box::new(ranges)),
+                        Ok(None) => {
+                            if !json {
+                                println!(
+                                    "No bootstrap whitelist: every IP is allowed to bootstrap"
+                                );
+                            }
+                            Ok(Box::new(()))
+                        }
+                        Err(e) => rpc_error!(e),
+                    };
+                }
+                let ranges = parse_vec::<IpRange>(&parameters[1..])?;
+                let res = match parameters[0].as_str() {
+                    "add" => client.private.node_add_to_bootstrap_whitelist(ranges).await,
+                    "remove" => {
+                        client
+                            .private
+                            .node_remove_from_bootstrap_whitelist(ranges)
+                            .await
+                    }
+                    _ => bail!("first parameter must be add or remove"),
+                };
+                match res {
+                    Ok(()) => {
+                        if !json {
+                            println!("Bootstrap whitelist successfully updated!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::bootstrap_blacklist => {
+                if parameters.is_empty() {
+                    return match client.private.node_bootstrap_blacklist().await {
+                        Ok(Some(ranges)) => Ok(Box::new(ranges)),
+                        Ok(None) => {
+                            if !json {
+                                println!("No bootstrap blacklist");
+                            }
+                            Ok(Box::new(()))
+                        }
+                        Err(e) => rpc_error!(e),
+                    };
+                }
+                let ranges = parse_vec::<IpRange>(&parameters[1..])?;
+                let res = match parameters[0].as_str() {
+                    "add" => client.private.node_add_to_bootstrap_blacklist(ranges).await,
+                    "remove" => {
+                        client
+                            .private
+                            .node_remove_from_bootstrap_blacklist(ranges)
+                            .await
+                    }
+                    _ => bail!("first parameter must be add or remove"),
+                };
+                match res {
+                    Ok(()) => {
+                        if !json {
+                            println!("Bootstrap blacklist successfully updated!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::get_bootstrap_sessions => {
+                match client.private.get_bootstrap_sessions().await {
+                    Ok(sessions) => {
+                        if sessions.is_empty() && !json {
+                            println!("No active bootstrap session");
+                        }
+                        Ok(Box::new(sessions))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
         }
     }
 }
@@ -1023,45 +1675,38 @@ async fn send_operation(
     fee: Amount,
     addr: Address,
     json: bool,
+    export_path: Option<&Path>,
 ) -> Result<Box<dyn Output>> {
-    let cfg = match client.public.get_status().await {
-        Ok(node_status) => node_status,
-        Err(e) => rpc_error!(e),
+    if let Some(path) = export_path {
+        let multisig_policy = wallet.find_multisig_policy(&addr).cloned();
+        return export_unsigned_operation(client, op, fee, addr, multisig_policy, json, path).await;
     }
-    .config;
-
-    let slot = get_current_latest_block_slot(cfg.thread_count, cfg.t0, cfg.genesis_timestamp, 0)? // clock compensation is zero
-        .unwrap_or_else(|| Slot::new(0, 0));
-    let mut expire_period = slot.period + cfg.operation_validity_periods;
-    if slot.thread >= addr.get_thread(cfg.thread_count) {
-        expire_period += 1;
-    };
 
-    let op = wallet.create_operation(
-        Operation {
-            fee,
-            expire_period,
-            op,
-        },
-        addr,
-    )?;
-
-    match client
-        .public
-        .send_operations(vec![OperationInput {
-            creator_public_key: op.creator_public_key,
-            serialized_content: op.serialized_data,
-            signature: op.signature,
-        }])
+    if wallet.find_multisig_policy(&addr).is_some() {
+        bail!(
+            "{} is a multisig address, prepare its operations with export_unsigned_operation",
+            addr
+        );
+    }
+    let keypair = wallet
+        .find_associated_keypair(&addr)
+        .ok_or(WalletError::MissingKeyError(addr))?;
+
+    // the commands already warn about the balance of the sender
+    match OperationBuilder::new(op)
+        .fee(fee)
+        .check_balance(false)
+        .send(&client.public, keypair)
         .await
     {
-        Ok(operation_ids) => {
+        Ok(operation_id) => {
             if !json {
                 println!("Sent operation IDs:");
             }
-            Ok(Box::new(operation_ids))
+            Ok(Box::new(vec![operation_id]))
         }
-        Err(e) => rpc_error!(e),
+        Err(SdkError::RpcError(e)) => rpc_error!(e),
+        Err(e) => bail!(e),
     }
 }
 
@@ -1071,6 +1716,81 @@ pub fn parse_vec<T: std::str::FromStr>(args: &[String]) -> anyhow::Result<Vec<T>
     args.iter().map(|x| x.parse::<T>()).collect()
 }
 
+/// prepares an operation without signing it and writes it to `path`
+async fn export_unsigned_operation(
+    client: &Client,
+    op: OperationType,
+    fee: Amount,
+    addr: Address,
+    multisig_policy: Option<MultisigPolicy>,
+    json: bool,
+    path: &Path,
+) -> Result<Box<dyn Output>> {
+    // the commands already warn about the balance of the sender
+    let mut builder = OperationBuilder::new(op).fee(fee).check_balance(false);
+    if let Some(policy) = multisig_policy {
+        builder = builder.multisig_policy(policy);
+    }
+    let operation = match builder.build_unsigned(&client.public, addr).await {
+        Ok(operation) => operation,
+        Err(SdkError::RpcError(e)) => rpc_error!(e),
+        Err(e) => bail!(e),
+    };
+    let encoded_operation = UnsignedOperation {
+        creator_address: addr,
+        operation,
+    }
+    .to_bs58_check()?;
+    tokio::fs::write(path, &encoded_operation).await?;
+    if !json {
+        println!(
+            "Unsigned operation written to {}, sign it with wallet_sign_operation:",
+            path.display()
+        );
+    }
+    Ok(Box::new(encoded_operation))
+}
+
+/// tells how many signatures a multisig operation still misses
+fn print_multisig_progress(operation: &PartiallySignedOperation) {
+    let policy = &operation.signatures.policy;
+    if operation.is_complete() {
+        println!("The operation has enough signatures, send it with send_signed_operation.");
+    } else {
+        println!(
+            "{} of the {} required signatures collected, have the other keys sign it with wallet_sign_operation.",
+            operation.signatures.signatures.len(),
+            policy.threshold
+        );
+    }
+}
+
+/// reads an encoded operation from the file at the path `parameter`,
+/// or takes it from the parameter itself if it is given as `data=EncodedOperation`
+async fn read_encoded_operation(parameter: &str) -> Result<String> {
+    if let Some(encoded_operation) = parameter.strip_prefix("data=") {
+        return Ok(encoded_operation.to_string());
+    }
+    let path = Path::new(parameter);
+    if !path.is_file() {
+        bail!(
+            "{} is not a file, give an encoded operation as data=EncodedOperation",
+            parameter
+        );
+    }
+    Ok(tokio::fs::read_to_string(path).await?)
+}
+
+/// separates the optional trailing `passphrase=Passphrase` parameter from the others
+fn split_passphrase(parameters: &[String]) -> (String, &[String]) {
+    match parameters.split_last() {
+        Some((last, rest)) if last.starts_with("passphrase=") => {
+            (last["passphrase=".len()..].to_string(), rest)
+        }
+        _ => (String::new(), parameters),
+    }
+}
+
 /// reads a file
 async fn get_file_as_byte_vec(filename: &std::path::Path) -> Result<Vec<u8>> {
     Ok(tokio::fs::read(filename).await?)
@@ -4,12 +4,12 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 use crate::settings::SETTINGS;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use atty::Stream;
 use cmds::Command;
 use console::style;
 use dialoguer::Password;
-use massa_sdk::{Client, HttpConfig};
+use massa_sdk::{Client, HttpConfig, TlsClientConfig};
 use massa_wallet::Wallet;
 use serde::Serialize;
 use std::net::IpAddr;
@@ -79,6 +79,34 @@ fn ask_password(wallet_path: &Path) -> String {
     }
 }
 
+/// Read the private API token.
+/// A missing file is only warned about, as the node may not require authentication.
+fn read_private_api_token(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(token) => {
+            let token = token.trim();
+            if token.is_empty() {
+                bail!("private API token file {} is empty", path.display());
+            }
+            Ok(Some(token.to_string()))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!(
+                "{}: private API token file {} not found, private API calls will fail if the node requires authentication",
+                style("WARNING").yellow(),
+                path.display()
+            );
+            Ok(None)
+        }
+        Err(err) => Err(err).with_context(|| {
+            format!(
+                "could not read the private API token file {}",
+                path.display()
+            )
+        }),
+    }
+}
+
 #[paw::main]
 fn main(args: Args) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -103,6 +131,15 @@ async fn run(args: Args) -> Result<()> {
         id_kind: SETTINGS.http.id_kind.clone(),
         max_log_length: SETTINGS.http.max_log_length,
         headers: SETTINGS.http.headers.clone(),
+        private_api_token: read_private_api_token(&SETTINGS.default_node.private_api_token_file)?,
+        tls: SETTINGS.http.enable_tls.then(|| TlsClientConfig {
+            server_name: SETTINGS.http.tls_server_name.clone(),
+            pinned_certificates: SETTINGS.http.pinned_certificates.clone(),
+            client_certificate: SETTINGS.http.client_certificate.clone(),
+        }),
+        reconnect_initial_delay: SETTINGS.http.reconnect_initial_delay,
+        reconnect_max_delay: SETTINGS.http.reconnect_max_delay,
+        reconnect_max_attempts: SETTINGS.http.reconnect_max_attempts,
     };
 
     // TODO: move settings loading in another crate ... see #1277
@@ -134,7 +171,7 @@ async fn run(args: Args) -> Result<()> {
     // ...
     let password = args.password.unwrap_or_else(|| ask_password(&args.wallet));
     let mut wallet = Wallet::new(args.wallet, password)?;
-    let client = Client::new(address, public_port, private_port, &http_config).await;
+    let client = Client::new(address, public_port, private_port, &http_config).await?;
     if atty::is(Stream::Stdout) && args.command == Command::help && !args.json {
         // Interactive mode
         repl::run(&client, &mut wallet).await?;
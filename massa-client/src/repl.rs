@@ -6,10 +6,13 @@ use anyhow::Result;
 use console::style;
 use erased_serde::{Serialize, Serializer};
 use massa_models::api::{
-    AddressInfo, BlockInfo, DatastoreEntryOutput, EndorsementInfo, NodeStatus, OperationInfo,
+    AddressInfo, AddressSelections, BannedPeer, BlockInfo, BootstrapSessionInfo, ConnectedPeer,
+    DatastoreEntryOutput, EndorsementInfo, FinalStateHash, LedgerProof, NodeStatus, OperationInfo,
+    StakingReport,
 };
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::ip_range::IpRange;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{address::Address, operation::OperationId};
@@ -107,7 +110,7 @@ pub(crate) async fn run(client: &Client, wallet: &mut Wallet) -> Result<()> {
         validator: MatchingBracketValidator::new(),
     };
     let config = Config::builder()
-        .auto_add_history(true)
+        .auto_add_history(false)
         .completion_prompt_limit(100)
         .completion_type(CompletionType::List)
         .max_history_size(10000)
@@ -124,10 +127,13 @@ pub(crate) async fn run(client: &Client, wallet: &mut Wallet) -> Result<()> {
                 if line.is_empty() {
                     continue;
                 }
-                rl.add_history_entry(line.as_str());
                 let input: Vec<String> =
                     group_parameters(line.split_whitespace().map(|x| x.to_string()).collect());
                 let cmd: Result<Command, ParseError> = input[0].parse();
+                // the words of a mnemonic phrase are kept out of the history and its file
+                if !matches!(cmd, Ok(Command::wallet_restore_mnemonic)) {
+                    rl.add_history_entry(line.as_str());
+                }
                 let parameters = input[1..].to_vec();
                 // Print result of evaluated command
                 match cmd {
@@ -264,6 +270,38 @@ impl Output for Vec<AddressInfo> {
     }
 }
 
+impl Output for Vec<AddressSelections> {
+    fn pretty_print(&self) {
+        for address_selections in self {
+            println!("{}", address_selections);
+        }
+    }
+}
+
+impl Output for Vec<StakingReport> {
+    fn pretty_print(&self) {
+        for report in self {
+            println!("{}", report);
+        }
+    }
+}
+
+impl Output for Vec<FinalStateHash> {
+    fn pretty_print(&self) {
+        for final_state_hash in self {
+            println!("{}", final_state_hash);
+        }
+    }
+}
+
+impl Output for Vec<LedgerProof> {
+    fn pretty_print(&self) {
+        for ledger_proof in self {
+            println!("{}", ledger_proof);
+        }
+    }
+}
+
 impl Output for Vec<DatastoreEntryOutput> {
     fn pretty_print(&self) {
         for data_entry in self {
@@ -296,6 +334,38 @@ impl Output for Vec<OperationId> {
     }
 }
 
+impl Output for Vec<BootstrapSessionInfo> {
+    fn pretty_print(&self) {
+        for session in self {
+            println!("{}", session);
+        }
+    }
+}
+
+impl Output for Vec<BannedPeer> {
+    fn pretty_print(&self) {
+        for peer in self {
+            println!("{}", peer);
+        }
+    }
+}
+
+impl Output for Vec<ConnectedPeer> {
+    fn pretty_print(&self) {
+        for peer in self {
+            println!("{}", peer);
+        }
+    }
+}
+
+impl Output for Vec<IpRange> {
+    fn pretty_print(&self) {
+        for range in self {
+            println!("{}", range);
+        }
+    }
+}
+
 impl Output for Vec<Address> {
     fn pretty_print(&self) {
         for addr in self {
@@ -24,6 +24,7 @@ pub struct DefaultNode {
     pub ip: IpAddr,
     pub private_port: u16,
     pub public_port: u16,
+    pub private_api_token_file: PathBuf,
 }
 
 /// Http Client settings.
@@ -37,6 +38,13 @@ pub struct HttpSettings {
     pub id_kind: String,
     pub max_log_length: u32,
     pub headers: Vec<(String, String)>,
+    pub enable_tls: bool,
+    pub tls_server_name: Option<String>,
+    pub pinned_certificates: Vec<PathBuf>,
+    pub client_certificate: Option<(PathBuf, PathBuf)>,
+    pub reconnect_initial_delay: MassaTime,
+    pub reconnect_max_delay: MassaTime,
+    pub reconnect_max_attempts: u32,
 }
 
 #[cfg(test)]
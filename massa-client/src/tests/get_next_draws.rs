@@ -0,0 +1,184 @@
+use crate::cmds::Command;
+use erased_serde::Serialize;
+use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::types::error::{CallError, ErrorObject};
+use jsonrpsee::RpcModule;
+use massa_models::address::Address;
+use massa_models::api::{AddressSelections, BlockDrawInfo, EndorsementDrawInfo, SelectionsFilter};
+use massa_models::slot::Slot;
+use massa_sdk::{Client, HttpConfig};
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+use massa_wallet::Wallet;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+/// number of addresses above which the stub node rejects the requests
+const MAX_ARGUMENTS: usize = 2;
+
+/// Starts a node answering `get_selections` with three draws of each kind per address.
+/// Returns the node handle, its port and the filters it received.
+async fn start_stub_node() -> (ServerHandle, u16, Arc<Mutex<Vec<SelectionsFilter>>>) {
+    let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+    let port = server.local_addr().unwrap().port();
+    let filters = Arc::new(Mutex::new(Vec::new()));
+    let mut module = RpcModule::new(filters.clone());
+    module
+        .register_method("get_selections", |params, filters| {
+            let filter: SelectionsFilter = params.one()?;
+            filters.lock().unwrap().push(filter.clone());
+            if filter.addresses.len() > MAX_ARGUMENTS {
+                return Err(CallError::Custom(ErrorObject::owned(
+                    -32000,
+                    "bad request: too many arguments",
+                    None::<()>,
+                ))
+                .into());
+            }
+            Ok(filter
+                .addresses
+                .into_iter()
+                .map(|address| AddressSelections {
+                    address,
+                    block_draws: (1..=3)
+                        .map(|period| BlockDrawInfo {
+                            slot: Slot::new(period, 0),
+                            timestamp: MassaTime::from_millis(period),
+                        })
+                        .collect(),
+                    endorsement_draws: (1..=3)
+                        .map(|period| EndorsementDrawInfo {
+                            slot: Slot::new(period, 1),
+                            index: 0,
+                            timestamp: MassaTime::from_millis(period),
+                        })
+                        .collect(),
+                })
+                .collect::<Vec<_>>())
+        })
+        .unwrap();
+    (server.start(module).unwrap(), port, filters)
+}
+
+async fn get_client(port: u16) -> Client {
+    let http_config = HttpConfig {
+        max_request_body_size: 52428800,
+        request_timeout: MassaTime::from_millis(10000),
+        max_concurrent_requests: 100,
+        certificate_store: "Native".to_string(),
+        id_kind: "Number".to_string(),
+        max_log_length: 1024,
+        headers: Vec::new(),
+        private_api_token: None,
+        tls: None,
+        reconnect_initial_delay: MassaTime::from_millis(100),
+        reconnect_max_delay: MassaTime::from_millis(1000),
+        reconnect_max_attempts: 1,
+    };
+    Client::new("127.0.0.1".parse().unwrap(), port, port, &http_config)
+        .await
+        .unwrap()
+}
+
+async fn get_next_draws(
+    client: &Client,
+    wallet: &mut Wallet,
+    parameters: &[&str],
+) -> anyhow::Result<Vec<AddressSelections>> {
+    let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+    let output = Command::get_next_draws
+        .run(client, wallet, &parameters, true)
+        .await?;
+    let mut json = Vec::new();
+    output.erased_serialize(&mut <dyn erased_serde::Serializer>::erase(
+        &mut serde_json::Serializer::new(&mut json),
+    ))?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+#[tokio::test]
+async fn test_get_next_draws() {
+    let (node, port, filters) = start_stub_node().await;
+    let client = get_client(port).await;
+    let temp_dir = TempDir::new().unwrap();
+    let mut wallet = Wallet::new(temp_dir.path().join("wallet.dat"), "test".to_string()).unwrap();
+
+    // the draw count is required
+    let err = get_next_draws(&client, &mut wallet, &[]).await.unwrap_err();
+    assert!(err.to_string().contains("wrong number of parameters"));
+    let err = get_next_draws(&client, &mut wallet, &["two"])
+        .await
+        .unwrap_err();
+    assert!(err.downcast_ref::<std::num::ParseIntError>().is_some());
+
+    // without address, the wallet addresses are looked up
+    let err = get_next_draws(&client, &mut wallet, &["2"])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("the wallet is empty"));
+    assert!(filters.lock().unwrap().is_empty());
+    let mut wallet_addresses = wallet
+        .add_keypairs(vec![KeyPair::generate(), KeyPair::generate()])
+        .unwrap();
+    let selections = get_next_draws(&client, &mut wallet, &["2"]).await.unwrap();
+    let filter = filters.lock().unwrap().pop().unwrap();
+    let mut requested_addresses = filter.addresses.clone();
+    requested_addresses.sort();
+    wallet_addresses.sort();
+    assert_eq!(requested_addresses, wallet_addresses);
+
+    // the draws are requested from the current slot to the end of the drawn cycles,
+    // then only the first ones are kept
+    assert!(filter.start.is_none());
+    assert!(filter.end.is_none());
+    assert_eq!(selections.len(), 2);
+    for selection in selections {
+        assert_eq!(
+            selection
+                .block_draws
+                .iter()
+                .map(|draw| draw.slot)
+                .collect::<Vec<_>>(),
+            vec![Slot::new(1, 0), Slot::new(2, 0)]
+        );
+        assert_eq!(
+            selection
+                .endorsement_draws
+                .iter()
+                .map(|draw| draw.slot)
+                .collect::<Vec<_>>(),
+            vec![Slot::new(1, 1), Slot::new(2, 1)]
+        );
+    }
+
+    // given addresses are looked up instead of the wallet ones
+    let address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let selections = get_next_draws(&client, &mut wallet, &["1", &address.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        filters.lock().unwrap().pop().unwrap().addresses,
+        vec![address]
+    );
+    assert_eq!(selections.len(), 1);
+    assert_eq!(selections[0].address, address);
+    assert_eq!(selections[0].block_draws.len(), 1);
+    assert_eq!(selections[0].endorsement_draws.len(), 1);
+
+    // the node rejects too many addresses
+    let addresses: Vec<String> = (0..=MAX_ARGUMENTS)
+        .map(|_| Address::from_public_key(&KeyPair::generate().get_public_key()).to_string())
+        .collect();
+    let mut parameters = vec!["1"];
+    parameters.extend(addresses.iter().map(String::as_str));
+    let err = get_next_draws(&client, &mut wallet, &parameters)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too many arguments"));
+    assert_eq!(
+        filters.lock().unwrap().pop().unwrap().addresses.len(),
+        MAX_ARGUMENTS + 1
+    );
+
+    node.stop().unwrap();
+}
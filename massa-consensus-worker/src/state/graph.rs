@@ -166,6 +166,7 @@ impl ConsensusState {
                 },
             );
             self.discarded_index.insert(*block_id);
+            self.note_discarded_block(&DiscardReason::Stale);
             Ok(())
         } else {
             return Err(ConsensusError::ContainerInconsistency(format!("inconsistency inside block statuses removing stale blocks adding {} - block {} is missing", add_block_id, block_id)));
@@ -81,6 +81,14 @@ pub struct ConsensusState {
     pub protocol_blocks: VecDeque<(MassaTime, BlockId)>,
     /// Stale block timestamp
     pub stale_block_stats: VecDeque<MassaTime>,
+    /// Number of blocks added to the graph since the node started
+    pub processed_block_total: u64,
+    /// Number of blocks discarded as invalid since the node started
+    pub invalid_block_total: u64,
+    /// Number of blocks discarded as stale since the node started
+    pub stale_block_total: u64,
+    /// Number of blocks discarded as final since the node started
+    pub final_block_total: u64,
     /// the time span considered for stats
     pub stats_history_timespan: MassaTime,
     /// the time span considered for desynchronization detection
@@ -170,6 +170,7 @@ impl ConsensusState {
                     }
                     HeaderCheckOutcome::Discard(reason) => {
                         self.maybe_note_attack_attempt(&reason, &block_id);
+                        self.note_discarded_block(&reason);
                         massa_trace!("consensus.block_graph.process.incoming_header.discarded", {"block_id": block_id, "reason": reason});
                         // count stales
                         if reason == DiscardReason::Stale {
@@ -293,6 +294,7 @@ impl ConsensusState {
                     }
                     HeaderCheckOutcome::Discard(reason) => {
                         self.maybe_note_attack_attempt(&reason, &block_id);
+                        self.note_discarded_block(&reason);
                         massa_trace!("consensus.block_graph.process.incoming_block.discarded", {"block_id": block_id, "reason": reason});
                         // count stales
                         if reason == DiscardReason::Stale {
@@ -514,6 +516,7 @@ impl ConsensusState {
             },
         );
         self.active_index.insert(add_block_id);
+        self.processed_block_total += 1;
 
         // add as child to parents
         // add as descendant to ancestors. Note: descendants are never removed.
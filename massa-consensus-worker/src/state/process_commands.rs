@@ -167,6 +167,7 @@ impl ConsensusState {
     pub fn mark_invalid_block(&mut self, block_id: &BlockId, header: WrappedHeader) {
         let reason = DiscardReason::Invalid("invalid".to_string());
         self.maybe_note_attack_attempt(&reason, block_id);
+        self.note_discarded_block(&reason);
         massa_trace!("consensus.block_graph.process.invalid_block", {"block_id": block_id, "reason": reason});
 
         // add to discard
@@ -101,6 +101,7 @@ impl ConsensusState {
                 },
             );
             self.discarded_index.insert(discard_active_h);
+            self.note_discarded_block(&DiscardReason::Final);
 
             discarded_finals.insert(discard_active_h, *discarded_active);
         }
@@ -299,6 +300,7 @@ impl ConsensusState {
                 massa_trace!("consensus.block_graph.prune_waiting_for_dependencies", {"hash": block_id, "reason": reason_opt});
 
                 if let Some(reason) = reason_opt {
+                    self.note_discarded_block(&reason);
                     // add to stats if reason is Stale
                     if reason == DiscardReason::Stale {
                         self.new_stale_blocks
@@ -1,5 +1,5 @@
 use super::ConsensusState;
-use massa_consensus_exports::error::ConsensusError;
+use massa_consensus_exports::{block_status::DiscardReason, error::ConsensusError};
 use massa_models::stats::ConsensusStats;
 use massa_time::MassaTime;
 use std::cmp::max;
@@ -38,9 +38,22 @@ impl ConsensusState {
             clique_count,
             start_timespan: timespan_start,
             end_timespan: timespan_end,
+            processed_block_total: self.processed_block_total,
+            invalid_block_total: self.invalid_block_total,
+            stale_block_total: self.stale_block_total,
+            final_block_total: self.final_block_total,
         })
     }
 
+    /// Count a block discarded for the given reason
+    pub fn note_discarded_block(&mut self, reason: &DiscardReason) {
+        match reason {
+            DiscardReason::Invalid(_) => self.invalid_block_total += 1,
+            DiscardReason::Stale => self.stale_block_total += 1,
+            DiscardReason::Final => self.final_block_total += 1,
+        }
+    }
+
     /// Must be called each tick to update stats. Will detect if a desynchronization happened
     pub fn stats_tick(&mut self) -> Result<(), ConsensusError> {
         // check if there are any final blocks is coming from protocol
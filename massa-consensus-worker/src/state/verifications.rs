@@ -109,10 +109,21 @@ impl ConsensusState {
             Err(_) => return Ok(HeaderCheckOutcome::WaitForSlot), // TODO properly handle PoS errors
         };
         if creator_addr != slot_draw_address {
-            // it was not the creator's turn to create a block for this slot
-            return Ok(HeaderCheckOutcome::Discard(DiscardReason::Invalid(
-                format!("Bad creator turn for the slot:{}", header.content.slot),
-            )));
+            // the block can also be created by the staking delegate of the drawn address
+            let slot_draw_delegate = match self
+                .channels
+                .selector_controller
+                .get_staking_delegate(header.content.slot, &slot_draw_address)
+            {
+                Ok(delegate) => delegate,
+                Err(_) => return Ok(HeaderCheckOutcome::WaitForSlot),
+            };
+            if slot_draw_delegate != Some(creator_addr) {
+                // it was not the creator's turn to create a block for this slot
+                return Ok(HeaderCheckOutcome::Discard(DiscardReason::Invalid(
+                    format!("Bad creator turn for the slot:{}", header.content.slot),
+                )));
+            }
         }
 
         // check if block is in the future: queue it
@@ -389,14 +400,25 @@ impl ConsensusState {
         };
         for endorsement in header.content.endorsements.iter() {
             // check that the draw is correct
-            if endorsement.creator_address != endorsement_draws[endorsement.content.index as usize]
-            {
-                return Ok(EndorsementsCheckOutcome::Discard(DiscardReason::Invalid(
-                    format!(
-                        "endorser draw mismatch for header in slot: {}",
-                        header.content.slot
-                    ),
-                )));
+            // (the endorsement can also be created by the staking delegate of the drawn address)
+            let endorsement_draw = endorsement_draws[endorsement.content.index as usize];
+            if endorsement.creator_address != endorsement_draw {
+                let endorsement_draw_delegate = match self
+                    .channels
+                    .selector_controller
+                    .get_staking_delegate(header.content.slot, &endorsement_draw)
+                {
+                    Ok(delegate) => delegate,
+                    Err(_) => return Ok(EndorsementsCheckOutcome::WaitForSlot),
+                };
+                if endorsement_draw_delegate != Some(endorsement.creator_address) {
+                    return Ok(EndorsementsCheckOutcome::Discard(DiscardReason::Invalid(
+                        format!(
+                            "endorser draw mismatch for header in slot: {}",
+                            header.content.slot
+                        ),
+                    )));
+                }
             }
 
             // note that the following aspects are checked in protocol
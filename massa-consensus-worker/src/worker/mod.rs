@@ -87,6 +87,10 @@ pub fn start_consensus_worker(
         gi_head: Default::default(),
         final_block_stats: Default::default(),
         stale_block_stats: Default::default(),
+        processed_block_total: 0,
+        invalid_block_total: 0,
+        stale_block_total: 0,
+        final_block_total: 0,
         protocol_blocks: Default::default(),
         wishlist: Default::default(),
         launch_time: MassaTime::now(config.clock_compensation_millis).unwrap(),
@@ -7,7 +7,7 @@ use crate::ExecutionError;
 use crate::{ExecutionAddressInfo, ReadOnlyExecutionOutput};
 use massa_models::address::Address;
 use massa_models::amount::Amount;
-use massa_models::api::EventFilter;
+use massa_models::api::{EventFilter, FinalStateHash, LedgerProof, LedgerProofInput};
 use massa_models::block::BlockId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
@@ -93,6 +93,16 @@ pub trait ExecutionController: Send + Sync {
     /// Get execution statistics
     fn get_stats(&self) -> ExecutionStats;
 
+    /// Get the hash of the latest final state
+    fn get_final_state_hash(&self) -> FinalStateHash;
+
+    /// Get the hashes of the final state at the output of the given final slots.
+    /// Slots that are neither the latest final slot nor in the final state changes history are skipped.
+    fn get_final_state_hashes(&self, slots: &[Slot]) -> Vec<FinalStateHash>;
+
+    /// Get the values of final ledger sub-entries along with their proofs against the latest final state hash
+    fn get_ledger_proofs(&self, inputs: &[LedgerProofInput]) -> Vec<LedgerProof>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ExecutionController>`.
     fn clone_box(&self) -> Box<dyn ExecutionController>;
@@ -27,6 +27,9 @@ pub enum ExecutionError {
     /// `RollSell` error: {0}
     RollSellError(String),
 
+    /// `SetStakingDelegate` error: {0}
+    StakingDelegateError(String),
+
     /// `Transaction` error: {0}
     TransactionError(String),
 
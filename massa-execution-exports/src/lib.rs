@@ -55,8 +55,9 @@ pub use error::ExecutionError;
 pub use event_store::EventStore;
 pub use settings::{ExecutionConfig, StorageCostsConstants};
 pub use types::{
-    ExecutionAddressInfo, ExecutionOutput, ExecutionStackElement, ReadOnlyCallRequest,
-    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ExecutionAddressInfo, ExecutionOutput, ExecutionStackElement, ProductionRewards,
+    ReadOnlyCallRequest, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
 };
 
 #[cfg(feature = "testing")]
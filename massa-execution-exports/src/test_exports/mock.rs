@@ -6,11 +6,12 @@ use crate::{
     ExecutionAddressInfo, ExecutionController, ExecutionError, ReadOnlyExecutionOutput,
     ReadOnlyExecutionRequest,
 };
+use massa_hash::Hash;
 use massa_ledger_exports::LedgerEntry;
 use massa_models::{
     address::Address,
     amount::Amount,
-    api::EventFilter,
+    api::{EventFilter, FinalStateHash, LedgerProof, LedgerProofInput},
     block::BlockId,
     operation::OperationId,
     output_event::SCOutputEvent,
@@ -121,9 +122,25 @@ impl ExecutionController for MockExecutionController {
             final_block_count: 0,
             final_executed_operations_count: 0,
             active_cursor: Slot::new(0, 0),
+            final_cursor: Slot::new(0, 0),
         }
     }
 
+    fn get_final_state_hash(&self) -> FinalStateHash {
+        FinalStateHash {
+            slot: Slot::new(0, 0),
+            hash: Hash::compute_from(&[]),
+        }
+    }
+
+    fn get_final_state_hashes(&self, _slots: &[Slot]) -> Vec<FinalStateHash> {
+        Vec::new()
+    }
+
+    fn get_ledger_proofs(&self, _inputs: &[LedgerProofInput]) -> Vec<LedgerProof> {
+        Vec::new()
+    }
+
     fn update_blockclique_status(
         &self,
         finalized_blocks: HashMap<Slot, BlockId>,
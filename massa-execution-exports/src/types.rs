@@ -5,6 +5,7 @@
 use crate::event_store::EventStore;
 use massa_final_state::StateChanges;
 use massa_models::datastore::Datastore;
+use massa_models::prehash::PreHashMap;
 use massa_models::{
     address::Address, address::ExecutionAddressCycleInfo, amount::Amount, block::BlockId,
     slot::Slot,
@@ -47,6 +48,29 @@ pub struct ExecutionOutput {
     pub state_changes: StateChanges,
     /// events emitted by the execution step
     pub events: EventStore,
+    /// coins earned by block and endorsement producers during the execution step
+    pub production_rewards: PreHashMap<Address, ProductionRewards>,
+}
+
+/// Coins earned by an address by producing blocks and endorsements.
+///
+/// They are only indexed by the execution worker for the API and are not part of the final state.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProductionRewards {
+    /// coins earned by creating blocks and by having created blocks endorsed
+    pub block_rewards: Amount,
+    /// coins earned by creating endorsements
+    pub endorsement_rewards: Amount,
+}
+
+impl ProductionRewards {
+    /// Extends the rewards with other rewards
+    pub fn extend(&mut self, rewards: &ProductionRewards) {
+        self.block_rewards = self.block_rewards.saturating_add(rewards.block_rewards);
+        self.endorsement_rewards = self
+            .endorsement_rewards
+            .saturating_add(rewards.endorsement_rewards);
+    }
 }
 
 /// structure describing the output of a read only execution
@@ -15,6 +15,7 @@ use massa_async_pool::{AsyncMessage, AsyncMessageId};
 use massa_executed_ops::ExecutedOpsChanges;
 use massa_execution_exports::{
     EventStore, ExecutionConfig, ExecutionError, ExecutionOutput, ExecutionStackElement,
+    ProductionRewards,
 };
 use massa_final_state::{FinalState, StateChanges};
 use massa_ledger_exports::LedgerChanges;
@@ -25,6 +26,7 @@ use massa_models::{
     block::BlockId,
     operation::OperationId,
     output_event::{EventExecutionContext, SCOutputEvent},
+    prehash::PreHashMap,
     slot::Slot,
 };
 use massa_pos_exports::PoSChanges;
@@ -115,6 +117,9 @@ pub(crate) struct ExecutionContext {
     /// generated events during this execution, with multiple indexes
     pub events: EventStore,
 
+    /// block and endorsement production rewards credited during this execution
+    pub production_rewards: PreHashMap<Address, ProductionRewards>,
+
     /// Unsafe random state (can be predicted and manipulated)
     pub unsafe_rng: Xoshiro256PlusPlus,
 
@@ -168,6 +173,7 @@ impl ExecutionContext {
             stack: Default::default(),
             read_only: Default::default(),
             events: Default::default(),
+            production_rewards: Default::default(),
             unsafe_rng: Xoshiro256PlusPlus::from_seed([0u8; 32]),
             creator_address: Default::default(),
             origin_operation_id: Default::default(),
@@ -615,6 +621,16 @@ impl ExecutionContext {
             .add_rolls(buyer_addr, roll_count);
     }
 
+    /// Set or revoke (if `None`) the staking delegate of a roll owner.
+    ///
+    /// # Arguments
+    /// * `owner_addr`: address owning the rolls
+    /// * `delegate`: address allowed to create blocks and endorsements in the name of the owner
+    pub fn set_staking_delegate(&mut self, owner_addr: &Address, delegate: Option<Address>) {
+        self.speculative_roll_state
+            .set_staking_delegate(owner_addr, delegate);
+    }
+
     /// Try to sell `roll_count` rolls from the seller address.
     ///
     /// # Arguments
@@ -651,6 +667,28 @@ impl ExecutionContext {
             .update_production_stats(creator, slot, block_id);
     }
 
+    /// Record block production rewards credited to an address.
+    /// They are not part of the state changes and are only indexed for the API.
+    ///
+    /// # Arguments
+    /// * `address`: the rewarded address
+    /// * `block_rewards`: coins earned by creating a block or having a block endorsed
+    /// * `endorsement_rewards`: coins earned by creating an endorsement
+    pub fn add_production_rewards(
+        &mut self,
+        address: &Address,
+        block_rewards: Amount,
+        endorsement_rewards: Amount,
+    ) {
+        self.production_rewards
+            .entry(*address)
+            .or_default()
+            .extend(&ProductionRewards {
+                block_rewards,
+                endorsement_rewards,
+            });
+    }
+
     /// Execute the deferred credits of `slot`.
     ///
     /// # Arguments
@@ -723,6 +761,7 @@ impl ExecutionContext {
             block_id: std::mem::take(&mut self.opt_block_id),
             state_changes,
             events: std::mem::take(&mut self.events),
+            production_rewards: std::mem::take(&mut self.production_rewards),
         }
     }
 
@@ -9,7 +9,7 @@ use massa_execution_exports::{
     ExecutionAddressInfo, ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager,
     ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
 };
-use massa_models::api::EventFilter;
+use massa_models::api::{EventFilter, FinalStateHash, LedgerProof, LedgerProofInput};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::stats::ExecutionStats;
@@ -256,6 +256,21 @@ impl ExecutionController for ExecutionControllerImpl {
         self.execution_state.read().get_stats()
     }
 
+    /// Get the hash of the latest final state
+    fn get_final_state_hash(&self) -> FinalStateHash {
+        self.execution_state.read().get_final_state_hash()
+    }
+
+    /// Get the hashes of the final state at the output of the given final slots
+    fn get_final_state_hashes(&self, slots: &[Slot]) -> Vec<FinalStateHash> {
+        self.execution_state.read().get_final_state_hashes(slots)
+    }
+
+    /// Get the values of final ledger sub-entries along with their proofs against the latest final state hash
+    fn get_ledger_proofs(&self, inputs: &[LedgerProofInput]) -> Vec<LedgerProof> {
+        self.execution_state.read().get_ledger_proofs(inputs)
+    }
+
     /// Returns a boxed clone of self.
     /// Allows cloning `Box<dyn ExecutionController>`,
     /// see `massa-execution-exports/controller_traits.rs`
@@ -15,14 +15,14 @@ use crate::stats::ExecutionStatsCounter;
 use massa_async_pool::AsyncMessage;
 use massa_execution_exports::{
     EventStore, ExecutionConfig, ExecutionError, ExecutionOutput, ExecutionStackElement,
-    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    ProductionRewards, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
 use massa_final_state::FinalState;
 use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
 use massa_models::address::ExecutionAddressCycleInfo;
-use massa_models::api::EventFilter;
+use massa_models::api::{EventFilter, FinalStateHash, LedgerProof, LedgerProofInput};
 use massa_models::output_event::SCOutputEvent;
-use massa_models::prehash::PreHashSet;
+use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::stats::ExecutionStats;
 use massa_models::{
     address::Address,
@@ -62,6 +62,11 @@ pub(crate) struct ExecutionState {
     pub final_cursor: Slot,
     // store containing execution events that became final
     final_events: EventStore,
+    // production rewards that became final, indexed by cycle.
+    // They are not part of the final state: only the cycles executed since the node started are known.
+    final_rewards: BTreeMap<u64, PreHashMap<Address, ProductionRewards>>,
+    // first cycle whose final slots were all executed by this node, rewards of older cycles are unknown
+    rewards_start_cycle: u64,
     // final state with atomic R/W access
     final_state: Arc<RwLock<FinalState>>,
     // execution context (see documentation in context.rs)
@@ -111,6 +116,16 @@ impl ExecutionState {
             active_history,
             // empty final event store: it is not recovered through bootstrap
             final_events: Default::default(),
+            // empty final rewards index: it is not recovered through bootstrap
+            final_rewards: Default::default(),
+            // genesis slots yield no rewards, otherwise the cycle of the last final slot was partially executed elsewhere
+            rewards_start_cycle: if last_final_slot.period == 0 {
+                0
+            } else {
+                last_final_slot
+                    .get_cycle(config.periods_per_cycle)
+                    .saturating_add(1)
+            },
             // no active slots executed yet: set active_cursor to the last final block
             active_cursor: last_final_slot,
             final_cursor: last_final_slot,
@@ -124,7 +139,8 @@ impl ExecutionState {
 
     /// Get execution statistics
     pub fn get_stats(&self) -> ExecutionStats {
-        self.stats_counter.get_stats(self.active_cursor)
+        self.stats_counter
+            .get_stats(self.active_cursor, self.final_cursor)
     }
 
     /// Applies the output of an execution to the final execution state.
@@ -146,9 +162,25 @@ impl ExecutionState {
         }
 
         // apply state changes to the final ledger
-        self.final_state
-            .write()
-            .finalize(exec_out.slot, exec_out.state_changes);
+        let oldest_cycle = {
+            let mut final_state = self.final_state.write();
+            final_state.finalize(exec_out.slot, exec_out.state_changes);
+            final_state
+                .pos_state
+                .cycle_history
+                .front()
+                .map(|info| info.cycle)
+        };
+
+        // index the final production rewards for the cycles still kept in the PoS history
+        let cycle = exec_out.slot.get_cycle(self.config.periods_per_cycle);
+        let cycle_rewards = self.final_rewards.entry(cycle).or_default();
+        for (addr, rewards) in exec_out.production_rewards.iter() {
+            cycle_rewards.entry(*addr).or_default().extend(rewards);
+        }
+        if let Some(oldest_cycle) = oldest_cycle {
+            self.final_rewards = self.final_rewards.split_off(&oldest_cycle);
+        }
 
         // update the final ledger's slot
         self.final_cursor = exec_out.slot;
@@ -304,6 +336,9 @@ impl ExecutionState {
             OperationType::Transaction { .. } => {
                 self.execute_transaction_op(&operation.content.op, sender_addr)
             }
+            OperationType::SetStakingDelegate { .. } => {
+                self.execute_set_staking_delegate_op(&operation.content.op, sender_addr)
+            }
         };
 
         {
@@ -421,6 +456,49 @@ impl ExecutionState {
         Ok(())
     }
 
+    /// Execute an operation of type `SetStakingDelegate`
+    /// Will panic if called with another operation type
+    ///
+    /// # Arguments
+    /// * `operation`: the `WrappedOperation` to process, must be a `SetStakingDelegate`
+    /// * `owner_addr`: address of the sender, owner of the delegated rolls
+    pub fn execute_set_staking_delegate_op(
+        &self,
+        operation: &OperationType,
+        owner_addr: Address,
+    ) -> Result<(), ExecutionError> {
+        // process staking delegation operations only
+        let delegate = match operation {
+            OperationType::SetStakingDelegate { delegate } => delegate,
+            _ => panic!("unexpected operation type"),
+        };
+
+        // acquire write access to the context
+        let mut context = context_guard!(self);
+
+        // Set call stack
+        // This needs to be defined before anything can fail, so that the emitted event contains the right stack
+        context.stack = vec![ExecutionStackElement {
+            address: owner_addr,
+            coins: Amount::default(),
+            owned_addresses: vec![owner_addr],
+            operation_datastore: None,
+        }];
+
+        // an address cannot be its own delegate
+        if *delegate == Some(owner_addr) {
+            return Err(ExecutionError::StakingDelegateError(format!(
+                "{} cannot designate itself as staking delegate",
+                owner_addr
+            )));
+        }
+
+        // set or revoke the delegate
+        context.set_staking_delegate(&owner_addr, *delegate);
+
+        Ok(())
+    }
+
     /// Execute an operation of type `Transaction`
     /// Will panic if called with another operation type
     ///
@@ -768,6 +846,21 @@ impl ExecutionState {
                     .collect::<Vec<_>>()
             };
 
+            // Blocks and endorsements may be signed by the staking delegate of the drawn address:
+            // in that case, rewards and production stats go to the drawn roll owner.
+            let selection = selector.get_selection(*slot).ok();
+            let get_staking_owner =
+                |slot: Slot, signer: Address, drawn: Option<Address>| match drawn {
+                    Some(drawn)
+                        if drawn != signer
+                            && selector.get_staking_delegate(slot, &drawn).ok().flatten()
+                                == Some(signer) =>
+                    {
+                        drawn
+                    }
+                    _ => signer,
+                };
+
             // gather all available endorsement creators and target blocks
             let (endorsement_creators, endorsement_targets): &(Vec<Address>, Vec<BlockId>) =
                 &stored_block
@@ -776,7 +869,18 @@ impl ExecutionState {
                     .content
                     .endorsements
                     .iter()
-                    .map(|endo| (endo.creator_address, endo.content.endorsed_block))
+                    .map(|endo| {
+                        (
+                            get_staking_owner(
+                                *slot,
+                                endo.creator_address,
+                                selection.as_ref().and_then(|sel| {
+                                    sel.endorsements.get(endo.content.index as usize).copied()
+                                }),
+                            ),
+                            endo.content.endorsed_block,
+                        )
+                    })
                     .unzip();
 
             // deduce endorsement target block creators
@@ -785,10 +889,15 @@ impl ExecutionState {
                 endorsement_targets
                     .iter()
                     .map(|b_id| {
-                        blocks
+                        let endorsed_block = blocks
                             .get(b_id)
-                            .expect("endorsed block absent from storage")
-                            .creator_address
+                            .expect("endorsed block absent from storage");
+                        let endorsed_slot = endorsed_block.content.header.content.slot;
+                        get_staking_owner(
+                            endorsed_slot,
+                            endorsed_block.creator_address,
+                            selector.get_producer(endorsed_slot).ok(),
+                        )
                     })
                     .collect::<Vec<_>>()
             };
@@ -816,7 +925,11 @@ impl ExecutionState {
             }
 
             // Get block creator address
-            let block_creator_addr = stored_block.creator_address;
+            let block_creator_addr = get_staking_owner(
+                *slot,
+                stored_block.creator_address,
+                selection.map(|sel| sel.producer),
+            );
 
             // acquire lock on execution context
             let mut context = context_guard!(self);
@@ -842,6 +955,11 @@ impl ExecutionState {
                 ) {
                     Ok(_) => {
                         remaining_credit = remaining_credit.saturating_sub(block_credit_part);
+                        context.add_production_rewards(
+                            endorsement_creator,
+                            Amount::zero(),
+                            block_credit_part,
+                        );
                     }
                     Err(err) => {
                         debug!(
@@ -860,6 +978,11 @@ impl ExecutionState {
                 ) {
                     Ok(_) => {
                         remaining_credit = remaining_credit.saturating_sub(block_credit_part);
+                        context.add_production_rewards(
+                            &endorsement_target_creator,
+                            block_credit_part,
+                            Amount::zero(),
+                        );
                     }
                     Err(err) => {
                         debug!(
@@ -871,13 +994,20 @@ impl ExecutionState {
             }
 
             // Credit block creator with remaining_credit
-            if let Err(err) =
-                context.transfer_coins(None, Some(block_creator_addr), remaining_credit, false)
-            {
-                debug!(
-                    "failed to credit {} coins to block creator {} on block execution: {}",
-                    remaining_credit, block_creator_addr, err
-                )
+            match context.transfer_coins(None, Some(block_creator_addr), remaining_credit, false) {
+                Ok(_) => {
+                    context.add_production_rewards(
+                        &block_creator_addr,
+                        remaining_credit,
+                        Amount::zero(),
+                    );
+                }
+                Err(err) => {
+                    debug!(
+                        "failed to credit {} coins to block creator {} on block execution: {}",
+                        remaining_credit, block_creator_addr, err
+                    )
+                }
             }
         } else {
             // the slot is a miss, check who was supposed to be the creator and update production stats
@@ -1165,6 +1295,58 @@ impl ExecutionState {
         (final_keys, candidate_keys)
     }
 
+    /// Get the hash of the latest final state
+    pub fn get_final_state_hash(&self) -> FinalStateHash {
+        let final_state = self.final_state.read();
+        FinalStateHash {
+            slot: final_state.slot,
+            hash: final_state.final_state_hash,
+        }
+    }
+
+    /// Get the hashes of the final state at the output of the given final slots.
+    /// Slots that are neither the latest final slot nor in the final state changes history are skipped.
+    pub fn get_final_state_hashes(&self, slots: &[Slot]) -> Vec<FinalStateHash> {
+        let final_state = self.final_state.read();
+        slots
+            .iter()
+            .filter_map(|slot| {
+                final_state
+                    .get_state_hash(*slot)
+                    .map(|hash| FinalStateHash { slot: *slot, hash })
+            })
+            .collect()
+    }
+
+    /// Get the values of final ledger sub-entries along with their proofs against the latest final state hash.
+    /// Everything is read under the same final state lock so that the proofs are consistent with the returned hash.
+    pub fn get_ledger_proofs(&self, inputs: &[LedgerProofInput]) -> Vec<LedgerProof> {
+        let final_state = self.final_state.read();
+        let ledger_root = final_state.ledger.get_ledger_root();
+        let other_state_hashes = final_state.get_state_hash_components();
+        let final_state_hash = FinalStateHash {
+            slot: final_state.slot,
+            hash: final_state.final_state_hash,
+        };
+        inputs
+            .iter()
+            .map(|input| {
+                let (value, proof) = final_state
+                    .ledger
+                    .get_key_proof(&input.sub_entry.ledger_key(&input.address));
+                LedgerProof {
+                    address: input.address,
+                    sub_entry: input.sub_entry.clone(),
+                    value,
+                    proof,
+                    ledger_root,
+                    other_state_hashes: other_state_hashes.clone(),
+                    final_state_hash,
+                }
+            })
+            .collect()
+    }
+
     /// Returns for a given cycle the stakers taken into account
     /// by the selector. That correspond to the `roll_counts` in `cycle - 3`.
     ///
@@ -1261,9 +1443,35 @@ impl ExecutionState {
         ops
     }
 
-    /// Gets the production stats for an address at all cycles
+    /// Gets the production stats and rewards for an address at all cycles
     pub fn get_address_cycle_infos(&self, address: &Address) -> Vec<ExecutionAddressCycleInfo> {
-        context_guard!(self).get_address_cycle_infos(address, self.config.periods_per_cycle)
+        let mut cycle_infos =
+            context_guard!(self).get_address_cycle_infos(address, self.config.periods_per_cycle);
+
+        // sum the final and active rewards of each cycle
+        let mut rewards: BTreeMap<u64, ProductionRewards> = BTreeMap::new();
+        for (cycle, cycle_rewards) in self.final_rewards.iter() {
+            if let Some(addr_rewards) = cycle_rewards.get(address) {
+                rewards.entry(*cycle).or_default().extend(addr_rewards);
+            }
+        }
+        for output in &self.active_history.read().0 {
+            if let Some(addr_rewards) = output.production_rewards.get(address) {
+                rewards
+                    .entry(output.slot.get_cycle(self.config.periods_per_cycle))
+                    .or_default()
+                    .extend(addr_rewards);
+            }
+        }
+        for info in cycle_infos.iter_mut() {
+            if info.cycle < self.rewards_start_cycle {
+                continue;
+            }
+            let cycle_rewards = rewards.remove(&info.cycle).unwrap_or_default();
+            info.block_rewards = Some(cycle_rewards.block_rewards);
+            info.endorsement_rewards = Some(cycle_rewards.endorsement_rewards);
+        }
+        cycle_infos
     }
 
     /// Get future deferred credits of an address
@@ -93,6 +93,18 @@ impl SpeculativeRollState {
         *count = count.saturating_add(roll_count);
     }
 
+    /// Set or revoke (if `None`) the staking delegate of a roll owner.
+    /// Validity checks must be performed _outside_ of this function.
+    ///
+    /// # Arguments
+    /// * `owner_addr`: address owning the rolls
+    /// * `delegate`: address allowed to create blocks and endorsements in the name of the owner
+    pub fn set_staking_delegate(&mut self, owner_addr: &Address, delegate: Option<Address>) {
+        self.added_changes
+            .staking_delegate_changes
+            .insert(*owner_addr, delegate);
+    }
+
     /// Try to sell `roll_count` rolls from the seller address.
     ///
     /// # Arguments
@@ -337,7 +349,9 @@ impl SpeculativeRollState {
                 is_final: c.complete,
                 ok_count: 0,
                 nok_count: 0,
-                active_rolls: None, // will be filled afterwards
+                active_rolls: None,        // will be filled afterwards
+                block_rewards: None,       // filled by the execution state
+                endorsement_rewards: None, // filled by the execution state
             };
             if let Some(prod_stats) = c.production_stats.get(address) {
                 cur_item.ok_count = prod_stats.block_success_count;
@@ -360,7 +374,9 @@ impl SpeculativeRollState {
                         is_final: false,
                         ok_count: 0,
                         nok_count: 0,
-                        active_rolls: None, // will be filled afterwards
+                        active_rolls: None,        // will be filled afterwards
+                        block_rewards: None,       // filled by the execution state
+                        endorsement_rewards: None, // filled by the execution state
                     });
                 }
 
@@ -393,7 +409,9 @@ impl SpeculativeRollState {
                     is_final: false,
                     ok_count: 0,
                     nok_count: 0,
-                    active_rolls: None, // will be filled afterwards
+                    active_rolls: None,        // will be filled afterwards
+                    block_rewards: None,       // filled by the execution state
+                    endorsement_rewards: None, // filled by the execution state
                 });
             }
 
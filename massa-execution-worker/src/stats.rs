@@ -68,7 +68,7 @@ impl ExecutionStatsCounter {
     }
 
     /// get statistics
-    pub fn get_stats(&self, active_cursor: Slot) -> ExecutionStats {
+    pub fn get_stats(&self, active_cursor: Slot, final_cursor: Slot) -> ExecutionStats {
         let current_time =
             MassaTime::now(self.compensation_millis).expect("could not get current time");
         let start_time = current_time.saturating_sub(self.time_window_duration);
@@ -86,6 +86,7 @@ impl ExecutionStatsCounter {
             time_window_start: start_time,
             time_window_end: current_time,
             active_cursor,
+            final_cursor,
         }
     }
 }
@@ -463,6 +463,86 @@ pub fn roll_buy() {
     manager.stop();
 }
 
+#[test]
+#[serial]
+pub fn set_staking_delegate() {
+    // setup the period duration
+    let exec_cfg = ExecutionConfig {
+        t0: 100.into(),
+        cursor_delay: 0.into(),
+        ..ExecutionConfig::default()
+    };
+    // get a sample final state
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+
+    // init the storage
+    let mut storage = Storage::create_root();
+    // start the execution worker
+    let (mut manager, controller) = start_execution_worker(
+        exec_cfg.clone(),
+        sample_state.clone(),
+        sample_state.read().pos_state.selector.clone(),
+    );
+    // initialize the execution system with genesis blocks
+    init_execution_worker(&exec_cfg, &storage, controller.clone());
+    // generate the keypair of the roll owner and a delegate address
+    let keypair = KeyPair::from_str("S1JJeHiZv1C1zZN5GLFcbz6EXYiccmUPLkYuDFA3kayjxP39kFQ").unwrap();
+    let address = Address::from_public_key(&keypair.get_public_key());
+    let delegate = Address::from_public_key(&KeyPair::generate().get_public_key());
+    // create the operations: delegating to itself must fail
+    let operations: Vec<WrappedOperation> = [Some(address), Some(delegate)]
+        .into_iter()
+        .map(|delegate| {
+            Operation::new_wrapped(
+                Operation {
+                    fee: Amount::zero(),
+                    expire_period: 10,
+                    op: OperationType::SetStakingDelegate { delegate },
+                },
+                OperationSerializer::new(),
+                &keypair,
+            )
+            .unwrap()
+        })
+        .collect();
+    // create the block contaning the delegation operations
+    storage.store_operations(operations.clone());
+    let block = create_block(KeyPair::generate(), operations, Slot::new(1, 0)).unwrap();
+    // store the block in storage
+    storage.store_block(block.clone());
+    // set our block as a final block so the delegation is processed
+    let mut finalized_blocks: HashMap<Slot, BlockId> = Default::default();
+    finalized_blocks.insert(block.content.header.content.slot, block.id);
+    let mut block_storage: PreHashMap<BlockId, Storage> = Default::default();
+    block_storage.insert(block.id, storage.clone());
+    controller.update_blockclique_status(
+        finalized_blocks,
+        Default::default(),
+        block_storage.clone(),
+    );
+    std::thread::sleep(Duration::from_millis(10));
+    // check the delegate of the owner address
+    let sample_read = sample_state.read();
+    assert_eq!(
+        sample_read
+            .pos_state
+            .cycle_history
+            .back()
+            .unwrap()
+            .staking_delegates
+            .get(&address),
+        Some(&delegate)
+    );
+    // check that the self delegation failed
+    let events = controller.get_filtered_sc_output_event(EventFilter::default());
+    assert_eq!(events.len(), 1);
+    assert!(events[0]
+        .data
+        .contains("cannot designate itself as staking delegate"));
+    // stop the execution controller
+    manager.stop();
+}
+
 #[test]
 #[serial]
 pub fn roll_sell() {
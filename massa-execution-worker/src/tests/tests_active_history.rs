@@ -45,11 +45,13 @@ fn test_active_history_deferred_credits() {
                 seed_bits: Default::default(),
                 roll_changes: Default::default(),
                 production_stats: Default::default(),
+                staking_delegate_changes: Default::default(),
                 deferred_credits: credits,
             },
             executed_ops_changes: Default::default(),
         },
         events: Default::default(),
+        production_rewards: Default::default(),
     };
 
     let active_history = ActiveHistory {
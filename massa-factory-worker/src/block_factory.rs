@@ -131,13 +131,23 @@ impl BlockFactoryWorker {
             }
         };
 
-        // check if the block producer address is handled by the wallet
+        // check if the block producer address or its staking delegate is handled by the wallet
         let block_producer_keypair_ref = self.wallet.read();
         let block_producer_keypair = if let Some(kp) =
             block_producer_keypair_ref.find_associated_keypair(&block_producer_addr)
         {
             // the selected block producer is managed locally => continue to attempt block production
             kp
+        } else if let Some(kp) = self
+            .channels
+            .selector
+            .get_staking_delegate(slot, &block_producer_addr)
+            .ok()
+            .flatten()
+            .and_then(|delegate| block_producer_keypair_ref.find_associated_keypair(&delegate))
+        {
+            // the staking delegate of the selected block producer is managed locally => produce in its name
+            kp
         } else {
             // the selected block producer is not managed locally => quit
             return;
@@ -143,11 +143,21 @@ impl EndorsementFactoryWorker {
         {
             let wallet = self.wallet.read();
             for (index, producer_addr) in producer_addrs.into_iter().enumerate() {
-                // check if the block producer address is handled by the wallet
+                // check if the block producer address or its staking delegate is handled by the wallet
                 let producer_keypair =
                     if let Some(kp) = wallet.find_associated_keypair(&producer_addr) {
                         // the selected block producer is managed locally => continue to attempt endorsement production
                         kp.clone()
+                    } else if let Some(kp) = self
+                        .channels
+                        .selector
+                        .get_staking_delegate(slot, &producer_addr)
+                        .ok()
+                        .flatten()
+                        .and_then(|delegate| wallet.find_associated_keypair(&delegate))
+                    {
+                        // the staking delegate of the selected block producer is managed locally => endorse in its name
+                        kp.clone()
                     } else {
                         // the selected block producer is not managed locally => continue
                         continue;
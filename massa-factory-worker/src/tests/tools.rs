@@ -133,6 +133,13 @@ impl TestFactory {
                     println!("test in receiver");
                     response_tx.send(Ok(producer_address)).unwrap();
                 }
+                Ok(MockSelectorControllerMessage::GetStakingDelegate {
+                    slot: _,
+                    address: _,
+                    response_tx,
+                }) => {
+                    response_tx.send(Ok(None)).unwrap();
+                }
                 Ok(MockSelectorControllerMessage::GetSelection {
                     slot: _,
                     response_tx,
@@ -8,11 +8,12 @@
 use crate::{config::FinalStateConfig, error::FinalStateError, state_changes::StateChanges};
 use massa_async_pool::{AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
 use massa_executed_ops::ExecutedOps;
-use massa_hash::Hash;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::{get_address_from_key, LedgerChanges, LedgerController};
 use massa_models::{slot::Slot, streaming_step::StreamingStep};
 use massa_pos_exports::{DeferredCredits, PoSFinalState, SelectorController};
 use std::collections::VecDeque;
+use tokio::sync::watch;
 use tracing::{debug, info};
 
 /// Represents a final state `(ledger, async pool, executed_ops and the state of the PoS)`
@@ -32,6 +33,13 @@ pub struct FinalState {
     /// history of recent final state changes, useful for streaming bootstrap
     /// `front = oldest`, `back = newest`
     pub changes_history: VecDeque<(Slot, StateChanges)>,
+    /// hash of the final state at the output of `slot`
+    pub final_state_hash: Hash,
+    /// history of the final state hashes of the slots of `changes_history`
+    /// `front = oldest`, `back = newest`
+    pub final_state_hash_history: VecDeque<(Slot, Hash)>,
+    /// sender of `slot`, updated each time a slot is finalized
+    pub(crate) slot_sender: watch::Sender<Slot>,
 }
 
 impl FinalState {
@@ -63,7 +71,7 @@ impl FinalState {
         let executed_ops = ExecutedOps::new(config.executed_ops_config.clone());
 
         // generate the final state
-        Ok(FinalState {
+        let mut final_state = FinalState {
             slot,
             ledger,
             async_pool,
@@ -71,7 +79,30 @@ impl FinalState {
             config,
             executed_ops,
             changes_history: Default::default(), // no changes in history
-        })
+            final_state_hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+            final_state_hash_history: Default::default(),
+            slot_sender: watch::channel(slot).0,
+        };
+        final_state.final_state_hash = final_state.compute_state_hash();
+        Ok(final_state)
+    }
+
+    /// Returns a receiver notified each time a slot is finalized, holding the slot of the final state
+    pub fn subscribe_slot(&self) -> watch::Receiver<Slot> {
+        self.slot_sender.subscribe()
+    }
+
+    /// Resets the final state to an empty state at the last genesis slot, used to bootstrap it again from scratch
+    pub fn reset(&mut self) {
+        self.slot = Slot::new(0, self.config.thread_count.saturating_sub(1));
+        self.ledger.reset();
+        self.async_pool = AsyncPool::new(self.config.async_pool_config.clone());
+        self.pos_state.reset();
+        self.executed_ops = ExecutedOps::new(self.config.executed_ops_config.clone());
+        self.changes_history.clear();
+        self.final_state_hash_history.clear();
+        self.final_state_hash = self.compute_state_hash();
+        self.slot_sender.send_replace(self.slot);
     }
 
     /// Performs the initial draws.
@@ -110,24 +141,60 @@ impl FinalState {
         self.executed_ops
             .apply_changes(changes.executed_ops_changes.clone(), self.slot);
 
-        // push history element and limit history size
+        // compute the final state hash
+        self.final_state_hash = self.compute_state_hash();
+        info!(
+            "final_state hash at slot {}: {}",
+            slot, self.final_state_hash
+        );
+
+        // push history elements and limit history size
         if self.config.final_history_length > 0 {
             while self.changes_history.len() >= self.config.final_history_length {
                 self.changes_history.pop_front();
             }
             self.changes_history.push_back((slot, changes));
+            while self.final_state_hash_history.len() >= self.config.final_history_length {
+                self.final_state_hash_history.pop_front();
+            }
+            self.final_state_hash_history
+                .push_back((slot, self.final_state_hash));
         }
 
-        // final hash computing and sub hashes logging
-        // 1. init hash concatenation with the ledger hash
+        self.slot_sender.send_replace(slot);
+    }
+
+    /// Computes the hash of the current final state from the hashes of its components,
+    /// logging every sub hash.
+    ///
+    /// The root of the ledger sparse Merkle tree comes first so that a ledger proof
+    /// can be checked against the final state hash given the other component hashes.
+    pub fn compute_state_hash(&self) -> Hash {
+        // 1. init hash concatenation with the ledger sparse Merkle root
+        let ledger_root = self.ledger.get_ledger_root();
+        let mut hash_concat: Vec<u8> = ledger_root.to_bytes().to_vec();
+        debug!("ledger root at slot {}: {}", self.slot, ledger_root);
+        // 2. other components hashes
+        for hash in self.get_state_hash_components() {
+            hash_concat.extend(hash.to_bytes());
+        }
+        // 3. final state hash
+        Hash::compute_from(&hash_concat)
+    }
+
+    /// Gets the hashes of the final state components following the ledger root in the final state hash, in order,
+    /// logging every sub hash.
+    pub fn get_state_hash_components(&self) -> Vec<Hash> {
+        let slot = self.slot;
+        // 1. ledger hash
         let ledger_hash = self.ledger.get_ledger_hash();
-        let mut hash_concat: Vec<u8> = ledger_hash.to_bytes().to_vec();
+        let mut hashes = vec![ledger_hash];
         debug!("ledger hash at slot {}: {}", slot, ledger_hash);
         // 2. async_pool hash
-        hash_concat.extend(self.async_pool.hash.to_bytes());
+        hashes.push(self.async_pool.hash);
         debug!("async_pool hash at slot {}: {}", slot, self.async_pool.hash);
         // 3. pos deferred_credit hash
-        hash_concat.extend(self.pos_state.deferred_credits.hash.to_bytes());
+        hashes.push(self.pos_state.deferred_credits.hash);
         debug!(
             "deferred_credit hash at slot {}: {}",
             slot, self.pos_state.deferred_credits.hash
@@ -136,21 +203,31 @@ impl FinalState {
         let n = (self.pos_state.cycle_history.len() == self.config.pos_config.cycle_history_length)
             as usize;
         for cycle_info in self.pos_state.cycle_history.iter().skip(n) {
-            hash_concat.extend(cycle_info.global_hash.to_bytes());
+            hashes.push(cycle_info.global_hash);
             debug!(
                 "cycle ({}) hash at slot {}: {}",
                 cycle_info.cycle, slot, cycle_info.global_hash
             );
         }
         // 5. executed operations hash
-        hash_concat.extend(self.executed_ops.hash.to_bytes());
+        hashes.push(self.executed_ops.hash);
         debug!(
             "executed_ops hash at slot {}: {}",
             slot, self.executed_ops.hash
         );
-        // 6. final state hash
-        let final_state_hash = Hash::compute_from(&hash_concat);
-        info!("final_state hash at slot {}: {}", slot, final_state_hash);
+        hashes
+    }
+
+    /// Gets the final state hash at the output of `slot`,
+    /// if `slot` is the current final slot or one of the slots of the changes history.
+    pub fn get_state_hash(&self, slot: Slot) -> Option<Hash> {
+        if slot == self.slot {
+            return Some(self.final_state_hash);
+        }
+        self.final_state_hash_history
+            .iter()
+            .find(|(history_slot, _)| *history_slot == slot)
+            .map(|(_, hash)| *hash)
     }
 
     /// Used for bootstrap.
@@ -6,9 +6,11 @@ use std::collections::VecDeque;
 
 use massa_async_pool::AsyncPool;
 use massa_executed_ops::ExecutedOps;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerController;
 use massa_models::slot::Slot;
 use massa_pos_exports::PoSFinalState;
+use tokio::sync::watch;
 
 use crate::{FinalState, FinalStateConfig, StateChanges};
 
@@ -22,7 +24,7 @@ pub fn create_final_state(
     pos_state: PoSFinalState,
     executed_ops: ExecutedOps,
 ) -> FinalState {
-    FinalState {
+    let mut final_state = FinalState {
         config,
         slot,
         ledger,
@@ -30,7 +32,12 @@ pub fn create_final_state(
         changes_history,
         pos_state,
         executed_ops,
-    }
+        final_state_hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        final_state_hash_history: Default::default(),
+        slot_sender: watch::channel(slot).0,
+    };
+    final_state.final_state_hash = final_state.compute_state_hash();
+    final_state
 }
 
 /// asserts that two `FinalState` are equal
@@ -67,6 +74,11 @@ pub fn assert_eq_final_state_hash(v1: &FinalState, v2: &FinalState) {
         v2.ledger.get_ledger_hash(),
         "ledger hash mismatch"
     );
+    assert_eq!(
+        v1.ledger.get_ledger_root(),
+        v2.ledger.get_ledger_root(),
+        "ledger root mismatch"
+    );
     assert_eq!(
         v1.async_pool.hash, v2.async_pool.hash,
         "async pool hash mismatch"
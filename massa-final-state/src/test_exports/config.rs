@@ -7,6 +7,7 @@ use std::path::PathBuf;
 use crate::{FinalState, FinalStateConfig};
 use massa_async_pool::{AsyncPool, AsyncPoolConfig};
 use massa_executed_ops::{ExecutedOps, ExecutedOpsConfig};
+use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerConfig;
 use massa_ledger_worker::FinalLedger;
 use massa_models::{
@@ -17,19 +18,25 @@ use massa_models::{
     slot::Slot,
 };
 use massa_pos_exports::{PoSConfig, PoSFinalState};
+use tokio::sync::watch;
 
 impl FinalState {
     /// Create a final stat
     pub fn create_final_state(pos_state: PoSFinalState, config: FinalStateConfig) -> Self {
-        FinalState {
+        let mut final_state = FinalState {
             slot: Slot::new(0, 0),
             ledger: Box::new(FinalLedger::new(config.ledger_config.clone())),
             async_pool: AsyncPool::new(config.async_pool_config.clone()),
             pos_state,
             executed_ops: ExecutedOps::new(config.executed_ops_config.clone()),
             changes_history: Default::default(),
+            final_state_hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+            final_state_hash_history: Default::default(),
+            slot_sender: watch::channel(Slot::new(0, 0)).0,
             config,
-        }
+        };
+        final_state.final_state_hash = final_state.compute_state_hash();
+        final_state
     }
 }
 
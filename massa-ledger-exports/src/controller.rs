@@ -1,6 +1,7 @@
 use massa_hash::Hash;
 use massa_models::{
-    address::Address, amount::Amount, error::ModelsError, slot::Slot, streaming_step::StreamingStep,
+    address::Address, amount::Amount, error::ModelsError, slot::Slot,
+    sparse_merkle::SparseMerkleProof, streaming_step::StreamingStep,
 };
 use std::collections::BTreeSet;
 use std::fmt::Debug;
@@ -14,6 +15,9 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Loads ledger from file
     fn load_initial_ledger(&mut self) -> Result<(), LedgerError>;
 
+    /// Removes every entry of the ledger, used to bootstrap it again from scratch
+    fn reset(&mut self);
+
     /// Gets the balance of a ledger entry
     ///
     /// # Returns
@@ -61,6 +65,18 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Get the current disk ledger hash
     fn get_ledger_hash(&self) -> Hash;
 
+    /// Get the root of the sparse Merkle tree authenticating the disk ledger entries
+    fn get_ledger_root(&self) -> Hash;
+
+    /// Get the size in bytes of the disk ledger files, as tracked by the database
+    fn get_disk_size(&self) -> u64;
+
+    /// Get the value of a ledger key along with its sparse Merkle proof against the ledger root
+    ///
+    /// # Returns
+    /// A tuple containing the value, or `None` if the key is absent, and its proof against the ledger root
+    fn get_key_proof(&self, key: &[u8]) -> (Option<Vec<u8>>, SparseMerkleProof);
+
     /// Get a part of the ledger
     /// Used for bootstrap
     /// Return: Tuple with data and last key
@@ -69,6 +85,16 @@ pub trait LedgerController: Send + Sync + Debug {
         last_key: StreamingStep<Vec<u8>>,
     ) -> Result<(Vec<u8>, StreamingStep<Vec<u8>>), ModelsError>;
 
+    /// Get a part of the ledger restricted to the addresses in `[start, end)`
+    /// Used for parallel bootstrap
+    /// Return: Tuple with data and last key, the last key being `StreamingStep::Finished` at the end of the range
+    fn get_ledger_range_part(
+        &self,
+        last_key: StreamingStep<Vec<u8>>,
+        start: Option<&Address>,
+        end: Option<&Address>,
+    ) -> Result<(Vec<u8>, StreamingStep<Vec<u8>>), ModelsError>;
+
     /// Set a part of the ledger
     /// Used for bootstrap
     /// Return: Last key inserted
@@ -6,9 +6,7 @@ use massa_serialization::{DeserializeError, Deserializer, SerializeError, Serial
 use nom::error::{ContextError, ParseError};
 use std::ops::Bound::Included;
 
-pub const BALANCE_IDENT: u8 = 0u8;
-pub const BYTECODE_IDENT: u8 = 1u8;
-pub const DATASTORE_IDENT: u8 = 2u8;
+pub use massa_models::ledger_models::{BALANCE_IDENT, BYTECODE_IDENT, DATASTORE_IDENT};
 
 /// Balance key formatting macro
 #[macro_export]
@@ -12,6 +12,7 @@ use massa_models::{
     amount::{Amount, AmountDeserializer},
     error::ModelsError,
     slot::Slot,
+    sparse_merkle::SparseMerkleProof,
     streaming_step::StreamingStep,
 };
 use massa_serialization::{DeserializeError, Deserializer};
@@ -159,11 +160,34 @@ impl LedgerController for FinalLedger {
         }
     }
 
+    /// Removes every entry of the disk ledger, along with its metadata and sparse Merkle tree
+    fn reset(&mut self) {
+        self.sorted_ledger.reset();
+    }
+
     /// Get the current disk ledger hash
     fn get_ledger_hash(&self) -> Hash {
         self.sorted_ledger.get_ledger_hash()
     }
 
+    /// Get the root of the sparse Merkle tree authenticating the disk ledger entries
+    fn get_ledger_root(&self) -> Hash {
+        self.sorted_ledger.get_ledger_root()
+    }
+
+    /// Get the size in bytes of the disk ledger files, as tracked by the database
+    fn get_disk_size(&self) -> u64 {
+        self.sorted_ledger.get_disk_size()
+    }
+
+    /// Get the value of a ledger key along with its sparse Merkle proof.
+    ///
+    /// # Returns
+    /// A tuple containing the value, or `None` if the key is absent, and its proof against the ledger root
+    fn get_key_proof(&self, key: &[u8]) -> (Option<Vec<u8>>, SparseMerkleProof) {
+        self.sorted_ledger.get_key_proof(key)
+    }
+
     /// Get a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -177,6 +201,22 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_ledger_part(last_key)
     }
 
+    /// Get a part of the disk ledger restricted to the addresses in `[start, end)`.
+    ///
+    /// Solely used by the parallel bootstrap.
+    ///
+    /// # Returns
+    /// A tuple containing the data and the last returned key
+    fn get_ledger_range_part(
+        &self,
+        last_key: StreamingStep<Vec<u8>>,
+        start: Option<&Address>,
+        end: Option<&Address>,
+    ) -> Result<(Vec<u8>, StreamingStep<Vec<u8>>), ModelsError> {
+        self.sorted_ledger
+            .get_ledger_range_part(last_key, start, end)
+    }
+
     /// Set a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
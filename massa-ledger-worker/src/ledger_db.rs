@@ -10,6 +10,11 @@ use massa_models::{
     error::ModelsError,
     serialization::{VecU8Deserializer, VecU8Serializer},
     slot::{Slot, SlotSerializer},
+    sparse_merkle::{
+        sparse_merkle_empty_hash, sparse_merkle_node_hash, sparse_merkle_node_position,
+        sparse_merkle_path, sparse_merkle_path_bit, sparse_merkle_sibling_position,
+        SparseMerkleLeaf, SparseMerkleProof,
+    },
     streaming_step::StreamingStep,
 };
 use massa_serialization::{Deserializer, Serializer, U64VarIntSerializer};
@@ -33,10 +38,12 @@ use massa_models::amount::{Amount, AmountDeserializer};
 
 const LEDGER_CF: &str = "ledger";
 const METADATA_CF: &str = "metadata";
+const MERKLE_CF: &str = "merkle";
 const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
 const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
 const CF_ERROR: &str = "critical: rocksdb column family operation failed";
 const LEDGER_HASH_ERROR: &str = "critical: saved ledger hash is corrupted";
+const MERKLE_NODE_ERROR: &str = "critical: saved ledger merkle node is corrupted";
 const KEY_LEN_SER_ERROR: &str = "critical: key length serialization failed";
 const SLOT_KEY: &[u8; 1] = b"s";
 const LEDGER_HASH_KEY: &[u8; 1] = b"h";
@@ -96,6 +103,67 @@ fn test_end_prefix() {
     assert_eq!(end_prefix(&[5, 6, 255]), Some(vec![5, 7]));
 }
 
+/// Key of a sparse Merkle tree node in the merkle column: the node depth followed by the meaningful bytes of its position
+fn merkle_node_key(depth: usize, position: &[u8; HASH_SIZE_BYTES]) -> Vec<u8> {
+    let mut key = (depth as u16).to_be_bytes().to_vec();
+    key.extend_from_slice(&position[..(depth + 7) / 8]);
+    key
+}
+
+/// Node of the sparse Merkle tree, stored in the merkle column as a tag byte followed by its content
+enum MerkleNode {
+    /// empty subtree, not stored
+    Empty,
+    /// subtree holding a single key
+    Leaf(SparseMerkleLeaf),
+    /// subtree holding several keys, with its hash
+    Internal(Hash),
+}
+
+const MERKLE_LEAF_TAG: u8 = 0;
+const MERKLE_INTERNAL_TAG: u8 = 1;
+
+impl MerkleNode {
+    fn hash(&self) -> Hash {
+        match self {
+            MerkleNode::Empty => sparse_merkle_empty_hash(),
+            MerkleNode::Leaf(leaf) => leaf.node_hash(),
+            MerkleNode::Internal(hash) => *hash,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            MerkleNode::Empty => Vec::new(),
+            MerkleNode::Leaf(leaf) => [
+                [MERKLE_LEAF_TAG].as_slice(),
+                leaf.path.to_bytes().as_slice(),
+                leaf.entry_hash.to_bytes().as_slice(),
+            ]
+            .concat(),
+            MerkleNode::Internal(hash) => {
+                [[MERKLE_INTERNAL_TAG].as_slice(), hash.to_bytes().as_slice()].concat()
+            }
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        match bytes.split_first() {
+            Some((&MERKLE_LEAF_TAG, content)) if content.len() == 2 * HASH_SIZE_BYTES => {
+                let (path, entry_hash) = content.split_at(HASH_SIZE_BYTES);
+                MerkleNode::Leaf(SparseMerkleLeaf {
+                    path: Hash::from_bytes(path.try_into().expect(MERKLE_NODE_ERROR)),
+                    entry_hash: Hash::from_bytes(entry_hash.try_into().expect(MERKLE_NODE_ERROR)),
+                })
+            }
+            Some((&MERKLE_INTERNAL_TAG, content)) => MerkleNode::Internal(Hash::from_bytes(
+                content.try_into().expect(MERKLE_NODE_ERROR),
+            )),
+            _ => panic!("{}", MERKLE_NODE_ERROR),
+        }
+    }
+}
+
 /// Batch containing write operations to perform on disk and cache for the ledger hash computing
 pub struct LedgerBatch {
     // Rocksdb write batch
@@ -104,6 +172,8 @@ pub struct LedgerBatch {
     ledger_hash: Hash,
     // Added entry hashes in the current batch
     aeh_list: BTreeMap<Vec<u8>, Hash>,
+    // New sparse Merkle leaves of the keys modified in the current batch, `None` for deleted keys
+    merkle_leaves: BTreeMap<Vec<u8>, Option<Hash>>,
 }
 
 impl LedgerBatch {
@@ -112,6 +182,7 @@ impl LedgerBatch {
             write_batch: WriteBatch::default(),
             ledger_hash,
             aeh_list: BTreeMap::new(),
+            merkle_leaves: BTreeMap::new(),
         }
     }
 }
@@ -137,6 +208,7 @@ impl LedgerDB {
             vec![
                 ColumnFamilyDescriptor::new(LEDGER_CF, Options::default()),
                 ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+                ColumnFamilyDescriptor::new(MERKLE_CF, Options::default()),
             ],
         )
         .expect(OPEN_ERROR);
@@ -212,6 +284,7 @@ impl LedgerDB {
 
     /// Apply the given operation batch to the disk ledger
     fn write_batch(&self, mut batch: LedgerBatch) {
+        self.update_merkle_tree(&mut batch);
         let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
         batch
             .write_batch
@@ -241,6 +314,50 @@ impl LedgerDB {
         batch.ledger_hash ^= Hash::compute_from(&slot_bytes);
     }
 
+    /// Get the size of the SST files of every column family.
+    ///
+    /// Read from the `rocksdb.total-sst-files-size` property so that the disk is not walked.
+    pub fn get_disk_size(&self) -> u64 {
+        [LEDGER_CF, METADATA_CF, MERKLE_CF]
+            .into_iter()
+            .map(|cf| {
+                let handle = self.db.cf_handle(cf).expect(CF_ERROR);
+                self.db
+                    .property_int_value_cf(handle, "rocksdb.total-sst-files-size")
+                    .expect(CRUD_ERROR)
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Delete every key of every column family
+    ///
+    /// Each column family is cleared with a single range tombstone instead of one deletion per key.
+    pub fn reset(&self) {
+        let mut write_batch = WriteBatch::default();
+        for cf in [LEDGER_CF, METADATA_CF, MERKLE_CF] {
+            let handle = self.db.cf_handle(cf).expect(CF_ERROR);
+            let first_key = self
+                .db
+                .iterator_cf(handle, IteratorMode::Start)
+                .flatten()
+                .map(|(key, _)| key)
+                .next();
+            let last_key = self
+                .db
+                .iterator_cf(handle, IteratorMode::End)
+                .flatten()
+                .map(|(key, _)| key)
+                .next();
+            if let (Some(first_key), Some(last_key)) = (first_key, last_key) {
+                // the range end is exclusive
+                write_batch.delete_range_cf(handle, &first_key, &last_key);
+                write_batch.delete_cf(handle, &last_key);
+            }
+        }
+        self.db.write(write_batch).expect(CRUD_ERROR);
+    }
+
     /// Get the current disk ledger hash
     pub fn get_ledger_hash(&self) -> Hash {
         let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
@@ -255,6 +372,201 @@ impl LedgerDB {
         }
     }
 
+    /// Get a sparse Merkle tree node, `MerkleNode::Empty` if it is not stored
+    fn get_merkle_node(&self, depth: usize, position: &[u8; HASH_SIZE_BYTES]) -> MerkleNode {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        match self
+            .db
+            .get_cf(handle, merkle_node_key(depth, position))
+            .expect(CRUD_ERROR)
+        {
+            Some(node_bytes) => MerkleNode::from_bytes(&node_bytes),
+            None => MerkleNode::Empty,
+        }
+    }
+
+    /// Store a sparse Merkle tree node, or remove it if it is empty
+    fn put_merkle_node(
+        &self,
+        write_batch: &mut WriteBatch,
+        depth: usize,
+        position: &[u8; HASH_SIZE_BYTES],
+        node: &MerkleNode,
+    ) {
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        match node {
+            MerkleNode::Empty => write_batch.delete_cf(handle, merkle_node_key(depth, position)),
+            _ => write_batch.put_cf(handle, merkle_node_key(depth, position), node.to_bytes()),
+        }
+    }
+
+    /// Recompute the sparse Merkle tree nodes on the paths of the keys modified in the batch.
+    /// Only the existing nodes on these paths are read, so the cost of a key is the depth of its leaf.
+    fn update_merkle_tree(&self, batch: &mut LedgerBatch) {
+        if batch.merkle_leaves.is_empty() {
+            return;
+        }
+        // sorted by path so that the changes of a subtree are contiguous, left subtree first
+        let changes: Vec<([u8; HASH_SIZE_BYTES], Option<Hash>)> =
+            std::mem::take(&mut batch.merkle_leaves)
+                .into_iter()
+                .map(|(key, leaf)| (sparse_merkle_path(&key), leaf))
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .collect();
+        self.update_merkle_subtree(&mut batch.write_batch, 0, &[0; HASH_SIZE_BYTES], &changes);
+    }
+
+    /// Apply the `changes` of the keys located under the node of depth `depth` at `position`,
+    /// write the updated nodes to `write_batch` and return the updated node
+    fn update_merkle_subtree(
+        &self,
+        write_batch: &mut WriteBatch,
+        depth: usize,
+        position: &[u8; HASH_SIZE_BYTES],
+        changes: &[([u8; HASH_SIZE_BYTES], Option<Hash>)],
+    ) -> MerkleNode {
+        let node = self.get_merkle_node(depth, position);
+        if changes.is_empty() {
+            return node;
+        }
+        let updated_node = match node {
+            MerkleNode::Internal(_) => {
+                let split =
+                    changes.partition_point(|(path, _)| !sparse_merkle_path_bit(path, depth));
+                let right_position = sparse_merkle_sibling_position(position, depth + 1);
+                let left =
+                    self.update_merkle_subtree(write_batch, depth + 1, position, &changes[..split]);
+                let right = self.update_merkle_subtree(
+                    write_batch,
+                    depth + 1,
+                    &right_position,
+                    &changes[split..],
+                );
+                // a subtree left with a single key is replaced by its leaf
+                match (left, right) {
+                    (MerkleNode::Empty, MerkleNode::Empty) => MerkleNode::Empty,
+                    (leaf @ MerkleNode::Leaf(_), MerkleNode::Empty) => {
+                        self.put_merkle_node(write_batch, depth + 1, position, &MerkleNode::Empty);
+                        leaf
+                    }
+                    (MerkleNode::Empty, leaf @ MerkleNode::Leaf(_)) => {
+                        self.put_merkle_node(
+                            write_batch,
+                            depth + 1,
+                            &right_position,
+                            &MerkleNode::Empty,
+                        );
+                        leaf
+                    }
+                    (left, right) => {
+                        MerkleNode::Internal(sparse_merkle_node_hash(&left.hash(), &right.hash()))
+                    }
+                }
+            }
+            MerkleNode::Leaf(leaf) => {
+                // rebuild the subtree from the keys of the changes and the one of the leaf, if it is not changed
+                let mut leaves: Vec<([u8; HASH_SIZE_BYTES], Hash)> = changes
+                    .iter()
+                    .filter_map(|(path, entry_hash)| entry_hash.map(|hash| (*path, hash)))
+                    .collect();
+                let leaf_path = leaf.path.into_bytes();
+                if !changes.iter().any(|(path, _)| *path == leaf_path) {
+                    let index = leaves.partition_point(|(path, _)| *path < leaf_path);
+                    leaves.insert(index, (leaf_path, leaf.entry_hash));
+                }
+                self.build_merkle_subtree(write_batch, depth, position, &leaves)
+            }
+            MerkleNode::Empty => {
+                let leaves: Vec<([u8; HASH_SIZE_BYTES], Hash)> = changes
+                    .iter()
+                    .filter_map(|(path, entry_hash)| entry_hash.map(|hash| (*path, hash)))
+                    .collect();
+                self.build_merkle_subtree(write_batch, depth, position, &leaves)
+            }
+        };
+        self.put_merkle_node(write_batch, depth, position, &updated_node);
+        updated_node
+    }
+
+    /// Build the subtree of depth `depth` at `position` holding the sorted `leaves`, where no node is stored yet.
+    /// The nodes below the returned one are written to `write_batch`.
+    fn build_merkle_subtree(
+        &self,
+        write_batch: &mut WriteBatch,
+        depth: usize,
+        position: &[u8; HASH_SIZE_BYTES],
+        leaves: &[([u8; HASH_SIZE_BYTES], Hash)],
+    ) -> MerkleNode {
+        match leaves {
+            [] => MerkleNode::Empty,
+            [(path, entry_hash)] => MerkleNode::Leaf(SparseMerkleLeaf {
+                path: Hash::from_bytes(path),
+                entry_hash: *entry_hash,
+            }),
+            _ => {
+                let split =
+                    leaves.partition_point(|(path, _)| !sparse_merkle_path_bit(path, depth));
+                let right_position = sparse_merkle_sibling_position(position, depth + 1);
+                let left =
+                    self.build_merkle_subtree(write_batch, depth + 1, position, &leaves[..split]);
+                let right = self.build_merkle_subtree(
+                    write_batch,
+                    depth + 1,
+                    &right_position,
+                    &leaves[split..],
+                );
+                self.put_merkle_node(write_batch, depth + 1, position, &left);
+                self.put_merkle_node(write_batch, depth + 1, &right_position, &right);
+                MerkleNode::Internal(sparse_merkle_node_hash(&left.hash(), &right.hash()))
+            }
+        }
+    }
+
+    /// Get the root of the sparse Merkle tree authenticating the ledger entries
+    pub fn get_ledger_root(&self) -> Hash {
+        self.get_merkle_node(0, &[0; HASH_SIZE_BYTES]).hash()
+    }
+
+    /// Get the value of a ledger key along with its sparse Merkle proof.
+    ///
+    /// # Returns
+    /// A tuple containing:
+    /// * the value of the key, or `None` if the key is absent (the proof is then an exclusion proof)
+    /// * the proof of the value against the ledger root
+    pub fn get_key_proof(&self, key: &[u8]) -> (Option<Vec<u8>>, SparseMerkleProof) {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let value = self.db.get_cf(handle, key).expect(CRUD_ERROR);
+        let path = sparse_merkle_path(key);
+        let mut siblings = Vec::new();
+        let mut depth = 0;
+        let mut node = self.get_merkle_node(depth, &[0; HASH_SIZE_BYTES]);
+        // go down the path until it ends on a leaf or an empty subtree
+        while let MerkleNode::Internal(_) = node {
+            depth += 1;
+            let position = sparse_merkle_node_position(&path, depth);
+            let sibling =
+                self.get_merkle_node(depth, &sparse_merkle_sibling_position(&position, depth));
+            siblings.push(match sibling {
+                MerkleNode::Empty => None,
+                sibling => Some(sibling.hash()),
+            });
+            node = self.get_merkle_node(depth, &position);
+        }
+        siblings.reverse();
+        let other_leaf = match node {
+            MerkleNode::Leaf(leaf) if leaf.path.into_bytes() != path => Some(leaf),
+            _ => None,
+        };
+        (
+            value,
+            SparseMerkleProof {
+                siblings,
+                other_leaf,
+            },
+        )
+    }
+
     /// Internal function to put a key & value and perform the ledger hash XORs
     fn put_entry_value(
         &self,
@@ -270,6 +582,7 @@ impl LedgerDB {
         let hash = Hash::compute_from(&[&len_bytes, key, value].concat());
         batch.ledger_hash ^= hash;
         batch.aeh_list.insert(key.to_vec(), hash);
+        batch.merkle_leaves.insert(key.to_vec(), Some(hash));
         batch.write_batch.put_cf(handle, key, value);
     }
 
@@ -367,6 +680,7 @@ impl LedgerDB {
         let hash = Hash::compute_from(&[&len_bytes, key, value].concat());
         batch.ledger_hash ^= hash;
         batch.aeh_list.insert(key.to_vec(), hash);
+        batch.merkle_leaves.insert(key.to_vec(), Some(hash));
         batch.write_batch.put_cf(handle, key, value);
     }
 
@@ -420,6 +734,7 @@ impl LedgerDB {
                 .expect(KEY_LEN_SER_ERROR);
             batch.ledger_hash ^= Hash::compute_from(&[&len_bytes, key, &prev_bytes].concat());
         }
+        batch.merkle_leaves.insert(key.to_vec(), None);
         batch.write_batch.delete_cf(handle, key);
     }
 
@@ -465,6 +780,27 @@ impl LedgerDB {
     pub fn get_ledger_part(
         &self,
         cursor: StreamingStep<Vec<u8>>,
+    ) -> Result<(Vec<u8>, StreamingStep<Vec<u8>>), ModelsError> {
+        self.get_ledger_range_part(cursor, None, None)
+    }
+
+    /// Get a part of the disk Ledger restricted to the entries of the addresses in `[start, end)`.
+    /// Used by the bootstrap to stream disjoint ranges of the ledger from several servers.
+    ///
+    /// # Arguments
+    /// * `cursor`: last key taken in the previous part
+    /// * `start`: first address of the range, from the beginning of the ledger if `None`
+    /// * `end`: excluded last address of the range, until the end of the ledger if `None`
+    ///
+    /// # Returns
+    /// A tuple containing:
+    /// * The ledger part as bytes
+    /// * The last taken key, or `StreamingStep::Finished` if the range was entirely streamed
+    pub fn get_ledger_range_part(
+        &self,
+        cursor: StreamingStep<Vec<u8>>,
+        start: Option<&Address>,
+        end: Option<&Address>,
     ) -> Result<(Vec<u8>, StreamingStep<Vec<u8>>), ModelsError> {
         let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
         let opt = ReadOptions::default();
@@ -472,26 +808,42 @@ impl LedgerDB {
         let key_serializer = KeySerializer::new();
         let mut ledger_part = Vec::new();
 
-        // Creates an iterator from the next element after the last if defined, otherwise initialize it at the first key of the ledger.
-        let (db_iterator, mut new_cursor) = match cursor {
-            StreamingStep::Started => (
-                self.db.iterator_cf_opt(handle, opt, IteratorMode::Start),
-                StreamingStep::Started,
-            ),
-            StreamingStep::Ongoing(last_key) => {
-                let mut iter = self.db.iterator_cf_opt(
+        // Creates an iterator from the last key if defined, otherwise initialize it at the first key of the range.
+        let (db_iterator, last_key) = match cursor {
+            StreamingStep::Started => match start {
+                Some(address) => (
+                    self.db.iterator_cf_opt(
+                        handle,
+                        opt,
+                        IteratorMode::From(address.to_bytes(), Direction::Forward),
+                    ),
+                    None,
+                ),
+                None => (
+                    self.db.iterator_cf_opt(handle, opt, IteratorMode::Start),
+                    None,
+                ),
+            },
+            StreamingStep::Ongoing(last_key) => (
+                self.db.iterator_cf_opt(
                     handle,
                     opt,
                     IteratorMode::From(&last_key, Direction::Forward),
-                );
-                iter.next();
-                (iter, StreamingStep::Finished(None))
-            }
+                ),
+                Some(last_key),
+            ),
             StreamingStep::Finished(_) => return Ok((ledger_part, cursor)),
         };
+        let mut new_cursor = StreamingStep::Finished(None);
 
-        // Iterates over the whole database
+        // Iterates over the range, skipping the last key if it is still in the ledger
         for (key, entry) in db_iterator.flatten() {
+            if Some(key.as_ref()) == last_key.as_deref() {
+                continue;
+            }
+            if matches!(end, Some(end) if key.as_ref() >= &end.to_bytes()[..]) {
+                break;
+            }
             if (ledger_part.len() as u64) < (self.ledger_part_size_message_bytes) {
                 key_serializer.serialize(&key.to_vec(), &mut ledger_part)?;
                 ser.serialize(&entry.to_vec(), &mut ledger_part)?;
@@ -623,16 +975,23 @@ impl LedgerDB {
 #[cfg(test)]
 mod tests {
     use super::LedgerDB;
-    use crate::ledger_db::{LedgerBatch, LedgerSubEntry, LEDGER_HASH_INITIAL_BYTES};
+    use crate::ledger_db::{
+        LedgerBatch, LedgerSubEntry, CF_ERROR, LEDGER_HASH_INITIAL_BYTES, MERKLE_CF,
+    };
     use massa_hash::Hash;
-    use massa_ledger_exports::{LedgerEntry, LedgerEntryUpdate, SetOrKeep};
+    use massa_ledger_exports::{
+        balance_key, data_key, LedgerEntry, LedgerEntryUpdate, SetOrKeep, BALANCE_IDENT,
+        DATASTORE_IDENT,
+    };
     use massa_models::{
         address::Address,
         amount::{Amount, AmountDeserializer},
+        sparse_merkle::{sparse_merkle_empty_hash, sparse_merkle_root_from_proof},
         streaming_step::StreamingStep,
     };
     use massa_serialization::{DeserializeError, Deserializer};
     use massa_signature::KeyPair;
+    use rocksdb::IteratorMode;
     use std::collections::BTreeMap;
     use std::ops::Bound::Included;
     use std::str::FromStr;
@@ -694,6 +1053,27 @@ mod tests {
             db.get_ledger_hash()
         );
 
+        // check inclusion and exclusion proofs against the ledger root
+        let ledger_root = db.get_ledger_root();
+        assert_ne!(ledger_root, sparse_merkle_empty_hash());
+        for key in [
+            balance_key!(addr),
+            data_key!(addr, b"2".to_vec()),
+            data_key!(addr, b"4".to_vec()),
+        ] {
+            let (value, proof) = db.get_key_proof(&key);
+            assert_eq!(
+                sparse_merkle_root_from_proof(&key, value.as_deref(), &proof).unwrap(),
+                ledger_root
+            );
+        }
+        let (value, proof) = db.get_key_proof(&data_key!(addr, b"2".to_vec()));
+        assert_ne!(
+            sparse_merkle_root_from_proof(&data_key!(addr, b"2".to_vec()), None, &proof).unwrap(),
+            ledger_root
+        );
+        assert_eq!(value, Some(b"b".to_vec()));
+
         // delete entry
         let mut batch = LedgerBatch::new(ledger_hash);
         db.delete_entry(&addr, &mut batch);
@@ -706,6 +1086,93 @@ mod tests {
         );
         assert!(db.get_sub_entry(&addr, LedgerSubEntry::Balance).is_none());
         assert!(db.get_entire_datastore(&addr).is_empty());
+        assert_eq!(db.get_ledger_root(), sparse_merkle_empty_hash());
+    }
+
+    /// `reset` empties every column family, including their first and last keys
+    #[test]
+    fn test_ledger_reset() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (db, _) = init_test_ledger(addr);
+        let other_addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let mut batch = LedgerBatch::new(db.get_ledger_hash());
+        db.put_entry(&other_addr, LedgerEntry::default(), &mut batch);
+        db.write_batch(batch);
+
+        db.reset();
+
+        for cf in [LEDGER_CF, METADATA_CF, MERKLE_CF] {
+            let handle = db.db.cf_handle(cf).expect(CF_ERROR);
+            assert_eq!(
+                db.db.iterator_cf(handle, IteratorMode::Start).count(),
+                0,
+                "column family {} is not empty",
+                cf
+            );
+        }
+        assert_eq!(
+            Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES),
+            db.get_ledger_hash()
+        );
+        assert!(db.get_sub_entry(&addr, LedgerSubEntry::Balance).is_none());
+    }
+
+    /// The sparse Merkle tree only depends on the ledger content, not on the order of the updates
+    #[test]
+    fn test_ledger_merkle_tree_is_canonical() {
+        let entry = |count: u8| LedgerEntry {
+            balance: Amount::from_str("42").unwrap(),
+            datastore: (0..count).map(|i| (vec![i], vec![i; 3])).collect(),
+            ..Default::default()
+        };
+        let addrs: Vec<Address> = (0..4)
+            .map(|_| Address::from_public_key(&KeyPair::generate().get_public_key()))
+            .collect();
+
+        // all the entries at once
+        let temp_dir_a = TempDir::new().unwrap();
+        let mut db_a = LedgerDB::new(temp_dir_a.path().to_path_buf(), 32, 255, 1_000_000);
+        let mut batch = LedgerBatch::new(db_a.get_ledger_hash());
+        for addr in &addrs[..3] {
+            db_a.put_entry(addr, entry(20), &mut batch);
+        }
+        db_a.write_batch(batch);
+
+        // one entry per batch, with an additional entry later deleted
+        let temp_dir_b = TempDir::new().unwrap();
+        let mut db_b = LedgerDB::new(temp_dir_b.path().to_path_buf(), 32, 255, 1_000_000);
+        for addr in addrs.iter().rev() {
+            let mut batch = LedgerBatch::new(db_b.get_ledger_hash());
+            db_b.put_entry(addr, entry(20), &mut batch);
+            db_b.write_batch(batch);
+        }
+        let mut batch = LedgerBatch::new(db_b.get_ledger_hash());
+        db_b.delete_entry(&addrs[3], &mut batch);
+        db_b.write_batch(batch);
+
+        // same root and same stored nodes
+        let ledger_root = db_a.get_ledger_root();
+        assert_eq!(ledger_root, db_b.get_ledger_root());
+        let merkle_nodes = |db: &LedgerDB| -> Vec<(Box<[u8]>, Box<[u8]>)> {
+            let handle = db.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+            db.db
+                .iterator_cf(handle, IteratorMode::Start)
+                .flatten()
+                .collect()
+        };
+        assert_eq!(merkle_nodes(&db_a), merkle_nodes(&db_b));
+
+        // the proofs are short and lead to the root
+        for addr in &addrs {
+            for key in (0..21u8).map(|i| data_key!(addr, vec![i])) {
+                let (value, proof) = db_b.get_key_proof(&key);
+                assert!(proof.siblings.len() < 32);
+                assert_eq!(
+                    sparse_merkle_root_from_proof(&key, value.as_deref(), &proof).unwrap(),
+                    ledger_root
+                );
+            }
+        }
     }
 
     #[test]
@@ -716,4 +1183,28 @@ mod tests {
         let res = db.get_ledger_part(StreamingStep::Started).unwrap();
         db.set_ledger_part(&res.0[..]).unwrap();
     }
+
+    #[test]
+    fn test_ledger_range_parts() {
+        let pub_a = KeyPair::generate().get_public_key();
+        let a = Address::from_public_key(&pub_a);
+        let (db, _) = init_test_ledger(a);
+
+        // the range ending at the address excludes all of its entries
+        let (part, cursor) = db
+            .get_ledger_range_part(StreamingStep::Started, None, Some(&a))
+            .unwrap();
+        assert!(part.is_empty());
+        assert!(cursor.finished());
+
+        // the range starting at the address contains all of its entries
+        let (part, cursor) = db
+            .get_ledger_range_part(StreamingStep::Started, Some(&a), None)
+            .unwrap();
+        assert!(!part.is_empty());
+        let (rest, cursor) = db.get_ledger_range_part(cursor, Some(&a), None).unwrap();
+        assert!(rest.is_empty());
+        assert!(cursor.finished());
+        db.set_ledger_part(&part[..]).unwrap();
+    }
 }
@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::amount::Amount;
 use crate::error::ModelsError;
 use crate::prehash::PreHashed;
 use massa_hash::{Hash, HashDeserializer};
@@ -299,4 +300,10 @@ pub struct ExecutionAddressCycleInfo {
     pub nok_count: u64,
     /// number of active rolls the address had at that cycle (if still available)
     pub active_rolls: Option<u64>,
+    /// coins earned by creating blocks and by having created blocks endorsed during that cycle,
+    /// `None` if the node did not execute every final slot of that cycle
+    pub block_rewards: Option<Amount>,
+    /// coins earned by creating endorsements during that cycle,
+    /// `None` if the node did not execute every final slot of that cycle
+    pub endorsement_rewards: Option<Amount>,
 }
@@ -2,14 +2,20 @@
 
 use crate::address::ExecutionAddressCycleInfo;
 use crate::endorsement::{EndorsementId, WrappedEndorsement};
-use crate::ledger_models::LedgerData;
-use crate::node::NodeId;
+use crate::error::ModelsError;
+use crate::ledger_models::{LedgerData, BALANCE_IDENT, BYTECODE_IDENT, DATASTORE_IDENT};
+use crate::node::{BanReason, NodeId};
 use crate::operation::{OperationId, WrappedOperation};
-use crate::stats::{ConsensusStats, ExecutionStats, NetworkStats};
+use crate::sparse_merkle::{sparse_merkle_root_from_proof, SparseMerkleProof};
+use crate::stats::{
+    BootstrapStats, ConsensusStats, ExecutionStats, MessageTrafficStats, NetworkStats,
+};
+use crate::streaming_step::StreamingStep;
 use crate::{
     address::Address, amount::Amount, block::Block, block::BlockId, config::CompactConfig,
-    slot::Slot, version::Version,
+    multisig::MultisigSignatures, slot::Slot, version::Version,
 };
+use massa_hash::Hash;
 use massa_signature::{PublicKey, Signature};
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
@@ -25,6 +31,9 @@ pub struct OperationInput {
     pub signature: Signature,
     /// The serialized version of the content `base58` encoded
     pub serialized_content: Vec<u8>,
+    /// The signatures of a multisig creator, `creator_public_key` and `signature` then hold the first of them
+    #[serde(default)]
+    pub multisig: Option<MultisigSignatures>,
 }
 
 /// node status
@@ -54,6 +63,10 @@ pub struct NodeStatus {
     pub network_stats: NetworkStats,
     /// execution stats
     pub execution_stats: ExecutionStats,
+    /// hash of the latest final state
+    pub final_state_hash: FinalStateHash,
+    /// bootstrap server stats
+    pub bootstrap_stats: BootstrapStats,
     /// compact configuration
     pub config: CompactConfig,
 }
@@ -91,6 +104,11 @@ impl std::fmt::Display for NodeStatus {
 
         writeln!(f, "{}", self.execution_stats)?;
 
+        writeln!(f, "{}", self.bootstrap_stats)?;
+
+        writeln!(f, "Final state hash: {}", self.final_state_hash)?;
+        writeln!(f)?;
+
         writeln!(f, "Connected nodes:")?;
         for (node_id, (ip_addr, is_outgoing)) in &self.connected_nodes {
             writeln!(
@@ -105,6 +123,247 @@ impl std::fmt::Display for NodeStatus {
     }
 }
 
+/// Active bootstrap session served by the node
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BootstrapSessionInfo {
+    /// IP of the bootstrapping peer
+    pub ip: IpAddr,
+    /// session start time
+    pub start_time: MassaTime,
+    /// bytes sent to the peer so far
+    pub bytes_sent: u64,
+    /// final state slot of the last bootstrap part sent
+    pub slot: Option<Slot>,
+    /// ledger cursor: address of the last ledger entry sent
+    pub ledger_step: StreamingStep<Address>,
+    /// async pool cursor: emission slot of the last message sent
+    pub async_pool_step: StreamingStep<Slot>,
+    /// PoS cycle history cursor: last cycle sent
+    pub cycle_step: StreamingStep<u64>,
+    /// PoS deferred credits cursor: last credit slot sent
+    pub credits_step: StreamingStep<Slot>,
+    /// executed operations cursor: last expiration slot sent
+    pub executed_ops_step: StreamingStep<Slot>,
+    /// consensus cursor: number of blocks sent
+    pub consensus_step: StreamingStep<usize>,
+}
+
+impl std::fmt::Display for BootstrapSessionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Peer IP: {}", self.ip)?;
+        writeln!(f, "\tStart time: {}", self.start_time.to_utc_string())?;
+        writeln!(f, "\tBytes sent: {}", self.bytes_sent)?;
+        if let Some(slot) = self.slot {
+            writeln!(f, "\tFinal state slot: {}", slot)?;
+        }
+        writeln!(f, "\tLedger: {}", self.ledger_step)?;
+        writeln!(f, "\tAsync pool: {}", self.async_pool_step)?;
+        writeln!(f, "\tCycle history: {}", self.cycle_step)?;
+        writeln!(f, "\tDeferred credits: {}", self.credits_step)?;
+        writeln!(f, "\tExecuted operations: {}", self.executed_ops_step)?;
+        writeln!(f, "\tConsensus blocks: {}", self.consensus_step)?;
+        Ok(())
+    }
+}
+
+/// Peer currently banned by the node
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct BannedPeer {
+    /// IP of the banned peer
+    pub ip: IpAddr,
+    /// why the peer was banned
+    pub reason: BanReason,
+    /// time at which the ban is lifted, None if it lasts until the peer is unbanned
+    pub expiry: Option<MassaTime>,
+}
+
+impl std::fmt::Display for BannedPeer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.ip, self.reason)?;
+        match self.expiry {
+            Some(expiry) => write!(f, ", until {}", expiry.to_utc_string()),
+            None => write!(f, ", until unbanned"),
+        }
+    }
+}
+
+/// Node we are connected to, with statistics about the connection
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ConnectedPeer {
+    /// id of the node
+    pub node_id: NodeId,
+    /// ip of the node
+    pub ip: IpAddr,
+    /// true if we initiated the connection
+    pub is_outgoing: bool,
+    /// version announced by the node during the handshake
+    pub version: Version,
+    /// time elapsed since the handshake succeeded
+    pub connection_age: MassaTime,
+    /// last time a message was sent to or received from the node
+    pub last_activity: Option<MassaTime>,
+    /// round-trip time of the last peer list request sent to the node
+    pub ping_rtt: Option<MassaTime>,
+    /// number of blocks asked to the node that it did not deliver yet
+    pub ask_block_queue_depth: usize,
+    /// traffic exchanged with the node, by message type
+    pub traffic: BTreeMap<String, MessageTrafficStats>,
+}
+
+impl std::fmt::Display for ConnectedPeer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Node {} ({}, {}):",
+            self.node_id,
+            self.ip,
+            if self.is_outgoing { "out" } else { "in" }
+        )?;
+        writeln!(f, "\tVersion: {}", self.version)?;
+        writeln!(
+            f,
+            "\tConnection age: {} s",
+            self.connection_age.to_millis() / 1000
+        )?;
+        match self.last_activity {
+            Some(last_activity) => {
+                writeln!(f, "\tLast activity: {}", last_activity.to_utc_string())?
+            }
+            None => writeln!(f, "\tLast activity: none")?,
+        }
+        match self.ping_rtt {
+            Some(rtt) => writeln!(f, "\tPing: {} ms", rtt.to_millis())?,
+            None => writeln!(f, "\tPing: unknown")?,
+        }
+        writeln!(f, "\tAsk block queue depth: {}", self.ask_block_queue_depth)?;
+        writeln!(f, "\tTraffic:")?;
+        for (message_type, traffic) in &self.traffic {
+            writeln!(f, "\t\t{}: {}", message_type, traffic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hash of the final state at the output of a final slot
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct FinalStateHash {
+    /// final slot
+    pub slot: Slot,
+    /// hash of the final state at the output of `slot`
+    pub hash: Hash,
+}
+
+impl std::fmt::Display for FinalStateHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at slot {}", self.hash, self.slot)
+    }
+}
+
+/// Final ledger sub-entry of an address targeted by a ledger proof query
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum LedgerProofSubEntry {
+    /// balance
+    Balance,
+    /// bytecode
+    Bytecode,
+    /// datastore entry at the given datastore key
+    Datastore(Vec<u8>),
+}
+
+impl LedgerProofSubEntry {
+    /// Disk ledger key of the sub-entry of `address`
+    pub fn ledger_key(&self, address: &Address) -> Vec<u8> {
+        match self {
+            LedgerProofSubEntry::Balance => [&address.to_bytes()[..], &[BALANCE_IDENT]].concat(),
+            LedgerProofSubEntry::Bytecode => [&address.to_bytes()[..], &[BYTECODE_IDENT]].concat(),
+            LedgerProofSubEntry::Datastore(key) => {
+                [&address.to_bytes()[..], &[DATASTORE_IDENT], key].concat()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LedgerProofSubEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerProofSubEntry::Balance => write!(f, "balance"),
+            LedgerProofSubEntry::Bytecode => write!(f, "bytecode"),
+            LedgerProofSubEntry::Datastore(key) => write!(f, "datastore entry {:?}", key),
+        }
+    }
+}
+
+/// Ledger proof query input structure
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct LedgerProofInput {
+    /// address of the entry
+    pub address: Address,
+    /// targeted sub-entry
+    pub sub_entry: LedgerProofSubEntry,
+}
+
+/// Final ledger sub-entry value along with its inclusion or exclusion proof against the final state hash
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct LedgerProof {
+    /// address of the entry
+    pub address: Address,
+    /// targeted sub-entry
+    pub sub_entry: LedgerProofSubEntry,
+    /// serialized value of the sub-entry, `None` if it is absent from the final ledger
+    pub value: Option<Vec<u8>>,
+    /// proof of the value in the ledger sparse Merkle tree
+    pub proof: SparseMerkleProof,
+    /// root of the ledger sparse Merkle tree
+    pub ledger_root: Hash,
+    /// hashes of the other final state components, following the ledger root in the final state hash
+    pub other_state_hashes: Vec<Hash>,
+    /// final state hash the proof is checked against
+    pub final_state_hash: FinalStateHash,
+}
+
+impl LedgerProof {
+    /// Checks that the value is the one of the sub-entry in the final state of hash `final_state_hash`.
+    ///
+    /// The final state hash itself must be trusted by other means, for example by comparing it with the one of other nodes.
+    pub fn verify(&self) -> Result<(), ModelsError> {
+        let key = self.sub_entry.ledger_key(&self.address);
+        let ledger_root = sparse_merkle_root_from_proof(&key, self.value.as_deref(), &self.proof)?;
+        if ledger_root != self.ledger_root {
+            return Err(ModelsError::InvalidLedgerProof(
+                "the proof does not lead to the ledger root".into(),
+            ));
+        }
+        let mut hash_concat: Vec<u8> = ledger_root.to_bytes().to_vec();
+        for hash in self.other_state_hashes.iter() {
+            hash_concat.extend(hash.to_bytes());
+        }
+        if Hash::compute_from(&hash_concat) != self.final_state_hash.hash {
+            return Err(ModelsError::InvalidLedgerProof(
+                "the ledger root is not part of the final state hash".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LedgerProof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Sub-entry: {}", self.sub_entry)?;
+        match &self.value {
+            Some(value) => writeln!(f, "Value: {:?}", value)?,
+            None => writeln!(f, "Value: absent")?,
+        }
+        writeln!(f, "Ledger root: {}", self.ledger_root)?;
+        writeln!(f, "Final state hash: {}", self.final_state_hash)?;
+        match self.verify() {
+            Ok(()) => writeln!(f, "Proof: valid")?,
+            Err(err) => writeln!(f, "Proof: {}", err)?,
+        }
+        Ok(())
+    }
+}
+
 /// Operation and contextual info about it
 #[derive(Debug, Deserialize, Serialize)]
 pub struct OperationInfo {
@@ -330,6 +589,166 @@ impl std::fmt::Display for IndexedSlot {
     }
 }
 
+/// Block production statistics and rewards of an address over a cycle
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CycleProductionReport {
+    /// cycle number
+    pub cycle: u64,
+    /// true if that cycle is final
+    pub is_final: bool,
+    /// number of active rolls the address had at that cycle (if still available)
+    pub active_rolls: Option<u64>,
+    /// number of blocks created during that cycle
+    pub ok_count: u64,
+    /// number of blocks missed during that cycle
+    pub nok_count: u64,
+    /// ratio of missed blocks over block production opportunities (0 if there was none)
+    pub miss_ratio: f64,
+    /// true if the miss ratio is above the maximum allowed one:
+    /// the rolls of the address will be deactivated if it stays so until the end of the cycle
+    pub deactivation_risk: bool,
+    /// coins earned by creating blocks and by having created blocks endorsed.
+    /// Rewards are indexed by the node itself: `None` for cycles it did not entirely execute
+    pub block_rewards: Option<Amount>,
+    /// coins earned by creating endorsements, `None` for cycles the node did not entirely execute
+    pub endorsement_rewards: Option<Amount>,
+}
+
+/// Staking report of an address over all the cycles kept in the PoS state
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StakingReport {
+    /// the address
+    pub address: Address,
+    /// maximum miss ratio above which rolls are deactivated at the end of a cycle
+    pub max_miss_ratio: f64,
+    /// per-cycle reports, sorted by cycle
+    pub cycles: Vec<CycleProductionReport>,
+    /// total block rewards over the reported cycles whose rewards are known
+    pub total_block_rewards: Amount,
+    /// total endorsement rewards over the reported cycles whose rewards are known
+    pub total_endorsement_rewards: Amount,
+}
+
+impl std::fmt::Display for StakingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address {}:", self.address)?;
+        writeln!(
+            f,
+            "\tRewards: blocks={}, endorsements={}",
+            self.total_block_rewards, self.total_endorsement_rewards
+        )?;
+        writeln!(
+            f,
+            "\tMaximum miss ratio: {:.2}%",
+            self.max_miss_ratio * 100.0
+        )?;
+        writeln!(f, "\tCycles:")?;
+        for report in &self.cycles {
+            writeln!(
+                f,
+                "\t\tCycle {} ({}): produced {} and missed {} blocks ({:.2}% missed){}{}",
+                report.cycle,
+                if report.is_final {
+                    "final"
+                } else {
+                    "candidate"
+                },
+                report.ok_count,
+                report.nok_count,
+                report.miss_ratio * 100.0,
+                match report.active_rolls {
+                    Some(rolls) => format!(" with {} active rolls", rolls),
+                    None => "".into(),
+                },
+                display_if_true(report.deactivation_risk, " deactivation risk"),
+            )?;
+            match (report.block_rewards, report.endorsement_rewards) {
+                (Some(block_rewards), Some(endorsement_rewards)) => writeln!(
+                    f,
+                    "\t\t\tRewards: blocks={}, endorsements={}",
+                    block_rewards, endorsement_rewards
+                )?,
+                _ => writeln!(f, "\t\t\tRewards: unknown (cycle not executed by the node)")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// filter used when retrieving the selector draws of addresses
+#[derive(Default, Debug, Deserialize, Clone, Serialize)]
+pub struct SelectionsFilter {
+    /// addresses whose draws are looked up
+    pub addresses: Vec<Address>,
+    /// optional start slot (included), defaults to the current slot
+    pub start: Option<Slot>,
+    /// optional end slot (excluded), defaults to the end of the last drawn cycle
+    pub end: Option<Slot>,
+}
+
+/// A slot where an address was drawn to produce a block
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct BlockDrawInfo {
+    /// slot
+    pub slot: Slot,
+    /// estimated timestamp of the slot
+    pub timestamp: MassaTime,
+}
+
+/// A slot where an address was drawn to create an endorsement
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct EndorsementDrawInfo {
+    /// slot
+    pub slot: Slot,
+    /// endorsement index in the slot
+    pub index: usize,
+    /// estimated timestamp of the slot
+    pub timestamp: MassaTime,
+}
+
+/// Upcoming block and endorsement draws of an address
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AddressSelections {
+    /// the address
+    pub address: Address,
+    /// block draws, sorted by slot
+    pub block_draws: Vec<BlockDrawInfo>,
+    /// endorsement draws, sorted by slot
+    pub endorsement_draws: Vec<EndorsementDrawInfo>,
+}
+
+impl std::fmt::Display for AddressSelections {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address {}:", self.address)?;
+        writeln!(f, "\tBlock draws:")?;
+        if self.block_draws.is_empty() {
+            writeln!(f, "		none")?;
+        }
+        for draw in &self.block_draws {
+            writeln!(
+                f,
+                "		Slot {} at {}",
+                draw.slot,
+                draw.timestamp.to_utc_string()
+            )?;
+        }
+        writeln!(f, "\tEndorsement draws:")?;
+        if self.endorsement_draws.is_empty() {
+            writeln!(f, "		none")?;
+        }
+        for draw in &self.endorsement_draws {
+            writeln!(
+                f,
+                "		Slot {} (index {}) at {}",
+                draw.slot,
+                draw.index,
+                draw.timestamp.to_utc_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /// Less information about an address
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompactAddressInfo {
@@ -1,6 +1,7 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::endorsement::{EndorsementId, EndorsementSerializer, EndorsementSerializerLW};
+use crate::multisig::MultisigSignatures;
 use crate::prehash::PreHashed;
 use crate::wrapped::{Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer};
 use crate::{
@@ -166,6 +167,7 @@ impl WrappedContent for Block {
             signature: content.header.signature,
             creator_public_key: content.header.creator_public_key,
             creator_address: content.header.creator_address,
+            multisig: None,
             id: U::new(*content.header.id.get_hash()),
             content,
             serialized_data: content_serialized,
@@ -175,6 +177,7 @@ impl WrappedContent for Block {
     fn serialize(
         _signature: &Signature,
         _creator_public_key: &PublicKey,
+        _multisig: Option<&MultisigSignatures>,
         serialized_content: &[u8],
         buffer: &mut Vec<u8>,
     ) -> Result<(), SerializeError> {
@@ -201,6 +204,7 @@ impl WrappedContent for Block {
                 signature: content.header.signature,
                 creator_public_key: content.header.creator_public_key,
                 creator_address: content.header.creator_address,
+                multisig: None,
                 id: U::new(*content.header.id.get_hash()),
                 content,
                 serialized_data: buffer[..buffer.len() - rest.len()].to_vec(),
@@ -64,7 +64,27 @@ lazy_static::lazy_static! {
         if cfg!(feature = "sandbox") {
             "SAND.0.0"
         } else {
-            "TEST.17.2"
+            "TEST.19.0"
+        }
+        .parse()
+        .unwrap()
+    };
+    /// first node version sending the final state hash at the end of the bootstrap
+    pub static ref BOOTSTRAP_FINAL_STATE_HASH_VERSION: Version = {
+        if cfg!(feature = "sandbox") {
+            "SAND.0.0"
+        } else {
+            "TEST.19.0"
+        }
+        .parse()
+        .unwrap()
+    };
+    /// first node version negotiating the compression of the bootstrap server messages
+    pub static ref BOOTSTRAP_COMPRESSION_VERSION: Version = {
+        if cfg!(feature = "sandbox") {
+            "SAND.0.0"
+        } else {
+            "TEST.19.0"
         }
         .parse()
         .unwrap()
@@ -152,6 +172,8 @@ pub const MAX_OPERATION_DATASTORE_ENTRY_COUNT: u64 = 128;
 pub const MAX_FUNCTION_NAME_LENGTH: u16 = u16::MAX;
 /// Maximum size of parameters in call SC
 pub const MAX_PARAMETERS_SIZE: u32 = 10_000_000;
+/// Maximum number of public keys of a multisig address
+pub const MAX_MULTISIG_PUBLIC_KEYS: u32 = 16;
 /// Maximum length of `rng_seed` in thread cycle
 pub const MAX_RNG_SEED_LENGTH: u32 = PERIODS_PER_CYCLE.saturating_mul(THREAD_COUNT as u64) as u32;
 // ***********************
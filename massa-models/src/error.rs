@@ -54,6 +54,12 @@ pub enum ModelsError {
     OutdatedBootstrapCursor,
     /// Error raised {0}
     ErrorRaised(String),
+    /// invalid ledger proof: {0}
+    InvalidLedgerProof(String),
+    /// invalid IP range: {0}
+    InvalidIpRange(String),
+    /// multisig error: {0}
+    MultisigError(String),
 }
 
 impl From<nom::Err<nom::error::Error<&[u8]>>> for ModelsError {
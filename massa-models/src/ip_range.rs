@@ -0,0 +1,229 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::error::ModelsError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Range of IP addresses in CIDR notation (`ip/prefix_len`), a single IP being a range of maximal prefix length.
+///
+/// IPv4-mapped IPv6 addresses are handled as IPv4 addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpRange {
+    /// first address of the range
+    ip: IpAddr,
+    /// number of leading bits shared by the addresses of the range
+    prefix_len: u8,
+}
+
+/// Converts an IPv4-mapped IPv6 address into an IPv4 address
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => IpAddr::V4(ip),
+            None => IpAddr::V6(ip),
+        },
+        ip => ip,
+    }
+}
+
+/// Clears the bits of `ip` past `prefix_len`
+fn mask_ip(ip: IpAddr, prefix_len: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+/// Maximal prefix length of the family of `ip`
+fn max_prefix_len(ip: &IpAddr) -> u8 {
+    match ip {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+impl IpRange {
+    /// Creates the range of the addresses sharing the first `prefix_len` bits of `ip`
+    pub fn new(ip: IpAddr, prefix_len: u8) -> Result<Self, ModelsError> {
+        if prefix_len > max_prefix_len(&ip) {
+            return Err(ModelsError::InvalidIpRange(format!(
+                "prefix length {} is too large for {}",
+                prefix_len, ip
+            )));
+        }
+        let (ip, prefix_len) = match (ip, canonical_ip(ip)) {
+            (IpAddr::V6(_), IpAddr::V4(ipv4)) if prefix_len >= 96 => {
+                (IpAddr::V4(ipv4), prefix_len - 96)
+            }
+            _ => (ip, prefix_len),
+        };
+        Ok(IpRange {
+            ip: mask_ip(ip, prefix_len),
+            prefix_len,
+        })
+    }
+
+    /// Checks whether `ip` belongs to the range
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = canonical_ip(*ip);
+        max_prefix_len(&ip) == max_prefix_len(&self.ip) && mask_ip(ip, self.prefix_len) == self.ip
+    }
+
+    /// Checks whether every address of `other` belongs to the range
+    pub fn contains_range(&self, other: &IpRange) -> bool {
+        self.prefix_len <= other.prefix_len && self.contains(&other.ip)
+    }
+
+    /// Returns the ranges covering the addresses of the range that do not belong to `other`.
+    ///
+    /// Two ranges are either disjoint or one contains the other. When `other` is strictly inside the range,
+    /// the result is made of the sibling of each range containing `other`, from the largest to the smallest.
+    pub fn subtract(&self, other: &IpRange) -> Vec<IpRange> {
+        if other.contains_range(self) {
+            return Vec::new();
+        }
+        if !self.contains_range(other) {
+            return vec![*self];
+        }
+        (self.prefix_len + 1..=other.prefix_len)
+            .map(|prefix_len| IpRange {
+                ip: mask_ip(flip_bit(other.ip, prefix_len - 1), prefix_len),
+                prefix_len,
+            })
+            .collect()
+    }
+}
+
+/// Flips the bit of `ip` of index `index`, the most significant one being of index 0
+fn flip_bit(ip: IpAddr, index: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(u32::from(ip) ^ (1 << (31 - index)))),
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(u128::from(ip) ^ (1 << (127 - index)))),
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(ip: IpAddr) -> Self {
+        let ip = canonical_ip(ip);
+        IpRange {
+            prefix_len: max_prefix_len(&ip),
+            ip,
+        }
+    }
+}
+
+impl std::fmt::Display for IpRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.prefix_len == max_prefix_len(&self.ip) {
+            write!(f, "{}", self.ip)
+        } else {
+            write!(f, "{}/{}", self.ip, self.prefix_len)
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = ModelsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ModelsError::InvalidIpRange(s.to_string());
+        match s.split_once('/') {
+            Some((ip, prefix_len)) => IpRange::new(
+                ip.parse().map_err(|_| invalid())?,
+                prefix_len.parse().map_err(|_| invalid())?,
+            ),
+            None => Ok(IpRange::from(s.parse::<IpAddr>().map_err(|_| invalid())?)),
+        }
+    }
+}
+
+impl ::serde::Serialize for IpRange {
+    fn serialize<S: ::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(&self.to_string())
+    }
+}
+
+impl<'de> ::serde::Deserialize<'de> for IpRange {
+    fn deserialize<D: ::serde::Deserializer<'de>>(d: D) -> Result<IpRange, D::Error> {
+        struct IpRangeVisitor;
+
+        impl<'de> ::serde::de::Visitor<'de> for IpRangeVisitor {
+            type Value = IpRange;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an IP address or a CIDR range (ip/prefix_len)")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: ::serde::de::Error,
+            {
+                IpRange::from_str(v).map_err(E::custom)
+            }
+        }
+        d.deserialize_str(IpRangeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_range() {
+        let range = IpRange::from_str("192.168.1.17/24").unwrap();
+        assert_eq!(range.to_string(), "192.168.1.0/24");
+        assert!(range.contains(&"192.168.1.200".parse().unwrap()));
+        assert!(range.contains(&"::ffff:192.168.1.3".parse().unwrap()));
+        assert!(!range.contains(&"192.168.2.1".parse().unwrap()));
+        assert!(!range.contains(&"2001:db8::1".parse().unwrap()));
+
+        let single = IpRange::from_str("::ffff:10.0.0.1").unwrap();
+        assert_eq!(single.to_string(), "10.0.0.1");
+        assert!(single.contains(&"10.0.0.1".parse().unwrap()));
+        assert!(!single.contains(&"10.0.0.2".parse().unwrap()));
+
+        let v6 = IpRange::from_str("2001:db8::/32").unwrap();
+        assert!(v6.contains(&"2001:db8:1::1".parse().unwrap()));
+        assert_eq!(
+            IpRange::from_str("0.0.0.0/0").unwrap().to_string(),
+            "0.0.0.0/0"
+        );
+        assert!(IpRange::from_str("10.0.0.0/33").is_err());
+        assert!(IpRange::from_str("not an ip").is_err());
+    }
+
+    #[test]
+    fn test_ip_range_subtract() {
+        let range = IpRange::from_str("10.0.0.0/24").unwrap();
+        let ip = IpRange::from_str("10.0.0.1").unwrap();
+        let remaining = range.subtract(&ip);
+        assert_eq!(remaining.len(), 8);
+        assert!(remaining.iter().all(|part| range.contains_range(part)));
+        assert!(remaining.iter().all(|part| !part.contains_range(&ip)));
+        assert_eq!(remaining[0].to_string(), "10.0.0.128/25");
+        assert_eq!(remaining[7].to_string(), "10.0.0.0");
+        // the parts cover the 255 other addresses of the range
+        let covered: u32 = remaining
+            .iter()
+            .map(|part| 1u32 << (32 - part.prefix_len))
+            .sum();
+        assert_eq!(covered, 255);
+
+        assert!(ip.subtract(&range).is_empty());
+        assert_eq!(
+            range.subtract(&IpRange::from_str("10.0.1.0/24").unwrap()),
+            vec![range]
+        );
+        assert_eq!(
+            range.subtract(&IpRange::from_str("2001:db8::/32").unwrap()),
+            vec![range]
+        );
+    }
+}
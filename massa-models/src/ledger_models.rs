@@ -24,6 +24,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::hash_map;
 use std::ops::Bound::Included;
 
+/// Identifier of the balance in a disk ledger key, following the address bytes
+pub const BALANCE_IDENT: u8 = 0u8;
+/// Identifier of the bytecode in a disk ledger key, following the address bytes
+pub const BYTECODE_IDENT: u8 = 1u8;
+/// Identifier of the datastore in a disk ledger key, following the address bytes and preceding the datastore key
+pub const DATASTORE_IDENT: u8 = 2u8;
+
 /// a consensus ledger entry
 #[derive(Debug, Default, Deserialize, Clone, Copy, Serialize)]
 pub struct LedgerData {
@@ -33,8 +33,12 @@ pub mod endorsement;
 pub mod error;
 /// execution related structures
 pub mod execution;
+/// IP address ranges
+pub mod ip_range;
 /// ledger related structures
 pub mod ledger_models;
+/// multi-signature addresses
+pub mod multisig;
 /// node related structure
 pub mod node;
 /// operations
@@ -49,6 +53,8 @@ pub mod rolls;
 pub mod serialization;
 /// slots
 pub mod slot;
+/// sparse Merkle tree primitives used to authenticate the ledger
+pub mod sparse_merkle;
 /// various statistics
 pub mod stats;
 /// bootstrap streaming cursor
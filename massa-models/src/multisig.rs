@@ -0,0 +1,539 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! m-of-n multi-signature addresses.
+//!
+//! A multisig address is the hash of a policy: a threshold and a sorted set of public keys.
+//! Content created by such an address is valid once `threshold` distinct keys of the policy
+//! have signed its id.
+
+use crate::{address::Address, config::MAX_MULTISIG_PUBLIC_KEYS, error::ModelsError};
+use massa_hash::Hash;
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
+};
+use massa_signature::{
+    KeyPair, PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer,
+};
+use nom::combinator::verify;
+use nom::error::{context, ContextError, ParseError};
+use nom::multi::length_count;
+use nom::sequence::tuple;
+use nom::{IResult, Parser};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound::Included;
+
+/// Domain separator of the multisig address hashes
+const MULTISIG_ADDRESS_DOMAIN: &[u8] = b"massa_multisig_address";
+
+/// Threshold and public keys controlling a multisig address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigPolicy {
+    /// number of signatures required
+    pub threshold: u32,
+    /// public keys allowed to sign, sorted and without duplicates
+    pub public_keys: Vec<PublicKey>,
+}
+
+impl MultisigPolicy {
+    /// Creates a policy requiring `threshold` signatures among `public_keys`, in any order
+    pub fn new(threshold: u32, mut public_keys: Vec<PublicKey>) -> Result<Self, ModelsError> {
+        public_keys.sort();
+        let policy = MultisigPolicy {
+            threshold,
+            public_keys,
+        };
+        policy.check()?;
+        Ok(policy)
+    }
+
+    /// Checks the threshold and that the keys are sorted and distinct
+    pub fn check(&self) -> Result<(), ModelsError> {
+        let key_count = self.public_keys.len();
+        if key_count > MAX_MULTISIG_PUBLIC_KEYS as usize {
+            return Err(ModelsError::MultisigError(format!(
+                "{} public keys given, at most {} allowed",
+                key_count, MAX_MULTISIG_PUBLIC_KEYS
+            )));
+        }
+        if self.threshold == 0 || self.threshold as usize > key_count {
+            return Err(ModelsError::MultisigError(format!(
+                "threshold {} out of range for {} public keys",
+                self.threshold, key_count
+            )));
+        }
+        if self.public_keys.windows(2).any(|keys| keys[0] >= keys[1]) {
+            return Err(ModelsError::MultisigError(
+                "public keys must be sorted and distinct".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Address controlled by the policy
+    pub fn address(&self) -> Address {
+        let mut hash_data = MULTISIG_ADDRESS_DOMAIN.to_vec();
+        hash_data.extend(self.threshold.to_be_bytes());
+        for public_key in &self.public_keys {
+            hash_data.extend(public_key.to_bytes());
+        }
+        Address(Hash::compute_from(&hash_data))
+    }
+
+    /// Hash of the content created by the address, that its signers must sign
+    pub fn content_hash(&self, serialized_content: &[u8]) -> Hash {
+        let mut hash_data = self.address().to_bytes().to_vec();
+        hash_data.extend(serialized_content);
+        Hash::compute_from(&hash_data)
+    }
+
+    /// Position of `public_key` in the policy
+    pub fn key_index(&self, public_key: &PublicKey) -> Option<u32> {
+        self.public_keys
+            .binary_search(public_key)
+            .ok()
+            .map(|index| index as u32)
+    }
+}
+
+impl std::fmt::Display for MultisigPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}-of-{} multisig address {}",
+            self.threshold,
+            self.public_keys.len(),
+            self.address()
+        )?;
+        for (index, public_key) in self.public_keys.iter().enumerate() {
+            writeln!(f, "\tKey {}: {}", index, public_key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Signatures collected for a multisig address, indexed by the position of the key in the policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigSignatures {
+    /// policy of the creator address
+    pub policy: MultisigPolicy,
+    /// signatures of the content id
+    pub signatures: BTreeMap<u32, Signature>,
+}
+
+impl MultisigSignatures {
+    /// Creates an empty set of signatures for `policy`
+    pub fn new(policy: MultisigPolicy) -> Self {
+        MultisigSignatures {
+            policy,
+            signatures: BTreeMap::new(),
+        }
+    }
+
+    /// Signs `hash` with `keypair`, which must be one of the keys of the policy
+    pub fn sign(&mut self, keypair: &KeyPair, hash: &Hash) -> Result<(), ModelsError> {
+        let index = self
+            .policy
+            .key_index(&keypair.get_public_key())
+            .ok_or_else(|| {
+                ModelsError::MultisigError(format!(
+                    "the key is not part of the policy of {}",
+                    self.policy.address()
+                ))
+            })?;
+        self.signatures.insert(index, keypair.sign(hash)?);
+        Ok(())
+    }
+
+    /// Adds the signatures collected by `other` for the same policy
+    pub fn merge(&mut self, other: MultisigSignatures) -> Result<(), ModelsError> {
+        if other.policy != self.policy {
+            return Err(ModelsError::MultisigError(
+                "the signatures were collected for another policy".to_string(),
+            ));
+        }
+        self.signatures.extend(other.signatures);
+        Ok(())
+    }
+
+    /// Returns true if enough signatures were collected
+    pub fn is_complete(&self) -> bool {
+        self.signatures.len() >= self.policy.threshold as usize
+    }
+
+    /// Signatures with the public key that made them, ready for batch verification
+    pub fn signed_by(&self) -> Result<Vec<(Signature, PublicKey)>, ModelsError> {
+        self.signatures
+            .iter()
+            .map(|(index, signature)| {
+                self.policy
+                    .public_keys
+                    .get(*index as usize)
+                    .map(|public_key| (*signature, *public_key))
+                    .ok_or_else(|| {
+                        ModelsError::MultisigError(format!("no public key at index {}", index))
+                    })
+            })
+            .collect()
+    }
+
+    /// Checks the policy, that enough signatures were collected and that they all sign `hash`
+    pub fn verify(&self, hash: &Hash) -> Result<(), ModelsError> {
+        if !self.is_complete() {
+            return Err(ModelsError::MultisigError(format!(
+                "{} signatures collected, {} required",
+                self.signatures.len(),
+                self.policy.threshold
+            )));
+        }
+        self.verify_collected(hash)
+    }
+
+    /// Checks each signature collected so far against `hash`, whether the threshold
+    /// is reached or not
+    pub fn verify_collected(&self, hash: &Hash) -> Result<(), ModelsError> {
+        self.policy.check()?;
+        for (signature, public_key) in self.signed_by()? {
+            public_key.verify_signature(hash, &signature)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializer for `MultisigPolicy`
+#[derive(Default)]
+pub struct MultisigPolicySerializer {
+    u32_serializer: U32VarIntSerializer,
+}
+
+impl MultisigPolicySerializer {
+    /// Creates a new `MultisigPolicySerializer`
+    pub fn new() -> Self {
+        Self {
+            u32_serializer: U32VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<MultisigPolicy> for MultisigPolicySerializer {
+    fn serialize(
+        &self,
+        value: &MultisigPolicy,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.u32_serializer.serialize(&value.threshold, buffer)?;
+        let key_count: u32 = value.public_keys.len().try_into().map_err(|err| {
+            SerializeError::GeneralError(format!("too many multisig public keys: {}", err))
+        })?;
+        self.u32_serializer.serialize(&key_count, buffer)?;
+        for public_key in &value.public_keys {
+            buffer.extend(public_key.to_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `MultisigPolicy`
+pub struct MultisigPolicyDeserializer {
+    threshold_deserializer: U32VarIntDeserializer,
+    key_count_deserializer: U32VarIntDeserializer,
+    public_key_deserializer: PublicKeyDeserializer,
+}
+
+impl MultisigPolicyDeserializer {
+    /// Creates a new `MultisigPolicyDeserializer`
+    pub const fn new() -> Self {
+        Self {
+            threshold_deserializer: U32VarIntDeserializer::new(
+                Included(1),
+                Included(MAX_MULTISIG_PUBLIC_KEYS),
+            ),
+            key_count_deserializer: U32VarIntDeserializer::new(
+                Included(1),
+                Included(MAX_MULTISIG_PUBLIC_KEYS),
+            ),
+            public_key_deserializer: PublicKeyDeserializer::new(),
+        }
+    }
+}
+
+impl Default for MultisigPolicyDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<MultisigPolicy> for MultisigPolicyDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], MultisigPolicy, E> {
+        let (rest, policy) = context(
+            "Failed MultisigPolicy deserialization",
+            tuple((
+                context("Failed threshold deserialization", |input| {
+                    self.threshold_deserializer.deserialize(input)
+                }),
+                length_count(
+                    context("Failed key count deserialization", |input| {
+                        self.key_count_deserializer.deserialize(input)
+                    }),
+                    context("Failed public key deserialization", |input| {
+                        self.public_key_deserializer.deserialize(input)
+                    }),
+                ),
+            )),
+        )
+        .map(|(threshold, public_keys)| MultisigPolicy {
+            threshold,
+            public_keys,
+        })
+        .parse(buffer)?;
+        // only the canonical form is accepted, so that a policy has a single encoding
+        if policy.check().is_err() {
+            return Err(nom::Err::Error(ParseError::from_error_kind(
+                buffer,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+        Ok((rest, policy))
+    }
+}
+
+/// Serializer for `MultisigSignatures`
+#[derive(Default)]
+pub struct MultisigSignaturesSerializer {
+    policy_serializer: MultisigPolicySerializer,
+    u32_serializer: U32VarIntSerializer,
+}
+
+impl MultisigSignaturesSerializer {
+    /// Creates a new `MultisigSignaturesSerializer`
+    pub fn new() -> Self {
+        Self {
+            policy_serializer: MultisigPolicySerializer::new(),
+            u32_serializer: U32VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<MultisigSignatures> for MultisigSignaturesSerializer {
+    /// ## Example
+    /// ```rust
+    /// use massa_hash::Hash;
+    /// use massa_models::multisig::{MultisigPolicy, MultisigSignatures, MultisigSignaturesSerializer};
+    /// use massa_serialization::Serializer;
+    /// use massa_signature::KeyPair;
+    ///
+    /// let keypair = KeyPair::generate();
+    /// let policy = MultisigPolicy::new(1, vec![keypair.get_public_key()]).unwrap();
+    /// let mut signatures = MultisigSignatures::new(policy);
+    /// signatures.sign(&keypair, &Hash::compute_from(b"content")).unwrap();
+    /// let mut buffer = Vec::new();
+    /// MultisigSignaturesSerializer::new().serialize(&signatures, &mut buffer).unwrap();
+    /// ```
+    fn serialize(
+        &self,
+        value: &MultisigSignatures,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.policy_serializer.serialize(&value.policy, buffer)?;
+        let signature_count: u32 = value.signatures.len().try_into().map_err(|err| {
+            SerializeError::GeneralError(format!("too many multisig signatures: {}", err))
+        })?;
+        self.u32_serializer.serialize(&signature_count, buffer)?;
+        for (index, signature) in &value.signatures {
+            self.u32_serializer.serialize(index, buffer)?;
+            buffer.extend(signature.to_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `MultisigSignatures`
+pub struct MultisigSignaturesDeserializer {
+    policy_deserializer: MultisigPolicyDeserializer,
+    signature_count_deserializer: U32VarIntDeserializer,
+    index_deserializer: U32VarIntDeserializer,
+    signature_deserializer: SignatureDeserializer,
+}
+
+impl MultisigSignaturesDeserializer {
+    /// Creates a new `MultisigSignaturesDeserializer`
+    pub const fn new() -> Self {
+        Self {
+            policy_deserializer: MultisigPolicyDeserializer::new(),
+            signature_count_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_MULTISIG_PUBLIC_KEYS),
+            ),
+            index_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_MULTISIG_PUBLIC_KEYS - 1),
+            ),
+            signature_deserializer: SignatureDeserializer::new(),
+        }
+    }
+}
+
+impl Default for MultisigSignaturesDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<MultisigSignatures> for MultisigSignaturesDeserializer {
+    /// ## Example
+    /// ```rust
+    /// use massa_hash::Hash;
+    /// use massa_models::multisig::{MultisigPolicy, MultisigSignatures, MultisigSignaturesDeserializer, MultisigSignaturesSerializer};
+    /// use massa_serialization::{DeserializeError, Deserializer, Serializer};
+    /// use massa_signature::KeyPair;
+    ///
+    /// let keypairs = vec![KeyPair::generate(), KeyPair::generate()];
+    /// let policy = MultisigPolicy::new(2, keypairs.iter().map(|k| k.get_public_key()).collect()).unwrap();
+    /// let mut signatures = MultisigSignatures::new(policy);
+    /// signatures.sign(&keypairs[1], &Hash::compute_from(b"content")).unwrap();
+    /// let mut buffer = Vec::new();
+    /// MultisigSignaturesSerializer::new().serialize(&signatures, &mut buffer).unwrap();
+    /// let (rest, deserialized) = MultisigSignaturesDeserializer::new()
+    ///     .deserialize::<DeserializeError>(&buffer)
+    ///     .unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(signatures, deserialized);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], MultisigSignatures, E> {
+        context(
+            "Failed MultisigSignatures deserialization",
+            tuple((
+                |input| self.policy_deserializer.deserialize(input),
+                // the signatures are sorted by strictly increasing signer index, so that each
+                // set of signatures has a single serialization
+                context(
+                    "Failed signer index order verification",
+                    verify(
+                        length_count(
+                            context("Failed signature count deserialization", |input| {
+                                self.signature_count_deserializer.deserialize(input)
+                            }),
+                            tuple((
+                                context("Failed signer index deserialization", |input| {
+                                    self.index_deserializer.deserialize(input)
+                                }),
+                                context("Failed signature deserialization", |input| {
+                                    self.signature_deserializer.deserialize(input)
+                                }),
+                            )),
+                        ),
+                        |signatures: &Vec<(u32, Signature)>| {
+                            signatures.windows(2).all(|pair| pair[0].0 < pair[1].0)
+                        },
+                    ),
+                ),
+            )),
+        )
+        .map(|(policy, signatures)| MultisigSignatures {
+            policy,
+            signatures: signatures.into_iter().collect(),
+        })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_serialization::DeserializeError;
+
+    fn keypairs(count: usize) -> Vec<KeyPair> {
+        (0..count).map(|_| KeyPair::generate()).collect()
+    }
+
+    #[test]
+    fn test_policy_address_ignores_key_order() {
+        let keypairs = keypairs(3);
+        let public_keys: Vec<PublicKey> = keypairs.iter().map(|k| k.get_public_key()).collect();
+        let mut reversed = public_keys.clone();
+        reversed.reverse();
+        let policy = MultisigPolicy::new(2, public_keys.clone()).unwrap();
+        assert_eq!(
+            policy.address(),
+            MultisigPolicy::new(2, reversed).unwrap().address()
+        );
+        assert_ne!(
+            policy.address(),
+            MultisigPolicy::new(3, public_keys.clone())
+                .unwrap()
+                .address()
+        );
+        assert!(MultisigPolicy::new(0, public_keys.clone()).is_err());
+        assert!(MultisigPolicy::new(4, public_keys.clone()).is_err());
+        assert!(MultisigPolicy::new(1, vec![public_keys[0], public_keys[0]]).is_err());
+    }
+
+    #[test]
+    fn test_threshold_signatures() {
+        let keypairs = keypairs(3);
+        let policy =
+            MultisigPolicy::new(2, keypairs.iter().map(|k| k.get_public_key()).collect()).unwrap();
+        let hash = Hash::compute_from(b"content");
+
+        let mut first = MultisigSignatures::new(policy.clone());
+        first.sign(&keypairs[0], &hash).unwrap();
+        assert!(!first.is_complete());
+        assert!(first.verify(&hash).is_err());
+
+        let mut second = MultisigSignatures::new(policy);
+        second.sign(&keypairs[2], &hash).unwrap();
+        first.merge(second).unwrap();
+        assert!(first.is_complete());
+        first.verify(&hash).unwrap();
+        assert!(first.verify(&Hash::compute_from(b"other")).is_err());
+
+        assert!(first.sign(&KeyPair::generate(), &hash).is_err());
+    }
+
+    #[test]
+    fn test_reject_unordered_signer_indices() {
+        let keypairs = keypairs(3);
+        let policy =
+            MultisigPolicy::new(2, keypairs.iter().map(|k| k.get_public_key()).collect()).unwrap();
+        let hash = Hash::compute_from(b"content");
+        let signature_0 = keypairs[0].sign(&hash).unwrap();
+        let signature_2 = keypairs[2].sign(&hash).unwrap();
+
+        let serialize = |entries: &[(u32, Signature)]| {
+            let mut buffer = Vec::new();
+            MultisigPolicySerializer::new()
+                .serialize(&policy, &mut buffer)
+                .unwrap();
+            U32VarIntSerializer::new()
+                .serialize(&(entries.len() as u32), &mut buffer)
+                .unwrap();
+            for (index, signature) in entries {
+                U32VarIntSerializer::new()
+                    .serialize(index, &mut buffer)
+                    .unwrap();
+                buffer.extend(signature.to_bytes());
+            }
+            buffer
+        };
+        let deserializer = MultisigSignaturesDeserializer::new();
+
+        let (rest, signatures) = deserializer
+            .deserialize::<DeserializeError>(&serialize(&[(0, signature_0), (2, signature_2)]))
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(signatures.signatures.len(), 2);
+        // decreasing and repeated indices give other serializations of the same signatures
+        assert!(deserializer
+            .deserialize::<DeserializeError>(&serialize(&[(2, signature_2), (0, signature_0)]))
+            .is_err());
+        assert!(deserializer
+            .deserialize::<DeserializeError>(&serialize(&[(0, signature_0), (0, signature_0)]))
+            .is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::error::ModelsError;
+use displaydoc::Display;
 use massa_signature::PublicKey;
 use serde::{Deserialize, Serialize};
 
@@ -26,3 +27,20 @@ impl std::str::FromStr for NodeId {
         Ok(NodeId(PublicKey::from_str(s)?))
     }
 }
+
+/// Reason why a peer was banned
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BanReason {
+    /// banned by the node operator
+    Manual,
+    /// sent an invalid block header
+    InvalidHeader,
+    /// sent invalid operations for a block
+    InvalidBlockOperations,
+    /// sent an invalid operation
+    InvalidOperation,
+    /// sent an invalid endorsement
+    InvalidEndorsement,
+    /// propagated a block detected as an attack
+    AttackBlock,
+}
@@ -1,8 +1,13 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::datastore::{Datastore, DatastoreDeserializer, DatastoreSerializer};
+use crate::multisig::{
+    MultisigSignatures, MultisigSignaturesDeserializer, MultisigSignaturesSerializer,
+};
 use crate::prehash::{PreHashSet, PreHashed};
-use crate::wrapped::{Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer};
+use crate::wrapped::{
+    deserialize_content, Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer,
+};
 use crate::{
     address::{Address, AddressDeserializer},
     amount::{Amount, AmountDeserializer, AmountSerializer},
@@ -14,9 +19,16 @@ use massa_serialization::{
     Deserializer, SerializeError, Serializer, U16VarIntDeserializer, U16VarIntSerializer,
     U32VarIntDeserializer, U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
+use massa_signature::{
+    PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer, PUBLIC_KEY_SIZE_BYTES,
+    SIGNATURE_SIZE_BYTES,
+};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::value;
 use nom::error::context;
 use nom::multi::length_count;
-use nom::sequence::tuple;
+use nom::sequence::{preceded, tuple};
 use nom::AsBytes;
 use nom::Parser;
 use nom::{
@@ -35,6 +47,11 @@ pub const OPERATION_ID_SIZE_BYTES: usize = massa_hash::HASH_SIZE_BYTES;
 /// Size in bytes of the serialized operation ID prefix
 pub const OPERATION_ID_PREFIX_SIZE_BYTES: usize = 17;
 
+/// Signature slot of the operations signed by the keys of a multisig address.
+/// Its scalar is not reduced, so it is not a valid signature: no single signer operation starts
+/// with it, and the nodes predating multisig addresses reject these operations.
+const MULTISIG_SIGNATURE_MARKER: [u8; SIGNATURE_SIZE_BYTES] = [0xff; SIGNATURE_SIZE_BYTES];
+
 /// operation id
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub struct OperationId(Hash);
@@ -197,6 +214,7 @@ enum OperationTypeId {
     RollSell = 2,
     ExecuteSC = 3,
     CallSC = 4,
+    SetStakingDelegate = 5,
 }
 
 /// the operation as sent in the network
@@ -224,7 +242,122 @@ impl std::fmt::Display for Operation {
 /// signed operation
 pub type WrappedOperation = Wrapped<Operation, OperationId>;
 
-impl WrappedContent for Operation {}
+/// Signer of a deserialized operation
+enum OperationSigner {
+    Single(Signature, PublicKey),
+    Multisig(MultisigSignatures),
+}
+
+/// Operations signed by one key start with the signature and the public key, as they always did.
+/// Those of a multisig address start with `MULTISIG_SIGNATURE_MARKER` followed by the collected
+/// signatures
+impl WrappedContent for Operation {
+    fn serialize(
+        signature: &Signature,
+        creator_public_key: &PublicKey,
+        multisig: Option<&MultisigSignatures>,
+        serialized_content: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        match multisig {
+            Some(multisig) => {
+                buffer.extend(MULTISIG_SIGNATURE_MARKER);
+                MultisigSignaturesSerializer::new().serialize(multisig, buffer)?;
+            }
+            None => {
+                buffer.extend(signature.into_bytes());
+                buffer.extend(creator_public_key.into_bytes());
+            }
+        }
+        buffer.extend(serialized_content);
+        Ok(())
+    }
+
+    fn deserialize<
+        'a,
+        E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+        DC: Deserializer<Self>,
+        U: Id,
+    >(
+        content_serializer: Option<&dyn Serializer<Self>>,
+        signature_deserializer: &SignatureDeserializer,
+        creator_public_key_deserializer: &PublicKeyDeserializer,
+        content_deserializer: &DC,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Wrapped<Self, U>, E> {
+        let multisig_deserializer = MultisigSignaturesDeserializer::new();
+        let (serialized_data, signer) = context(
+            "Failed operation signer deserialization",
+            alt((
+                preceded(tag(&MULTISIG_SIGNATURE_MARKER[..]), |input| {
+                    multisig_deserializer.deserialize(input)
+                })
+                .map(OperationSigner::Multisig),
+                tuple((
+                    context("Failed signature deserialization", |input| {
+                        signature_deserializer.deserialize(input)
+                    }),
+                    context("Failed public_key deserialization", |input| {
+                        creator_public_key_deserializer.deserialize(input)
+                    }),
+                ))
+                .map(|(signature, public_key)| OperationSigner::Single(signature, public_key)),
+            )),
+        )(buffer)?;
+        let (rest, (content, content_serialized)) =
+            deserialize_content(content_serializer, content_deserializer, serialized_data)?;
+        let wrapped = match signer {
+            OperationSigner::Single(signature, creator_public_key) => {
+                let mut serialized_full_data = creator_public_key.to_bytes().to_vec();
+                serialized_full_data.extend(&content_serialized);
+                Wrapped {
+                    content,
+                    signature,
+                    creator_public_key,
+                    creator_address: Address::from_public_key(&creator_public_key),
+                    multisig: None,
+                    id: U::new(Hash::compute_from(&serialized_full_data)),
+                    serialized_data: content_serialized,
+                }
+            }
+            OperationSigner::Multisig(multisig) => {
+                // the first signature stands for the others where a single one is expected
+                let (signature, creator_public_key) = multisig
+                    .signed_by()
+                    .ok()
+                    .and_then(|signed_by| signed_by.first().copied())
+                    .ok_or_else(|| {
+                        nom::Err::Error(ParseError::from_error_kind(
+                            buffer,
+                            nom::error::ErrorKind::Verify,
+                        ))
+                    })?;
+                Wrapped {
+                    content,
+                    signature,
+                    creator_public_key,
+                    creator_address: multisig.policy.address(),
+                    id: U::new(multisig.policy.content_hash(&content_serialized)),
+                    multisig: Some(multisig),
+                    serialized_data: content_serialized,
+                }
+            }
+        };
+        Ok((rest, wrapped))
+    }
+
+    fn signatures_size(multisig: Option<&MultisigSignatures>) -> usize {
+        match multisig {
+            Some(multisig) => {
+                let mut buffer = Vec::new();
+                // the policy was checked on creation, so the serialization cannot fail
+                let _ = MultisigSignaturesSerializer::new().serialize(multisig, &mut buffer);
+                SIGNATURE_SIZE_BYTES.saturating_add(buffer.len())
+            }
+            None => SIGNATURE_SIZE_BYTES.saturating_add(PUBLIC_KEY_SIZE_BYTES),
+        }
+    }
+}
 
 /// Serializer for `Operation`
 pub struct OperationSerializer {
@@ -421,6 +554,13 @@ pub enum OperationType {
         /// Extra coins that are spent from the caller's balance and transferred to the target
         coins: Amount,
     },
+    /// the sender designates an address allowed to create blocks and endorsements
+    /// in its name with its rolls (or revokes the current delegate if `None`).
+    /// Rewards are still credited to the sender.
+    SetStakingDelegate {
+        /// delegate address, whose key must be loaded in the staking node
+        delegate: Option<Address>,
+    },
 }
 
 impl std::fmt::Display for OperationType {
@@ -464,6 +604,13 @@ impl std::fmt::Display for OperationType {
                 writeln!(f, "\t- max_gas:{}", max_gas)?;
                 writeln!(f, "\t- coins:{}", coins)?;
             }
+            OperationType::SetStakingDelegate { delegate } => {
+                writeln!(f, "Set staking delegate:")?;
+                match delegate {
+                    Some(delegate) => writeln!(f, "\t- delegate:{}", delegate)?,
+                    None => writeln!(f, "\t- delegate: none (revoked)")?,
+                }
+            }
         }
         Ok(())
     }
@@ -565,6 +712,17 @@ impl Serializer<OperationType> for OperationTypeSerializer {
                     .serialize(target_func, buffer)?;
                 self.vec_u8_serializer.serialize(param, buffer)?;
             }
+            OperationType::SetStakingDelegate { delegate } => {
+                self.u32_serializer
+                    .serialize(&u32::from(OperationTypeId::SetStakingDelegate), buffer)?;
+                match delegate {
+                    Some(delegate) => {
+                        buffer.push(1);
+                        buffer.extend(delegate.to_bytes());
+                    }
+                    None => buffer.push(0),
+                }
+            }
         }
         Ok(())
     }
@@ -744,6 +902,18 @@ impl Deserializer<OperationType> for OperationTypeDeserializer {
                     },
                 )
                 .parse(input),
+                OperationTypeId::SetStakingDelegate => context(
+                    "Failed SetStakingDelegate deserialization",
+                    alt((
+                        value(None, tag(&[0])),
+                        preceded(tag(&[1]), |input| {
+                            self.address_deserializer.deserialize(input)
+                        })
+                        .map(Some),
+                    )),
+                )
+                .map(|delegate| OperationType::SetStakingDelegate { delegate })
+                .parse(input),
             }
         })
         .parse(buffer)
@@ -769,14 +939,14 @@ impl WrappedOperation {
             OperationType::RollBuy { .. } => 0,
             OperationType::RollSell { .. } => 0,
             OperationType::Transaction { .. } => 0,
+            OperationType::SetStakingDelegate { .. } => 0,
         }
     }
 
     /// get the addresses that are involved in this operation from a ledger point of view
     pub fn get_ledger_involved_addresses(&self) -> PreHashSet<Address> {
         let mut res = PreHashSet::<Address>::default();
-        let emitter_address = Address::from_public_key(&self.creator_public_key);
-        res.insert(emitter_address);
+        res.insert(self.creator_address);
         match &self.content.op {
             OperationType::Transaction {
                 recipient_address, ..
@@ -789,6 +959,7 @@ impl WrappedOperation {
             OperationType::CallSC { target_addr, .. } => {
                 res.insert(*target_addr);
             }
+            OperationType::SetStakingDelegate { .. } => {}
         }
         res
     }
@@ -802,6 +973,7 @@ impl WrappedOperation {
             OperationType::RollSell { .. } => Amount::zero(),
             OperationType::ExecuteSC { .. } => Amount::zero(),
             OperationType::CallSC { coins, .. } => *coins,
+            OperationType::SetStakingDelegate { .. } => Amount::zero(),
         };
 
         // add all fees and return
@@ -814,13 +986,16 @@ impl WrappedOperation {
         match self.content.op {
             OperationType::Transaction { .. } => {}
             OperationType::RollBuy { .. } => {
-                res.insert(Address::from_public_key(&self.creator_public_key));
+                res.insert(self.creator_address);
             }
             OperationType::RollSell { .. } => {
-                res.insert(Address::from_public_key(&self.creator_public_key));
+                res.insert(self.creator_address);
             }
             OperationType::ExecuteSC { .. } => {}
             OperationType::CallSC { .. } => {}
+            OperationType::SetStakingDelegate { .. } => {
+                res.insert(self.creator_address);
+            }
         }
         Ok(res)
     }
@@ -1235,6 +1410,7 @@ mod tests {
     };
 
     use super::*;
+    use crate::multisig::MultisigPolicy;
     use massa_serialization::DeserializeError;
     use massa_signature::KeyPair;
     use serial_test::serial;
@@ -1313,6 +1489,97 @@ mod tests {
         assert_eq!(op.get_validity_range(10), 40..=50);
     }
 
+    #[test]
+    #[serial]
+    fn test_multisig_transaction() {
+        let signer_keypairs = vec![
+            KeyPair::generate(),
+            KeyPair::generate(),
+            KeyPair::generate(),
+        ];
+        let policy = MultisigPolicy::new(
+            2,
+            signer_keypairs.iter().map(|k| k.get_public_key()).collect(),
+        )
+        .unwrap();
+        let content = Operation {
+            fee: Amount::from_str("20").unwrap(),
+            op: OperationType::Transaction {
+                recipient_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+                amount: Amount::from_str("100").unwrap(),
+            },
+            expire_period: 50,
+        };
+        let mut ser_content = Vec::new();
+        OperationSerializer::new()
+            .serialize(&content, &mut ser_content)
+            .unwrap();
+        let hash = policy.content_hash(&ser_content);
+        let mut signatures = MultisigSignatures::new(policy.clone());
+        signatures.sign(&signer_keypairs[0], &hash).unwrap();
+        signatures.sign(&signer_keypairs[2], &hash).unwrap();
+
+        let op: WrappedOperation =
+            Operation::new_wrapped_multisig(content, OperationSerializer::new(), signatures)
+                .unwrap();
+        assert_eq!(op.creator_address, policy.address());
+        op.verify_signature().unwrap();
+        assert!(op
+            .get_ledger_involved_addresses()
+            .contains(&policy.address()));
+
+        let mut ser_op = Vec::new();
+        WrappedSerializer::new()
+            .serialize(&op, &mut ser_op)
+            .unwrap();
+        assert_eq!(ser_op.len(), op.serialized_size());
+        let (rest, res_op): (&[u8], WrappedOperation) =
+            WrappedDeserializer::new(OperationDeserializer::new(
+                MAX_DATASTORE_VALUE_LENGTH,
+                MAX_FUNCTION_NAME_LENGTH,
+                MAX_PARAMETERS_SIZE,
+                MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+                MAX_OPERATION_DATASTORE_KEY_LENGTH,
+                MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+            ))
+            .deserialize::<DeserializeError>(&ser_op)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(res_op, op);
+        res_op.verify_signature().unwrap();
+
+        // a single signature is below the threshold
+        let mut partial = res_op.clone();
+        partial.multisig.as_mut().unwrap().signatures.remove(&2);
+        assert!(partial.verify_signature().is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_single_signer_layout() {
+        // the operations signed by one key keep the layout of the nodes predating multisig
+        let keypair = KeyPair::generate();
+        let content = Operation {
+            fee: Amount::from_str("20").unwrap(),
+            op: OperationType::RollBuy { roll_count: 3 },
+            expire_period: 50,
+        };
+        let op: WrappedOperation =
+            Operation::new_wrapped(content, OperationSerializer::new(), &keypair).unwrap();
+        let mut ser_op = Vec::new();
+        WrappedSerializer::new()
+            .serialize(&op, &mut ser_op)
+            .unwrap();
+        let mut legacy = op.signature.to_bytes().to_vec();
+        legacy.extend(op.creator_public_key.to_bytes());
+        legacy.extend(&op.serialized_data);
+        assert_eq!(ser_op, legacy);
+        assert_eq!(ser_op.len(), op.serialized_size());
+
+        // the multisig marker can never be read as the signature of a single signer
+        assert!(Signature::from_bytes(&MULTISIG_SIGNATURE_MARKER).is_err());
+    }
+
     #[test]
     #[serial]
     fn test_executesc() {
@@ -1462,4 +1729,48 @@ mod tests {
 
         assert_eq!(op.get_validity_range(10), 40..=50);
     }
+
+    #[test]
+    #[serial]
+    fn test_set_staking_delegate() {
+        let sender_keypair = KeyPair::generate();
+        let delegate_keypair = KeyPair::generate();
+        let delegate = Address::from_public_key(&delegate_keypair.get_public_key());
+
+        for op in [
+            OperationType::SetStakingDelegate {
+                delegate: Some(delegate),
+            },
+            OperationType::SetStakingDelegate { delegate: None },
+        ] {
+            let mut ser_type = Vec::new();
+            OperationTypeSerializer::new()
+                .serialize(&op, &mut ser_type)
+                .unwrap();
+            let (rest, res_type) = OperationTypeDeserializer::new(
+                MAX_DATASTORE_VALUE_LENGTH,
+                MAX_FUNCTION_NAME_LENGTH,
+                MAX_PARAMETERS_SIZE,
+                MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+                MAX_OPERATION_DATASTORE_KEY_LENGTH,
+                MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+            )
+            .deserialize::<DeserializeError>(&ser_type)
+            .unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(res_type, op);
+
+            let content = Operation {
+                fee: Amount::from_str("20").unwrap(),
+                op,
+                expire_period: 50,
+            };
+            let op = Operation::new_wrapped(content, OperationSerializer::new(), &sender_keypair)
+                .unwrap();
+            assert_eq!(
+                op.get_roll_involved_addresses().unwrap(),
+                PreHashSet::from_iter([Address::from_public_key(&sender_keypair.get_public_key())])
+            );
+        }
+    }
 }
@@ -0,0 +1,244 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Compressed sparse Merkle tree primitives used to authenticate the ledger entries.
+//!
+//! The path of a key is given by the bits of the hash of the key, most significant bit first (`false` = left, `true` = right).
+//! A subtree containing a single key is replaced by a leaf node located at the root of that subtree,
+//! so that a key sits at the depth of the shortest prefix of its path that no other key shares,
+//! which is about the logarithm of the number of keys instead of `SPARSE_MERKLE_DEPTH`.
+//! * an empty subtree hashes to the zero hash
+//! * a leaf node hashes to the hash of `LEAF_NODE_PREFIX`, the path of its key and the hash of the key-value pair
+//! * an internal node hashes to the hash of `INTERNAL_NODE_PREFIX` and the hashes of its two children
+
+use crate::error::ModelsError;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_serialization::{Serializer, U64VarIntSerializer};
+use serde::{Deserialize, Serialize};
+
+/// Maximal depth of the sparse Merkle tree, one level per bit of a key hash
+pub const SPARSE_MERKLE_DEPTH: usize = HASH_SIZE_BYTES * 8;
+
+/// Prefix of the hashed content of a leaf node
+const LEAF_NODE_PREFIX: u8 = 0;
+
+/// Prefix of the hashed content of an internal node
+const INTERNAL_NODE_PREFIX: u8 = 1;
+
+/// Leaf node of the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleLeaf {
+    /// path of the key, see `sparse_merkle_path`
+    pub path: Hash,
+    /// hash of the key-value pair, see `sparse_merkle_leaf_hash`
+    pub entry_hash: Hash,
+}
+
+impl SparseMerkleLeaf {
+    /// Hash of the leaf node
+    pub fn node_hash(&self) -> Hash {
+        Hash::compute_from(
+            &[
+                [LEAF_NODE_PREFIX].as_slice(),
+                self.path.to_bytes().as_slice(),
+                self.entry_hash.to_bytes().as_slice(),
+            ]
+            .concat(),
+        )
+    }
+}
+
+/// Proof of the value of a key, or of its absence, against the root of the tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleProof {
+    /// siblings of the key path, from the level of the node ending the path up to the root level,
+    /// `None` standing for an empty subtree
+    pub siblings: Vec<Option<Hash>>,
+    /// for an absent key, leaf node of the other key ending its path, `None` if the path ends in an empty subtree
+    pub other_leaf: Option<SparseMerkleLeaf>,
+}
+
+/// Hash of an empty subtree
+pub fn sparse_merkle_empty_hash() -> Hash {
+    Hash::from_bytes(&[0; HASH_SIZE_BYTES])
+}
+
+/// Path of a key in the tree
+pub fn sparse_merkle_path(key: &[u8]) -> [u8; HASH_SIZE_BYTES] {
+    Hash::compute_from(key).into_bytes()
+}
+
+/// Hash of a key-value pair
+pub fn sparse_merkle_leaf_hash(key: &[u8], value: &[u8]) -> Hash {
+    let mut len_bytes = Vec::new();
+    // u64 serialization never fails
+    U64VarIntSerializer::new()
+        .serialize(&(key.len() as u64), &mut len_bytes)
+        .unwrap();
+    Hash::compute_from(&[&len_bytes, key, value].concat())
+}
+
+/// Hash of an internal node
+pub fn sparse_merkle_node_hash(left: &Hash, right: &Hash) -> Hash {
+    Hash::compute_from(
+        &[
+            [INTERNAL_NODE_PREFIX].as_slice(),
+            left.to_bytes().as_slice(),
+            right.to_bytes().as_slice(),
+        ]
+        .concat(),
+    )
+}
+
+/// Direction taken by `path` when going down from `depth` to `depth + 1` (`true` = right)
+pub fn sparse_merkle_path_bit(path: &[u8; HASH_SIZE_BYTES], depth: usize) -> bool {
+    (path[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Position of the node of depth `depth` on `path`: the first `depth` bits of `path`, the others being cleared
+pub fn sparse_merkle_node_position(
+    path: &[u8; HASH_SIZE_BYTES],
+    depth: usize,
+) -> [u8; HASH_SIZE_BYTES] {
+    let mut position = [0u8; HASH_SIZE_BYTES];
+    let full_bytes = depth / 8;
+    position[..full_bytes].copy_from_slice(&path[..full_bytes]);
+    if depth % 8 != 0 {
+        position[full_bytes] = path[full_bytes] & (0xffu8 << (8 - depth % 8));
+    }
+    position
+}
+
+/// Position of the sibling of the node of depth `depth` located at `position`
+pub fn sparse_merkle_sibling_position(
+    position: &[u8; HASH_SIZE_BYTES],
+    depth: usize,
+) -> [u8; HASH_SIZE_BYTES] {
+    let mut sibling = *position;
+    sibling[(depth - 1) / 8] ^= 1 << (7 - (depth - 1) % 8);
+    sibling
+}
+
+/// Computes the root of the tree from a key, its value (`None` if the key is absent) and its proof
+pub fn sparse_merkle_root_from_proof(
+    key: &[u8],
+    value: Option<&[u8]>,
+    proof: &SparseMerkleProof,
+) -> Result<Hash, ModelsError> {
+    let depth = proof.siblings.len();
+    if depth > SPARSE_MERKLE_DEPTH {
+        return Err(ModelsError::InvalidLedgerProof(format!(
+            "expected at most {} siblings, got {}",
+            SPARSE_MERKLE_DEPTH, depth
+        )));
+    }
+    let path = sparse_merkle_path(key);
+    let mut hash = match (value, &proof.other_leaf) {
+        (Some(value), None) => SparseMerkleLeaf {
+            path: Hash::from_bytes(&path),
+            entry_hash: sparse_merkle_leaf_hash(key, value),
+        }
+        .node_hash(),
+        (Some(_), Some(_)) => {
+            return Err(ModelsError::InvalidLedgerProof(
+                "the proof of a present key cannot end with another leaf".into(),
+            ))
+        }
+        (None, None) => sparse_merkle_empty_hash(),
+        (None, Some(other_leaf)) => {
+            let other_path = other_leaf.path.into_bytes();
+            if other_path == path
+                || sparse_merkle_node_position(&other_path, depth)
+                    != sparse_merkle_node_position(&path, depth)
+            {
+                return Err(ModelsError::InvalidLedgerProof(
+                    "the other leaf does not end the path of the key".into(),
+                ));
+            }
+            other_leaf.node_hash()
+        }
+    };
+    for (depth, sibling) in (1..=depth).rev().zip(proof.siblings.iter()) {
+        let sibling = sibling.unwrap_or_else(sparse_merkle_empty_hash);
+        hash = if sparse_merkle_path_bit(&path, depth - 1) {
+            sparse_merkle_node_hash(&sibling, &hash)
+        } else {
+            sparse_merkle_node_hash(&hash, &sibling)
+        };
+    }
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_merkle_positions() {
+        let path = [0b1011_0110u8; HASH_SIZE_BYTES];
+        assert_eq!(sparse_merkle_node_position(&path, 0), [0; HASH_SIZE_BYTES]);
+        assert_eq!(sparse_merkle_node_position(&path, 3)[0], 0b1010_0000);
+        assert_eq!(
+            sparse_merkle_node_position(&path, SPARSE_MERKLE_DEPTH),
+            path
+        );
+        assert!(sparse_merkle_path_bit(&path, 0));
+        assert!(!sparse_merkle_path_bit(&path, 1));
+        let position = sparse_merkle_node_position(&path, 3);
+        assert_eq!(sparse_merkle_sibling_position(&position, 3)[0], 0b1000_0000);
+    }
+
+    #[test]
+    fn test_sparse_merkle_empty_proof() {
+        let proof = SparseMerkleProof {
+            siblings: Vec::new(),
+            other_leaf: None,
+        };
+        assert_eq!(
+            sparse_merkle_root_from_proof(b"key", None, &proof).unwrap(),
+            sparse_merkle_empty_hash()
+        );
+        assert_ne!(
+            sparse_merkle_root_from_proof(b"key", Some(b"value"), &proof).unwrap(),
+            sparse_merkle_empty_hash()
+        );
+        let too_long = SparseMerkleProof {
+            siblings: vec![None; SPARSE_MERKLE_DEPTH + 1],
+            other_leaf: None,
+        };
+        assert!(sparse_merkle_root_from_proof(b"key", None, &too_long).is_err());
+    }
+
+    #[test]
+    fn test_sparse_merkle_single_leaf_proofs() {
+        // a tree holding a single key is its leaf node
+        let leaf = SparseMerkleLeaf {
+            path: Hash::compute_from(b"key"),
+            entry_hash: sparse_merkle_leaf_hash(b"key", b"value"),
+        };
+        let root = leaf.node_hash();
+        let inclusion = SparseMerkleProof {
+            siblings: Vec::new(),
+            other_leaf: None,
+        };
+        assert_eq!(
+            sparse_merkle_root_from_proof(b"key", Some(b"value"), &inclusion).unwrap(),
+            root
+        );
+        assert_ne!(
+            sparse_merkle_root_from_proof(b"key", Some(b"other value"), &inclusion).unwrap(),
+            root
+        );
+
+        // any other key ends on that leaf
+        let exclusion = SparseMerkleProof {
+            siblings: Vec::new(),
+            other_leaf: Some(leaf),
+        };
+        assert_eq!(
+            sparse_merkle_root_from_proof(b"other key", None, &exclusion).unwrap(),
+            root
+        );
+        assert!(sparse_merkle_root_from_proof(b"key", None, &exclusion).is_err());
+        assert!(sparse_merkle_root_from_proof(b"other key", Some(b"value"), &exclusion).is_err());
+    }
+}
@@ -18,6 +18,8 @@ pub struct ExecutionStats {
     pub final_executed_operations_count: usize,
     /// active execution cursor slot
     pub active_cursor: Slot,
+    /// final execution cursor slot
+    pub final_cursor: Slot,
 }
 
 impl std::fmt::Display for ExecutionStats {
@@ -44,6 +46,7 @@ impl std::fmt::Display for ExecutionStats {
             self.final_executed_operations_count
         )?;
         writeln!(f, "\tActive cursor: {}", self.active_cursor)?;
+        writeln!(f, "\tFinal cursor: {}", self.final_cursor)?;
         Ok(())
     }
 }
@@ -61,6 +64,14 @@ pub struct NetworkStats {
     pub banned_peer_count: u64,
     /// active node count
     pub active_node_count: u64,
+    /// in connections refused because their subnet reached its connection limit
+    pub subnet_rejected_in_connection_count: u64,
+    /// out connection attempts refused because their subnet reached its connection limit
+    pub subnet_rejected_out_connection_count: u64,
+    /// bytes received from nodes since the node started
+    pub bytes_in: u64,
+    /// bytes sent to nodes since the node started
+    pub bytes_out: u64,
 }
 
 impl std::fmt::Display for NetworkStats {
@@ -71,6 +82,74 @@ impl std::fmt::Display for NetworkStats {
         writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
         writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
         writeln!(f, "\tActive nodes: {}", self.active_node_count)?;
+        writeln!(
+            f,
+            "\tSubnet limit rejections: {} in, {} out",
+            self.subnet_rejected_in_connection_count, self.subnet_rejected_out_connection_count
+        )?;
+        writeln!(
+            f,
+            "\tTraffic: {} bytes in, {} bytes out",
+            self.bytes_in, self.bytes_out
+        )?;
+        Ok(())
+    }
+}
+
+/// traffic exchanged with a node for one message type
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageTrafficStats {
+    /// number of messages received
+    pub messages_in: u64,
+    /// number of messages sent
+    pub messages_out: u64,
+    /// number of bytes received
+    pub bytes_in: u64,
+    /// number of bytes sent
+    pub bytes_out: u64,
+}
+
+impl std::fmt::Display for MessageTrafficStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} in ({} bytes), {} out ({} bytes)",
+            self.messages_in, self.bytes_in, self.messages_out, self.bytes_out
+        )
+    }
+}
+
+/// stats produced by the bootstrap server
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BootstrapStats {
+    /// active sessions count
+    pub active_session_count: u64,
+    /// successfully completed sessions count
+    pub completed_session_count: u64,
+    /// failed sessions count
+    pub failed_session_count: u64,
+    /// count of the sessions refused because the peer already bootstrapped less than `per_ip_min_interval` ago
+    pub refused_session_count: u64,
+    /// count of the sessions refused because `max_simultaneous_bootstraps` was reached
+    pub no_slot_session_count: u64,
+}
+
+impl std::fmt::Display for BootstrapStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Bootstrap server stats:")?;
+        writeln!(f, "\tActive sessions: {}", self.active_session_count)?;
+        writeln!(f, "\tCompleted sessions: {}", self.completed_session_count)?;
+        writeln!(f, "\tFailed sessions: {}", self.failed_session_count)?;
+        writeln!(
+            f,
+            "\tSessions refused (per IP interval): {}",
+            self.refused_session_count
+        )?;
+        writeln!(
+            f,
+            "\tSessions refused (no slot available): {}",
+            self.no_slot_session_count
+        )?;
         Ok(())
     }
 }
@@ -88,6 +167,14 @@ pub struct ConsensusStats {
     pub stale_block_count: u64,
     ///  number of actives cliques
     pub clique_count: u64,
+    /// number of blocks added to the graph since the node started
+    pub processed_block_total: u64,
+    /// number of blocks discarded as invalid since the node started
+    pub invalid_block_total: u64,
+    /// number of blocks discarded as stale since the node started
+    pub stale_block_total: u64,
+    /// number of blocks discarded as final since the node started
+    pub final_block_total: u64,
 }
 
 impl std::fmt::Display for ConsensusStats {
@@ -106,6 +193,14 @@ impl std::fmt::Display for ConsensusStats {
         writeln!(f, "\tFinal block count: {}", self.final_block_count)?;
         writeln!(f, "\tStale block count: {}", self.stale_block_count)?;
         writeln!(f, "\tClique count: {}", self.clique_count)?;
+        writeln!(
+            f,
+            "\tBlocks since start: {} processed, {} invalid, {} stale, {} final",
+            self.processed_block_total,
+            self.invalid_block_total,
+            self.stale_block_total,
+            self.final_block_total
+        )?;
         Ok(())
     }
 }
@@ -6,10 +6,11 @@ use nom::{
     error::{context, ContextError, ParseError},
     IResult, Parser,
 };
+use serde::{Deserialize, Serialize};
 use std::{marker::PhantomData, ops::Bound::Included};
 
 /// Streaming step cursor
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum StreamingStep<T> {
     /// Started step, only when launching the streaming
     Started,
@@ -26,6 +27,25 @@ impl<T> StreamingStep<T> {
     pub fn finished(&self) -> bool {
         matches!(self, StreamingStep::Finished(_))
     }
+
+    /// Maps the content of the step
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> StreamingStep<U> {
+        match self {
+            StreamingStep::Started => StreamingStep::Started,
+            StreamingStep::Ongoing(content) => StreamingStep::Ongoing(f(content)),
+            StreamingStep::Finished(content) => StreamingStep::Finished(content.map(f)),
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for StreamingStep<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamingStep::Started => write!(f, "started"),
+            StreamingStep::Ongoing(content) => write!(f, "ongoing, at {}", content),
+            StreamingStep::Finished(_) => write!(f, "finished"),
+        }
+    }
 }
 
 /// `StreamingStep` serializer
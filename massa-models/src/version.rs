@@ -191,6 +191,12 @@ impl Version {
     pub fn is_compatible(&self, other: &Version) -> bool {
         self.instance == other.instance && self.major == other.major
     }
+
+    /// true if compatible with `other` and not older than it.
+    /// Used to enable protocol features that were added in a minor version
+    pub fn is_at_least(&self, other: &Version) -> bool {
+        self.is_compatible(other) && self.minor >= other.minor
+    }
 }
 
 impl fmt::Display for Version {
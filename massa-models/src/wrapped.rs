@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::{address::Address, error::ModelsError};
+use crate::{address::Address, error::ModelsError, multisig::MultisigSignatures};
 use massa_hash::Hash;
 use massa_serialization::{Deserializer, SerializeError, Serializer};
 use massa_signature::{
@@ -29,6 +29,9 @@ where
     pub creator_public_key: PublicKey,
     /// the content creator address
     pub creator_address: Address,
+    /// signatures of a multisig creator, `signature` and `creator_public_key` then hold the first of them
+    #[serde(default)]
+    pub multisig: Option<MultisigSignatures>,
     /// Id
     pub id: U,
     #[serde(skip)]
@@ -67,19 +70,51 @@ where
             signature: keypair.sign(&hash)?,
             creator_public_key: public_key,
             creator_address,
+            multisig: None,
             content,
             serialized_data: content_serialized,
             id: U::new(hash),
         })
     }
 
+    /// Creates a wrapped version of the object created by a multisig address.
+    /// The signatures must have been made on `policy.content_hash` of the serialized content.
+    fn new_wrapped_multisig<SC: Serializer<Self>, U: Id>(
+        content: Self,
+        content_serializer: SC,
+        signatures: MultisigSignatures,
+    ) -> Result<Wrapped<Self, U>, ModelsError> {
+        let mut content_serialized = Vec::new();
+        content_serializer.serialize(&content, &mut content_serialized)?;
+        let (signature, creator_public_key) = signatures
+            .signed_by()?
+            .first()
+            .copied()
+            .ok_or_else(|| ModelsError::MultisigError("no signature collected".to_string()))?;
+        Ok(Wrapped {
+            signature,
+            creator_public_key,
+            creator_address: signatures.policy.address(),
+            id: U::new(signatures.policy.content_hash(&content_serialized)),
+            multisig: Some(signatures),
+            content,
+            serialized_data: content_serialized,
+        })
+    }
+
     /// Serialize the wrapped structure
     fn serialize(
         signature: &Signature,
         creator_public_key: &PublicKey,
+        multisig: Option<&MultisigSignatures>,
         serialized_content: &[u8],
         buffer: &mut Vec<u8>,
     ) -> Result<(), SerializeError> {
+        if multisig.is_some() {
+            return Err(SerializeError::GeneralError(
+                "this content cannot be created by a multisig address".to_string(),
+            ));
+        }
         buffer.extend(signature.into_bytes());
         buffer.extend(creator_public_key.into_bytes());
         buffer.extend(serialized_content);
@@ -110,22 +145,8 @@ where
                 }),
             )),
         )(buffer)?;
-        let (rest, content) = content_deserializer.deserialize(serialized_data)?;
-        let content_serialized = if let Some(content_serializer) = content_serializer {
-            let mut content_buffer = Vec::new();
-            content_serializer
-                .serialize(&content, &mut content_buffer)
-                .map_err(|_| {
-                    nom::Err::Error(ParseError::from_error_kind(
-                        rest,
-                        nom::error::ErrorKind::Fail,
-                    ))
-                })?;
-            content_buffer
-        } else {
-            // Avoid getting the rest of the data in the serialized data
-            serialized_data[..serialized_data.len() - rest.len()].to_vec()
-        };
+        let (rest, (content, content_serialized)) =
+            deserialize_content(content_serializer, content_deserializer, serialized_data)?;
         let creator_address = Address::from_public_key(&creator_public_key);
         let mut serialized_full_data = creator_public_key.to_bytes().to_vec();
         serialized_full_data.extend(&content_serialized);
@@ -136,11 +157,47 @@ where
                 signature,
                 creator_public_key,
                 creator_address,
-                serialized_data: content_serialized.to_vec(),
+                multisig: None,
+                serialized_data: content_serialized,
                 id: U::new(Hash::compute_from(&serialized_full_data)),
             },
         ))
     }
+
+    /// Size of the signatures and public keys in the serialized wrapped structure
+    fn signatures_size(_multisig: Option<&MultisigSignatures>) -> usize {
+        SIGNATURE_SIZE_BYTES.saturating_add(PUBLIC_KEY_SIZE_BYTES)
+    }
+}
+
+/// Deserialize the content of a wrapped structure, returns it with its serialized form
+pub(crate) fn deserialize_content<
+    'a,
+    T,
+    E: ParseError<&'a [u8]> + ContextError<&'a [u8]>,
+    DC: Deserializer<T>,
+>(
+    content_serializer: Option<&dyn Serializer<T>>,
+    content_deserializer: &DC,
+    serialized_data: &'a [u8],
+) -> IResult<&'a [u8], (T, Vec<u8>), E> {
+    let (rest, content) = content_deserializer.deserialize(serialized_data)?;
+    let content_serialized = if let Some(content_serializer) = content_serializer {
+        let mut content_buffer = Vec::new();
+        content_serializer
+            .serialize(&content, &mut content_buffer)
+            .map_err(|_| {
+                nom::Err::Error(ParseError::from_error_kind(
+                    rest,
+                    nom::error::ErrorKind::Fail,
+                ))
+            })?;
+        content_buffer
+    } else {
+        // Avoid getting the rest of the data in the serialized data
+        serialized_data[..serialized_data.len() - rest.len()].to_vec()
+    };
+    Ok((rest, (content, content_serialized)))
 }
 
 impl<T, U> Display for Wrapped<T, U>
@@ -165,17 +222,26 @@ where
 {
     /// check if self has been signed by public key
     pub fn verify_signature(&self) -> Result<(), ModelsError> {
-        Ok(self
-            .creator_public_key
-            .verify_signature(self.id.get_hash(), &self.signature)?)
+        match &self.multisig {
+            Some(multisig) => {
+                if multisig.policy.address() != self.creator_address {
+                    return Err(ModelsError::MultisigError(
+                        "the policy does not match the creator address".to_string(),
+                    ));
+                }
+                multisig.verify(self.id.get_hash())
+            }
+            None => Ok(self
+                .creator_public_key
+                .verify_signature(self.id.get_hash(), &self.signature)?),
+        }
     }
 
     /// get full serialized size
     pub fn serialized_size(&self) -> usize {
         self.serialized_data
             .len()
-            .saturating_add(SIGNATURE_SIZE_BYTES)
-            .saturating_add(PUBLIC_KEY_SIZE_BYTES)
+            .saturating_add(T::signatures_size(self.multisig.as_ref()))
     }
 }
 
@@ -214,6 +280,7 @@ impl WrappedSerializer {
         T::serialize(
             &value.signature,
             &value.creator_public_key,
+            value.multisig.as_ref(),
             &content_buffer,
             buffer,
         )
@@ -229,6 +296,7 @@ where
         T::serialize(
             &value.signature,
             &value.creator_public_key,
+            value.multisig.as_ref(),
             &value.serialized_data,
             buffer,
         )
@@ -71,13 +71,15 @@
 
 use crate::{BootstrapPeers, ConnectionClosureReason, Peers};
 use massa_models::{
+    api::ConnectedPeer,
     block::{BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::{OperationId, OperationPrefixIds, WrappedOperation},
     stats::NetworkStats,
 };
+use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, net::IpAddr};
 use tokio::sync::oneshot;
@@ -179,9 +181,23 @@ pub enum NetworkCommand {
     /// get peers for bootstrap server
     GetBootstrapPeers(oneshot::Sender<BootstrapPeers>),
     /// Ban a list of peer by their node id
-    NodeBanByIds(Vec<NodeId>),
+    NodeBanByIds {
+        /// node ids
+        ids: Vec<NodeId>,
+        /// ban duration, None for the default duration of the reason
+        duration: Option<MassaTime>,
+        /// why the peers are banned
+        reason: BanReason,
+    },
     /// Ban a list of peer by their ip address
-    NodeBanByIps(Vec<IpAddr>),
+    NodeBanByIps {
+        /// ip addresses
+        ips: Vec<IpAddr>,
+        /// ban duration, None for the default duration of the reason
+        duration: Option<MassaTime>,
+        /// why the peers are banned
+        reason: BanReason,
+    },
     /// Unban a list of peer by their node id
     NodeUnbanByIds(Vec<NodeId>),
     /// Unban a list of peer by their ip address
@@ -206,6 +222,13 @@ pub enum NetworkCommand {
         /// response channels
         response_tx: oneshot::Sender<NetworkStats>,
     },
+    /// gets the connected nodes with statistics about their connection
+    GetConnectedPeers {
+        /// response channels
+        response_tx: oneshot::Sender<Vec<ConnectedPeer>>,
+    },
+    /// Close the connections to a list of nodes without banning them
+    NodeDisconnectByIds(Vec<NodeId>),
     /// Send a batch of full operations
     SendOperations {
         /// to node id
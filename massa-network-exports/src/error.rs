@@ -111,6 +111,8 @@ pub enum NetworkConnectionErrorType {
     SelfConnection,
     /// A banned peer is trying to connect: {0}
     BannedPeerTryingToConnect(IpAddr),
+    /// Max connections reached for the subnet of: {0}
+    MaxSubnetConnectionsReached(IpAddr),
     /// Unexpected error
     UnexpectedError,
 }
@@ -14,8 +14,8 @@ pub use error::{HandshakeErrorType, NetworkConnectionErrorType, NetworkError};
 pub use establisher::{Establisher, Listener, ReadHalf, WriteHalf};
 pub use network_controller::{NetworkCommandSender, NetworkEventReceiver, NetworkManager};
 pub use peers::{
-    BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, ConnectionCount, Peer,
-    PeerInfo, PeerType, Peers,
+    ip_subnet, BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer,
+    ConnectionCount, Peer, PeerInfo, PeerReputation, PeerType, Peers,
 };
 pub use settings::NetworkConfig;
 
@@ -6,13 +6,15 @@ use crate::{
     BlockInfoReply, BootstrapPeers, NetworkCommand, NetworkEvent, Peers,
 };
 use massa_models::{
+    api::ConnectedPeer,
     block::{BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::{OperationPrefixIds, WrappedOperation},
     stats::NetworkStats,
 };
+use massa_time::MassaTime;
 use std::{
     collections::{HashMap, VecDeque},
     net::IpAddr,
@@ -32,18 +34,48 @@ pub struct NetworkCommandSender(pub mpsc::Sender<NetworkCommand>);
 
 impl NetworkCommandSender {
     /// ban node(s) by id(s)
-    pub async fn node_ban_by_ids(&self, ids: Vec<NodeId>) -> Result<(), NetworkError> {
+    ///
+    /// # Arguments
+    /// * `ids`: node ids to ban
+    /// * `duration`: ban duration, None for the default duration of the reason
+    /// (until unbanned for manual bans, `ban_duration` otherwise)
+    /// * `reason`: why the nodes are banned
+    pub async fn node_ban_by_ids(
+        &self,
+        ids: Vec<NodeId>,
+        duration: Option<MassaTime>,
+        reason: BanReason,
+    ) -> Result<(), NetworkError> {
         self.0
-            .send(NetworkCommand::NodeBanByIds(ids))
+            .send(NetworkCommand::NodeBanByIds {
+                ids,
+                duration,
+                reason,
+            })
             .await
             .map_err(|_| NetworkError::ChannelError("could not send BanId command".into()))?;
         Ok(())
     }
 
     /// ban node(s) by ip(s)
-    pub async fn node_ban_by_ips(&self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
+    ///
+    /// # Arguments
+    /// * `ips`: ip addresses to ban
+    /// * `duration`: ban duration, None for the default duration of the reason
+    /// (until unbanned for manual bans, `ban_duration` otherwise)
+    /// * `reason`: why the peers are banned
+    pub async fn node_ban_by_ips(
+        &self,
+        ips: Vec<IpAddr>,
+        duration: Option<MassaTime>,
+        reason: BanReason,
+    ) -> Result<(), NetworkError> {
         self.0
-            .send(NetworkCommand::NodeBanByIps(ips))
+            .send(NetworkCommand::NodeBanByIps {
+                ips,
+                duration,
+                reason,
+            })
             .await
             .map_err(|_| NetworkError::ChannelError("could not send BanIp command".into()))?;
         Ok(())
@@ -160,6 +192,31 @@ impl NetworkCommandSender {
             .map_err(|_| NetworkError::ChannelError("could not send GetStats upstream".into()))
     }
 
+    /// get the connected nodes with statistics about their connection
+    pub async fn get_connected_peers(&self) -> Result<Vec<ConnectedPeer>, NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::GetConnectedPeers { response_tx })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send GetConnectedPeers command".into())
+            })?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not send GetConnectedPeers upstream".into())
+        })
+    }
+
+    /// close the connections to the given nodes, without banning them
+    pub async fn node_disconnect_by_ids(&self, ids: Vec<NodeId>) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::NodeDisconnectByIds(ids))
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send NodeDisconnectByIds command".into())
+            })?;
+        Ok(())
+    }
+
     /// Send the order to get bootstrap peers.
     pub async fn get_bootstrap_peers(&self) -> Result<BootstrapPeers, NetworkError> {
         let (response_tx, response_rx) = oneshot::channel::<BootstrapPeers>();
@@ -1,7 +1,7 @@
 use crate::settings::PeerTypeConnectionConfig;
 use displaydoc::Display;
 use enum_map::Enum;
-use massa_models::node::NodeId;
+use massa_models::node::{BanReason, NodeId};
 use massa_models::serialization::{IpAddrDeserializer, IpAddrSerializer};
 use massa_serialization::{
     Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
@@ -12,7 +12,10 @@ use nom::multi::length_count;
 use nom::{IResult, Parser};
 use serde::{Deserialize, Serialize};
 use std::ops::Bound::Included;
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
 /// Associate a peer info with nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Peer {
@@ -175,6 +178,79 @@ impl Default for PeerType {
     }
 }
 
+/// Statistics used to rank peers when choosing out connection candidates
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PeerReputation {
+    /// Smoothed handshake duration in milliseconds, None if we never completed a handshake
+    pub latency: Option<MassaTime>,
+    /// Cumulated time spent connected to that peer
+    pub uptime: MassaTime,
+    /// Number of block headers received from that peer
+    pub blocks_delivered: u64,
+    /// Number of full operations received from that peer
+    pub operations_delivered: u64,
+    /// Number of protocol violations that led to a ban
+    pub violations: u64,
+}
+
+impl Default for PeerReputation {
+    fn default() -> Self {
+        PeerReputation {
+            latency: None,
+            uptime: MassaTime::from(0),
+            blocks_delivered: 0,
+            operations_delivered: 0,
+            violations: 0,
+        }
+    }
+}
+
+impl PeerReputation {
+    /// Weight of a single protocol violation in the score
+    const VIOLATION_PENALTY: f64 = 50.0;
+
+    /// Records a new handshake duration,
+    /// using an exponential moving average to smooth out outliers
+    pub fn record_latency(&mut self, latency: MassaTime) {
+        self.latency = Some(match self.latency {
+            Some(previous) => MassaTime::from((previous.to_millis() * 3 + latency.to_millis()) / 4),
+            None => latency,
+        });
+    }
+
+    /// Reputation score of the peer, the higher the better.
+    ///
+    /// Useful deliveries and uptime are counted on a logarithmic scale
+    /// so that long lived peers can't build an unassailable lead,
+    /// while each protocol violation weighs heavily on the score.
+    pub fn score(&self) -> f64 {
+        let uptime_minutes = self.uptime.to_millis() as f64 / 60_000.0;
+        let latency_penalty = self
+            .latency
+            .map_or(0.0, |latency| latency.to_millis() as f64 / 100.0);
+        10.0 * (self.blocks_delivered as f64).ln_1p()
+            + 2.0 * (self.operations_delivered as f64).ln_1p()
+            + uptime_minutes.ln_1p()
+            - latency_penalty
+            - Self::VIOLATION_PENALTY * self.violations as f64
+    }
+}
+
+/// Returns the subnet an ip belongs to: its /24 for IPv4 and its /48 for IPv6.
+/// Used to spread connections over several networks.
+pub fn ip_subnet(ip: &IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+            IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+        }
+    }
+}
+
 /// All information concerning a peer is here
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub struct PeerInfo {
@@ -190,6 +266,15 @@ pub struct PeerInfo {
     pub advertised: bool,
     /// peer was banned
     pub banned: bool,
+    /// why the peer was banned
+    #[serde(default)]
+    pub ban_reason: Option<BanReason>,
+    /// time at which the ban is lifted, None if it lasts until the peer is unbanned
+    #[serde(default)]
+    pub ban_expiry: Option<MassaTime>,
+    /// Reputation gathered from our past connections with that peer
+    #[serde(default)]
+    pub reputation: PeerReputation,
     /// Current number of active out connection attempts with that peer.
     /// Isn't dump into peer file.
     #[serde(default = "usize::default")]
@@ -207,16 +292,26 @@ pub struct PeerInfo {
 impl PeerInfo {
     /// Cleans up the `PeerInfo` by normalizing the IP address
     /// and resetting active connection counts.
+    /// Bans without a reason (from older peer files) are lifted.
     pub fn cleanup(&mut self) {
         // canonicalize IP
         self.ip = self.ip.to_canonical();
-        self.banned = false;
+        if self.ban_reason.is_none() {
+            self.lift_ban();
+        }
         // ensure that connections are set to zero
         self.active_out_connection_attempts = 0;
         self.active_out_connections = 0;
         self.active_in_connections = 0;
     }
 
+    /// Lifts the ban of the peer, if any
+    pub fn lift_ban(&mut self) {
+        self.banned = false;
+        self.ban_reason = None;
+        self.ban_expiry = None;
+    }
+
     /// Returns true if there is at least one connection attempt /
     /// one active connection in either direction
     /// with this peer
@@ -244,6 +339,9 @@ impl PeerInfo {
             active_in_connections: 0,
             peer_type: Default::default(),
             banned: false,
+            ban_reason: None,
+            ban_expiry: None,
+            reputation: Default::default(),
         }
     }
 
@@ -32,6 +32,12 @@ pub struct NetworkConfig {
     pub peer_types_config: EnumMap<PeerType, PeerTypeConnectionConfig>,
     /// Limit on the number of in connections per ip.
     pub max_in_connections_per_ip: usize,
+    /// Limit on the number of out connections and attempts towards standard peers of the same subnet
+    /// (/24 for IPv4, /48 for IPv6), to keep our view of the network diverse.
+    pub max_out_connections_per_subnet: usize,
+    /// Limit on the number of in and out connections and attempts with standard peers of the same subnet
+    /// (the same subnets as `max_out_connections_per_subnet`).
+    pub max_connections_per_subnet: usize,
     /// Limit on the number of idle peers we remember.
     pub max_idle_peers: usize,
     /// Limit on the number of banned peers we remember.
@@ -48,6 +54,8 @@ pub struct NetworkConfig {
     pub max_send_wait_network_event: MassaTime,
     /// Time after which we forget a node
     pub ban_timeout: MassaTime,
+    /// Duration of the bans triggered by protocol violations
+    pub ban_duration: MassaTime,
     /// Timeout Duration when we send a `PeerList` in handshake
     pub peer_list_send_timeout: MassaTime,
     /// Max number of in connection overflowed managed by the handshake that send a list of peers
@@ -152,6 +160,8 @@ pub mod tests {
                 wakeup_interval: MassaTime::from_millis(10_000),
                 peers_file: std::path::PathBuf::new(),
                 max_in_connections_per_ip: 2,
+                max_out_connections_per_subnet: 3,
+                max_connections_per_subnet: 100,
                 max_idle_peers: 3,
                 max_banned_peers: 3,
                 peers_file_dump_interval: MassaTime::from_millis(10_000),
@@ -161,6 +171,7 @@ pub mod tests {
                 max_send_wait_node_event: MassaTime::from_millis(100),
                 max_send_wait_network_event: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
+                ban_duration: MassaTime::from_millis(100_000_000),
                 initial_peers_file: std::path::PathBuf::new(),
                 peer_list_send_timeout: MassaTime::from_millis(500),
                 max_in_connection_overflow: 2,
@@ -219,6 +230,8 @@ pub mod tests {
                 peers_file: peers_file.to_path_buf(),
                 wakeup_interval: MassaTime::from_millis(3000),
                 max_in_connections_per_ip: 100,
+                max_out_connections_per_subnet: 100,
+                max_connections_per_subnet: 100,
                 max_idle_peers: 100,
                 max_banned_peers: 100,
                 peers_file_dump_interval: MassaTime::from_millis(30000),
@@ -228,6 +241,7 @@ pub mod tests {
                 max_send_wait_node_event: MassaTime::from_millis(100),
                 max_send_wait_network_event: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
+                ban_duration: MassaTime::from_millis(100_000_000),
                 initial_peers_file: peers_file.to_path_buf(),
                 peer_list_send_timeout: MassaTime::from_millis(50),
                 max_in_connection_overflow: 10,
@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 //! `Flexbuffer` layer between raw data and our objects.
+use crate::connection_stats::SharedConnectionStats;
 use crate::messages::{MessageDeserializer, MessageSerializer};
 
 use super::messages::Message;
@@ -20,6 +21,7 @@ pub struct WriteBinder {
     pub(crate) write_half: WriteHalf,
     message_index: u64,
     max_message_size: u32,
+    stats: Option<SharedConnectionStats>,
 }
 
 impl WriteBinder {
@@ -33,9 +35,15 @@ impl WriteBinder {
             write_half,
             message_index: 0,
             max_message_size,
+            stats: None,
         }
     }
 
+    /// Records the traffic of all the following messages in `stats`.
+    pub(crate) fn set_stats(&mut self, stats: SharedConnectionStats) {
+        self.stats = Some(stats);
+    }
+
     /// Sends a serialized message.
     ///
     /// # Argument
@@ -48,13 +56,16 @@ impl WriteBinder {
             .len()
             .try_into()
             .map_err(|_| NetworkError::GeneralProtocolError("message too long".into()))?;
-        self.write_half
-            .write_all(&msg_size.to_be_bytes_min(self.max_message_size)?[..])
-            .await?;
+        let size_field = msg_size.to_be_bytes_min(self.max_message_size)?;
+        self.write_half.write_all(&size_field[..]).await?;
 
         // send message
         self.write_half.write_all(&buf).await?;
 
+        if let Some(stats) = &self.stats {
+            stats.lock().record_sent(msg, size_field.len() + buf.len());
+        }
+
         let res_index = self.message_index;
         self.message_index += 1;
         //        massa_trace!("binder.send END", { "index": res_index });
@@ -71,6 +82,7 @@ pub struct ReadBinder {
     msg_size: Option<u32>,
     max_message_size: u32,
     message_deserializer: MessageDeserializer,
+    stats: Option<SharedConnectionStats>,
 }
 
 impl ReadBinder {
@@ -93,9 +105,15 @@ impl ReadBinder {
             msg_size: None,
             max_message_size,
             message_deserializer,
+            stats: None,
         }
     }
 
+    /// Records the traffic of all the following messages in `stats`.
+    pub(crate) fn set_stats(&mut self, stats: SharedConnectionStats) {
+        self.stats = Some(stats);
+    }
+
     /// Awaits the next incoming message and deserializes it. Asynchronous cancel-safe.
     /// Returns the message, as well as the serialized object in the case of a block.
     ///
@@ -177,6 +195,13 @@ impl ReadBinder {
                 NetworkError::ModelsError(ModelsError::DeserializeError(err.to_string()))
             })?;
 
+        if let Some(stats) = &self.stats {
+            let size_field_len = u32::be_bytes_min_length(self.max_message_size);
+            stats
+                .lock()
+                .record_received(&res_msg, size_field_len + self.buf.len());
+        }
+
         // now the message readout is over, we reset the state to start reading the next message's size field again at the next run
         self.cursor = 0;
         self.msg_size = None;
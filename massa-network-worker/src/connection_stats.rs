@@ -0,0 +1,172 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Statistics about the connection to a node.
+//!
+//! They are updated by the binders of the node worker each time a message
+//! is sent or received, and read by the network worker when asked for the
+//! connected peers.
+
+use crate::messages::{Message, MessageTypeId};
+use massa_models::{
+    api::ConnectedPeer, node::NodeId, stats::MessageTrafficStats, version::Version,
+};
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    net::IpAddr,
+    sync::Arc,
+    time::Instant,
+};
+
+/// Connection statistics shared between a node worker and the network worker
+pub(crate) type SharedConnectionStats = Arc<Mutex<ConnectionStats>>;
+
+/// Number of message types, `MessageTypeId` values going from 0 to `MESSAGE_TYPE_COUNT - 1`
+const MESSAGE_TYPE_COUNT: usize = MessageTypeId::ReplyForBlocks as usize + 1;
+
+/// Maximum number of unanswered peer list requests remembered,
+/// a node answering none of them should not make the queue grow forever
+const MAX_PENDING_PEER_LIST_REQUESTS: usize = 16;
+
+/// Statistics about the connection to a node
+pub(crate) struct ConnectionStats {
+    /// version announced by the node during the handshake
+    version: Version,
+    /// when the handshake succeeded
+    start: Instant,
+    /// last time a message was sent or received
+    last_activity: Option<MassaTime>,
+    /// when the unanswered peer list requests were sent, oldest first:
+    /// nodes answer them in order, and send peer lists only when asked
+    pending_peer_list_requests: VecDeque<Instant>,
+    /// round-trip time of the last answered peer list request
+    ping_rtt: Option<MassaTime>,
+    /// traffic indexed by message type id
+    traffic: [MessageTrafficStats; MESSAGE_TYPE_COUNT],
+}
+
+impl ConnectionStats {
+    /// Creates the statistics of a connection that just finished its handshake
+    pub fn new_shared(version: Version) -> SharedConnectionStats {
+        Arc::new(Mutex::new(ConnectionStats {
+            version,
+            start: Instant::now(),
+            last_activity: None,
+            pending_peer_list_requests: VecDeque::new(),
+            ping_rtt: None,
+            traffic: Default::default(),
+        }))
+    }
+
+    /// Notes that `message`, which took `size` bytes on the wire, was sent
+    pub fn record_sent(&mut self, message: &Message, size: usize) {
+        if matches!(message, Message::AskPeerList) {
+            if self.pending_peer_list_requests.len() >= MAX_PENDING_PEER_LIST_REQUESTS {
+                self.pending_peer_list_requests.pop_front();
+            }
+            self.pending_peer_list_requests.push_back(Instant::now());
+        }
+        let traffic = self.traffic_mut(message);
+        traffic.messages_out = traffic.messages_out.saturating_add(1);
+        traffic.bytes_out = traffic.bytes_out.saturating_add(size as u64);
+    }
+
+    /// Notes that `message`, which took `size` bytes on the wire, was received
+    pub fn record_received(&mut self, message: &Message, size: usize) {
+        // only a peer list answering one of our requests measures the round-trip time
+        if matches!(message, Message::PeerList(_)) {
+            if let Some(asked) = self.pending_peer_list_requests.pop_front() {
+                self.ping_rtt = Some(MassaTime::from_millis(asked.elapsed().as_millis() as u64));
+            }
+        }
+        let traffic = self.traffic_mut(message);
+        traffic.messages_in = traffic.messages_in.saturating_add(1);
+        traffic.bytes_in = traffic.bytes_in.saturating_add(size as u64);
+    }
+
+    /// Traffic of all message types added up
+    pub fn total_traffic(&self) -> MessageTrafficStats {
+        self.traffic
+            .iter()
+            .fold(MessageTrafficStats::default(), |acc, t| {
+                MessageTrafficStats {
+                    messages_in: acc.messages_in.saturating_add(t.messages_in),
+                    messages_out: acc.messages_out.saturating_add(t.messages_out),
+                    bytes_in: acc.bytes_in.saturating_add(t.bytes_in),
+                    bytes_out: acc.bytes_out.saturating_add(t.bytes_out),
+                }
+            })
+    }
+
+    /// Get the traffic counters of the type of `message` and note the activity
+    fn traffic_mut(&mut self, message: &Message) -> &mut MessageTrafficStats {
+        self.last_activity = MassaTime::now(0).ok();
+        &mut self.traffic[u32::from(MessageTypeId::from(message)) as usize]
+    }
+
+    /// Export the statistics for the API.
+    /// The ask block queue depth is only known by the protocol worker and is left to zero.
+    pub fn to_connected_peer(
+        &self,
+        node_id: NodeId,
+        ip: IpAddr,
+        is_outgoing: bool,
+    ) -> ConnectedPeer {
+        ConnectedPeer {
+            node_id,
+            ip,
+            is_outgoing,
+            version: self.version,
+            connection_age: MassaTime::from_millis(self.start.elapsed().as_millis() as u64),
+            last_activity: self.last_activity,
+            ping_rtt: self.ping_rtt,
+            ask_block_queue_depth: 0,
+            traffic: self
+                .traffic
+                .iter()
+                .enumerate()
+                .filter(|(_, traffic)| **traffic != MessageTrafficStats::default())
+                .filter_map(|(index, traffic)| {
+                    let id = MessageTypeId::try_from(index as u32).ok()?;
+                    Some((format!("{:?}", id), *traffic))
+                })
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_ping_rtt_only_measures_answered_requests() {
+        let stats = ConnectionStats::new_shared(Version::from_str("TEST.1.0").unwrap());
+        let mut stats = stats.lock();
+
+        // a peer list nobody asked for is not an answer
+        stats.record_received(&Message::PeerList(Vec::new()), 10);
+        assert_eq!(stats.ping_rtt, None);
+
+        // an answered request measures the round-trip time
+        stats.record_sent(&Message::AskPeerList, 5);
+        assert_eq!(stats.pending_peer_list_requests.len(), 1);
+        stats.record_received(&Message::PeerList(Vec::new()), 10);
+        assert!(stats.ping_rtt.is_some());
+        assert!(stats.pending_peer_list_requests.is_empty());
+
+        // the traffic is counted by message type
+        let peer = stats.to_connected_peer(
+            NodeId(massa_signature::KeyPair::generate().get_public_key()),
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)),
+            true,
+        );
+        assert_eq!(peer.traffic.len(), 2);
+        assert_eq!(peer.traffic["PeerList"].messages_in, 2);
+        assert_eq!(peer.traffic["PeerList"].bytes_in, 20);
+        assert_eq!(peer.traffic["AskPeerList"].messages_out, 1);
+        assert_eq!(stats.total_traffic().bytes_out, 5);
+    }
+}
@@ -34,8 +34,10 @@ use rand::{rngs::StdRng, RngCore, SeedableRng};
 use tokio::{task::JoinHandle, time::timeout};
 use tracing::debug;
 
-/// Type alias for more readability
-pub type HandshakeReturnType = Result<(NodeId, ReadBinder, WriteBinder), NetworkError>;
+/// Type alias for more readability.
+/// On success, also returns the time the handshake took and the version of the peer.
+pub type HandshakeReturnType =
+    Result<(NodeId, ReadBinder, WriteBinder, MassaTime, Version), NetworkError>;
 
 /// Manages handshakes.
 pub struct HandshakeWorker {
@@ -128,6 +130,8 @@ impl HandshakeWorker {
     /// Returns a tuple `(ConnectionId, Result)`.
     /// Creates the binders to communicate with that node.
     async fn run(mut self) -> HandshakeReturnType {
+        let start = MassaTime::now(0)?;
+
         // generate random bytes
         let mut self_random_bytes = [0u8; 32];
         StdRng::from_entropy().fill_bytes(&mut self_random_bytes);
@@ -211,6 +215,13 @@ impl HandshakeWorker {
                 NetworkError::HandshakeError(HandshakeErrorType::HandshakeInvalidSignature)
             })?;
 
-        Ok((other_node_id, self.reader, self.writer))
+        let latency = MassaTime::now(0)?.saturating_sub(start);
+        Ok((
+            other_node_id,
+            self.reader,
+            self.writer,
+            latency,
+            other_version,
+        ))
     }
 }
@@ -90,6 +90,24 @@ pub(crate) enum MessageTypeId {
     ReplyForBlocks,
 }
 
+impl From<&Message> for MessageTypeId {
+    fn from(message: &Message) -> Self {
+        match message {
+            Message::HandshakeInitiation { .. } => MessageTypeId::HandshakeInitiation,
+            Message::HandshakeReply { .. } => MessageTypeId::HandshakeReply,
+            Message::BlockHeader(_) => MessageTypeId::BlockHeader,
+            Message::AskForBlocks(_) => MessageTypeId::AskForBlocks,
+            Message::ReplyForBlocks(_) => MessageTypeId::ReplyForBlocks,
+            Message::AskPeerList => MessageTypeId::AskPeerList,
+            Message::PeerList(_) => MessageTypeId::PeerList,
+            Message::OperationsAnnouncement(_) => MessageTypeId::OperationsAnnouncement,
+            Message::AskForOperations(_) => MessageTypeId::AskForOperations,
+            Message::Operations(_) => MessageTypeId::Operations,
+            Message::Endorsements(_) => MessageTypeId::Endorsements,
+        }
+    }
+}
+
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u32)]
 pub(crate) enum BlockInfoType {
@@ -10,8 +10,8 @@
 //! async fn manage_network_command(&mut self, cmd: NetworkCommand) -> Result<(), NetworkError> {
 //!     use crate::network_cmd_impl::*;
 //!     match cmd {
-//!         NetworkCommand::NodeBanByIps(ips) => on_node_ban_by_ips_cmd(self, ips).await?,
-//!         NetworkCommand::NodeBanByIds(ids) => on_node_ban_by_ids_cmd(self, ids).await?,
+//!         NetworkCommand::NodeBanByIps { ips, duration, reason } => on_node_ban_by_ips_cmd(self, ips, duration, reason).await?,
+//!         NetworkCommand::NodeBanByIds { ids, duration, reason } => on_node_ban_by_ids_cmd(self, ids, duration, reason).await?,
 //!         NetworkCommand::SendBlockHeader { node, header } => on_send_block_header_cmd(self, node, header).await?,
 //!         NetworkCommand::AskForBlocks { list } => on_ask_for_block_cmd(self, list).await,
 //!         NetworkCommand::SendBlock { node, block } => on_send_block_cmd(self, node, block).await?,
@@ -24,10 +24,11 @@ use futures::{stream::FuturesUnordered, StreamExt};
 use massa_hash::Hash;
 use massa_logging::massa_trace;
 use massa_models::{
+    api::ConnectedPeer,
     block::{BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::{OperationPrefixIds, WrappedOperation},
     stats::NetworkStats,
 };
@@ -35,6 +36,7 @@ use massa_network_exports::{
     AskForBlocksInfo, BlockInfoReply, BootstrapPeers, ConnectionClosureReason, ConnectionId,
     NetworkError, NodeCommand, Peer, Peers,
 };
+use massa_time::MassaTime;
 use std::{
     collections::{HashMap, HashSet},
     net::IpAddr,
@@ -68,9 +70,14 @@ async fn ban_connection_ids(worker: &mut NetworkWorker, ids: HashSet<ConnectionI
 
 /// Ban the connections corresponding to `ips` from the `worker`
 /// See also `ban_connection_ids`
-async fn node_ban_by_ips(worker: &mut NetworkWorker, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
+async fn node_ban_by_ips(
+    worker: &mut NetworkWorker,
+    ips: Vec<IpAddr>,
+    duration: Option<MassaTime>,
+    reason: BanReason,
+) -> Result<(), NetworkError> {
     for ip in ips.iter() {
-        worker.peer_info_db.peer_banned(ip)?;
+        worker.peer_info_db.peer_banned(ip, duration, reason)?;
     }
     let connexion_ids = worker
         .active_connections
@@ -90,11 +97,16 @@ async fn node_ban_by_ips(worker: &mut NetworkWorker, ips: Vec<IpAddr>) -> Result
 
 /// Ban the connections corresponding to node `ids` from the `worker`
 /// See also `ban_connection_ids`
-async fn node_ban_by_ids(worker: &mut NetworkWorker, ids: Vec<NodeId>) -> Result<(), NetworkError> {
+async fn node_ban_by_ids(
+    worker: &mut NetworkWorker,
+    ids: Vec<NodeId>,
+    duration: Option<MassaTime>,
+    reason: BanReason,
+) -> Result<(), NetworkError> {
     // get all connection IDs to ban
     let connection_ids_to_ban = ids
         .iter()
-        .map(|id| get_connection_ids(worker, id))
+        .map(|id| get_connection_ids(worker, id, duration, reason))
         .filter(|res| res.is_ok())
         .flat_map(|res| res.unwrap())
         .collect::<HashSet<_>>();
@@ -153,23 +165,27 @@ async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peer
 pub async fn on_node_ban_by_ips_cmd(
     worker: &mut NetworkWorker,
     ips: Vec<IpAddr>,
+    duration: Option<MassaTime>,
+    reason: BanReason,
 ) -> Result<(), NetworkError> {
     massa_trace!(
         "network_worker.manage_network_command receive NetworkCommand::NodeBanByIps",
-        { "ips": ips }
+        { "ips": ips, "duration": duration, "reason": reason }
     );
-    node_ban_by_ips(worker, ips).await
+    node_ban_by_ips(worker, ips, duration, reason).await
 }
 
 pub async fn on_node_ban_by_ids_cmd(
     worker: &mut NetworkWorker,
     ids: Vec<NodeId>,
+    duration: Option<MassaTime>,
+    reason: BanReason,
 ) -> Result<(), NetworkError> {
     massa_trace!(
         "network_worker.manage_network_command receive NetworkCommand::NodeBanByIds",
-        { "ids": ids }
+        { "ids": ids, "duration": duration, "reason": reason }
     );
-    node_ban_by_ids(worker, ids).await
+    node_ban_by_ids(worker, ids, duration, reason).await
 }
 
 pub async fn on_send_block_header_cmd(
@@ -328,6 +344,16 @@ pub async fn on_get_stats_cmd(
     worker: &mut NetworkWorker,
     response_tx: oneshot::Sender<NetworkStats>,
 ) {
+    let (bytes_in, bytes_out) = worker.connection_stats.values().fold(
+        worker.closed_connection_bytes,
+        |(bytes_in, bytes_out), stats| {
+            let traffic = stats.lock().total_traffic();
+            (
+                bytes_in.saturating_add(traffic.bytes_in),
+                bytes_out.saturating_add(traffic.bytes_out),
+            )
+        },
+    );
     let res = NetworkStats {
         in_connection_count: worker.peer_info_db.get_in_connection_count(),
         out_connection_count: worker.peer_info_db.get_out_connection_count(),
@@ -339,12 +365,61 @@ pub async fn on_get_stats_cmd(
             .filter(|(_, p)| p.banned)
             .fold(0, |acc, _| acc + 1),
         active_node_count: worker.active_nodes.len() as u64,
+        subnet_rejected_in_connection_count: worker
+            .peer_info_db
+            .subnet_rejected_in_connection_count,
+        subnet_rejected_out_connection_count: worker
+            .peer_info_db
+            .subnet_rejected_out_connection_count,
+        bytes_in,
+        bytes_out,
     };
     if response_tx.send(res).is_err() {
         warn!("network: could not send NodeSignMessage response upstream");
     }
 }
 
+pub async fn on_get_connected_peers_cmd(
+    worker: &mut NetworkWorker,
+    response_tx: oneshot::Sender<Vec<ConnectedPeer>>,
+) {
+    let res = worker
+        .active_nodes
+        .iter()
+        .filter_map(|(node_id, (conn_id, _))| {
+            let (ip, is_outgoing) = worker.active_connections.get(conn_id)?;
+            let stats = worker.connection_stats.get(node_id)?;
+            Some(stats.lock().to_connected_peer(*node_id, *ip, *is_outgoing))
+        })
+        .collect();
+    if response_tx.send(res).is_err() {
+        warn!("network: could not send GetConnectedPeers response upstream");
+    }
+}
+
+/// Close the connections to the given nodes without banning them.
+/// Nothing prevents the nodes from connecting again later.
+pub async fn on_node_disconnect_by_ids_cmd(worker: &mut NetworkWorker, ids: Vec<NodeId>) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::NodeDisconnectByIds",
+        { "ids": ids }
+    );
+    for id in ids.iter() {
+        if let Some((_, node_command_tx)) = worker.active_nodes.get(id) {
+            let res = node_command_tx
+                .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+                .await;
+            if res.is_err() {
+                massa_trace!(
+                    "network.network_worker.manage_network_command", {"err": NetworkError::ChannelError(
+                        "close node command send failed".into(),
+                    ).to_string()}
+                );
+            }
+        }
+    }
+}
+
 /// Network worker received the command `NetworkCommand::SendOperations` from
 /// the controller. Happen when the program has received a new set of operation
 /// or run a kind of "send operations" loop.
@@ -426,11 +501,13 @@ pub async fn on_ask_for_operations_cmd(
 fn get_connection_ids(
     worker: &mut NetworkWorker,
     node: &NodeId,
+    duration: Option<MassaTime>,
+    reason: BanReason,
 ) -> Result<HashSet<ConnectionId>, NetworkError> {
     let mut ids: HashSet<ConnectionId> = HashSet::new();
     if let Some((orig_conn_id, _)) = worker.active_nodes.get(node) {
         if let Some((orig_ip, _)) = worker.active_connections.get(orig_conn_id) {
-            worker.peer_info_db.peer_banned(orig_ip)?;
+            worker.peer_info_db.peer_banned(orig_ip, duration, reason)?;
             for (target_conn_id, (target_ip, _)) in worker.active_connections.iter() {
                 if target_ip == orig_ip {
                     ids.insert(*target_conn_id);
@@ -96,6 +96,15 @@ pub mod event_impl {
         };
     }
 
+    /// Credits the peer behind `from` with the blocks and operations it delivered
+    fn credit_delivery(worker: &mut NetworkWorker, from: &NodeId, blocks: u64, operations: u64) {
+        if let Some((conn_id, _)) = worker.active_nodes.get(from) {
+            if let Some((ip, _)) = worker.active_connections.get(conn_id) {
+                worker.peer_info_db.peer_delivered(ip, blocks, operations);
+            }
+        }
+    }
+
     // Implementation of the node event management functions
     pub fn on_received_peer_list(
         worker: &mut NetworkWorker,
@@ -134,6 +143,7 @@ pub mod event_impl {
             "network_worker.on_node_event receive NetworkEvent::ReceivedBlockHeader",
             {"hash": header.id.get_hash(), "header": header, "node": from}
         );
+        credit_delivery(worker, &from, 1, 0);
         if let Err(err) = worker
             .event
             .send(NetworkEvent::ReceivedBlockHeader {
@@ -201,6 +211,7 @@ pub mod event_impl {
             "network_worker.on_node_event receive NetworkEvent::ReceivedOperations",
             { "operations": operations }
         );
+        credit_delivery(worker, &from, 0, operations.len() as u64);
         if let Err(err) = worker
             .event
             .send(NetworkEvent::ReceivedOperations {
@@ -6,6 +6,7 @@ use super::{
 };
 use crate::{
     binders::{ReadBinder, WriteBinder},
+    connection_stats::{ConnectionStats, SharedConnectionStats},
     handshake_worker::HandshakeWorker,
     messages::{Message, MessageDeserializer},
     network_event::EventSender,
@@ -55,6 +56,10 @@ pub struct NetworkWorker {
     node_event_rx: mpsc::Receiver<NodeEvent>,
     /// Ids of active nodes mapped to Connection id, node command sender and handle on the associated node worker.
     pub(crate) active_nodes: HashMap<NodeId, (ConnectionId, mpsc::Sender<NodeCommand>)>,
+    /// Statistics about the connections of active nodes.
+    pub(crate) connection_stats: HashMap<NodeId, SharedConnectionStats>,
+    /// Bytes received from and sent to nodes whose connection is closed.
+    pub(crate) closed_connection_bytes: (u64, u64),
     /// Node worker handles
     node_worker_handles:
         FuturesUnordered<JoinHandle<(NodeId, Result<ConnectionClosureReason, NetworkError>)>>,
@@ -117,6 +122,8 @@ impl NetworkWorker {
             handshake_peer_list_futures: FuturesUnordered::new(),
             node_event_rx,
             active_nodes: HashMap::new(),
+            connection_stats: HashMap::new(),
+            closed_connection_bytes: (0, 0),
             node_worker_handles: FuturesUnordered::new(),
             active_connections: HashMap::new(),
             version,
@@ -140,7 +147,19 @@ impl NetworkWorker {
                 for ip in candidate_ips {
                     debug!("starting outgoing connection attempt towards ip={}", ip);
                     massa_trace!("out_connection_attempt_start", { "ip": ip });
-                    self.peer_info_db.new_out_connection_attempt(&ip)?;
+                    match self.peer_info_db.new_out_connection_attempt(&ip) {
+                        Ok(()) => {}
+                        Err(NetworkError::PeerConnectionError(
+                            NetworkConnectionErrorType::MaxSubnetConnectionsReached(_),
+                        )) => {
+                            debug!(
+                                "outgoing connection attempt towards ip={} refused: subnet limit reached",
+                                ip
+                            );
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    }
                     let mut connector = self
                         .establisher
                         .get_connector(self.cfg.connect_timeout)
@@ -232,6 +251,13 @@ impl NetworkWorker {
                     let _ = self
                         .event.send(NetworkEvent::ConnectionClosed(node_id))
                         .await;
+                    if let Some(stats) = self.connection_stats.remove(&node_id) {
+                        let traffic = stats.lock().total_traffic();
+                        self.closed_connection_bytes.0 =
+                            self.closed_connection_bytes.0.saturating_add(traffic.bytes_in);
+                        self.closed_connection_bytes.1 =
+                            self.closed_connection_bytes.1.saturating_add(traffic.bytes_out);
+                    }
                     if let Some((connection_id, _)) = self
                         .active_nodes
                         .remove(&node_id) {
@@ -327,7 +353,7 @@ impl NetworkWorker {
         });
         match outcome {
             // a handshake finished, and succeeded
-            Ok((new_node_id, socket_reader, socket_writer)) => {
+            Ok((new_node_id, mut socket_reader, mut socket_writer, latency, version)) => {
                 debug!(
                     "handshake with connection_id={} succeeded => node_id={}",
                     new_connection_id, new_node_id
@@ -381,6 +407,13 @@ impl NetworkWorker {
                                     NetworkError::ActiveConnectionMissing(new_connection_id)
                                 })?;
                         self.peer_info_db.peer_alive(ip)?;
+                        self.peer_info_db.peer_handshake_latency(ip, latency)?;
+
+                        // record the traffic of the connection
+                        let stats = ConnectionStats::new_shared(version);
+                        socket_reader.set_stats(stats.clone());
+                        socket_writer.set_stats(stats.clone());
+                        self.connection_stats.insert(new_node_id, stats);
 
                         // spawn node_controller_fn
                         let (node_command_tx, node_command_rx) =
@@ -511,8 +544,16 @@ impl NetworkWorker {
     async fn manage_network_command(&mut self, cmd: NetworkCommand) -> Result<(), NetworkError> {
         use crate::network_cmd_impl::*;
         match cmd {
-            NetworkCommand::NodeBanByIps(ips) => on_node_ban_by_ips_cmd(self, ips).await?,
-            NetworkCommand::NodeBanByIds(ids) => on_node_ban_by_ids_cmd(self, ids).await?,
+            NetworkCommand::NodeBanByIps {
+                ips,
+                duration,
+                reason,
+            } => on_node_ban_by_ips_cmd(self, ips, duration, reason).await?,
+            NetworkCommand::NodeBanByIds {
+                ids,
+                duration,
+                reason,
+            } => on_node_ban_by_ids_cmd(self, ids, duration, reason).await?,
             NetworkCommand::SendBlockHeader { node, header } => {
                 on_send_block_header_cmd(self, node, header).await?
             }
@@ -542,6 +583,12 @@ impl NetworkWorker {
             NetworkCommand::NodeUnbanByIds(ids) => on_node_unban_by_ids_cmd(self, ids).await?,
             NetworkCommand::NodeUnbanByIps(ips) => on_node_unban_by_ips_cmd(self, ips).await?,
             NetworkCommand::GetStats { response_tx } => on_get_stats_cmd(self, response_tx).await,
+            NetworkCommand::GetConnectedPeers { response_tx } => {
+                on_get_connected_peers_cmd(self, response_tx).await
+            }
+            NetworkCommand::NodeDisconnectByIds(ids) => {
+                on_node_disconnect_by_ids_cmd(self, ids).await
+            }
             NetworkCommand::Whitelist(ips) => on_whitelist_cmd(self, ips).await?,
             NetworkCommand::RemoveFromWhitelist(ips) => {
                 on_remove_from_whitelist_cmd(self, ips).await?
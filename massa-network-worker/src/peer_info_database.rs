@@ -3,6 +3,8 @@
 use enum_map::EnumMap;
 use itertools::Itertools;
 use massa_logging::massa_trace;
+use massa_models::node::BanReason;
+use massa_network_exports::ip_subnet;
 use massa_network_exports::settings::PeerTypeConnectionConfig;
 use massa_network_exports::ConnectionCount;
 use massa_network_exports::NetworkConfig;
@@ -36,6 +38,23 @@ pub struct PeerInfoDatabase {
     pub(crate) wakeup_interval: MassaTime,
     /// Clock compensation.
     pub(crate) clock_compensation: i64,
+    /// Last time connected peers were credited with uptime
+    pub(crate) last_uptime_update: MassaTime,
+    /// Number of in connections refused because of the per subnet limit
+    pub(crate) subnet_rejected_in_connection_count: u64,
+    /// Number of out connection attempts refused because of the per subnet limit
+    pub(crate) subnet_rejected_out_connection_count: u64,
+    /// Connections count with standard peers for each subnet (see `ip_subnet`), subnets without connections being absent
+    pub(crate) subnet_connection_count: HashMap<IpAddr, SubnetConnectionCount>,
+}
+
+/// Connections count with the standard peers of a subnet
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SubnetConnectionCount {
+    /// out connections and attempts
+    pub(crate) out_connections: usize,
+    /// in and out connections and attempts
+    pub(crate) connections: usize,
 }
 
 /// Saves advertised and non standard peers to a file.
@@ -54,10 +73,13 @@ async fn dump_peers(
             json!({
                 "ip": peer.ip,
                 "banned": peer.banned,
+                "ban_reason": peer.ban_reason,
+                "ban_expiry": peer.ban_expiry,
                 "peer_type": peer.peer_type,
                 "last_alive": peer.last_alive,
                 "last_failure": peer.last_failure,
                 "advertised": peer.advertised,
+                "reputation": peer.reputation,
             })
         })
         .collect();
@@ -78,7 +100,7 @@ async fn dump_peers(
 /// * `peers`: peers to clean up
 /// * `opt_new_peers`: optional peers to add to the database
 /// * `clock_compensation`: to be sync with server time
-/// * `ban_timeout`: after that time we forget we banned a peer
+/// * `ban_timeout`: after that time we forget we banned a peer, unless its ban has not expired yet
 pub(crate) fn cleanup_peers(
     cfg: &NetworkConfig,
     peers: &mut HashMap<IpAddr, PeerInfo>,
@@ -118,10 +140,12 @@ pub(crate) fn cleanup_peers(
     };
 
     // split between peers that need to be kept (keep_peers),
-    // inactive banned peers (banned_peers)
+    // inactive peers banned manually until they are unbanned (manually_banned_peers),
+    // other inactive banned peers (banned_peers)
     // and other inactive but advertised peers (idle_peers)
     // drop other peers (inactive non-advertised, non-keep)
     let mut keep_peers: Vec<PeerInfo> = Vec::new();
+    let mut manually_banned_peers: Vec<PeerInfo> = Vec::new();
     let mut banned_peers: Vec<PeerInfo> = Vec::new();
     let mut idle_peers: Vec<PeerInfo> = Vec::new();
     for (ip, p) in peers.drain() {
@@ -137,6 +161,9 @@ pub(crate) fn cleanup_peers(
         }
         if p.peer_type != Default::default() || p.is_active() {
             keep_peers.push(p);
+        } else if p.banned && p.ban_reason == Some(BanReason::Manual) && p.ban_expiry.is_none() {
+            // neither `ban_timeout` nor `max_banned_peers` apply to them
+            manually_banned_peers.push(p);
         } else if p.banned {
             banned_peers.push(p);
         } else if p.advertised {
@@ -153,14 +180,19 @@ pub(crate) fn cleanup_peers(
     idle_peers.truncate(cfg.max_idle_peers);
 
     // sort and truncate inactive banned peers
-    // forget about old banned peers
-    let ban_limit = MassaTime::now(clock_compensation)?.saturating_sub(ban_timeout);
-    banned_peers.retain(|p| p.last_failure.map_or(false, |v| v >= ban_limit));
+    // forget about old banned peers, keeping the ones with an ongoing timed ban
+    let now = MassaTime::now(clock_compensation)?;
+    let ban_limit = now.saturating_sub(ban_timeout);
+    banned_peers.retain(|p| {
+        p.ban_expiry.map_or(false, |expiry| expiry > now)
+            || p.last_failure.map_or(false, |v| v >= ban_limit)
+    });
     banned_peers.sort_unstable_by_key(|&p| (std::cmp::Reverse(p.last_failure), p.last_alive));
     banned_peers.truncate(cfg.max_banned_peers);
 
     // gather everything back
     peers.extend(keep_peers.into_iter().map(|p| (p.ip, p)));
+    peers.extend(manually_banned_peers.into_iter().map(|p| (p.ip, p)));
     peers.extend(banned_peers.into_iter().map(|p| (p.ip, p)));
     peers.extend(idle_peers.into_iter().map(|p| (p.ip, p)));
     Ok(())
@@ -244,6 +276,10 @@ impl PeerInfoDatabase {
             wakeup_interval,
             clock_compensation,
             peer_types_connection_count: EnumMap::default(),
+            last_uptime_update: MassaTime::now(clock_compensation)?,
+            subnet_rejected_in_connection_count: 0,
+            subnet_rejected_out_connection_count: 0,
+            subnet_connection_count: HashMap::new(),
         })
     }
 
@@ -291,8 +327,30 @@ impl PeerInfoDatabase {
     ///////////////////////
 
     /// Refreshes the peer list. Should be called at regular intervals.
-    /// Performs multiple cleanup tasks e.g. remove old banned peers
+    /// Performs multiple cleanup tasks e.g. lift expired bans, remove old banned peers
+    /// and credits connected peers with the uptime elapsed since the last call.
     pub fn update(&mut self) -> Result<(), NetworkError> {
+        let now = MassaTime::now(self.clock_compensation)?;
+        let mut bans_lifted = false;
+        for peer in self
+            .peers
+            .values_mut()
+            .filter(|p| p.banned && p.ban_expiry.map_or(false, |expiry| expiry <= now))
+        {
+            massa_trace!("peer_ban_expired", { "ip": peer.ip, "reason": peer.ban_reason });
+            peer.lift_ban();
+            bans_lifted = true;
+        }
+
+        let elapsed = now.saturating_sub(self.last_uptime_update);
+        self.last_uptime_update = now;
+        for peer in self
+            .peers
+            .values_mut()
+            .filter(|p| p.active_out_connections > 0 || p.active_in_connections > 0)
+        {
+            peer.reputation.uptime = peer.reputation.uptime.saturating_add(elapsed);
+        }
         cleanup_peers(
             &self.network_settings,
             &mut self.peers,
@@ -300,6 +358,9 @@ impl PeerInfoDatabase {
             self.clock_compensation,
             self.network_settings.ban_timeout,
         )?;
+        if bans_lifted {
+            self.request_dump()?;
+        }
         Ok(())
     }
 
@@ -344,7 +405,7 @@ impl PeerInfoDatabase {
             let ip = ip.to_canonical();
             if let Some(peer) = self.peers.get_mut(&ip) {
                 update_happened = update_happened || peer.banned;
-                peer.banned = false;
+                peer.lift_ban();
             }
         }
         self.update()?;
@@ -357,6 +418,7 @@ impl PeerInfoDatabase {
     pub async fn whitelist(&mut self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
         for ip in ips.into_iter() {
             let ip = ip.to_canonical();
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let old_pt = if let Some(peer) = self.peers.get_mut(&ip) {
                 let pt = peer.peer_type;
                 if pt == PeerType::WhiteListed {
@@ -370,6 +432,7 @@ impl PeerInfoDatabase {
                 self.peers.insert(ip, p);
                 continue;
             };
+            self.update_subnet_connection_count(&ip, subnet_contribution);
             // update global connection counts by peer type
             let peer = *self.peers.get(&ip).unwrap(); // filled just before
             if peer.active_out_connection_attempts > 0 {
@@ -394,6 +457,7 @@ impl PeerInfoDatabase {
     pub async fn remove_from_whitelist(&mut self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
         for ip in ips.into_iter() {
             let ip = ip.to_canonical();
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let old_pt = if let Some(peer) = self.peers.get_mut(&ip) {
                 let old = peer.peer_type;
                 peer.peer_type = Default::default();
@@ -401,6 +465,7 @@ impl PeerInfoDatabase {
             } else {
                 return Ok(());
             };
+            self.update_subnet_connection_count(&ip, subnet_contribution);
 
             if old_pt != Default::default() {
                 // update global connection counts by peer type
@@ -435,6 +500,15 @@ impl PeerInfoDatabase {
         if !ip.is_global() {
             return Err(NetworkError::InvalidIpError(ip));
         }
+        if self.get_peer_type(&ip).unwrap_or_default() == PeerType::Standard
+            && self.is_subnet_connection_limit_reached(&ip)
+        {
+            self.subnet_rejected_out_connection_count += 1;
+            return Err(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::MaxSubnetConnectionsReached(ip),
+            ));
+        }
+        let subnet_contribution = self.get_subnet_contribution(&ip);
         let peer_type = if let Some(peer) = self.peers.get(&ip) {
             if self.can_try_new_out_connection(peer.peer_type) {
                 // Can unwrap because we checked above that there is a peer.
@@ -456,6 +530,7 @@ impl PeerInfoDatabase {
                 NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
             ))
         }?;
+        self.update_subnet_connection_count(&ip, subnet_contribution);
         self.increase_global_active_out_connection_attempt_count(peer_type, &ip)?;
         self.update()
     }
@@ -478,6 +553,47 @@ impl PeerInfoDatabase {
         self.request_dump()
     }
 
+    /// Records the duration of a successful handshake with the peer.
+    ///
+    /// # Arguments
+    /// * ip : ip address of the considered peer.
+    /// * latency : time taken by the handshake.
+    pub fn peer_handshake_latency(
+        &mut self,
+        ip: &IpAddr,
+        latency: MassaTime,
+    ) -> Result<(), NetworkError> {
+        let ip = ip.to_canonical();
+        self.peers
+            .get_mut(&ip)
+            .ok_or_else(|| {
+                NetworkError::PeerConnectionError(
+                    NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
+                )
+            })?
+            .reputation
+            .record_latency(latency);
+        Ok(())
+    }
+
+    /// Credits the peer with useful blocks and operations it sent us.
+    /// Unknown peers are ignored.
+    ///
+    /// # Arguments
+    /// * ip : ip address of the considered peer.
+    /// * blocks : number of block headers received.
+    /// * operations : number of full operations received.
+    pub fn peer_delivered(&mut self, ip: &IpAddr, blocks: u64, operations: u64) {
+        if let Some(peer) = self.peers.get_mut(&ip.to_canonical()) {
+            peer.reputation.blocks_delivered =
+                peer.reputation.blocks_delivered.saturating_add(blocks);
+            peer.reputation.operations_delivered = peer
+                .reputation
+                .operations_delivered
+                .saturating_add(operations);
+        }
+    }
+
     /// Sets the peer status as failed.
     /// Requests a dump.
     ///
@@ -500,15 +616,34 @@ impl PeerInfoDatabase {
     /// If the peer is not active, the database is cleaned up.
     /// A dump is requested.
     ///
-    /// # Argument
+    /// # Arguments
     /// * ip : ip address of the considered peer.
-    pub fn peer_banned(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
+    /// * duration : ban duration. If None, manual bans last until the peer is unbanned
+    ///   and other bans last `ban_duration`.
+    /// * reason : why the peer is banned.
+    pub fn peer_banned(
+        &mut self,
+        ip: &IpAddr,
+        duration: Option<MassaTime>,
+        reason: BanReason,
+    ) -> Result<(), NetworkError> {
         let ip = ip.to_canonical();
+        let now = MassaTime::now(self.clock_compensation)?;
+        let duration = match (duration, reason) {
+            (Some(duration), _) => Some(duration),
+            (None, BanReason::Manual) => None,
+            (None, _) => Some(self.network_settings.ban_duration),
+        };
         let peer = self
             .peers
             .entry(ip)
             .or_insert_with(|| PeerInfo::new(ip, false));
-        peer.last_failure = Some(MassaTime::now(self.clock_compensation)?);
+        peer.last_failure = Some(now);
+        if reason != BanReason::Manual {
+            peer.reputation.violations = peer.reputation.violations.saturating_add(1);
+        }
+        peer.ban_reason = Some(reason);
+        peer.ban_expiry = duration.map(|duration| now.saturating_add(duration));
         if !peer.banned {
             peer.banned = true;
             if !peer.is_active() {
@@ -540,6 +675,7 @@ impl PeerInfoDatabase {
                     NetworkConnectionErrorType::CloseConnectionWithNoConnectionToClose(ip),
                 ));
             }
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
@@ -547,7 +683,9 @@ impl PeerInfoDatabase {
             })?;
             peer.active_out_connections -= 1;
             let peer_type = peer.peer_type;
-            if !peer.is_active() && peer.peer_type == Default::default() {
+            let inactive = !peer.is_active() && peer.peer_type == Default::default();
+            self.update_subnet_connection_count(&ip, subnet_contribution);
+            if inactive {
                 self.update()?;
                 self.request_dump()?;
             }
@@ -579,6 +717,7 @@ impl PeerInfoDatabase {
                     NetworkConnectionErrorType::CloseConnectionWithNoConnectionToClose(ip),
                 ));
             }
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
@@ -586,7 +725,9 @@ impl PeerInfoDatabase {
             })?;
             peer.active_in_connections -= 1;
             let peer_type = peer.peer_type;
-            if !peer.is_active() && peer.peer_type == PeerType::Standard {
+            let inactive = !peer.is_active() && peer.peer_type == PeerType::Standard;
+            self.update_subnet_connection_count(&ip, subnet_contribution);
+            if inactive {
                 self.update()?;
                 self.request_dump()?;
             }
@@ -634,6 +775,7 @@ impl PeerInfoDatabase {
                     NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
                 ));
             }
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
@@ -644,14 +786,18 @@ impl PeerInfoDatabase {
 
             if peer.banned {
                 peer.last_failure = Some(MassaTime::now(self.clock_compensation)?);
-                if !peer.is_active() && peer.peer_type == Default::default() {
+                let inactive = !peer.is_active() && peer.peer_type == Default::default();
+                self.update_subnet_connection_count(&ip, subnet_contribution);
+                if inactive {
                     self.update()?;
                 }
                 self.request_dump()?;
                 return Ok(false);
             }
             peer.active_out_connections += 1;
-            peer.peer_type
+            let peer_type = peer.peer_type;
+            self.update_subnet_connection_count(&ip, subnet_contribution);
+            peer_type
         };
         self.increase_global_active_out_connection_count(peer_type)?;
         self.request_dump()?;
@@ -679,6 +825,7 @@ impl PeerInfoDatabase {
                     NetworkConnectionErrorType::TooManyConnectionFailure(ip),
                 ));
             }
+            let subnet_contribution = self.get_subnet_contribution(&ip);
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
@@ -687,7 +834,9 @@ impl PeerInfoDatabase {
             peer.active_out_connection_attempts -= 1;
             peer.last_failure = Some(MassaTime::now(self.clock_compensation)?);
             let pt = peer.peer_type;
-            if !peer.is_active() && peer.peer_type == PeerType::Standard {
+            let inactive = !peer.is_active() && peer.peer_type == PeerType::Standard;
+            self.update_subnet_connection_count(&ip, subnet_contribution);
+            if inactive {
                 self.update()?;
             }
             pt
@@ -735,6 +884,16 @@ impl PeerInfoDatabase {
             ));
         }
 
+        // then that the subnet of the peer has not reached its limit
+        if peer_type == PeerType::Standard && self.is_subnet_connection_limit_reached(&ip) {
+            massa_trace!("in_connection_refused_subnet_limit", { "ip": ip });
+            self.subnet_rejected_in_connection_count += 1;
+            return Err(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::MaxSubnetConnectionsReached(ip),
+            ));
+        }
+
+        let subnet_contribution = self.get_subnet_contribution(&ip);
         let peer_type = {
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
@@ -761,6 +920,7 @@ impl PeerInfoDatabase {
             }
             peer.peer_type
         };
+        self.update_subnet_connection_count(&ip, subnet_contribution);
 
         self.increase_global_active_in_connection_count(peer_type)?;
         self.request_dump()?;
@@ -771,8 +931,9 @@ impl PeerInfoDatabase {
     // public getters //
     ////////////////////
 
-    /// Sorts peers by `( last_failure, rev(last_success) )`
+    /// Sorts peers by reputation score, then by `( last_failure, rev(last_success) )`
     /// and returns as many peers as there are available slots to attempt outgoing connections to.
+    /// Standard peers are limited to `max_out_connections_per_subnet` per subnet.
     pub fn get_out_connection_candidate_ips(&self) -> Result<Vec<IpAddr>, NetworkError> {
         let mut connections = vec![];
         let mut peer_types: Vec<PeerType> = self
@@ -859,14 +1020,93 @@ impl PeerInfoDatabase {
             }
             p.is_peer_ready(self.wakeup_interval, now)
         };
-        let mut res: Vec<_> = self
-            .peers
-            .values()
-            .filter(f)
-            .take(available_slots)
-            .collect();
-        res.sort_unstable_by_key(|&p| (p.last_failure, std::cmp::Reverse(p.last_alive)));
-        Ok(res.into_iter().map(|p| p.ip).collect())
+        let mut res: Vec<_> = self.peers.values().filter(f).collect();
+        res.sort_by(|a, b| {
+            b.reputation
+                .score()
+                .total_cmp(&a.reputation.score())
+                .then_with(|| {
+                    (a.last_failure, Reverse(a.last_alive))
+                        .cmp(&(b.last_failure, Reverse(b.last_alive)))
+                })
+        });
+
+        // start from the standard connections we already have in each subnet:
+        // out connections and attempts are capped by `max_out_connections_per_subnet`
+        // so that a single network operator can't take all our out slots,
+        // and all connections by `max_connections_per_subnet`
+        let mut subnet_counts: HashMap<IpAddr, SubnetConnectionCount> = HashMap::new();
+        let mut candidates = Vec::new();
+        for p in res {
+            if candidates.len() >= available_slots {
+                break;
+            }
+            if peer_type == PeerType::Standard {
+                let subnet = ip_subnet(&p.ip);
+                let count = subnet_counts.entry(subnet).or_insert_with(|| {
+                    self.subnet_connection_count
+                        .get(&subnet)
+                        .copied()
+                        .unwrap_or_default()
+                });
+                if count.out_connections >= self.network_settings.max_out_connections_per_subnet
+                    || count.connections >= self.network_settings.max_connections_per_subnet
+                {
+                    continue;
+                }
+                count.out_connections += 1;
+                count.connections += 1;
+            }
+            candidates.push(p.ip);
+        }
+        Ok(candidates)
+    }
+
+    /// Returns true if the in and out connections and attempts with standard peers
+    /// in the subnet of `ip` reached `max_connections_per_subnet`
+    fn is_subnet_connection_limit_reached(&self, ip: &IpAddr) -> bool {
+        self.subnet_connection_count
+            .get(&ip_subnet(ip))
+            .map_or(0, |count| count.connections)
+            >= self.network_settings.max_connections_per_subnet
+    }
+
+    /// Out connections and attempts, and all connections and attempts, that the peer `ip` counts for in its subnet:
+    /// only standard peers are counted
+    fn get_subnet_contribution(&self, ip: &IpAddr) -> SubnetConnectionCount {
+        match self.peers.get(ip) {
+            Some(peer) if peer.peer_type == PeerType::Standard => {
+                let out_connections =
+                    peer.active_out_connection_attempts + peer.active_out_connections;
+                SubnetConnectionCount {
+                    out_connections,
+                    connections: out_connections + peer.active_in_connections,
+                }
+            }
+            _ => Default::default(),
+        }
+    }
+
+    /// Updates the connections count of the subnet of `ip` after a change of the peer,
+    /// `previous_contribution` being the result of `get_subnet_contribution` before the change
+    fn update_subnet_connection_count(
+        &mut self,
+        ip: &IpAddr,
+        previous_contribution: SubnetConnectionCount,
+    ) {
+        let contribution = self.get_subnet_contribution(ip);
+        if contribution == previous_contribution {
+            return;
+        }
+        let subnet = ip_subnet(ip);
+        let count = self.subnet_connection_count.entry(subnet).or_default();
+        count.out_connections = (count.out_connections + contribution.out_connections)
+            .saturating_sub(previous_contribution.out_connections);
+        count.connections = (count.connections + contribution.connections)
+            .saturating_sub(previous_contribution.connections);
+        if count.connections == 0 {
+            self.subnet_connection_count.remove(&subnet);
+        }
     }
 
     fn get_peer_type(&self, ip: &IpAddr) -> Option<PeerType> {
@@ -24,8 +24,9 @@ use massa_models::config::{
 use massa_models::{
     block::BlockId,
     endorsement::{Endorsement, EndorsementSerializer},
-    node::NodeId,
+    node::{BanReason, NodeId},
     slot::Slot,
+    version::Version,
     wrapped::WrappedContent,
 };
 use massa_network_exports::{settings::PeerTypeConnectionConfig, NodeCommand, NodeEvent};
@@ -37,6 +38,7 @@ use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use serial_test::serial;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     time::{Duration, Instant},
@@ -399,7 +401,7 @@ async fn test_peer_ban() {
 
             // ban connection1.
             network_command_sender
-                .node_ban_by_ids(vec![conn1_id])
+                .node_ban_by_ids(vec![conn1_id], None, BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -534,7 +536,7 @@ async fn test_peer_ban_by_ip() {
 
             // ban connection1.
             network_command_sender
-                .node_ban_by_ips(vec![mock_addr.ip()])
+                .node_ban_by_ips(vec![mock_addr.ip()], None, BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -632,6 +634,9 @@ async fn test_advertised_and_wakeup_interval() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }]);
     let network_conf = NetworkConfig {
         wakeup_interval: MassaTime::from_millis(500),
@@ -767,6 +772,9 @@ async fn test_block_not_found() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -949,6 +957,9 @@ async fn test_retry_connection_closed() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -975,7 +986,7 @@ async fn test_retry_connection_closed() {
 
             // Ban the node.
             network_command_sender
-                .node_ban_by_ids(vec![node_id])
+                .node_ban_by_ids(vec![node_id], None, BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -1048,6 +1059,9 @@ async fn test_operation_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1163,6 +1177,9 @@ async fn test_endorsements_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1273,3 +1290,109 @@ async fn test_endorsements_messages() {
     )
     .await;
 }
+
+// connect a peer, check that the traffic it generates is reported in the connected peers,
+// then disconnect it without banning it
+#[tokio::test]
+#[serial]
+async fn test_connected_peers_and_disconnect() {
+    // test config
+    let bind_port: u16 = 50_000;
+
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+    // add advertised peer to controller
+    let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo::new(mock_addr.ip(), true)]);
+    let network_conf = NetworkConfig::scenarios_default(bind_port, temp_peers_file.path());
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            // accept connection from controller to peer
+            let (conn1_id, conn1_r, mut conn1_w) = tools::full_connection_from_controller(
+                &mut network_event_receiver,
+                &mut mock_interface,
+                mock_addr,
+                1_000u64,
+                1_000u64,
+                1_000u64,
+                ConnectionId(0),
+            )
+            .await;
+            let conn1_drain = tools::incoming_message_drain_start(conn1_r).await;
+
+            // send an operation from the connected peer
+            conn1_w
+                .send(&Message::Operations(vec![get_transaction(50, 10)]))
+                .await
+                .unwrap();
+            if tools::wait_network_event(
+                &mut network_event_receiver,
+                1000.into(),
+                |msg| match msg {
+                    NetworkEvent::ReceivedOperations { node, .. } if node == conn1_id => Some(()),
+                    _ => None,
+                },
+            )
+            .await
+            .is_none()
+            {
+                panic!("Timeout while waiting for received operations event");
+            }
+
+            // the connection and its traffic are reported
+            let peers = network_command_sender.get_connected_peers().await.unwrap();
+            assert_eq!(peers.len(), 1);
+            assert_eq!(peers[0].node_id, conn1_id);
+            assert_eq!(peers[0].ip, mock_addr.ip());
+            assert!(peers[0].is_outgoing);
+            assert_eq!(peers[0].version, Version::from_str("TEST.1.10").unwrap());
+            assert!(peers[0].last_activity.is_some());
+            let operations_traffic = peers[0]
+                .traffic
+                .get("Operations")
+                .expect("operations traffic not reported");
+            assert_eq!(operations_traffic.messages_in, 1);
+            assert!(operations_traffic.bytes_in > 0);
+
+            // disconnect the peer
+            network_command_sender
+                .node_disconnect_by_ids(vec![conn1_id])
+                .await
+                .unwrap();
+            if let Some(node) =
+                tools::wait_network_event(&mut network_event_receiver, 1000.into(), |msg| match msg
+                {
+                    NetworkEvent::ConnectionClosed(node) => Some(node),
+                    _ => None,
+                })
+                .await
+            {
+                assert_eq!(node, conn1_id);
+            } else {
+                panic!("Timeout while waiting for connection closed event");
+            }
+            assert!(network_command_sender
+                .get_connected_peers()
+                .await
+                .unwrap()
+                .is_empty());
+
+            // the peer was not banned
+            let peers = network_command_sender.get_peers().await.unwrap();
+            assert!(!peers.peers[&mock_addr.ip()].peer_info.banned);
+
+            drop(conn1_w);
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![conn1_drain],
+            )
+        },
+    )
+    .await;
+}
@@ -3,6 +3,7 @@ use crate::{
     NetworkConfig, NetworkError,
 };
 use enum_map::enum_map;
+use massa_models::node::BanReason;
 use massa_network_exports::{
     settings::PeerTypeConnectionConfig, NetworkConnectionErrorType, PeerInfo, PeerType,
 };
@@ -55,6 +56,9 @@ async fn test_try_new_in_connection_in_connection_closed() {
         saver_watch_tx,
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
         peer_types_connection_count: Default::default(),
     };
 
@@ -159,6 +163,10 @@ async fn test_out_connection_attempt_failed() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     // test with no connection attempt before
@@ -270,6 +278,10 @@ async fn test_try_out_connection_attempt_success() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     // test with no connection attempt before
@@ -362,6 +374,10 @@ async fn test_new_out_connection_closed() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     //
@@ -443,6 +459,10 @@ async fn test_new_out_connection_attempt() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     // test with no peers.
@@ -530,6 +550,10 @@ async fn test_get_advertisable_peer_ips() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     // test with no peers.
@@ -652,6 +676,10 @@ async fn test_get_out_connection_candidate_ips() {
         peer_types_connection_count: Default::default(),
         wakeup_interval,
         clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
     };
 
     // test with no peers.
@@ -680,6 +708,199 @@ async fn test_get_out_connection_candidate_ips() {
     );
 }
 
+#[tokio::test]
+#[serial]
+async fn test_out_connection_candidates_reputation_and_subnet_quota() {
+    let network_settings = NetworkConfig {
+        max_out_connections_per_subnet: 2,
+        ..Default::default()
+    };
+    let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+
+    // three peers of the same /24, ranked by reputation
+    let mut good_peer =
+        default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
+    good_peer.reputation.blocks_delivered = 100;
+    peers.insert(good_peer.ip, good_peer);
+    let mut average_peer =
+        default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)));
+    average_peer.reputation.operations_delivered = 100;
+    peers.insert(average_peer.ip, average_peer);
+    let mut slow_peer =
+        default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)));
+    slow_peer
+        .reputation
+        .record_latency(MassaTime::from_millis(500));
+    peers.insert(slow_peer.ip, slow_peer);
+
+    // a peer from another /24 that misbehaved
+    let mut bad_peer =
+        default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 203, 0, 11)));
+    bad_peer.reputation.violations = 1;
+    peers.insert(bad_peer.ip, bad_peer);
+
+    let wakeup_interval = network_settings.wakeup_interval;
+    let (saver_watch_tx, _) = watch::channel(peers.clone());
+    let saver_join_handle = tokio::spawn(async move {});
+
+    let db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
+    };
+
+    // best peers first, the slow one is dropped by the subnet quota
+    let ip_list = db.get_out_connection_candidate_ips().unwrap();
+    assert_eq!(
+        ip_list,
+        vec![
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)),
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)),
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 203, 0, 11)),
+        ]
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subnet_connection_limit() {
+    let network_settings = NetworkConfig {
+        max_connections_per_subnet: 2,
+        ..Default::default()
+    };
+    let peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+    let wakeup_interval = network_settings.wakeup_interval;
+    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
+    let saver_join_handle =
+        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
+
+    let mut db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
+    };
+
+    // fill the subnet with one in connection and one out connection attempt
+    db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)))
+        .unwrap();
+    db.new_out_connection_attempt(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)))
+        .unwrap();
+
+    // both directions are refused for that subnet
+    let res = db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)));
+    assert!(matches!(
+        res,
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::MaxSubnetConnectionsReached(_)
+        ))
+    ));
+    let res = db.new_out_connection_attempt(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 14)));
+    assert!(matches!(
+        res,
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::MaxSubnetConnectionsReached(_)
+        ))
+    ));
+    assert_eq!(db.subnet_rejected_in_connection_count, 1);
+    assert_eq!(db.subnet_rejected_out_connection_count, 1);
+
+    // an IPv4-mapped IPv6 address belongs to the subnet of its IPv4 address
+    let res = db.try_new_in_connection(&IpAddr::V6(
+        std::net::Ipv4Addr::new(169, 202, 0, 15).to_ipv6_mapped(),
+    ));
+    assert!(matches!(
+        res,
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::MaxSubnetConnectionsReached(_)
+        ))
+    ));
+
+    // closing a connection frees a slot of the subnet
+    db.in_connection_closed(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)))
+        .unwrap();
+    db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)))
+        .unwrap();
+    assert_eq!(
+        db.subnet_connection_count
+            .get(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 0)))
+            .map(|count| count.connections),
+        Some(2)
+    );
+
+    // other subnets are not affected
+    db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 203, 0, 11)))
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_ban_expiry() {
+    let network_settings = NetworkConfig::default();
+    let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+    let timed_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    let manual_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12));
+    peers.insert(timed_ip, default_peer_info_not_connected(timed_ip));
+    peers.insert(manual_ip, default_peer_info_not_connected(manual_ip));
+
+    let wakeup_interval = network_settings.wakeup_interval;
+    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
+    let saver_join_handle =
+        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
+
+    let mut db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+        last_uptime_update: MassaTime::now(0).unwrap(),
+        subnet_rejected_in_connection_count: 0,
+        subnet_rejected_out_connection_count: 0,
+        subnet_connection_count: Default::default(),
+    };
+
+    db.peer_banned(
+        &timed_ip,
+        Some(MassaTime::from_millis(0)),
+        BanReason::InvalidHeader,
+    )
+    .unwrap();
+    db.peer_banned(&manual_ip, None, BanReason::Manual).unwrap();
+    let peer = db.peers.get(&timed_ip).unwrap();
+    assert!(peer.banned);
+    assert_eq!(peer.ban_reason, Some(BanReason::InvalidHeader));
+    assert_eq!(peer.reputation.violations, 1);
+    let peer = db.peers.get(&manual_ip).unwrap();
+    assert!(peer.banned);
+    assert_eq!(peer.ban_expiry, None);
+    assert_eq!(peer.reputation.violations, 0);
+
+    // the timed ban expired, the manual one lasts until unbanned
+    db.update().unwrap();
+    let peer = db.peers.get(&timed_ip).unwrap();
+    assert!(!peer.banned);
+    assert_eq!(peer.ban_reason, None);
+    assert!(db.peers.get(&manual_ip).unwrap().banned);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_cleanup_peers() {
@@ -739,6 +960,13 @@ async fn test_cleanup_peers() {
     banned_host3.last_alive = Some(now.checked_sub(900.into()).unwrap());
     banned_host3.last_failure = Some(now.checked_sub(2000.into()).unwrap());
 
+    // banned manually a long time ago, until unbanned
+    let mut manually_banned_host =
+        default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 26)));
+    manually_banned_host.banned = true;
+    manually_banned_host.ban_reason = Some(BanReason::Manual);
+    manually_banned_host.last_failure = Some(MassaTime::from_millis(0));
+
     let mut advertised_host1 =
         default_peer_info_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 35)));
 
@@ -763,6 +991,7 @@ async fn test_cleanup_peers() {
     peers.insert(same_host.ip, same_host);
     peers.insert(banned_host3.ip, banned_host3);
     peers.insert(banned_host2.ip, banned_host2);
+    peers.insert(manually_banned_host.ip, manually_banned_host);
 
     cleanup_peers(
         &network_settings,
@@ -779,6 +1008,7 @@ async fn test_cleanup_peers() {
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 23))));
     assert!(!peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 24))));
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 25))));
+    assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 26))));
 
     assert!(!peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 35))));
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 36))));
@@ -825,6 +1055,9 @@ fn default_peer_info_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 1,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }
 }
 
@@ -839,6 +1072,9 @@ fn default_peer_info_not_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        ban_expiry: None,
+        reputation: Default::default(),
     }
 }
 
@@ -879,6 +1115,9 @@ impl From<u32> for PeerInfoDatabase {
                 active_out_connections: 0,
                 active_in_connections: 0,
                 banned: ip[1] % 5 == 0,
+                ban_reason: None,
+                ban_expiry: None,
+                reputation: Default::default(),
             };
             peers.insert(peer.ip, peer);
         }
@@ -894,6 +1133,10 @@ impl From<u32> for PeerInfoDatabase {
             peer_types_connection_count: Default::default(),
             wakeup_interval,
             clock_compensation: 0,
+            last_uptime_update: MassaTime::now(0).unwrap(),
+            subnet_rejected_in_connection_count: 0,
+            subnet_rejected_out_connection_count: 0,
+            subnet_connection_count: Default::default(),
         }
     }
 }
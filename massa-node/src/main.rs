@@ -4,13 +4,17 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 extern crate massa_logging;
+use crate::metrics::{start_metrics_server, MetricsSources, MetricsStopHandle};
 use crate::settings::SETTINGS;
 
 use crossbeam_channel::{Receiver, TryRecvError};
 use dialoguer::Password;
-use massa_api::{APIConfig, Private, Public, RpcServer, StopHandle, API};
+use massa_api::{APIConfig, Private, Public, RateLimiter, RpcServer, StopHandle, TlsConfig, API};
 use massa_async_pool::AsyncPoolConfig;
-use massa_bootstrap::{get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager};
+use massa_bootstrap::{
+    get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager,
+    SharedBootstrapServerStatus, SharedWhiteBlackList,
+};
 use massa_consensus_exports::events::ConsensusEvent;
 use massa_consensus_exports::{ConsensusChannels, ConsensusConfig, ConsensusManager};
 use massa_consensus_worker::start_consensus_worker;
@@ -25,7 +29,8 @@ use massa_ledger_worker::FinalLedger;
 use massa_logging::massa_trace;
 use massa_models::address::Address;
 use massa_models::config::constants::{
-    ASYNC_POOL_BOOTSTRAP_PART_SIZE, BLOCK_REWARD, BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CHANNEL_SIZE,
+    ASYNC_POOL_BOOTSTRAP_PART_SIZE, BLOCK_REWARD, BOOTSTRAP_COMPRESSION_VERSION,
+    BOOTSTRAP_FINAL_STATE_HASH_VERSION, BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CHANNEL_SIZE,
     DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, DELTA_F0, ENDORSEMENT_COUNT, END_TIMESTAMP,
     EXECUTED_OPS_BOOTSTRAP_PART_SIZE, GENESIS_KEY, GENESIS_TIMESTAMP, INITIAL_DRAW_SEED,
     LEDGER_COST_PER_BYTE, LEDGER_ENTRY_BASE_SIZE, LEDGER_ENTRY_DATASTORE_BASE_SIZE,
@@ -59,6 +64,8 @@ use massa_storage::Storage;
 use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
+use rand::RngCore;
+use std::io::Write;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread::sleep;
@@ -69,10 +76,13 @@ use tokio::signal;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
+mod metrics;
 mod settings;
 
 async fn launch(
     node_wallet: Arc<RwLock<Wallet>>,
+    private_api_token: Option<String>,
+    bootstrap_white_black_list: SharedWhiteBlackList,
 ) -> (
     Receiver<ConsensusEvent>,
     Option<BootstrapManager>,
@@ -86,6 +96,7 @@ async fn launch(
     mpsc::Receiver<()>,
     StopHandle,
     StopHandle,
+    Option<MetricsStopHandle>,
 ) {
     info!("Node version : {}", *VERSION);
     if let Some(end) = *END_TIMESTAMP {
@@ -133,9 +144,11 @@ async fn launch(
         initial_rolls_path: SETTINGS.selector.initial_rolls_path.clone(),
     };
 
-    // Remove current disk ledger if there is one
+    // Remove current disk ledger if there is one, unless it holds the ledger parts of an interrupted bootstrap
     // NOTE: this is temporary, since we cannot currently handle bootstrap from remaining ledger
-    if SETTINGS.ledger.disk_ledger_path.exists() {
+    if SETTINGS.ledger.disk_ledger_path.exists()
+        && !SETTINGS.bootstrap.bootstrap_resume_file.exists()
+    {
         std::fs::remove_dir_all(SETTINGS.ledger.disk_ledger_path.clone())
             .expect("disk ledger delete failed");
     }
@@ -172,6 +185,7 @@ async fn launch(
         bootstrap_list: SETTINGS.bootstrap.bootstrap_list.clone(),
         bootstrap_whitelist_file: SETTINGS.bootstrap.bootstrap_whitelist_file.clone(),
         bootstrap_blacklist_file: SETTINGS.bootstrap.bootstrap_blacklist_file.clone(),
+        bootstrap_resume_file: SETTINGS.bootstrap.bootstrap_resume_file.clone(),
         bind: SETTINGS.bootstrap.bind,
         connect_timeout: SETTINGS.bootstrap.connect_timeout,
         read_timeout: SETTINGS.bootstrap.read_timeout,
@@ -187,6 +201,10 @@ async fn launch(
         ip_list_max_size: SETTINGS.bootstrap.ip_list_max_size,
         max_bytes_read_write: SETTINGS.bootstrap.max_bytes_read_write,
         max_bootstrap_message_size: MAX_BOOTSTRAP_MESSAGE_SIZE,
+        bootstrap_compression: SETTINGS.bootstrap.bootstrap_compression,
+        compression_version: *BOOTSTRAP_COMPRESSION_VERSION,
+        final_state_hash_version: *BOOTSTRAP_FINAL_STATE_HASH_VERSION,
+        parallel_bootstrap_servers: SETTINGS.bootstrap.parallel_bootstrap_servers,
         max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
         randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
         thread_count: THREAD_COUNT,
@@ -247,6 +265,8 @@ async fn launch(
         keypair_file: SETTINGS.network.keypair_file.clone(),
         peer_types_config: SETTINGS.network.peer_types_config.clone(),
         max_in_connections_per_ip: SETTINGS.network.max_in_connections_per_ip,
+        max_out_connections_per_subnet: SETTINGS.network.max_out_connections_per_subnet,
+        max_connections_per_subnet: SETTINGS.network.max_connections_per_subnet,
         max_idle_peers: SETTINGS.network.max_idle_peers,
         max_banned_peers: SETTINGS.network.max_banned_peers,
         peers_file_dump_interval: SETTINGS.network.peers_file_dump_interval,
@@ -255,6 +275,7 @@ async fn launch(
         max_send_wait_node_event: SETTINGS.network.max_send_wait_node_event,
         max_send_wait_network_event: SETTINGS.network.max_send_wait_network_event,
         ban_timeout: SETTINGS.network.ban_timeout,
+        ban_duration: SETTINGS.network.ban_duration,
         peer_list_send_timeout: SETTINGS.network.peer_list_send_timeout,
         max_in_connection_overflow: SETTINGS.network.max_in_connection_overflow,
         max_operations_per_message: SETTINGS.network.max_operations_per_message,
@@ -460,6 +481,7 @@ async fn launch(
     let factory_manager = start_factory(factory_config, node_wallet.clone(), factory_channels);
 
     // launch bootstrap server
+    let bootstrap_server_status = SharedBootstrapServerStatus::new();
     let bootstrap_manager = start_bootstrap_server(
         consensus_controller.clone(),
         network_command_sender.clone(),
@@ -469,10 +491,16 @@ async fn launch(
         private_key,
         bootstrap_state.compensation_millis,
         *VERSION,
+        bootstrap_white_black_list.clone(),
+        bootstrap_server_status.clone(),
     )
     .await
     .unwrap();
 
+    let public_rate_limiter = SETTINGS
+        .api
+        .enable_public_rate_limit
+        .then(|| RateLimiter::new(SETTINGS.api.public_rate_limit.clone()));
     let api_config: APIConfig = APIConfig {
         bind_private: SETTINGS.api.bind_private,
         bind_public: SETTINGS.api.bind_public,
@@ -499,13 +527,31 @@ async fn launch(
         genesis_timestamp: *GENESIS_TIMESTAMP,
         t0: T0,
         periods_per_cycle: PERIODS_PER_CYCLE,
+        max_miss_ratio: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+        private_api_token,
+        public_tls: SETTINGS.api.enable_public_tls.then(|| TlsConfig {
+            cert_path: SETTINGS.api.tls_cert_path.clone(),
+            key_path: SETTINGS.api.tls_key_path.clone(),
+            client_ca_path: None,
+            handshake_timeout: SETTINGS.api.tls_handshake_timeout,
+        }),
+        public_rate_limiter: public_rate_limiter.clone(),
+        private_tls: SETTINGS.api.enable_private_tls.then(|| TlsConfig {
+            cert_path: SETTINGS.api.tls_cert_path.clone(),
+            key_path: SETTINGS.api.tls_key_path.clone(),
+            client_ca_path: SETTINGS.api.private_tls_client_ca_path.clone(),
+            handshake_timeout: SETTINGS.api.tls_handshake_timeout,
+        }),
     };
     // spawn private API
     let (api_private, api_private_stop_rx) = API::<Private>::new(
         network_command_sender.clone(),
+        ProtocolCommandSender(protocol_command_sender.clone()),
         execution_controller.clone(),
         api_config.clone(),
         node_wallet,
+        Box::new(bootstrap_white_black_list),
+        Box::new(bootstrap_server_status.clone()),
     );
     let api_private_handle = api_private
         .serve(&SETTINGS.api.bind_private, &api_config)
@@ -526,12 +572,36 @@ async fn launch(
         bootstrap_state.compensation_millis,
         node_id,
         shared_storage.clone(),
+        Box::new(bootstrap_server_status.clone()),
     );
     let api_public_handle = api_public
         .serve(&SETTINGS.api.bind_public, &api_config)
         .await
         .expect("failed to start PUBLIC API");
 
+    // spawn metrics endpoint
+    let metrics_handle = if SETTINGS.metrics.enabled {
+        let sources = MetricsSources {
+            consensus_controller: consensus_controller.clone(),
+            execution_controller: execution_controller.clone(),
+            pool_controller: pool_controller.clone(),
+            network_command_sender: network_command_sender.clone(),
+            bootstrap_server_status: Box::new(bootstrap_server_status),
+            final_state: final_state.clone(),
+            thread_count: THREAD_COUNT,
+            t0: T0,
+            genesis_timestamp: *GENESIS_TIMESTAMP,
+            compensation_millis: bootstrap_state.compensation_millis,
+            public_rate_limiter,
+        };
+        Some(
+            start_metrics_server(SETTINGS.metrics.bind, sources)
+                .expect("failed to start metrics server"),
+        )
+    } else {
+        None
+    };
+
     #[cfg(feature = "deadlock_detection")]
     {
         // only for #[cfg]
@@ -574,6 +644,7 @@ async fn launch(
         api_private_stop_rx,
         api_private_handle,
         api_public_handle,
+        metrics_handle,
     )
 }
 
@@ -602,6 +673,7 @@ async fn stop(
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
+    metrics_handle: Option<MetricsStopHandle>,
 ) {
     // stop bootstrap
     if let Some(bootstrap_manager) = bootstrap_manager {
@@ -617,6 +689,11 @@ async fn stop(
     // stop private API
     api_private_handle.stop();
 
+    // stop metrics endpoint
+    if let Some(metrics_handle) = metrics_handle {
+        metrics_handle.stop();
+    }
+
     // stop factory
     factory_manager.stop();
 
@@ -682,6 +759,34 @@ fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLo
     )?)))
 }
 
+/// Load the private API token, generating it if the file does not exist yet
+fn load_private_api_token(path: &Path) -> anyhow::Result<String> {
+    if path.is_file() {
+        let token = std::fs::read_to_string(path)?.trim().to_string();
+        if token.is_empty() {
+            anyhow::bail!("private API token file {} is empty", path.display());
+        }
+        return Ok(token);
+    }
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // only the node owner may read the token
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(token.as_bytes())?;
+    info!("private API token generated in {}", path.display());
+    Ok(token)
+}
+
 #[paw::main]
 fn main(args: Args) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -730,6 +835,21 @@ async fn run(args: Args) -> anyhow::Result<()> {
     // load or create wallet, asking for password if necessary
     let node_wallet = load_wallet(args.password, &SETTINGS.factory.staking_wallet_path)?;
 
+    // load or create the private API token if authentication is enabled
+    let private_api_token = if SETTINGS.api.enable_private_auth {
+        Some(load_private_api_token(
+            &SETTINGS.api.private_auth_token_file,
+        )?)
+    } else {
+        None
+    };
+
+    // load the bootstrap whitelist and blacklist, kept across restarts
+    let bootstrap_white_black_list = SharedWhiteBlackList::new(
+        SETTINGS.bootstrap.bootstrap_whitelist_file.clone(),
+        SETTINGS.bootstrap.bootstrap_blacklist_file.clone(),
+    )?;
+
     loop {
         let (
             consensus_event_receiver,
@@ -744,7 +864,13 @@ async fn run(args: Args) -> anyhow::Result<()> {
             mut api_private_stop_rx,
             api_private_handle,
             api_public_handle,
-        ) = launch(node_wallet.clone()).await;
+            metrics_handle,
+        ) = launch(
+            node_wallet.clone(),
+            private_api_token.clone(),
+            bootstrap_white_black_list.clone(),
+        )
+        .await;
 
         // interrupt signal listener
         let (tx, rx) = crossbeam_channel::bounded(1);
@@ -808,6 +934,7 @@ async fn run(args: Args) -> anyhow::Result<()> {
             },
             api_private_handle,
             api_public_handle,
+            metrics_handle,
         )
         .await;
 
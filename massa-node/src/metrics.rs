@@ -0,0 +1,381 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Optional HTTP endpoint exposing the node metrics in the Prometheus text format.
+//!
+//! Metrics are not pushed by the workers: they are computed on each scrape by
+//! querying the same controllers as the API, so that dashboards do not need
+//! to poll the JSON-RPC.
+
+use enum_map::EnumMap;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use massa_api::RateLimiter;
+use massa_bootstrap_exports::BootstrapStatusController;
+use massa_consensus_exports::ConsensusController;
+use massa_execution_exports::ExecutionController;
+use massa_final_state::FinalState;
+use massa_models::{slot::Slot, timeslots::get_latest_block_slot_at_timestamp};
+use massa_network_exports::{NetworkCommandSender, PeerType};
+use massa_pool_exports::PoolController;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use std::{
+    convert::Infallible,
+    fmt::{Display, Write},
+    net::SocketAddr,
+    sync::Arc,
+};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+/// Content type of the Prometheus text exposition format
+const TEXT_FORMAT_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Everything the metrics are computed from
+pub struct MetricsSources {
+    /// consensus controller
+    pub consensus_controller: Box<dyn ConsensusController>,
+    /// execution controller
+    pub execution_controller: Box<dyn ExecutionController>,
+    /// pool controller
+    pub pool_controller: Box<dyn PoolController>,
+    /// network command sender
+    pub network_command_sender: NetworkCommandSender,
+    /// bootstrap server status
+    pub bootstrap_server_status: Box<dyn BootstrapStatusController>,
+    /// final state, for the asynchronous pool and the ledger
+    pub final_state: Arc<RwLock<FinalState>>,
+    /// thread count
+    pub thread_count: u8,
+    /// period duration
+    pub t0: MassaTime,
+    /// genesis timestamp
+    pub genesis_timestamp: MassaTime,
+    /// time compensation in milliseconds
+    pub compensation_millis: i64,
+    /// rate limiter of the public API, if enabled
+    pub public_rate_limiter: Option<RateLimiter>,
+}
+
+/// Used to stop the metrics server
+pub struct MetricsStopHandle(oneshot::Sender<()>);
+
+impl MetricsStopHandle {
+    /// Stop the metrics server
+    pub fn stop(self) {
+        // the server may already be gone
+        let _ = self.0.send(());
+    }
+}
+
+/// Start serving the metrics on `http://<bind>/metrics`
+pub fn start_metrics_server(
+    bind: SocketAddr,
+    sources: MetricsSources,
+) -> Result<MetricsStopHandle, hyper::Error> {
+    let sources = Arc::new(sources);
+    let make_service = make_service_fn(move |_| {
+        let sources = sources.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let sources = sources.clone();
+                async move { Ok::<_, Infallible>(handle_request(request, &sources).await) }
+            }))
+        }
+    });
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = Server::try_bind(&bind)?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            let _ = stop_rx.await;
+        });
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            error!("metrics server error: {}", err);
+        }
+    });
+    info!("metrics server listening on {}", bind);
+    Ok(MetricsStopHandle(stop_tx))
+}
+
+async fn handle_request(request: Request<Body>, sources: &MetricsSources) -> Response<Body> {
+    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+    let mut response = Response::new(Body::from(render_metrics(sources).await));
+    if let Ok(content_type) = TEXT_FORMAT_CONTENT_TYPE.parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+/// Writes metric families in the Prometheus text format
+#[derive(Default)]
+struct MetricsWriter(String);
+
+impl MetricsWriter {
+    /// Start a metric family
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {} {}", name, help);
+        let _ = writeln!(self.0, "# TYPE {} {}", name, kind);
+    }
+
+    /// Add a sample to the current family. `labels` is either empty or of the form `{key="value"}`
+    fn sample(&mut self, name: &str, labels: &str, value: impl Display) {
+        let _ = writeln!(self.0, "{}{} {}", name, labels, value);
+    }
+
+    /// Add a family made of a single unlabelled sample
+    fn single(&mut self, name: &str, kind: &str, help: &str, value: impl Display) {
+        self.family(name, kind, help);
+        self.sample(name, "", value);
+    }
+}
+
+/// Compute all the metrics. A source that cannot be queried is skipped.
+async fn render_metrics(sources: &MetricsSources) -> String {
+    let mut w = MetricsWriter::default();
+
+    // consensus
+    match sources.consensus_controller.get_stats() {
+        Ok(stats) => {
+            w.single(
+                "massa_consensus_processed_blocks_total",
+                "counter",
+                "Blocks added to the graph since the node started.",
+                stats.processed_block_total,
+            );
+            w.family(
+                "massa_consensus_discarded_blocks_total",
+                "counter",
+                "Blocks discarded since the node started, by reason.",
+            );
+            for (reason, count) in [
+                ("invalid", stats.invalid_block_total),
+                ("stale", stats.stale_block_total),
+                ("final", stats.final_block_total),
+            ] {
+                w.sample(
+                    "massa_consensus_discarded_blocks_total",
+                    &format!("{{reason=\"{}\"}}", reason),
+                    count,
+                );
+            }
+            w.single(
+                "massa_consensus_clique_count",
+                "gauge",
+                "Number of active cliques.",
+                stats.clique_count,
+            );
+        }
+        Err(err) => warn!("metrics: could not get consensus stats: {}", err),
+    }
+
+    // execution
+    let execution_stats = sources.execution_controller.get_stats();
+    if let Ok(Some(latest_slot)) = MassaTime::now(sources.compensation_millis)
+        .map_err(|err| err.to_string())
+        .and_then(|now| {
+            get_latest_block_slot_at_timestamp(
+                sources.thread_count,
+                sources.t0,
+                sources.genesis_timestamp,
+                now,
+            )
+            .map_err(|err| err.to_string())
+        })
+    {
+        let lag = |cursor: &Slot| {
+            latest_slot
+                .slots_since(cursor, sources.thread_count)
+                .unwrap_or(0)
+        };
+        w.single(
+            "massa_execution_final_slot_lag",
+            "gauge",
+            "Number of slots between the current slot and the final execution cursor.",
+            lag(&execution_stats.final_cursor),
+        );
+        w.single(
+            "massa_execution_active_slot_lag",
+            "gauge",
+            "Number of slots between the current slot and the active execution cursor.",
+            lag(&execution_stats.active_cursor),
+        );
+    }
+    let window_seconds = execution_stats
+        .time_window_end
+        .saturating_sub(execution_stats.time_window_start)
+        .to_millis() as f64
+        / 1000.0;
+    w.single(
+        "massa_execution_operations_per_second",
+        "gauge",
+        "Final operations executed per second over the execution stats time window.",
+        if window_seconds > 0.0 {
+            execution_stats.final_executed_operations_count as f64 / window_seconds
+        } else {
+            0.0
+        },
+    );
+    w.single(
+        "massa_execution_async_pool_length",
+        "gauge",
+        "Number of messages in the final asynchronous pool.",
+        sources.final_state.read().async_pool.len(),
+    );
+
+    // pool
+    w.family(
+        "massa_pool_operation_count",
+        "gauge",
+        "Operations in the pool, by thread.",
+    );
+    for (thread, count) in sources
+        .pool_controller
+        .get_operation_count_per_thread()
+        .into_iter()
+        .enumerate()
+    {
+        w.sample(
+            "massa_pool_operation_count",
+            &format!("{{thread=\"{}\"}}", thread),
+            count,
+        );
+    }
+
+    // network
+    match sources.network_command_sender.get_peers().await {
+        Ok(peers) => {
+            let mut peer_counts: EnumMap<PeerType, usize> = EnumMap::default();
+            for peer in peers.peers.values() {
+                if !peer.active_nodes.is_empty() {
+                    peer_counts[peer.peer_info.peer_type] += 1;
+                }
+            }
+            w.family(
+                "massa_network_connected_peers",
+                "gauge",
+                "Connected peers, by peer type.",
+            );
+            for (peer_type, count) in peer_counts {
+                w.sample(
+                    "massa_network_connected_peers",
+                    &format!("{{peer_type=\"{}\"}}", peer_type),
+                    count,
+                );
+            }
+        }
+        Err(err) => warn!("metrics: could not get peers: {}", err),
+    }
+    match sources.network_command_sender.get_network_stats().await {
+        Ok(stats) => {
+            w.single(
+                "massa_network_received_bytes_total",
+                "counter",
+                "Bytes received from other nodes since the node started.",
+                stats.bytes_in,
+            );
+            w.single(
+                "massa_network_sent_bytes_total",
+                "counter",
+                "Bytes sent to other nodes since the node started.",
+                stats.bytes_out,
+            );
+        }
+        Err(err) => warn!("metrics: could not get network stats: {}", err),
+    }
+
+    // bootstrap
+    let bootstrap_stats = sources.bootstrap_server_status.get_stats();
+    w.single(
+        "massa_bootstrap_active_sessions",
+        "gauge",
+        "Bootstrap sessions currently served.",
+        bootstrap_stats.active_session_count,
+    );
+    w.family(
+        "massa_bootstrap_sessions_total",
+        "counter",
+        "Bootstrap sessions since the node started, by outcome.",
+    );
+    for (outcome, count) in [
+        ("completed", bootstrap_stats.completed_session_count),
+        ("failed", bootstrap_stats.failed_session_count),
+        ("refused", bootstrap_stats.refused_session_count),
+        ("no_slot", bootstrap_stats.no_slot_session_count),
+    ] {
+        w.sample(
+            "massa_bootstrap_sessions_total",
+            &format!("{{outcome=\"{}\"}}", outcome),
+            count,
+        );
+    }
+
+    // API
+    if let Some(rate_limiter) = &sources.public_rate_limiter {
+        w.family(
+            "massa_api_rate_limited_calls_total",
+            "counter",
+            "Public API calls rejected by the rate limiter since the node started, by method.",
+        );
+        for (method, count) in rate_limiter.get_rejected_calls() {
+            w.sample(
+                "massa_api_rate_limited_calls_total",
+                &format!("{{method=\"{}\"}}", method),
+                count,
+            );
+        }
+    }
+
+    // ledger
+    w.single(
+        "massa_ledger_rocksdb_size_bytes",
+        "gauge",
+        "Size of the SST files of the ledger database.",
+        sources.final_state.read().ledger.get_disk_size(),
+    );
+
+    w.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricsWriter;
+
+    #[test]
+    fn test_metrics_writer_single() {
+        let mut w = MetricsWriter::default();
+        w.single("massa_test_gauge", "gauge", "A test gauge.", 42);
+        assert_eq!(
+            w.0,
+            "# HELP massa_test_gauge A test gauge.\n\
+             # TYPE massa_test_gauge gauge\n\
+             massa_test_gauge 42\n"
+        );
+    }
+
+    #[test]
+    fn test_metrics_writer_labelled_family() {
+        let mut w = MetricsWriter::default();
+        w.family("massa_test_total", "counter", "A test counter.");
+        w.sample("massa_test_total", "{outcome=\"completed\"}", 3);
+        w.sample("massa_test_total", "{outcome=\"failed\"}", 0);
+        w.single("massa_test_ratio", "gauge", "A test ratio.", 0.5);
+        assert_eq!(
+            w.0,
+            "# HELP massa_test_total A test counter.\n\
+             # TYPE massa_test_total counter\n\
+             massa_test_total{outcome=\"completed\"} 3\n\
+             massa_test_total{outcome=\"failed\"} 0\n\
+             # HELP massa_test_ratio A test ratio.\n\
+             # TYPE massa_test_ratio gauge\n\
+             massa_test_ratio 0.5\n"
+        );
+    }
+}
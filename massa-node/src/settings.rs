@@ -4,6 +4,8 @@
 use std::path::PathBuf;
 
 use enum_map::EnumMap;
+use massa_api::RateLimitConfig;
+use massa_bootstrap::BootstrapCompression;
 use massa_models::config::build_massa_settings;
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
@@ -54,6 +56,8 @@ pub struct NetworkSettings {
     pub keypair_file: PathBuf,
     pub peer_types_config: EnumMap<PeerType, PeerTypeConnectionConfig>,
     pub max_in_connections_per_ip: usize,
+    pub max_out_connections_per_subnet: usize,
+    pub max_connections_per_subnet: usize,
     pub max_idle_peers: usize,
     pub max_banned_peers: usize,
     pub peers_file_dump_interval: MassaTime,
@@ -62,6 +66,7 @@ pub struct NetworkSettings {
     pub max_send_wait_node_event: MassaTime,
     pub max_send_wait_network_event: MassaTime,
     pub ban_timeout: MassaTime,
+    pub ban_duration: MassaTime,
     pub peer_list_send_timeout: MassaTime,
     pub max_in_connection_overflow: usize,
     pub max_operations_per_message: u32,
@@ -75,6 +80,7 @@ pub struct BootstrapSettings {
     pub bootstrap_list: Vec<(SocketAddr, PublicKey)>,
     pub bootstrap_whitelist_file: std::path::PathBuf,
     pub bootstrap_blacklist_file: std::path::PathBuf,
+    pub bootstrap_resume_file: std::path::PathBuf,
     pub bind: Option<SocketAddr>,
     pub connect_timeout: MassaTime,
     pub read_timeout: MassaTime,
@@ -89,6 +95,8 @@ pub struct BootstrapSettings {
     pub per_ip_min_interval: MassaTime,
     pub ip_list_max_size: usize,
     pub max_bytes_read_write: f64,
+    pub bootstrap_compression: BootstrapCompression,
+    pub parallel_bootstrap_servers: usize,
 }
 
 /// Factory settings
@@ -127,6 +135,23 @@ pub struct APISettings {
     pub ping_interval: MassaTime,
     pub enable_http: bool,
     pub enable_ws: bool,
+    pub enable_private_auth: bool,
+    pub private_auth_token_file: PathBuf,
+    pub enable_public_tls: bool,
+    pub enable_private_tls: bool,
+    pub tls_cert_path: PathBuf,
+    pub tls_key_path: PathBuf,
+    pub private_tls_client_ca_path: Option<PathBuf>,
+    pub tls_handshake_timeout: MassaTime,
+    pub enable_public_rate_limit: bool,
+    pub public_rate_limit: RateLimitConfig,
+}
+
+/// Prometheus metrics endpoint configuration, read from a file configuration.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    pub bind: SocketAddr,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -142,6 +167,7 @@ pub struct Settings {
     pub ledger: LedgerSettings,
     pub selector: SelectionSettings,
     pub factory: FactorySettings,
+    pub metrics: MetricsSettings,
 }
 
 /// Consensus configuration
@@ -32,6 +32,9 @@ pub trait PoolController: Send + Sync {
     /// Get the number of operations in the pool
     fn get_operation_count(&self) -> usize;
 
+    /// Get the number of operations in the pool, per thread
+    fn get_operation_count_per_thread(&self) -> Vec<usize>;
+
     /// Check if the pool contains a list of endorsements. Returns one boolean per item.
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool>;
 
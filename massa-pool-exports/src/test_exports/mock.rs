@@ -57,6 +57,11 @@ pub enum MockPoolControllerMessage {
         /// Response channel
         response_tx: mpsc::Sender<usize>,
     },
+    /// Get the number of operations per thread
+    GetOperationCountPerThread {
+        /// Response channel
+        response_tx: mpsc::Sender<Vec<usize>>,
+    },
     /// Contains endorsements
     ContainsEndorsements {
         /// ids to search
@@ -190,6 +195,16 @@ impl PoolController for MockPoolController {
         response_rx.recv().unwrap()
     }
 
+    fn get_operation_count_per_thread(&self) -> Vec<usize> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::GetOperationCountPerThread { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -139,6 +139,11 @@ impl PoolController for PoolControllerImpl {
         self.operation_pool.read().len()
     }
 
+    /// Get the number of operations in the pool, per thread
+    fn get_operation_count_per_thread(&self) -> Vec<usize> {
+        self.operation_pool.read().len_per_thread()
+    }
+
     /// Check if the pool contains a list of endorsements. Returns one boolean per item.
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool> {
         let lck = self.endorsement_pool.read();
@@ -59,6 +59,14 @@ impl OperationPool {
         self.operations.len()
     }
 
+    /// Returns the number of operations in the pool, per thread
+    pub fn len_per_thread(&self) -> Vec<usize> {
+        self.sorted_ops_per_thread
+            .iter()
+            .map(|ops| ops.len())
+            .collect()
+    }
+
     /// Checks whether an element is stored in the pool.
     pub fn contains(&self, id: &OperationId) -> bool {
         self.operations.contains_key(id)
@@ -5,6 +5,8 @@
 
 use std::collections::BTreeMap;
 
+use serde::Serialize;
+
 use crate::PosResult;
 use massa_hash::Hash;
 use massa_models::address::Address;
@@ -15,7 +17,7 @@ use massa_models::slot::Slot;
 use std::collections::{HashMap, VecDeque};
 
 /// Selections of endorsements and producer
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Selection {
     /// Chosen endorsements
     pub endorsements: Vec<Address>,
@@ -36,11 +38,13 @@ pub trait SelectorController: Send + Sync {
     /// * `cycle`: cycle number to be drawn
     /// * `lookback_rolls`: look back rolls used for the draw (cycle - 3)
     /// * `lookback_seed`: look back seed hash for the draw (cycle - 2)
+    /// * `lookback_delegates`: look back staking delegates of the roll owners (cycle - 3)
     fn feed_cycle(
         &self,
         cycle: u64,
         lookback_rolls: BTreeMap<Address, u64>,
         lookback_seed: Hash,
+        lookback_delegates: BTreeMap<Address, Address>,
     ) -> PosResult<()>;
 
     /// Get [Selection] computed for a slot:
@@ -58,11 +62,23 @@ pub trait SelectorController: Send + Sync {
         end: Slot,
     ) -> PosResult<(Vec<Slot>, Vec<IndexedSlot>)>;
 
+    /// Get the range of cycles for which draws are available,
+    /// as `(first_cycle, last_cycle)` (both included).
+    /// Returns `None` if no cycle has been drawn yet.
+    fn get_drawn_cycle_range(&self) -> PosResult<Option<(u64, u64)>>;
+
     /// Get [Address] of the selected block producer for a given slot
     /// # Arguments
     /// * `slot`: target slot of the selection
     fn get_producer(&self, slot: Slot) -> PosResult<Address>;
 
+    /// Get the staking delegate allowed to create blocks and endorsements in the name
+    /// of a roll owner at a given slot, if the owner designated one.
+    /// # Arguments
+    /// * `slot`: target slot
+    /// * `address`: roll owner address
+    fn get_staking_delegate(&self, slot: Slot, address: &Address) -> PosResult<Option<Address>>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn SelectorController>`.
     fn clone_box(&self) -> Box<dyn SelectorController>;
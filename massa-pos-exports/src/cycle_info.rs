@@ -26,6 +26,10 @@ use crate::PoSChanges;
 
 const CYCLE_INFO_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
 
+/// Bit set in the serialized `complete` byte when the cycle carries staking delegates.
+/// Cycles without delegates keep the layout they had before delegation existed.
+const STAKING_DELEGATES_FLAG: u8 = 0b10;
+
 struct CycleInfoHashComputer {
     u64_ser: U64VarIntSerializer,
     address_ser: AddressSerializer,
@@ -69,6 +73,13 @@ impl CycleInfoHashComputer {
         Hash::compute_from(&buffer)
     }
 
+    fn compute_staking_delegate_entry_hash(&self, owner: &Address, delegate: &Address) -> Hash {
+        let mut buffer = Vec::new();
+        self.address_ser.serialize(owner, &mut buffer).unwrap();
+        self.address_ser.serialize(delegate, &mut buffer).unwrap();
+        Hash::compute_from(&buffer)
+    }
+
     fn compute_prod_stats_entry_hash(
         &self,
         address: &Address,
@@ -99,10 +110,14 @@ pub struct CycleInfo {
     pub rng_seed: BitVec<u8>,
     /// Per-address production statistics
     pub production_stats: PreHashMap<Address, ProductionStats>,
+    /// staking delegate of each address that designated one (owner, delegate)
+    pub staking_delegates: BTreeMap<Address, Address>,
     /// Hash of the roll counts
     pub roll_counts_hash: Hash,
     /// Hash of the production statistics
     pub production_stats_hash: Hash,
+    /// Hash of the staking delegates
+    pub staking_delegates_hash: Hash,
     /// Hash of the cycle state
     pub global_hash: Hash,
 }
@@ -115,10 +130,12 @@ impl CycleInfo {
         roll_counts: BTreeMap<Address, u64>,
         rng_seed: BitVec<u8>,
         production_stats: PreHashMap<Address, ProductionStats>,
+        staking_delegates: BTreeMap<Address, Address>,
     ) -> Self {
         let hash_computer = CycleInfoHashComputer::new();
         let mut roll_counts_hash = Hash::from_bytes(CYCLE_INFO_HASH_INITIAL_BYTES);
         let mut production_stats_hash = Hash::from_bytes(CYCLE_INFO_HASH_INITIAL_BYTES);
+        let mut staking_delegates_hash = Hash::from_bytes(CYCLE_INFO_HASH_INITIAL_BYTES);
 
         // compute the cycle hash
         let mut hash_concat: Vec<u8> = Vec::new();
@@ -133,6 +150,14 @@ impl CycleInfo {
             production_stats_hash ^= hash_computer.compute_prod_stats_entry_hash(addr, prod_stats);
         }
         hash_concat.extend(production_stats_hash.to_bytes());
+        for (owner, delegate) in &staking_delegates {
+            staking_delegates_hash ^=
+                hash_computer.compute_staking_delegate_entry_hash(owner, delegate);
+        }
+        // cycles without delegates keep the hash they had before delegation existed
+        if !staking_delegates.is_empty() {
+            hash_concat.extend(staking_delegates_hash.to_bytes());
+        }
 
         // compute the global hash
         let global_hash = Hash::compute_from(&hash_concat);
@@ -144,8 +169,10 @@ impl CycleInfo {
             roll_counts,
             rng_seed,
             production_stats,
+            staking_delegates,
             roll_counts_hash,
             production_stats_hash,
+            staking_delegates_hash,
             global_hash,
         }
     }
@@ -210,6 +237,22 @@ impl CycleInfo {
         }
         hash_concat.extend(self.production_stats_hash.to_bytes());
 
+        // set or revoke staking delegates
+        for (owner, opt_delegate) in changes.staking_delegate_changes {
+            if let Some(previous_delegate) = self.staking_delegates.remove(&owner) {
+                self.staking_delegates_hash ^=
+                    hash_computer.compute_staking_delegate_entry_hash(&owner, &previous_delegate);
+            }
+            if let Some(delegate) = opt_delegate {
+                self.staking_delegates_hash ^=
+                    hash_computer.compute_staking_delegate_entry_hash(&owner, &delegate);
+                self.staking_delegates.insert(owner, delegate);
+            }
+        }
+        if !self.staking_delegates.is_empty() {
+            hash_concat.extend(self.staking_delegates_hash.to_bytes());
+        }
+
         // if the cycle just completed, check that it has the right number of seed bits
         if self.complete && self.rng_seed.len() as u64 != slots_per_cycle {
             panic!("cycle completed with incorrect number of seed bits");
@@ -235,8 +278,10 @@ fn test_cycle_info_hash_computation() {
         BTreeMap::default(),
         BitVec::default(),
         PreHashMap::default(),
+        BTreeMap::default(),
     );
     let addr = Address::from_bytes(&[0u8; 32]);
+    let delegate = Address::from_bytes(&[1u8; 32]);
 
     // add changes
     let mut roll_changes = PreHashMap::default();
@@ -249,10 +294,13 @@ fn test_cycle_info_hash_computation() {
             block_failure_count: 0,
         },
     );
+    let mut staking_delegate_changes = PreHashMap::default();
+    staking_delegate_changes.insert(addr, Some(delegate));
     let changes = PoSChanges {
         seed_bits: bitvec![u8, Lsb0; 0, 10],
         roll_changes: roll_changes.clone(),
         production_stats: production_stats.clone(),
+        staking_delegate_changes: staking_delegate_changes.clone(),
         deferred_credits: DeferredCredits::default(),
     };
     cycle_a.apply_changes(changes, Slot::new(0, 0), 2, 2);
@@ -268,10 +316,13 @@ fn test_cycle_info_hash_computation() {
             block_failure_count: 6,
         },
     );
+    staking_delegate_changes.clear();
+    staking_delegate_changes.insert(addr, None);
     let changes = PoSChanges {
         seed_bits: bitvec![u8, Lsb0; 0, 20],
         roll_changes: roll_changes.clone(),
         production_stats: production_stats.clone(),
+        staking_delegate_changes: staking_delegate_changes.clone(),
         deferred_credits: DeferredCredits::default(),
     };
     cycle_a.apply_changes(changes, Slot::new(0, 1), 2, 2);
@@ -287,10 +338,13 @@ fn test_cycle_info_hash_computation() {
             block_failure_count: 12,
         },
     );
+    staking_delegate_changes.clear();
+    staking_delegate_changes.insert(addr, Some(delegate));
     let changes = PoSChanges {
         seed_bits: bitvec![u8, Lsb0; 0, 30],
         roll_changes,
         production_stats,
+        staking_delegate_changes,
         deferred_credits: DeferredCredits::default(),
     };
     cycle_a.apply_changes(changes, Slot::new(1, 0), 2, 2);
@@ -302,6 +356,7 @@ fn test_cycle_info_hash_computation() {
         cycle_a.roll_counts,
         cycle_a.rng_seed,
         cycle_a.production_stats,
+        cycle_a.staking_delegates,
     );
     assert_eq!(
         cycle_a.roll_counts_hash, cycle_b.roll_counts_hash,
@@ -311,12 +366,98 @@ fn test_cycle_info_hash_computation() {
         cycle_a.production_stats_hash, cycle_b.production_stats_hash,
         "production_stats_hash mismatch"
     );
+    assert_eq!(
+        cycle_a.staking_delegates_hash, cycle_b.staking_delegates_hash,
+        "staking_delegates_hash mismatch"
+    );
     assert_eq!(
         cycle_a.global_hash, cycle_b.global_hash,
         "global_hash mismatch"
     );
 }
 
+#[test]
+fn test_cycle_info_without_delegates_keeps_legacy_format() {
+    use massa_serialization::DeserializeError;
+
+    let addr = Address::from_bytes(&[0u8; 32]);
+    let delegate = Address::from_bytes(&[1u8; 32]);
+    let mut roll_counts = BTreeMap::default();
+    roll_counts.insert(addr, 10);
+    let cycle = CycleInfo::new_with_hash(
+        3,
+        true,
+        roll_counts.clone(),
+        BitVec::default(),
+        PreHashMap::default(),
+        BTreeMap::default(),
+    );
+
+    // the hash of a cycle without delegates is the one computed before delegation existed
+    let hash_computer = CycleInfoHashComputer::new();
+    let mut hash_concat: Vec<u8> = Vec::new();
+    hash_concat.extend(hash_computer.compute_cycle_hash(3).to_bytes());
+    hash_concat.extend(hash_computer.compute_complete_hash(true).to_bytes());
+    hash_concat.extend(
+        hash_computer
+            .compute_seed_hash(&BitVec::default())
+            .to_bytes(),
+    );
+    hash_concat.extend(
+        (Hash::from_bytes(CYCLE_INFO_HASH_INITIAL_BYTES)
+            ^ hash_computer.compute_roll_entry_hash(&addr, 10))
+        .to_bytes(),
+    );
+    hash_concat.extend(Hash::from_bytes(CYCLE_INFO_HASH_INITIAL_BYTES).to_bytes());
+    assert_eq!(cycle.global_hash, Hash::compute_from(&hash_concat));
+
+    // and it is serialized without the delegates field
+    let serializer = CycleInfoSerializer::new();
+    let deserializer = CycleInfoDeserializer::new(10, 10);
+    let mut buffer = Vec::new();
+    serializer.serialize(&cycle, &mut buffer).unwrap();
+    let mut legacy = Vec::new();
+    U64VarIntSerializer::new()
+        .serialize(&3, &mut legacy)
+        .unwrap();
+    legacy.push(1);
+    legacy.push(1);
+    legacy.extend(addr.to_bytes());
+    legacy.push(10);
+    BitVecSerializer::new()
+        .serialize(&BitVec::default(), &mut legacy)
+        .unwrap();
+    legacy.push(0);
+    assert_eq!(buffer, legacy);
+    let (rest, decoded) = deserializer
+        .deserialize::<DeserializeError>(&buffer)
+        .unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(decoded.global_hash, cycle.global_hash);
+
+    // a cycle with delegates round-trips and hashes its delegates
+    let mut staking_delegates = BTreeMap::default();
+    staking_delegates.insert(addr, delegate);
+    let delegated = CycleInfo::new_with_hash(
+        3,
+        true,
+        roll_counts,
+        BitVec::default(),
+        PreHashMap::default(),
+        staking_delegates,
+    );
+    assert_ne!(delegated.global_hash, cycle.global_hash);
+    let mut buffer = Vec::new();
+    serializer.serialize(&delegated, &mut buffer).unwrap();
+    buffer.extend([42]);
+    let (rest, decoded) = deserializer
+        .deserialize::<DeserializeError>(&buffer)
+        .unwrap();
+    assert_eq!(rest, [42]);
+    assert_eq!(decoded.staking_delegates, delegated.staking_delegates);
+    assert_eq!(decoded.global_hash, delegated.global_hash);
+}
+
 /// Serializer for `CycleInfo`
 pub struct CycleInfoSerializer {
     u64_ser: U64VarIntSerializer,
@@ -346,8 +487,12 @@ impl Serializer<CycleInfo> for CycleInfoSerializer {
         // cycle_info.cycle
         self.u64_ser.serialize(&value.cycle, buffer)?;
 
-        // cycle_info.complete
-        buffer.push(u8::from(value.complete));
+        // cycle_info.complete, flagged when staking delegates follow
+        let mut flags = u8::from(value.complete);
+        if !value.staking_delegates.is_empty() {
+            flags |= STAKING_DELEGATES_FLAG;
+        }
+        buffer.push(flags);
 
         // cycle_info.roll_counts
         self.u64_ser
@@ -364,6 +509,16 @@ impl Serializer<CycleInfo> for CycleInfoSerializer {
         self.production_stats_ser
             .serialize(&value.production_stats, buffer)?;
 
+        // cycle_info.staking_delegates, omitted when empty
+        if !value.staking_delegates.is_empty() {
+            self.u64_ser
+                .serialize(&(value.staking_delegates.len() as u64), buffer)?;
+            for (owner, delegate) in &value.staking_delegates {
+                buffer.extend(owner.to_bytes());
+                buffer.extend(delegate.to_bytes());
+            }
+        }
+
         Ok(())
     }
 }
@@ -374,6 +529,7 @@ pub struct CycleInfoDeserializer {
     rolls_deser: RollsDeserializer,
     bitvec_deser: BitVecDeserializer,
     production_stats_deser: ProductionStatsDeserializer,
+    staking_delegates_deser: StakingDelegatesDeserializer,
 }
 
 impl CycleInfoDeserializer {
@@ -384,6 +540,8 @@ impl CycleInfoDeserializer {
             rolls_deser: RollsDeserializer::new(max_rolls_length),
             bitvec_deser: BitVecDeserializer::new(),
             production_stats_deser: ProductionStatsDeserializer::new(max_production_stats_length),
+            // an address can only delegate its own rolls, so delegations are bounded like rolls
+            staking_delegates_deser: StakingDelegatesDeserializer::new(max_rolls_length),
         }
     }
 }
@@ -393,13 +551,28 @@ impl Deserializer<CycleInfo> for CycleInfoDeserializer {
         &self,
         buffer: &'a [u8],
     ) -> IResult<&'a [u8], CycleInfo, E> {
-        context(
+        #[allow(clippy::type_complexity)]
+        let (rest, (cycle, (complete, has_delegates), roll_counts, rng_seed, production_stats)): (
+            &'a [u8],
+            (
+                u64,                                  // cycle
+                (bool, bool),                         // complete, has staking delegates
+                Vec<(Address, u64)>,                  // roll_counts
+                BitVec<u8>,                           // rng_seed
+                PreHashMap<Address, ProductionStats>, // production_stats (address, n_success, n_fail)
+            ),
+        ) = context(
             "cycle_history",
             tuple((
                 context("cycle", |input| self.u64_deser.deserialize(input)),
                 context(
                     "complete",
-                    alt((value(true, tag(&[1])), value(false, tag(&[0])))),
+                    alt((
+                        value((false, false), tag(&[0])),
+                        value((true, false), tag(&[1])),
+                        value((false, true), tag(&[STAKING_DELEGATES_FLAG])),
+                        value((true, true), tag(&[STAKING_DELEGATES_FLAG | 1])),
+                    )),
                 ),
                 context("roll_counts", |input| self.rolls_deser.deserialize(input)),
                 context("rng_seed", |input| self.bitvec_deser.deserialize(input)),
@@ -408,25 +581,32 @@ impl Deserializer<CycleInfo> for CycleInfoDeserializer {
                 }),
             )),
         )
-        .map(
-            #[allow(clippy::type_complexity)]
-            |(cycle, complete, roll_counts, rng_seed, production_stats): (
-                u64,                                  // cycle
-                bool,                                 // complete
-                Vec<(Address, u64)>,                  // roll_counts
-                BitVec<u8>,                           // rng_seed
-                PreHashMap<Address, ProductionStats>, // production_stats (address, n_success, n_fail)
-            )| {
-                CycleInfo::new_with_hash(
-                    cycle,
-                    complete,
-                    roll_counts.into_iter().collect(),
-                    rng_seed,
-                    production_stats,
-                )
-            },
-        )
-        .parse(buffer)
+        .parse(buffer)?;
+
+        // staking delegates are only serialized when the cycle has some
+        let (rest, staking_delegates) = if has_delegates {
+            context(
+                "cycle_history",
+                context("staking_delegates", |input| {
+                    self.staking_delegates_deser.deserialize(input)
+                }),
+            )
+            .parse(rest)?
+        } else {
+            (rest, Vec::new())
+        };
+
+        Ok((
+            rest,
+            CycleInfo::new_with_hash(
+                cycle,
+                complete,
+                roll_counts.into_iter().collect(),
+                rng_seed,
+                production_stats,
+                staking_delegates.into_iter().collect(),
+            ),
+        ))
     }
 }
 
@@ -611,3 +791,48 @@ impl Deserializer<Vec<(Address, u64)>> for RollsDeserializer {
         .parse(buffer)
     }
 }
+
+/// Deserializer for staking delegates
+pub struct StakingDelegatesDeserializer {
+    length_deserializer: U64VarIntDeserializer,
+    address_deserializer: AddressDeserializer,
+}
+
+impl StakingDelegatesDeserializer {
+    /// Creates a new staking delegates deserializer
+    pub fn new(max_delegates_length: u64) -> StakingDelegatesDeserializer {
+        StakingDelegatesDeserializer {
+            // an empty delegate list is never serialized, see `STAKING_DELEGATES_FLAG`
+            length_deserializer: U64VarIntDeserializer::new(
+                Included(1),
+                Included(max_delegates_length),
+            ),
+            address_deserializer: AddressDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<Vec<(Address, Address)>> for StakingDelegatesDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<(Address, Address)>, E> {
+        context(
+            "Failed staking delegates deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.length_deserializer.deserialize(input)
+                }),
+                tuple((
+                    context("Failed owner address deserialization", |input| {
+                        self.address_deserializer.deserialize(input)
+                    }),
+                    context("Failed delegate address deserialization", |input| {
+                        self.address_deserializer.deserialize(input)
+                    }),
+                )),
+            ),
+        )
+        .parse(buffer)
+    }
+}
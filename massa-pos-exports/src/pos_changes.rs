@@ -4,16 +4,23 @@ use crate::{
 };
 use bitvec::prelude::*;
 use massa_models::{
-    address::Address,
+    address::{Address, AddressDeserializer},
     prehash::PreHashMap,
     serialization::{BitVecDeserializer, BitVecSerializer},
 };
-use massa_serialization::{Deserializer, SerializeError, Serializer, U64VarIntSerializer};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
 use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::value,
     error::{context, ContextError, ParseError},
-    sequence::tuple,
+    multi::length_count,
+    sequence::{preceded, tuple},
     IResult, Parser,
 };
+use std::ops::Bound::Included;
 
 /// Recap of all PoS changes
 #[derive(Default, Debug, Clone)]
@@ -27,6 +34,9 @@ pub struct PoSChanges {
     /// updated production statistics
     pub production_stats: PreHashMap<Address, ProductionStats>,
 
+    /// new staking delegates of addresses (`None` to revoke the current delegate)
+    pub staking_delegate_changes: PreHashMap<Address, Option<Address>>,
+
     /// set deferred credits indexed by target slot (can be set to 0 to cancel some, in case of slash)
     /// ordered structure to ensure slot iteration order is deterministic
     pub deferred_credits: DeferredCredits,
@@ -38,6 +48,7 @@ impl PoSChanges {
         self.seed_bits.is_empty()
             && self.roll_changes.is_empty()
             && self.production_stats.is_empty()
+            && self.staking_delegate_changes.is_empty()
             && self.deferred_credits.credits.is_empty()
     }
 
@@ -57,6 +68,10 @@ impl PoSChanges {
                 .extend(&other_stats);
         }
 
+        // extend staking delegate changes
+        self.staking_delegate_changes
+            .extend(other.staking_delegate_changes);
+
         // extend deferred credits
         self.deferred_credits.nested_extend(other.deferred_credits);
     }
@@ -106,6 +121,20 @@ impl Serializer<PoSChanges> for PoSChangesSerializer {
         self.production_stats_serializer
             .serialize(&value.production_stats, buffer)?;
 
+        // staking_delegate_changes
+        self.u64_serializer
+            .serialize(&(value.staking_delegate_changes.len() as u64), buffer)?;
+        for (addr, opt_delegate) in value.staking_delegate_changes.iter() {
+            buffer.extend(addr.to_bytes());
+            match opt_delegate {
+                Some(delegate) => {
+                    buffer.push(1);
+                    buffer.extend(delegate.to_bytes());
+                }
+                None => buffer.push(0),
+            }
+        }
+
         // deferred_credits
         self.deferred_credits_serializer
             .serialize(&value.deferred_credits, buffer)?;
@@ -119,6 +148,7 @@ pub struct PoSChangesDeserializer {
     bit_vec_deserializer: BitVecDeserializer,
     rolls_deserializer: RollsDeserializer,
     production_stats_deserializer: ProductionStatsDeserializer,
+    staking_delegate_changes_deserializer: StakingDelegateChangesDeserializer,
     deferred_credits_deserializer: DeferredCreditsDeserializer,
 }
 
@@ -136,6 +166,9 @@ impl PoSChangesDeserializer {
             production_stats_deserializer: ProductionStatsDeserializer::new(
                 max_production_stats_length,
             ),
+            staking_delegate_changes_deserializer: StakingDelegateChangesDeserializer::new(
+                max_rolls_length,
+            ),
             deferred_credits_deserializer: DeferredCreditsDeserializer::new(
                 thread_count,
                 max_credits_length,
@@ -161,19 +194,82 @@ impl Deserializer<PoSChanges> for PoSChangesDeserializer {
                 context("Failed production_stats deserialization", |input| {
                     self.production_stats_deserializer.deserialize(input)
                 }),
+                context("Failed staking_delegate_changes deserialization", |input| {
+                    self.staking_delegate_changes_deserializer
+                        .deserialize(input)
+                }),
                 context("Failed deferred_credits deserialization", |input| {
                     self.deferred_credits_deserializer.deserialize(input)
                 }),
             )),
         )
         .map(
-            |(seed_bits, roll_changes, production_stats, deferred_credits)| PoSChanges {
+            |(
+                seed_bits,
+                roll_changes,
+                production_stats,
+                staking_delegate_changes,
+                deferred_credits,
+            )| PoSChanges {
                 seed_bits,
                 roll_changes: roll_changes.into_iter().collect(),
                 production_stats,
+                staking_delegate_changes,
                 deferred_credits,
             },
         )
         .parse(buffer)
     }
 }
+
+/// Deserializer for staking delegate changes
+pub struct StakingDelegateChangesDeserializer {
+    length_deserializer: U64VarIntDeserializer,
+    address_deserializer: AddressDeserializer,
+}
+
+impl StakingDelegateChangesDeserializer {
+    /// Creates a new staking delegate changes deserializer
+    pub fn new(max_changes_length: u64) -> StakingDelegateChangesDeserializer {
+        StakingDelegateChangesDeserializer {
+            length_deserializer: U64VarIntDeserializer::new(
+                Included(u64::MIN),
+                Included(max_changes_length),
+            ),
+            address_deserializer: AddressDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<PreHashMap<Address, Option<Address>>> for StakingDelegateChangesDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PreHashMap<Address, Option<Address>>, E> {
+        context(
+            "Failed staking delegate changes deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.length_deserializer.deserialize(input)
+                }),
+                tuple((
+                    context("Failed owner address deserialization", |input| {
+                        self.address_deserializer.deserialize(input)
+                    }),
+                    context(
+                        "Failed delegate deserialization",
+                        alt((
+                            value(None, tag(&[0])),
+                            preceded(tag(&[1]), |input| {
+                                self.address_deserializer.deserialize(input)
+                            })
+                            .map(Some),
+                        )),
+                    ),
+                )),
+            ),
+        )
+        .map(|elements| elements.into_iter().collect())
+        .parse(buffer)
+    }
+}
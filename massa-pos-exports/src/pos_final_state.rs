@@ -59,6 +59,12 @@ impl PoSFinalState {
         })
     }
 
+    /// Removes the cycle history and the deferred credits, used to bootstrap them again from scratch
+    pub fn reset(&mut self) {
+        self.cycle_history.clear();
+        self.deferred_credits = DeferredCredits::default();
+    }
+
     /// Create the initial cycle based off the initial rolls.
     ///
     /// This should be called only if bootstrap did not happen.
@@ -80,6 +86,7 @@ impl PoSFinalState {
             self.initial_rolls.clone(),
             rng_seed,
             PreHashMap::default(),
+            BTreeMap::default(),
         ));
     }
 
@@ -138,6 +145,7 @@ impl PoSFinalState {
     ///     extend `roll_counts` with `changes.roll_changes`
     ///         delete all entries from `roll_counts` for which the roll count is zero
     ///     add each element of `changes.production_stats` to the cycle's `production_stats`
+    ///     set or remove the entries of `changes.staking_delegate_changes` in the cycle's `staking_delegates`
     /// for each `changes.deferred_credits` targeting cycle Ct:
     ///     overwrite `self.deferred_credits` entries of cycle Ct in `cycle_history` with the ones from change
     ///         remove entries for which Amount = 0
@@ -176,6 +184,7 @@ impl PoSFinalState {
                     info.roll_counts.clone(),
                     BitVec::with_capacity(slots_per_cycle),
                     PreHashMap::default(),
+                    info.staking_delegates.clone(),
                 ));
                 while self.cycle_history.len() > self.config.cycle_history_length {
                     self.cycle_history.pop_front();
@@ -231,8 +240,8 @@ impl PoSFinalState {
 
     /// Feeds the selector targeting a given draw cycle
     fn feed_selector(&self, draw_cycle: u64) -> PosResult<()> {
-        // get roll lookback
-        let lookback_rolls = match draw_cycle.checked_sub(3) {
+        // get roll and staking delegates lookback
+        let (lookback_rolls, lookback_delegates) = match draw_cycle.checked_sub(3) {
             // looking back in history
             Some(c) => {
                 let index = self
@@ -242,10 +251,13 @@ impl PoSFinalState {
                 if !cycle_info.complete {
                     return Err(PosError::CycleUnfinished(c));
                 }
-                cycle_info.roll_counts.clone()
+                (
+                    cycle_info.roll_counts.clone(),
+                    cycle_info.staking_delegates.clone(),
+                )
             }
-            // looking back to negative cycles
-            None => self.initial_rolls.clone(),
+            // looking back to negative cycles: no delegates at genesis
+            None => (self.initial_rolls.clone(), BTreeMap::default()),
         };
 
         // get seed lookback
@@ -266,9 +278,12 @@ impl PoSFinalState {
         };
 
         // feed selector
-        self.selector
-            .as_ref()
-            .feed_cycle(draw_cycle, lookback_rolls, lookback_seed)
+        self.selector.as_ref().feed_cycle(
+            draw_cycle,
+            lookback_rolls,
+            lookback_seed,
+            lookback_delegates,
+        )
     }
 
     /// Retrieves the amount of rolls a given address has at the latest cycle
@@ -25,6 +25,8 @@ pub enum MockSelectorControllerMessage {
         lookback_rolls: BTreeMap<Address, u64>,
         /// look back seed
         lookback_seed: Hash,
+        /// look back staking delegates
+        lookback_delegates: BTreeMap<Address, Address>,
     },
     /// Get a list of slots where address has been chosen to produce a block and a list where he is chosen for the endorsements.
     /// Look from the start slot to the end slot.
@@ -38,6 +40,11 @@ pub enum MockSelectorControllerMessage {
         /// Receiver to send the result to
         response_tx: mpsc::Sender<PosResult<(Vec<Slot>, Vec<IndexedSlot>)>>,
     },
+    /// Get the range of cycles for which draws are available
+    GetDrawnCycleRange {
+        /// Receiver to send the result to
+        response_tx: mpsc::Sender<PosResult<Option<(u64, u64)>>>,
+    },
     /// Get the entire selection of PoS. used for testing only
     GetEntireSelection {
         /// response channel
@@ -50,6 +57,15 @@ pub enum MockSelectorControllerMessage {
         /// Receiver to send the result to
         response_tx: mpsc::Sender<PosResult<Address>>,
     },
+    /// Get the staking delegate of an address at a specific slot
+    GetStakingDelegate {
+        /// Slot to search
+        slot: Slot,
+        /// Roll owner address
+        address: Address,
+        /// Receiver to send the result to
+        response_tx: mpsc::Sender<PosResult<Option<Address>>>,
+    },
     /// Get the selection for a block at a specific slot
     GetSelection {
         /// Slot to search
@@ -93,6 +109,7 @@ impl SelectorController for MockSelectorController {
         cycle: u64,
         lookback_rolls: BTreeMap<Address, u64>,
         lookback_seed: Hash,
+        lookback_delegates: BTreeMap<Address, Address>,
     ) -> PosResult<()> {
         self.0
             .lock()
@@ -100,6 +117,7 @@ impl SelectorController for MockSelectorController {
                 cycle,
                 lookback_rolls,
                 lookback_seed,
+                lookback_delegates,
             })
             .unwrap();
         Ok(())
@@ -148,6 +166,15 @@ impl SelectorController for MockSelectorController {
         response_rx.recv().unwrap()
     }
 
+    fn get_drawn_cycle_range(&self) -> PosResult<Option<(u64, u64)>> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .send(MockSelectorControllerMessage::GetDrawnCycleRange { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_producer(&self, slot: Slot) -> PosResult<Address> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -157,6 +184,19 @@ impl SelectorController for MockSelectorController {
         response_rx.recv().unwrap()
     }
 
+    fn get_staking_delegate(&self, slot: Slot, address: &Address) -> PosResult<Option<Address>> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .send(MockSelectorControllerMessage::GetStakingDelegate {
+                slot,
+                address: *address,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_selection(&self, slot: Slot) -> PosResult<Selection> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -0,0 +1,110 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Simulates the selector draws of a cycle from a roll distribution file
+//! and outputs the full draws along with per-address production statistics as JSON.
+//!
+//! Built with the `simulate_draws` feature:
+//! `cargo run -p massa_pos_worker --features simulate_draws --bin simulate_draws -- <rolls file>`
+
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    config::{ENDORSEMENT_COUNT, GENESIS_KEY, PERIODS_PER_CYCLE, THREAD_COUNT},
+    slot::Slot,
+};
+use massa_pos_exports::{Selection, SelectorConfig};
+use massa_pos_worker::{load_rolls_file, simulate_draws, AddressDrawStats};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Args {
+    /// Path of the roll distribution file (same format as `initial_rolls.json`)
+    #[structopt(parse(from_os_str))]
+    rolls: PathBuf,
+    /// Seed string, hashed to obtain the RNG seed of the draws
+    #[structopt(long, required_unless = "seed-hash", conflicts_with = "seed-hash")]
+    seed: Option<String>,
+    /// RNG seed hash of the draws, as used by the selector (look back seed of the cycle)
+    #[structopt(long)]
+    seed_hash: Option<Hash>,
+    /// Cycle to draw
+    #[structopt(long, default_value = "0")]
+    cycle: u64,
+    /// Number of threads (defaults to the network one)
+    #[structopt(long)]
+    thread_count: Option<u8>,
+    /// Number of periods per cycle (defaults to the network one)
+    #[structopt(long)]
+    periods_per_cycle: Option<u64>,
+    /// Number of endorsements per block (defaults to the network one)
+    #[structopt(long)]
+    endorsement_count: Option<u32>,
+    /// Address forced as the creator of genesis blocks (defaults to the network genesis address)
+    #[structopt(long)]
+    genesis_address: Option<Address>,
+    /// Only output the per-address statistics
+    #[structopt(long)]
+    stats_only: bool,
+}
+
+#[derive(Serialize)]
+struct SlotDraw<'a> {
+    slot: Slot,
+    selection: &'a Selection,
+}
+
+#[derive(Serialize)]
+struct SimulationOutput<'a> {
+    cycle: u64,
+    seed: Hash,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    draws: Option<Vec<SlotDraw<'a>>>,
+    address_stats: &'a BTreeMap<Address, AddressDrawStats>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+
+    let seed = match (args.seed_hash, args.seed) {
+        (Some(seed_hash), _) => seed_hash,
+        (None, Some(seed)) => Hash::compute_from(seed.as_bytes()),
+        (None, None) => anyhow::bail!("either --seed or --seed-hash must be provided"),
+    };
+    let cfg = SelectorConfig {
+        thread_count: args.thread_count.unwrap_or(THREAD_COUNT),
+        endorsement_count: args.endorsement_count.unwrap_or(ENDORSEMENT_COUNT),
+        periods_per_cycle: args.periods_per_cycle.unwrap_or(PERIODS_PER_CYCLE),
+        genesis_address: args
+            .genesis_address
+            .unwrap_or_else(|| Address::from_public_key(&GENESIS_KEY.get_public_key())),
+        // unused by the simulation
+        max_draw_cache: 0,
+        channel_size: 0,
+    };
+
+    let rolls = load_rolls_file(&args.rolls)?;
+    let simulation = simulate_draws(&cfg, args.cycle, rolls, seed)?;
+
+    let draws = if args.stats_only {
+        None
+    } else {
+        Some(
+            simulation
+                .sorted_draws()
+                .into_iter()
+                .map(|(slot, selection)| SlotDraw { slot, selection })
+                .collect(),
+        )
+    };
+    let output = SimulationOutput {
+        cycle: args.cycle,
+        seed,
+        draws,
+        address_stats: &simulation.address_stats,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
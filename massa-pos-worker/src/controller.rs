@@ -55,11 +55,13 @@ impl SelectorController for SelectorControllerImpl {
     /// * `cycle`: cycle number to be drawn
     /// * `lookback_rolls`: look back rolls used for the draw (cycle - 3)
     /// * `lookback_seed`: look back seed hash for the draw (cycle - 2)
+    /// * `lookback_delegates`: look back staking delegates of the roll owners (cycle - 3)
     fn feed_cycle(
         &self,
         cycle: u64,
         lookback_rolls: BTreeMap<Address, u64>,
         lookback_seed: Hash,
+        lookback_delegates: BTreeMap<Address, Address>,
     ) -> PosResult<()> {
         // check status
         {
@@ -73,6 +75,7 @@ impl SelectorController for SelectorControllerImpl {
                 cycle,
                 lookback_rolls,
                 lookback_seed,
+                lookback_delegates,
             })
             .map_err(|_err| {
                 PosError::ChannelDown(
@@ -112,6 +115,22 @@ impl SelectorController for SelectorControllerImpl {
             .ok_or(PosError::CycleUnavailable(cycle))
     }
 
+    /// Get the staking delegate of a roll owner at a given slot, if it designated one
+    /// # Arguments
+    /// * `slot`: target slot
+    /// * `address`: roll owner address
+    fn get_staking_delegate(&self, slot: Slot, address: &Address) -> PosResult<Option<Address>> {
+        let cycle = slot.get_cycle(self.periods_per_cycle);
+        let (_cache_cv, cache_lock) = &*self.cache;
+        let cache_guard = cache_lock.read();
+        let cache = cache_guard.as_ref().map_err(|err| err.clone())?;
+
+        cache
+            .get(cycle)
+            .map(|selections| selections.staking_delegates.get(address).copied())
+            .ok_or(PosError::CycleUnavailable(cycle))
+    }
+
     /// Return a list of slots where `address` has been chosen to produce a
     /// block and a list where he is chosen for the endorsements.
     /// Look from the `start` slot to the `end` slot.
@@ -146,6 +165,20 @@ impl SelectorController for SelectorControllerImpl {
         Ok((slot_producers, slot_endorsers))
     }
 
+    /// Get the range of cycles for which draws are available,
+    /// as `(first_cycle, last_cycle)` (both included).
+    /// Returns `None` if no cycle has been drawn yet.
+    fn get_drawn_cycle_range(&self) -> PosResult<Option<(u64, u64)>> {
+        let (_cache_cv, cache_lock) = &*self.cache;
+        let cache_guard = cache_lock.read();
+        let cache = cache_guard.as_ref().map_err(|err| err.clone())?;
+        Ok(cache
+            .0
+            .front()
+            .zip(cache.0.back())
+            .map(|(first, last)| (first.cycle, last.cycle)))
+    }
+
     /// Returns a boxed clone of self.
     /// Allows cloning `Box<dyn SelectorController>`,
     /// see `massa-pos-exports/controller_traits.rs`
@@ -55,6 +55,7 @@ pub(crate) fn perform_draws(
         draws: HashMap::with_capacity(
             (cfg.periods_per_cycle as usize) * (cfg.thread_count as usize),
         ),
+        staking_delegates: BTreeMap::default(),
     };
 
     let mut five_first_slots: Vec<(Slot, Selection)> = Vec::new();
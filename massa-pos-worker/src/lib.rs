@@ -4,6 +4,7 @@
 
 mod controller;
 mod draw;
+mod simulation;
 mod worker;
 
 use massa_hash::Hash;
@@ -24,6 +25,7 @@ pub(crate) enum Command {
         cycle: u64,
         lookback_rolls: BTreeMap<Address, u64>,
         lookback_seed: Hash,
+        lookback_delegates: BTreeMap<Address, Address>,
     },
     /// Stop the thread (usually sent by the manager and pushed at the top
     /// of the command queue)
@@ -62,11 +64,13 @@ impl DrawCache {
 
 /// Draws for a cycle, used in selector cache
 #[derive(Debug)]
-pub(crate) struct CycleDraws {
+pub struct CycleDraws {
     /// cycle number
     pub cycle: u64,
     /// cache of draws
     pub draws: HashMap<Slot, Selection>,
+    /// staking delegates of the roll owners for this cycle (owner, delegate)
+    pub staking_delegates: BTreeMap<Address, Address>,
 }
 
 /// Structure of the shared pointer to the computed draws, or error if the draw system failed.
@@ -75,6 +79,9 @@ pub(crate) type DrawCachePtr = Arc<(RwLockCondvar, RwLock<PosResult<DrawCache>>)
 /// Start thread selector
 pub use worker::start_selector_worker;
 
+/// Offline draw simulation
+pub use simulation::{load_rolls_file, simulate_draws, AddressDrawStats, DrawSimulation};
+
 // an RwLock condvar
 #[derive(Default)]
 struct RwLockCondvar {
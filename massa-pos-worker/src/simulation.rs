@@ -0,0 +1,199 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Offline simulation of the selector draws, used to analyse the fairness of
+//! the draws of a given roll distribution and to reproduce selector issues.
+
+use crate::{draw::perform_draws, CycleDraws};
+use massa_hash::Hash;
+use massa_models::{address::Address, slot::Slot};
+use massa_pos_exports::{PosError, PosResult, Selection, SelectorConfig};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// Expected and actual production counts of an address over a simulated cycle
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AddressDrawStats {
+    /// number of rolls used for the draws
+    pub rolls: u64,
+    /// expected number of block draws, excluding the genesis slots
+    pub expected_blocks: f64,
+    /// actual number of block draws, excluding the genesis slots
+    pub actual_blocks: u64,
+    /// expected number of endorsement draws
+    pub expected_endorsements: f64,
+    /// actual number of endorsement draws
+    pub actual_endorsements: u64,
+}
+
+/// Result of a simulated cycle draw
+#[derive(Debug)]
+pub struct DrawSimulation {
+    /// the full draws of the cycle
+    pub cycle_draws: CycleDraws,
+    /// production statistics of every address holding rolls
+    pub address_stats: BTreeMap<Address, AddressDrawStats>,
+}
+
+impl DrawSimulation {
+    /// get the draws sorted by slot
+    pub fn sorted_draws(&self) -> Vec<(Slot, &Selection)> {
+        let mut draws: Vec<_> = self
+            .cycle_draws
+            .draws
+            .iter()
+            .map(|(slot, selection)| (*slot, selection))
+            .collect();
+        draws.sort_unstable_by_key(|(slot, _)| *slot);
+        draws
+    }
+}
+
+/// Loads a roll distribution from a file using the format of `initial_rolls.json`
+pub fn load_rolls_file(path: &Path) -> PosResult<BTreeMap<Address, u64>> {
+    serde_json::from_str::<BTreeMap<Address, u64>>(
+        &std::fs::read_to_string(path).map_err(|err| {
+            PosError::RollsFileLoadingError(format!("error opening file: {}", err))
+        })?,
+    )
+    .map_err(|err| PosError::RollsFileLoadingError(format!("error while deserializing: {}", err)))
+}
+
+/// Performs the draws of a cycle exactly like the selector would,
+/// and compares the production of every address to its expected production.
+///
+/// # Parameters
+/// * `cfg`: selector configuration (thread count, periods per cycle, endorsement count and genesis address are used)
+/// * `cycle`: cycle to draw
+/// * `rolls`: roll distribution used for the draws
+/// * `seed`: RNG seed used for the draws
+pub fn simulate_draws(
+    cfg: &SelectorConfig,
+    cycle: u64,
+    rolls: BTreeMap<Address, u64>,
+    seed: Hash,
+) -> PosResult<DrawSimulation> {
+    let total_rolls = rolls.values().fold(0u64, |acc, r| acc.saturating_add(*r));
+    let cycle_draws = perform_draws(cfg, cycle, rolls.clone(), seed)?;
+
+    // genesis slots are forced to the genesis address and are excluded from block statistics
+    let block_draw_count = cycle_draws
+        .draws
+        .keys()
+        .filter(|slot| slot.period > 0)
+        .count() as f64;
+    let endorsement_draw_count = cycle_draws.draws.len() as f64 * f64::from(cfg.endorsement_count);
+
+    let mut address_stats: BTreeMap<Address, AddressDrawStats> = rolls
+        .into_iter()
+        .filter(|(_, roll_count)| *roll_count > 0)
+        .map(|(addr, roll_count)| {
+            let share = roll_count as f64 / total_rolls as f64;
+            (
+                addr,
+                AddressDrawStats {
+                    rolls: roll_count,
+                    expected_blocks: share * block_draw_count,
+                    expected_endorsements: share * endorsement_draw_count,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    for (slot, selection) in cycle_draws.draws.iter() {
+        if slot.period > 0 {
+            if let Some(stats) = address_stats.get_mut(&selection.producer) {
+                stats.actual_blocks += 1;
+            }
+        }
+        for endorser in selection.endorsements.iter() {
+            if let Some(stats) = address_stats.get_mut(endorser) {
+                stats.actual_endorsements += 1;
+            }
+        }
+    }
+
+    Ok(DrawSimulation {
+        cycle_draws,
+        address_stats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    /// Builds a keypair from a fixed secret so that the address order, and thus the draws, are reproducible
+    fn fixed_keypair(index: u8) -> KeyPair {
+        KeyPair::from_bytes(&[index; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_simulated_draws_follow_roll_distribution() {
+        let cfg = SelectorConfig {
+            thread_count: 32,
+            endorsement_count: 16,
+            max_draw_cache: 10,
+            periods_per_cycle: 128,
+            genesis_address: Address::from_public_key(&fixed_keypair(0).get_public_key()),
+            channel_size: 1024,
+        };
+        let rolls: BTreeMap<Address, u64> = (1..=4u8)
+            .map(|i| {
+                (
+                    Address::from_public_key(&fixed_keypair(i).get_public_key()),
+                    u64::from(i) * 10,
+                )
+            })
+            .collect();
+        let seed = Hash::compute_from(b"massa draw simulation test seed");
+
+        let simulation = simulate_draws(&cfg, 0, rolls.clone(), seed).unwrap();
+        let same_simulation = simulate_draws(&cfg, 0, rolls.clone(), seed).unwrap();
+        assert_eq!(simulation.address_stats, same_simulation.address_stats);
+
+        // the genesis slots of cycle 0 are excluded from the block statistics
+        let slot_count = u64::from(cfg.thread_count) * cfg.periods_per_cycle;
+        let block_draws = slot_count - u64::from(cfg.thread_count);
+        assert_eq!(
+            simulation
+                .address_stats
+                .values()
+                .map(|stats| stats.actual_blocks)
+                .sum::<u64>(),
+            block_draws
+        );
+        assert_eq!(
+            simulation
+                .address_stats
+                .values()
+                .map(|stats| stats.actual_endorsements)
+                .sum::<u64>(),
+            slot_count * u64::from(cfg.endorsement_count)
+        );
+
+        // every count is within 5 standard deviations of its expectation
+        for (addr, stats) in simulation.address_stats.iter() {
+            assert_eq!(stats.rolls, rolls[addr]);
+            let share = stats.rolls as f64 / 100.0;
+            assert!((stats.expected_blocks - share * block_draws as f64).abs() < 1e-6);
+            let block_tolerance = 5.0 * (stats.expected_blocks * (1.0 - share)).sqrt();
+            assert!(
+                (stats.actual_blocks as f64 - stats.expected_blocks).abs() < block_tolerance,
+                "{} drew {} blocks, expected {}",
+                addr,
+                stats.actual_blocks,
+                stats.expected_blocks
+            );
+            let endorsement_tolerance = 5.0 * (stats.expected_endorsements * (1.0 - share)).sqrt();
+            assert!(
+                (stats.actual_endorsements as f64 - stats.expected_endorsements).abs()
+                    < endorsement_tolerance,
+                "{} drew {} endorsements, expected {}",
+                addr,
+                stats.actual_endorsements,
+                stats.expected_endorsements
+            );
+        }
+    }
+}
@@ -109,19 +109,26 @@ impl SelectorThread {
     /// draws for future cycle.
     fn run(self) -> PosResult<()> {
         loop {
-            let (cycle, lookback_rolls, lookback_seed) = match self.input_mpsc.recv() {
-                Err(_) => break,
-                Ok(Command::Stop) => break,
-
-                Ok(Command::DrawInput {
-                    cycle,
-                    lookback_rolls,
-                    lookback_seed,
-                }) => (cycle, lookback_rolls, lookback_seed),
-            };
-
-            // perform draws
-            let draws_result = perform_draws(&self.cfg, cycle, lookback_rolls, lookback_seed);
+            let (cycle, lookback_rolls, lookback_seed, lookback_delegates) =
+                match self.input_mpsc.recv() {
+                    Err(_) => break,
+                    Ok(Command::Stop) => break,
+
+                    Ok(Command::DrawInput {
+                        cycle,
+                        lookback_rolls,
+                        lookback_seed,
+                        lookback_delegates,
+                    }) => (cycle, lookback_rolls, lookback_seed, lookback_delegates),
+                };
+
+            // perform draws and attach the staking delegates of the drawn roll owners
+            let draws_result = perform_draws(&self.cfg, cycle, lookback_rolls, lookback_seed).map(
+                |mut cycle_draws| {
+                    cycle_draws.staking_delegates = lookback_delegates;
+                    cycle_draws
+                },
+            );
 
             // add result to cache and notify waiters
             self.process_draws_result(cycle, draws_result)?;
@@ -7,12 +7,17 @@ use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::{
     block::{BlockId, WrappedHeader},
     endorsement::EndorsementId,
+    node::NodeId,
     operation::OperationId,
 };
 use massa_network_exports::NetworkEventReceiver;
 use massa_storage::Storage;
 use serde::Serialize;
-use tokio::{sync::mpsc, task::JoinHandle};
+use std::collections::HashMap;
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
 use tracing::info;
 
 /// block result: map block id to
@@ -50,6 +55,8 @@ pub enum ProtocolCommand {
     PropagateOperations(Storage),
     /// Propagate endorsements
     PropagateEndorsements(Storage),
+    /// Get the number of blocks asked to each active node and not received yet
+    GetAskBlockQueueDepths(oneshot::Sender<HashMap<NodeId, usize>>),
 }
 
 /// protocol management commands
@@ -130,6 +137,23 @@ impl ProtocolCommandSender {
                 ProtocolError::ChannelError("propagate_endorsements command send error".into())
             })
     }
+
+    /// get the number of blocks asked to each active node and not received yet
+    pub async fn get_ask_block_queue_depths(
+        &self,
+    ) -> Result<HashMap<NodeId, usize>, ProtocolError> {
+        massa_trace!("protocol.command_sender.get_ask_block_queue_depths", {});
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(ProtocolCommand::GetAskBlockQueueDepths(response_tx))
+            .await
+            .map_err(|_| {
+                ProtocolError::ChannelError("get_ask_block_queue_depths command send error".into())
+            })?;
+        response_rx.await.map_err(|_| {
+            ProtocolError::ChannelError("get_ask_block_queue_depths response read error".into())
+        })
+    }
 }
 
 /// protocol manager used to stop the protocol
@@ -270,7 +270,7 @@ pub async fn assert_banned_nodes(
         tokio::select! {
             msg = network_controller
                    .wait_command(2000.into(), |cmd| match cmd {
-                       NetworkCommand::NodeBanByIds(node) => Some(node),
+                       NetworkCommand::NodeBanByIds { ids, .. } => Some(ids),
                        _ => None,
                    })
              =>  {
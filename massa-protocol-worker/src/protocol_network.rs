@@ -11,7 +11,7 @@ use massa_logging::massa_trace;
 use massa_models::{
     block::Block,
     block::{BlockId, BlockSerializer, WrappedHeader},
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::{OperationId, WrappedOperation},
     prehash::{CapacityAllocator, PreHashSet},
     wrapped::{Id, Wrapped},
@@ -109,7 +109,9 @@ impl ProtocolWorker {
                         or a loss of sync between us and the remote node",
                         source_node_id,
                     );
-                    let _ = self.ban_node(&source_node_id).await;
+                    let _ = self
+                        .ban_node(&source_node_id, BanReason::InvalidHeader)
+                        .await;
                 }
             }
             NetworkEvent::ReceivedOperations { node, operations } => {
@@ -129,7 +131,7 @@ impl ProtocolWorker {
                         loss of sync between us and the remote node. Err = {}",
                         node, err
                     );
-                    let _ = self.ban_node(&node).await;
+                    let _ = self.ban_node(&node, BanReason::InvalidEndorsement).await;
                 }
             }
             NetworkEvent::ReceivedOperationAnnouncements {
@@ -261,7 +263,7 @@ impl ProtocolWorker {
                 or a loss of sync between us and the remote node. Err = {}",
                 from_node_id, err
             );
-            let _ = self.ban_node(&from_node_id).await;
+            let _ = self.ban_node(&from_node_id, BanReason::InvalidHeader).await;
             return Ok(());
         };
         if let Some(info) = self.block_wishlist.get_mut(&block_id) {
@@ -366,7 +368,9 @@ impl ProtocolWorker {
 
             if info.operations_size > self.config.max_serialized_operations_size_per_block {
                 warn!("Node id {} sent us a operation list for block id {} but the operations we already have in our records exceed max size.", from_node_id, block_id);
-                let _ = self.ban_node(&from_node_id).await;
+                let _ = self
+                    .ban_node(&from_node_id, BanReason::InvalidBlockOperations)
+                    .await;
                 return Ok(());
             }
 
@@ -388,7 +392,9 @@ impl ProtocolWorker {
             }
         } else {
             warn!("Node id {} sent us a operation list for block id {} but the hash in header doesn't match.", from_node_id, block_id);
-            let _ = self.ban_node(&from_node_id).await;
+            let _ = self
+                .ban_node(&from_node_id, BanReason::InvalidBlockOperations)
+                .await;
         }
         Ok(())
     }
@@ -421,7 +427,9 @@ impl ProtocolWorker {
                 "Node id {} sent us operations for block id {} but they failed at verifications. Err = {}",
                 from_node_id, block_id, err
             );
-            let _ = self.ban_node(&from_node_id).await;
+            let _ = self
+                .ban_node(&from_node_id, BanReason::InvalidBlockOperations)
+                .await;
             return Ok(());
         }
 
@@ -466,7 +474,9 @@ impl ProtocolWorker {
                 };
                 if full_op_size > self.config.max_serialized_operations_size_per_block {
                     warn!("Node id {} sent us full operations for block id {} but they exceed max size.", from_node_id, block_id);
-                    let _ = self.ban_node(&from_node_id).await;
+                    let _ = self
+                        .ban_node(&from_node_id, BanReason::InvalidBlockOperations)
+                        .await;
                     self.block_wishlist.remove(&block_id);
                     self.consensus_controller
                         .mark_invalid_block(block_id, header);
@@ -499,6 +509,7 @@ impl ProtocolWorker {
                         signature: header.signature,
                         creator_public_key: header.creator_public_key,
                         creator_address: header.creator_address,
+                        multisig: None,
                         id: block_id,
                         content: block,
                         serialized_data: content_serialized,
@@ -13,7 +13,7 @@ use massa_models::timeslots::get_block_slot_timestamp;
 use massa_models::{
     block::{BlockId, WrappedHeader},
     endorsement::{EndorsementId, WrappedEndorsement},
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::OperationPrefixId,
     operation::{OperationId, WrappedOperation},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
@@ -463,7 +463,7 @@ impl ProtocolWorker {
                     .collect();
                 for id in to_ban.iter() {
                     massa_trace!("protocol.protocol_worker.process_command.attack_block_detected.ban_node", { "node": id, "block_id": block_id });
-                    self.ban_node(id).await?;
+                    self.ban_node(id, BanReason::AttackBlock).await?;
                 }
                 massa_trace!(
                     "protocol.protocol_worker.process_command.attack_block_detected.end",
@@ -511,6 +511,16 @@ impl ProtocolWorker {
             ProtocolCommand::PropagateEndorsements(endorsements) => {
                 self.propagate_endorsements(&endorsements).await;
             }
+            ProtocolCommand::GetAskBlockQueueDepths(response_tx) => {
+                let depths = self
+                    .active_nodes
+                    .iter()
+                    .map(|(node_id, node_info)| (*node_id, node_info.asked_blocks.len()))
+                    .collect();
+                if response_tx.send(depths).is_err() {
+                    warn!("protocol: could not send ask block queue depths upstream");
+                }
+            }
         }
         massa_trace!("protocol.protocol_worker.process_command.end", {});
         Ok(())
@@ -738,15 +748,19 @@ impl ProtocolWorker {
         Ok(())
     }
 
-    /// Ban a node.
-    pub(crate) async fn ban_node(&mut self, node_id: &NodeId) -> Result<(), ProtocolError> {
-        massa_trace!("protocol.protocol_worker.ban_node", { "node": node_id });
+    /// Ban a node for `ban_duration`, as configured in the network.
+    pub(crate) async fn ban_node(
+        &mut self,
+        node_id: &NodeId,
+        reason: BanReason,
+    ) -> Result<(), ProtocolError> {
+        massa_trace!("protocol.protocol_worker.ban_node", { "node": node_id, "reason": reason });
         self.active_nodes.remove(node_id);
         if self.active_nodes.is_empty() {
             info!("Not connected to any peers.");
         }
         self.network_command_sender
-            .node_ban_by_ids(vec![*node_id])
+            .node_ban_by_ids(vec![*node_id], None, reason)
             .await
             .map_err(|_| ProtocolError::ChannelError("Ban node command send failed".into()))?;
         Ok(())
@@ -913,13 +927,30 @@ impl ProtocolWorker {
             };
         }
 
+        // gather the signatures to check, multisig operations bring one per signer
+        let mut signatures = Vec::with_capacity(new_operations.len());
+        for (op_id, op) in new_operations.iter() {
+            match &op.multisig {
+                Some(multisig) => {
+                    // the policy was checked on deserialization, only the threshold remains
+                    if !multisig.is_complete() {
+                        return Err(ProtocolError::WrongSignature);
+                    }
+                    let signed_by = multisig
+                        .signed_by()
+                        .map_err(|_err| ProtocolError::WrongSignature)?;
+                    signatures.extend(
+                        signed_by.into_iter().map(|(signature, public_key)| {
+                            (*op_id.get_hash(), signature, public_key)
+                        }),
+                    );
+                }
+                None => signatures.push((*op_id.get_hash(), op.signature, op.creator_public_key)),
+            }
+        }
+
         // optimized signature verification
-        verify_sigs_batch(
-            &new_operations
-                .iter()
-                .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.creator_public_key))
-                .collect::<Vec<_>>(),
-        )?;
+        verify_sigs_batch(&signatures)?;
 
         // add to checked operations
         self.checked_operations
@@ -13,7 +13,7 @@ use std::collections::VecDeque;
 use crate::protocol_worker::ProtocolWorker;
 use massa_logging::massa_trace;
 use massa_models::{
-    node::NodeId,
+    node::{BanReason, NodeId},
     operation::{OperationPrefixIds, WrappedOperation},
     prehash::CapacityAllocator,
 };
@@ -149,7 +149,7 @@ impl ProtocolWorker {
             .await
         {
             warn!("node {} sent us critically incorrect operation, which may be an attack attempt by the remote node or a loss of sync between us and the remote node. Err = {}", node_id, err);
-            let _ = self.ban_node(&node_id).await;
+            let _ = self.ban_node(&node_id, BanReason::InvalidOperation).await;
         }
     }
 
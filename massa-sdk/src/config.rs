@@ -1,5 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::TlsClientConfig;
 use massa_time::MassaTime;
 
 /// Http client settings.
@@ -20,4 +21,27 @@ pub struct HttpConfig {
     pub max_log_length: u32,
     /// custom headers to pass with every request.
     pub headers: Vec<(String, String)>,
+    /// bearer token passed to the private API, if it requires authentication.
+    pub private_api_token: Option<String>,
+    /// TLS settings, the node is reached in plain text if `None`.
+    pub tls: Option<TlsClientConfig>,
+    /// delay before the first websocket reconnection attempt, doubled after each failure.
+    pub reconnect_initial_delay: MassaTime,
+    /// maximum delay between two websocket reconnection attempts.
+    pub reconnect_max_delay: MassaTime,
+    /// number of websocket connection attempts before giving up.
+    pub reconnect_max_attempts: u32,
+}
+
+impl HttpConfig {
+    /// Copy of the settings adding the bearer token of the private API to the headers, if any
+    pub(crate) fn with_private_api_token_header(&self) -> HttpConfig {
+        let mut http_config = self.clone();
+        if let Some(token) = &self.private_api_token {
+            http_config
+                .headers
+                .push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        http_config
+    }
 }
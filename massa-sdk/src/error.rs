@@ -0,0 +1,49 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use displaydoc::Display;
+use massa_models::{address::Address, amount::Amount, operation::OperationId};
+use thiserror::Error;
+
+/// SDK error
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum SdkError {
+    /// connection error: {0}
+    ConnectionError(String),
+    /// RPC error: {0}
+    RpcError(#[from] jsonrpsee::core::Error),
+    /// Models error: {0}
+    ModelsError(#[from] massa_models::error::ModelsError),
+    /// Time error: {0}
+    TimeError(#[from] massa_time::TimeError),
+    /// Serialization error: {0}
+    SerializationError(#[from] massa_serialization::SerializeError),
+    /// address {0} not found
+    AddressNotFound(Address),
+    /// insufficient balance: {needed} needed, {available} available
+    InsufficientBalance {
+        /// coins needed by the operation
+        needed: Amount,
+        /// candidate balance of the creator
+        available: Amount,
+    },
+    /// insufficient roll count: {needed} needed, {available} available
+    InsufficientRolls {
+        /// rolls sold by the operation
+        needed: u64,
+        /// candidate roll count of the creator
+        available: u64,
+    },
+    /// the amount spent by the operation overflows
+    AmountOverflow,
+    /// gas estimation failed: {0}
+    GasEstimationError(String),
+    /// operation {0} expired before reaching the expected status
+    OperationExpired(OperationId),
+    /// offline operation error: {0}
+    OfflineFormatError(String),
+    /// the node did not return the expected value: {0}
+    MissingReturnValue(String),
+    /// the multisig policy controls {0}, not the creator address {1}
+    MultisigPolicyMismatch(Address, Address),
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! HTTPS transport of the SDK, used when TLS is enabled.
+//!
+//! The connections are opened by a `NodeTlsConnector`, so that the node certificate is checked
+//! against the pinned certificates or the configured store, and no plain-text hop is involved.
+
+use crate::tls::NodeTlsConnector;
+use crate::HttpConfig;
+use http::header::{HeaderMap, CONTENT_TYPE};
+use hyper::{body::HttpBody, Body, Client, Request, Uri};
+use jsonrpsee::core::traits::ToRpcParams;
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::types::error::{CallError, ErrorObjectOwned};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// JSON-RPC client sending its calls over HTTPS
+pub(crate) struct TlsHttpClient {
+    client: Client<NodeTlsConnector>,
+    /// URI of the calls, naming the node as its certificate does
+    uri: Uri,
+    headers: HeaderMap,
+    request_timeout: Duration,
+    max_request_body_size: u32,
+    /// whether the call ids are sent as strings
+    string_ids: bool,
+    next_id: AtomicU64,
+    /// limits the number of calls in progress
+    concurrent_requests: Semaphore,
+}
+
+impl TlsHttpClient {
+    /// Client of the node API reached through `connector` as `authority`
+    pub(crate) fn new(
+        connector: NodeTlsConnector,
+        authority: &str,
+        headers: HeaderMap,
+        http_config: &HttpConfig,
+    ) -> Result<TlsHttpClient, String> {
+        let uri = Uri::from_str(&format!("https://{}/", authority))
+            .map_err(|err| format!("invalid node address {}: {}", authority, err))?;
+        Ok(TlsHttpClient {
+            client: Client::builder().build(connector),
+            uri,
+            headers,
+            request_timeout: http_config.request_timeout.to_duration(),
+            max_request_body_size: http_config.max_request_body_size,
+            string_ids: http_config.id_kind == "String",
+            next_id: AtomicU64::new(0),
+            concurrent_requests: Semaphore::new(http_config.max_concurrent_requests),
+        })
+    }
+
+    /// Call `method`
+    pub(crate) async fn request<R, Params>(&self, method: &str, params: Params) -> RpcResult<R>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut call = Map::new();
+        call.insert("jsonrpc".to_string(), Value::from("2.0"));
+        call.insert(
+            "id".to_string(),
+            if self.string_ids {
+                Value::from(id.to_string())
+            } else {
+                Value::from(id)
+            },
+        );
+        call.insert("method".to_string(), Value::from(method));
+        if let Some(params) = params.to_rpc_params()? {
+            call.insert("params".to_string(), serde_json::from_str(params.get())?);
+        }
+        let body = Value::Object(call).to_string();
+        if body.len() > self.max_request_body_size as usize {
+            return Err(JsonRpseeError::Custom(format!(
+                "request of {} bytes exceeds the maximum of {} bytes",
+                body.len(),
+                self.max_request_body_size
+            )));
+        }
+
+        let mut request = Request::post(self.uri.clone());
+        for (name, value) in self.headers.iter() {
+            request = request.header(name, value);
+        }
+        let request = request
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .map_err(|err| JsonRpseeError::Custom(err.to_string()))?;
+
+        let _permit = self
+            .concurrent_requests
+            .acquire()
+            .await
+            .map_err(|err| JsonRpseeError::Custom(err.to_string()))?;
+        let (status, response) = tokio::time::timeout(self.request_timeout, async {
+            let response = self.client.request(request).await?;
+            let status = response.status();
+            let mut body = response.into_body();
+            let mut bytes = Vec::new();
+            while let Some(chunk) = body.data().await {
+                bytes.extend_from_slice(&chunk?);
+            }
+            Ok::<_, hyper::Error>((status, bytes))
+        })
+        .await
+        .map_err(|_| JsonRpseeError::RequestTimeout)?
+        .map_err(|err| JsonRpseeError::Custom(format!("HTTPS transport error: {}", err)))?;
+
+        let mut response: Map<String, Value> = match serde_json::from_slice(&response) {
+            Ok(response) => response,
+            Err(_) if !status.is_success() => {
+                return Err(JsonRpseeError::Custom(format!(
+                    "the node answered HTTP {}",
+                    status
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(error) = response.remove("error") {
+            let error: ErrorObjectOwned = serde_json::from_value(error)?;
+            return Err(JsonRpseeError::Call(CallError::Custom(error)));
+        }
+        match response.remove("result") {
+            Some(result) => Ok(serde_json::from_value(result)?),
+            None => Err(JsonRpseeError::Custom(
+                "the node answered neither a result nor an error".to_string(),
+            )),
+        }
+    }
+}
@@ -5,31 +5,59 @@
 #![warn(unused_crate_dependencies)]
 
 use http::header::HeaderName;
+use http::Uri;
 use jsonrpsee::core::client::{CertificateStore, ClientT, IdKind};
+use jsonrpsee::core::traits::ToRpcParams;
 use jsonrpsee::http_client::HttpClient;
 use jsonrpsee::rpc_params;
 use jsonrpsee::ws_client::{HeaderMap, HeaderValue};
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, AddressSelections, BannedPeer, BlockInfo, BlockSummary, BootstrapSessionInfo,
+    ConnectedPeer, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    FinalStateHash, LedgerProof, LedgerProofInput, NodeStatus, OperationInfo, OperationInput,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, SelectionsFilter, StakingReport, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::ip_range::IpRange;
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::{
-    address::Address, block::BlockId, endorsement::EndorsementId, operation::OperationId,
+    address::Address,
+    block::{Block, BlockId},
+    endorsement::EndorsementId,
+    operation::OperationId,
+    slot::Slot,
 };
+use massa_time::MassaTime;
 
 use jsonrpsee::{core::Error as JsonRpseeError, core::RpcResult, http_client::HttpClientBuilder};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 
 mod config;
+mod error;
+mod https;
+mod offline;
+mod operation;
+mod subscription;
+mod tls;
+mod ws;
 pub use config::HttpConfig;
+pub use error::SdkError;
+use https::TlsHttpClient;
+pub use offline::{
+    decode_operation_to_sign, decode_signed_operation, encode_signed_operation, OperationToSign,
+    PartiallySignedOperation, UnsignedOperation,
+};
+pub use operation::{OperationBuilder, OperationStatus};
+use serde::de::DeserializeOwned;
+pub use subscription::{NodeSubscription, SubscriptionStream};
+use tls::NodeTlsConnector;
+pub use tls::TlsClientConfig;
+use ws::{ReconnectingWsClient, WsTlsTarget};
 
 /// Client
 pub struct Client {
@@ -46,26 +74,112 @@ impl Client {
         public_port: u16,
         private_port: u16,
         http_config: &HttpConfig,
-    ) -> Client {
+    ) -> Result<Client, SdkError> {
         let public_socket_addr = SocketAddr::new(ip, public_port);
         let private_socket_addr = SocketAddr::new(ip, private_port);
-        let public_url = format!("http://{}", public_socket_addr);
-        let private_url = format!("http://{}", private_socket_addr);
-        Client {
-            public: RpcClient::from_url(&public_url, http_config).await,
-            private: RpcClient::from_url(&private_url, http_config).await,
-        }
+        Ok(Client {
+            public: RpcClient::from_addr(public_socket_addr, http_config).await?,
+            private: RpcClient::from_addr(
+                private_socket_addr,
+                &http_config.with_private_api_token_header(),
+            )
+            .await?,
+        })
+    }
+
+    /// creates a new client from the URLs of the APIs.
+    /// Each API is reached over websocket if its URL scheme is `ws` or `wss`, over HTTP otherwise.
+    pub async fn from_urls(
+        public_url: &str,
+        private_url: &str,
+        http_config: &HttpConfig,
+    ) -> Result<Client, SdkError> {
+        Ok(Client {
+            public: RpcClient::from_url(public_url, http_config).await?,
+            private: RpcClient::from_url(private_url, &http_config.with_private_api_token_header())
+                .await?,
+        })
     }
 }
 
-/// TODO add ws client
+/// Connection to the node
+enum Transport {
+    Http(HttpClient),
+    Https(TlsHttpClient),
+    Ws(ReconnectingWsClient),
+}
+
+/// Client of one of the node APIs, over HTTP or websocket
 pub struct RpcClient {
-    http_client: HttpClient,
+    transport: Transport,
 }
 
 impl RpcClient {
-    /// Default constructor
-    pub async fn from_url(url: &str, http_config: &HttpConfig) -> RpcClient {
+    /// Call `method` on the node
+    async fn request<R, Params>(&self, method: &str, params: Params) -> RpcResult<R>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        match &self.transport {
+            Transport::Http(client) => client.request(method, params).await,
+            Transport::Https(client) => client.request(method, params).await,
+            Transport::Ws(client) => client.request(method, params).await,
+        }
+    }
+
+    /// Subscribe to the notifications sent by the node for `subscribe_method`.
+    /// Only available over websocket: the stream ends if the connection is lost.
+    pub async fn subscribe<N, Params>(
+        &self,
+        subscribe_method: &str,
+        params: Params,
+        unsubscribe_method: &str,
+    ) -> Result<SubscriptionStream<N>, SdkError>
+    where
+        N: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        match &self.transport {
+            Transport::Http(_) | Transport::Https(_) => Err(JsonRpseeError::Custom(
+                "subscriptions need a websocket connection".into(),
+            )
+            .into()),
+            Transport::Ws(client) => Ok(SubscriptionStream::new(
+                client
+                    .subscribe(subscribe_method, params, unsubscribe_method)
+                    .await?,
+            )),
+        }
+    }
+
+    /// Subscribe to `S`. Only available over websocket: the stream ends if the connection is lost.
+    pub async fn subscribe_to<S, Params>(
+        &self,
+        params: Params,
+    ) -> Result<SubscriptionStream<S::Item>, SdkError>
+    where
+        S: NodeSubscription,
+        Params: ToRpcParams + Send,
+    {
+        self.subscribe(S::SUBSCRIBE_METHOD, params, S::UNSUBSCRIBE_METHOD)
+            .await
+    }
+
+    /// Connect to the node API listening on `addr`, over TLS if enabled in `http_config`
+    pub async fn from_addr(
+        addr: SocketAddr,
+        http_config: &HttpConfig,
+    ) -> Result<RpcClient, SdkError> {
+        RpcClient::from_url(&format!("http://{}", addr), http_config).await
+    }
+
+    /// Connect to `url`, over websocket if its scheme is `ws` or `wss`, over HTTP otherwise.
+    /// If TLS is enabled in `http_config`, the node is reached over TLS whatever the scheme.
+    pub async fn from_url(url: &str, http_config: &HttpConfig) -> Result<RpcClient, SdkError> {
+        let uri = Uri::from_str(url)
+            .map_err(|err| SdkError::ConnectionError(format!("invalid URL {}: {}", url, err)))?;
+        let is_ws = matches!(uri.scheme_str(), Some("ws") | Some("wss"));
         let certificate_store = match http_config.certificate_store.as_str() {
             "Native" => CertificateStore::Native,
             "WebPki" => CertificateStore::WebPki,
@@ -78,115 +192,245 @@ impl RpcClient {
         };
 
         let mut headers = HeaderMap::new();
-        http_config.headers.iter().for_each(|(key, value)| {
-            let header_name = match HeaderName::from_str(key.as_str()) {
-                Ok(header_name) => header_name,
-                Err(_) => panic!("invalid header name: {:?}", key),
+        for (key, value) in &http_config.headers {
+            let header_name = HeaderName::from_str(key.as_str()).map_err(|_| {
+                SdkError::ConnectionError(format!("invalid header name: {:?}", key))
+            })?;
+            let header_value = HeaderValue::from_str(value.as_str()).map_err(|_| {
+                SdkError::ConnectionError(format!("invalid header value: {:?}", value))
+            })?;
+            headers.insert(header_name, header_value);
+        }
+
+        if let Some(tls_config) = &http_config.tls {
+            let host = uri
+                .host()
+                .ok_or_else(|| SdkError::ConnectionError(format!("no host in the URL {}", url)))?;
+            let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+                Some("https") | Some("wss") => 443,
+                _ => 80,
+            });
+            // IPv6 hosts are bracketed in URLs
+            let unbracketed_host = host.trim_start_matches('[').trim_end_matches(']');
+            let node_addr = tokio::net::lookup_host((unbracketed_host, port))
+                .await
+                .map_err(|err| {
+                    SdkError::ConnectionError(format!("could not resolve {}: {}", host, err))
+                })?
+                .next()
+                .ok_or_else(|| SdkError::ConnectionError(format!("could not resolve {}", host)))?;
+            let connector = NodeTlsConnector::new(
+                node_addr,
+                unbracketed_host,
+                tls_config,
+                &http_config.certificate_store,
+            )
+            .map_err(SdkError::ConnectionError)?;
+            // the node must see the name of its certificate in the Host header
+            let authority = match &tls_config.server_name {
+                Some(name) => format!("{}:{}", name, port),
+                None => format!("{}:{}", host, port),
             };
-            let header_value = match HeaderValue::from_str(value.as_str()) {
-                Ok(header_name) => header_name,
-                Err(_) => panic!("invalid header value: {:?}", value),
+            let transport = if is_ws {
+                let target = WsTlsTarget {
+                    connector,
+                    authority,
+                    resource: uri
+                        .path_and_query()
+                        .map_or("/", |path| path.as_str())
+                        .to_string(),
+                };
+                Transport::Ws(
+                    ReconnectingWsClient::connect(
+                        url,
+                        http_config,
+                        headers,
+                        certificate_store,
+                        id_kind,
+                        Some(target),
+                    )
+                    .await?,
+                )
+            } else {
+                Transport::Https(
+                    TlsHttpClient::new(connector, &authority, headers, http_config)
+                        .map_err(SdkError::ConnectionError)?,
+                )
             };
-            headers.insert(header_name, header_value);
-        });
+            return Ok(RpcClient { transport });
+        }
+
+        if is_ws {
+            let ws_client = ReconnectingWsClient::connect(
+                url,
+                http_config,
+                headers,
+                certificate_store,
+                id_kind,
+                None,
+            )
+            .await?;
+            return Ok(RpcClient {
+                transport: Transport::Ws(ws_client),
+            });
+        }
 
-        match HttpClientBuilder::default()
+        let http_client = HttpClientBuilder::default()
             .max_request_body_size(http_config.max_request_body_size)
             .request_timeout(http_config.request_timeout.to_duration())
             .max_concurrent_requests(http_config.max_concurrent_requests)
             .certificate_store(certificate_store)
             .id_format(id_kind)
             .set_headers(headers)
-            .build(url)
-        {
-            Ok(http_client) => RpcClient { http_client },
-            Err(_) => panic!("unable to connect to Node."),
-        }
+            .build(url)?;
+        Ok(RpcClient {
+            transport: Transport::Http(http_client),
+        })
     }
 
     /// Gracefully stop the node.
     pub async fn stop_node(&self) -> RpcResult<()> {
-        self.http_client.request("stop_node", rpc_params![]).await
+        self.request("stop_node", rpc_params![]).await
     }
 
     /// Sign message with node's key.
     /// Returns the public key that signed the message and the signature.
     pub async fn node_sign_message(&self, message: Vec<u8>) -> RpcResult<PubkeySig> {
-        self.http_client
-            .request("node_sign_message", rpc_params![message])
+        self.request("node_sign_message", rpc_params![message])
             .await
     }
 
     /// Add a vector of new secret keys for the node to use to stake.
     /// No confirmation to expect.
     pub async fn add_staking_secret_keys(&self, secret_keys: Vec<String>) -> RpcResult<()> {
-        self.http_client
-            .request("add_staking_secret_keys", rpc_params![secret_keys])
+        self.request("add_staking_secret_keys", rpc_params![secret_keys])
             .await
     }
 
     /// Remove a vector of addresses used to stake.
     /// No confirmation to expect.
     pub async fn remove_staking_addresses(&self, addresses: Vec<Address>) -> RpcResult<()> {
-        self.http_client
-            .request("remove_staking_addresses", rpc_params![addresses])
+        self.request("remove_staking_addresses", rpc_params![addresses])
             .await
     }
 
     /// Return hash-set of staking addresses.
     pub async fn get_staking_addresses(&self) -> RpcResult<PreHashSet<Address>> {
-        self.http_client
-            .request("get_staking_addresses", rpc_params![])
+        self.request("get_staking_addresses", rpc_params![]).await
+    }
+
+    /// Bans given ip address(es) for the given duration,
+    /// or until they are unbanned if no duration is given.
+    /// No confirmation to expect.
+    pub async fn node_ban_by_ip(
+        &self,
+        ips: Vec<IpAddr>,
+        duration: Option<MassaTime>,
+    ) -> RpcResult<()> {
+        self.request("node_ban_by_ip", rpc_params![ips, duration])
             .await
     }
 
-    /// Bans given ip address(es)
+    /// Bans given node id(s) for the given duration,
+    /// or until they are unbanned if no duration is given.
     /// No confirmation to expect.
-    pub async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.http_client
-            .request("node_ban_by_ip", rpc_params![ips])
+    pub async fn node_ban_by_id(
+        &self,
+        ids: Vec<NodeId>,
+        duration: Option<MassaTime>,
+    ) -> RpcResult<()> {
+        self.request("node_ban_by_id", rpc_params![ids, duration])
             .await
     }
 
-    /// Bans given node id(s)
+    /// Get the banned peers with the reason and expiry of their ban
+    pub async fn get_banned_peers(&self) -> RpcResult<Vec<BannedPeer>> {
+        self.request("get_banned_peers", rpc_params![]).await
+    }
+
+    /// get the connected nodes with statistics about their connection
+    pub async fn get_peers(&self) -> RpcResult<Vec<ConnectedPeer>> {
+        self.request("get_peers", rpc_params![]).await
+    }
+
+    /// Close the connections to the given node id(s), without banning them
     /// No confirmation to expect.
-    pub async fn node_ban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
-        self.http_client
-            .request("node_ban_by_id", rpc_params![ids])
+    pub async fn node_disconnect_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
+        self.request("node_disconnect_by_id", rpc_params![ids])
             .await
     }
 
     /// Unban given ip address(es)
     /// No confirmation to expect.
     pub async fn node_unban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.http_client
-            .request("node_unban_by_ip", rpc_params![ips])
-            .await
+        self.request("node_unban_by_ip", rpc_params![ips]).await
     }
 
     /// Unban given node id(s)
     /// No confirmation to expect.
     pub async fn node_unban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
-        self.http_client
-            .request("node_unban_by_id", rpc_params![ids])
-            .await
+        self.request("node_unban_by_id", rpc_params![ids]).await
     }
 
     /// add ips to whitelist
     /// create peer if it was unknown
     pub async fn node_whitelist(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.http_client
-            .request("node_whitelist", rpc_params![ips])
-            .await
+        self.request("node_whitelist", rpc_params![ips]).await
     }
 
     /// remove IPs from whitelist
     pub async fn node_remove_from_whitelist(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
-        self.http_client
-            .request("node_remove_from_whitelist", rpc_params![ips])
+        self.request("node_remove_from_whitelist", rpc_params![ips])
+            .await
+    }
+
+    /// get the bootstrap whitelist, `None` if every IP is allowed to bootstrap
+    pub async fn node_bootstrap_whitelist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        self.request("node_bootstrap_whitelist", rpc_params![])
             .await
     }
 
+    /// add IPs and CIDR ranges to the bootstrap whitelist
+    pub async fn node_add_to_bootstrap_whitelist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.request("node_add_to_bootstrap_whitelist", rpc_params![ranges])
+            .await
+    }
+
+    /// remove IPs and CIDR ranges from the bootstrap whitelist
+    pub async fn node_remove_from_bootstrap_whitelist(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> RpcResult<()> {
+        self.request("node_remove_from_bootstrap_whitelist", rpc_params![ranges])
+            .await
+    }
+
+    /// get the bootstrap blacklist, `None` if there is none
+    pub async fn node_bootstrap_blacklist(&self) -> RpcResult<Option<Vec<IpRange>>> {
+        self.request("node_bootstrap_blacklist", rpc_params![])
+            .await
+    }
+
+    /// add IPs and CIDR ranges to the bootstrap blacklist
+    pub async fn node_add_to_bootstrap_blacklist(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.request("node_add_to_bootstrap_blacklist", rpc_params![ranges])
+            .await
+    }
+
+    /// remove IPs and CIDR ranges from the bootstrap blacklist
+    pub async fn node_remove_from_bootstrap_blacklist(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> RpcResult<()> {
+        self.request("node_remove_from_bootstrap_blacklist", rpc_params![ranges])
+            .await
+    }
+
+    /// get the sessions currently served by the bootstrap server
+    pub async fn get_bootstrap_sessions(&self) -> RpcResult<Vec<BootstrapSessionInfo>> {
+        self.request("get_bootstrap_sessions", rpc_params![]).await
+    }
+
     ////////////////
     // public-api //
     ////////////////
@@ -195,18 +439,18 @@ impl RpcClient {
 
     /// summary of the current state: time, last final blocks (hash, thread, slot, timestamp), clique count, connected nodes count
     pub async fn get_status(&self) -> RpcResult<NodeStatus> {
-        self.http_client.request("get_status", rpc_params![]).await
+        self.request("get_status", rpc_params![]).await
     }
 
     pub(crate) async fn _get_cliques(&self) -> RpcResult<Vec<Clique>> {
-        self.http_client.request("get_cliques", rpc_params![]).await
+        self.request("get_cliques", rpc_params![]).await
     }
 
     // Debug (specific information)
 
     /// Returns the active stakers and their roll counts for the current cycle.
     pub(crate) async fn _get_stakers(&self) -> RpcResult<PreHashMap<Address, u64>> {
-        self.http_client.request("get_stakers", rpc_params![]).await
+        self.request("get_stakers", rpc_params![]).await
     }
 
     /// Returns operations information associated to a given list of operations' IDs.
@@ -214,8 +458,7 @@ impl RpcClient {
         &self,
         operation_ids: Vec<OperationId>,
     ) -> RpcResult<Vec<OperationInfo>> {
-        self.http_client
-            .request("get_operations", rpc_params![operation_ids])
+        self.request("get_operations", rpc_params![operation_ids])
             .await
     }
 
@@ -224,15 +467,18 @@ impl RpcClient {
         &self,
         endorsement_ids: Vec<EndorsementId>,
     ) -> RpcResult<Vec<EndorsementInfo>> {
-        self.http_client
-            .request("get_endorsements", rpc_params![endorsement_ids])
+        self.request("get_endorsements", rpc_params![endorsement_ids])
             .await
     }
 
     /// Get information on a block given its `BlockId`
     pub async fn get_block(&self, block_id: BlockId) -> RpcResult<BlockInfo> {
-        self.http_client
-            .request("get_block", rpc_params![block_id])
+        self.request("get_block", rpc_params![block_id]).await
+    }
+
+    /// Get the block at a slot in the blockclique, if any
+    pub async fn get_blockclique_block_by_slot(&self, slot: Slot) -> RpcResult<Option<Block>> {
+        self.request("get_blockclique_block_by_slot", rpc_params![slot])
             .await
     }
 
@@ -241,8 +487,7 @@ impl RpcClient {
         &self,
         filter: EventFilter,
     ) -> RpcResult<Vec<SCOutputEvent>> {
-        self.http_client
-            .request("get_filtered_sc_output_event", rpc_params![filter])
+        self.request("get_filtered_sc_output_event", rpc_params![filter])
             .await
     }
 
@@ -252,25 +497,52 @@ impl RpcClient {
         &self,
         time_interval: TimeInterval,
     ) -> RpcResult<Vec<BlockSummary>> {
-        self.http_client
-            .request("get_graph_interval", rpc_params![time_interval])
+        self.request("get_graph_interval", rpc_params![time_interval])
             .await
     }
 
     /// Get info by addresses
     pub async fn get_addresses(&self, addresses: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
-        self.http_client
-            .request("get_addresses", rpc_params![addresses])
+        self.request("get_addresses", rpc_params![addresses]).await
+    }
+
+    /// Get the upcoming block and endorsement draws of addresses
+    pub async fn get_selections(
+        &self,
+        filter: SelectionsFilter,
+    ) -> RpcResult<Vec<AddressSelections>> {
+        self.request("get_selections", rpc_params![filter]).await
+    }
+
+    /// Get the block production statistics and rewards of addresses
+    pub async fn get_staking_reports(
+        &self,
+        addresses: Vec<Address>,
+    ) -> RpcResult<Vec<StakingReport>> {
+        self.request("get_staking_reports", rpc_params![addresses])
+            .await
+    }
+
+    /// Get the final state hashes at the given final slots
+    pub async fn get_final_state_hash(&self, slots: Vec<Slot>) -> RpcResult<Vec<FinalStateHash>> {
+        self.request("get_final_state_hash", rpc_params![slots])
             .await
     }
 
+    /// Get final ledger sub-entries along with their proofs against the latest final state hash
+    pub async fn get_ledger_proof(
+        &self,
+        inputs: Vec<LedgerProofInput>,
+    ) -> RpcResult<Vec<LedgerProof>> {
+        self.request("get_ledger_proof", rpc_params![inputs]).await
+    }
+
     /// Get datastore entries
     pub async fn get_datastore_entries(
         &self,
         input: Vec<DatastoreEntryInput>,
     ) -> RpcResult<Vec<DatastoreEntryOutput>> {
-        self.http_client
-            .request("get_datastore_entries", rpc_params![input])
+        self.request("get_datastore_entries", rpc_params![input])
             .await
     }
 
@@ -281,8 +553,7 @@ impl RpcClient {
         &self,
         operations: Vec<OperationInput>,
     ) -> RpcResult<Vec<OperationId>> {
-        self.http_client
-            .request("send_operations", rpc_params![operations])
+        self.request("send_operations", rpc_params![operations])
             .await
     }
 
@@ -291,16 +562,15 @@ impl RpcClient {
         &self,
         read_only_execution: ReadOnlyBytecodeExecution,
     ) -> RpcResult<ExecuteReadOnlyResponse> {
-        self.http_client
-            .request::<Vec<ExecuteReadOnlyResponse>, Vec<Vec<ReadOnlyBytecodeExecution>>>(
-                "execute_read_only_bytecode",
-                vec![vec![read_only_execution]],
-            )
-            .await?
-            .pop()
-            .ok_or_else(|| {
-                JsonRpseeError::Custom("missing return value on execute_read_only_bytecode".into())
-            })
+        self.request::<Vec<ExecuteReadOnlyResponse>, Vec<Vec<ReadOnlyBytecodeExecution>>>(
+            "execute_read_only_bytecode",
+            vec![vec![read_only_execution]],
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            JsonRpseeError::Custom("missing return value on execute_read_only_bytecode".into())
+        })
     }
 
     /// execute read only SC call
@@ -308,15 +578,14 @@ impl RpcClient {
         &self,
         read_only_execution: ReadOnlyCall,
     ) -> RpcResult<ExecuteReadOnlyResponse> {
-        self.http_client
-            .request::<Vec<ExecuteReadOnlyResponse>, Vec<Vec<ReadOnlyCall>>>(
-                "execute_read_only_call",
-                vec![vec![read_only_execution]],
-            )
-            .await?
-            .pop()
-            .ok_or_else(|| {
-                JsonRpseeError::Custom("missing return value on execute_read_only_call".into())
-            })
+        self.request::<Vec<ExecuteReadOnlyResponse>, Vec<Vec<ReadOnlyCall>>>(
+            "execute_read_only_call",
+            vec![vec![read_only_execution]],
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            JsonRpseeError::Custom("missing return value on execute_read_only_call".into())
+        })
     }
 }
@@ -0,0 +1,547 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Portable encoding of the operations signed on another machine.
+//!
+//! An operation is prepared online, with its fee and expiry period resolved against the node,
+//! and exported as an `UnsignedOperation`. Its creator signs it on an offline machine, then the
+//! signed operation is brought back online and submitted with `send_operations`.
+//! The operations of a multisig address go through a `PartiallySignedOperation` instead,
+//! which each signer signs in turn, or in parallel before the signatures are merged.
+//! All are encoded as prefixed base58check strings, that fit in a file or a QR code.
+
+use crate::SdkError;
+use massa_hash::Hash;
+use massa_models::address::{Address, ADDRESS_SIZE_BYTES};
+use massa_models::api::OperationInput;
+use massa_models::config::{
+    MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+    MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE,
+};
+use massa_models::multisig::{
+    MultisigPolicy, MultisigSignatures, MultisigSignaturesDeserializer,
+    MultisigSignaturesSerializer,
+};
+use massa_models::operation::{
+    Operation, OperationDeserializer, OperationSerializer, WrappedOperation,
+};
+use massa_models::wrapped::WrappedContent;
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_signature::{KeyPair, PublicKey, Signature, PUBLIC_KEY_SIZE_BYTES, SIGNATURE_SIZE_BYTES};
+use std::convert::TryInto;
+
+/// Prefix of the encoded unsigned operations
+const UNSIGNED_OPERATION_PREFIX: &str = "UO";
+
+/// Prefix of the encoded signed operations
+const SIGNED_OPERATION_PREFIX: &str = "SO";
+
+/// Prefix of the encoded operations of multisig addresses, whether complete or not
+const MULTISIG_OPERATION_PREFIX: &str = "MO";
+
+/// Version of the encoding
+const OFFLINE_FORMAT_VERSION: u8 = 0;
+
+/// Operation prepared online, waiting to be signed by its creator
+#[derive(Debug, Clone)]
+pub struct UnsignedOperation {
+    /// address whose key must sign the operation
+    pub creator_address: Address,
+    /// content of the operation
+    pub operation: Operation,
+}
+
+/// Decode a prefixed base58check string, and check its version
+fn decode(data: &str, prefix: &str) -> Result<Vec<u8>, SdkError> {
+    let encoded = data
+        .trim()
+        .strip_prefix(prefix)
+        .ok_or_else(|| SdkError::OfflineFormatError(format!("missing {} prefix", prefix)))?;
+    let bytes = bs58::decode(encoded)
+        .with_check(None)
+        .into_vec()
+        .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+    match bytes.split_first() {
+        Some((&OFFLINE_FORMAT_VERSION, rest)) => Ok(rest.to_vec()),
+        Some((version, _)) => Err(SdkError::OfflineFormatError(format!(
+            "unsupported version {}",
+            version
+        ))),
+        None => Err(SdkError::OfflineFormatError("empty data".to_string())),
+    }
+}
+
+/// Encode `bytes` as a prefixed and versioned base58check string
+fn encode(bytes: &[u8], prefix: &str) -> String {
+    let mut versioned = Vec::with_capacity(bytes.len() + 1);
+    versioned.push(OFFLINE_FORMAT_VERSION);
+    versioned.extend_from_slice(bytes);
+    format!(
+        "{}{}",
+        prefix,
+        bs58::encode(versioned).with_check().into_string()
+    )
+}
+
+/// Operation of a multisig address, collecting the signatures of its signers
+#[derive(Debug, Clone)]
+pub struct PartiallySignedOperation {
+    /// content of the operation
+    pub operation: Operation,
+    /// signatures collected so far
+    pub signatures: MultisigSignatures,
+}
+
+/// Split `N` bytes from the start of `bytes`
+fn split_array<const N: usize>(bytes: &[u8]) -> Result<([u8; N], &[u8]), SdkError> {
+    if bytes.len() < N {
+        return Err(SdkError::OfflineFormatError("truncated data".to_string()));
+    }
+    let (head, rest) = bytes.split_at(N);
+    Ok((head.try_into().expect("length checked above"), rest))
+}
+
+/// Deserialize an operation that must fill the rest of `bytes`
+fn deserialize_operation(bytes: &[u8]) -> Result<Operation, SdkError> {
+    let (rest, operation) = OperationDeserializer::new(
+        MAX_DATASTORE_VALUE_LENGTH,
+        MAX_FUNCTION_NAME_LENGTH,
+        MAX_PARAMETERS_SIZE,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    )
+    .deserialize::<DeserializeError>(bytes)
+    .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+    if !rest.is_empty() {
+        return Err(SdkError::OfflineFormatError(
+            "unexpected data after the operation".to_string(),
+        ));
+    }
+    Ok(operation)
+}
+
+impl UnsignedOperation {
+    /// Encode the operation as a string
+    pub fn to_bs58_check(&self) -> Result<String, SdkError> {
+        let mut bytes = self.creator_address.to_bytes().to_vec();
+        OperationSerializer::new().serialize(&self.operation, &mut bytes)?;
+        Ok(encode(&bytes, UNSIGNED_OPERATION_PREFIX))
+    }
+
+    /// Decode an operation encoded by `to_bs58_check`
+    pub fn from_bs58_check(data: &str) -> Result<UnsignedOperation, SdkError> {
+        let bytes = decode(data, UNSIGNED_OPERATION_PREFIX)?;
+        let (address_bytes, content) = split_array::<ADDRESS_SIZE_BYTES>(&bytes)?;
+        Ok(UnsignedOperation {
+            creator_address: Address::from_bytes(&address_bytes),
+            operation: deserialize_operation(content)?,
+        })
+    }
+
+    /// Start collecting the signatures of a multisig creator. Fails if `policy` does not
+    /// control the creator address.
+    pub fn into_multisig(
+        self,
+        policy: MultisigPolicy,
+    ) -> Result<PartiallySignedOperation, SdkError> {
+        if policy.address() != self.creator_address {
+            return Err(SdkError::OfflineFormatError(format!(
+                "the policy does not control the creator {}",
+                self.creator_address
+            )));
+        }
+        Ok(PartiallySignedOperation {
+            operation: self.operation,
+            signatures: MultisigSignatures::new(policy),
+        })
+    }
+
+    /// Sign the operation. Fails if `keypair` is not the key of its creator.
+    pub fn sign(self, keypair: &KeyPair) -> Result<WrappedOperation, SdkError> {
+        if Address::from_public_key(&keypair.get_public_key()) != self.creator_address {
+            return Err(SdkError::OfflineFormatError(format!(
+                "the key does not belong to the creator {}",
+                self.creator_address
+            )));
+        }
+        Ok(Operation::new_wrapped(
+            self.operation,
+            OperationSerializer::new(),
+            keypair,
+        )?)
+    }
+}
+
+impl PartiallySignedOperation {
+    /// Encode the operation and its signatures as a string
+    pub fn to_bs58_check(&self) -> Result<String, SdkError> {
+        let mut bytes = Vec::new();
+        MultisigSignaturesSerializer::new().serialize(&self.signatures, &mut bytes)?;
+        OperationSerializer::new().serialize(&self.operation, &mut bytes)?;
+        Ok(encode(&bytes, MULTISIG_OPERATION_PREFIX))
+    }
+
+    /// Decode an operation encoded by `to_bs58_check`, and check the signatures it carries
+    pub fn from_bs58_check(data: &str) -> Result<PartiallySignedOperation, SdkError> {
+        let bytes = decode(data, MULTISIG_OPERATION_PREFIX)?;
+        let (content, signatures) = MultisigSignaturesDeserializer::new()
+            .deserialize::<DeserializeError>(&bytes)
+            .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+        let operation = PartiallySignedOperation {
+            operation: deserialize_operation(content)?,
+            signatures,
+        };
+        operation.verify_signatures()?;
+        Ok(operation)
+    }
+
+    /// Address creating the operation
+    pub fn creator_address(&self) -> Address {
+        self.signatures.policy.address()
+    }
+
+    /// Serialized content of the operation
+    fn serialized_content(&self) -> Result<Vec<u8>, SdkError> {
+        let mut serialized_content = Vec::new();
+        OperationSerializer::new().serialize(&self.operation, &mut serialized_content)?;
+        Ok(serialized_content)
+    }
+
+    /// Add the signature of `keypair`, which must be one of the keys of the policy
+    pub fn sign(&mut self, keypair: &KeyPair) -> Result<(), SdkError> {
+        let hash = self
+            .signatures
+            .policy
+            .content_hash(&self.serialized_content()?);
+        Ok(self.signatures.sign(keypair, &hash)?)
+    }
+
+    /// Check each signature collected so far against the content of the operation
+    fn verify_signatures(&self) -> Result<(), SdkError> {
+        let hash = self
+            .signatures
+            .policy
+            .content_hash(&self.serialized_content()?);
+        self.signatures
+            .verify_collected(&hash)
+            .map_err(|err| SdkError::OfflineFormatError(format!("invalid signature: {}", err)))
+    }
+
+    /// Add the signatures collected in parallel for the same operation, once checked
+    pub fn merge(&mut self, other: PartiallySignedOperation) -> Result<(), SdkError> {
+        if self.serialized_content()? != other.serialized_content()? {
+            return Err(SdkError::OfflineFormatError(
+                "the signatures were collected for another operation".to_string(),
+            ));
+        }
+        other.verify_signatures()?;
+        Ok(self.signatures.merge(other.signatures)?)
+    }
+
+    /// Returns true if enough signatures were collected to send the operation
+    pub fn is_complete(&self) -> bool {
+        self.signatures.is_complete()
+    }
+
+    /// The operation, ready to be sent once enough signatures were collected
+    pub fn into_wrapped(self) -> Result<WrappedOperation, SdkError> {
+        let operation = Operation::new_wrapped_multisig(
+            self.operation,
+            OperationSerializer::new(),
+            self.signatures,
+        )?;
+        operation.verify_signature()?;
+        Ok(operation)
+    }
+}
+
+/// Operation to sign offline
+#[derive(Debug, Clone)]
+pub enum OperationToSign {
+    /// operation exported by its creator, encoded by `UnsignedOperation::to_bs58_check`
+    Unsigned(UnsignedOperation),
+    /// operation of a multisig address, encoded by `PartiallySignedOperation::to_bs58_check`
+    Multisig(PartiallySignedOperation),
+}
+
+/// Decode an operation to sign, according to the prefix of its encoding
+pub fn decode_operation_to_sign(data: &str) -> Result<OperationToSign, SdkError> {
+    let trimmed = data.trim();
+    if trimmed.starts_with(UNSIGNED_OPERATION_PREFIX) {
+        Ok(OperationToSign::Unsigned(
+            UnsignedOperation::from_bs58_check(data)?,
+        ))
+    } else if trimmed.starts_with(MULTISIG_OPERATION_PREFIX) {
+        Ok(OperationToSign::Multisig(
+            PartiallySignedOperation::from_bs58_check(data)?,
+        ))
+    } else {
+        Err(SdkError::OfflineFormatError(format!(
+            "expected an unsigned operation ({} prefix) or a multisig one ({} prefix)",
+            UNSIGNED_OPERATION_PREFIX, MULTISIG_OPERATION_PREFIX
+        )))
+    }
+}
+
+/// Encode a signed operation as a string
+pub fn encode_signed_operation(operation: &WrappedOperation) -> Result<String, SdkError> {
+    if let Some(signatures) = &operation.multisig {
+        return PartiallySignedOperation {
+            operation: operation.content.clone(),
+            signatures: signatures.clone(),
+        }
+        .to_bs58_check();
+    }
+    let mut bytes = Vec::with_capacity(operation.serialized_size());
+    bytes.extend(operation.signature.to_bytes());
+    bytes.extend(operation.creator_public_key.to_bytes());
+    bytes.extend(&operation.serialized_data);
+    Ok(encode(&bytes, SIGNED_OPERATION_PREFIX))
+}
+
+/// Decode a signed operation encoded by `encode_signed_operation`, ready to be sent.
+/// The operations of multisig addresses must have collected enough signatures,
+/// and the others must be signed by the key they carry.
+pub fn decode_signed_operation(data: &str) -> Result<OperationInput, SdkError> {
+    if data.trim().starts_with(MULTISIG_OPERATION_PREFIX) {
+        let operation = PartiallySignedOperation::from_bs58_check(data)?.into_wrapped()?;
+        return Ok(OperationInput {
+            creator_public_key: operation.creator_public_key,
+            signature: operation.signature,
+            serialized_content: operation.serialized_data,
+            multisig: operation.multisig,
+        });
+    }
+    let bytes = decode(data, SIGNED_OPERATION_PREFIX)?;
+    let (signature_bytes, rest) = split_array::<SIGNATURE_SIZE_BYTES>(&bytes)?;
+    let (public_key_bytes, serialized_content) = split_array::<PUBLIC_KEY_SIZE_BYTES>(rest)?;
+    deserialize_operation(serialized_content)?;
+    let creator_public_key = PublicKey::from_bytes(&public_key_bytes)
+        .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+    let signature = Signature::from_bytes(&signature_bytes)
+        .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+    let mut hash_data = public_key_bytes.to_vec();
+    hash_data.extend_from_slice(serialized_content);
+    creator_public_key
+        .verify_signature(&Hash::compute_from(&hash_data), &signature)
+        .map_err(|err| SdkError::OfflineFormatError(err.to_string()))?;
+    Ok(OperationInput {
+        creator_public_key,
+        signature,
+        serialized_content: serialized_content.to_vec(),
+        multisig: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::amount::Amount;
+    use massa_models::operation::OperationType;
+    use std::str::FromStr;
+
+    fn operation() -> Operation {
+        Operation {
+            fee: Amount::from_str("0.01").unwrap(),
+            expire_period: 42,
+            op: OperationType::Transaction {
+                recipient_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+                amount: Amount::from_str("100").unwrap(),
+            },
+        }
+    }
+
+    fn serialized(operation: &Operation) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        OperationSerializer::new()
+            .serialize(operation, &mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    /// Re-encode `encoded` with its base58check payload changed by `change`
+    fn tamper(encoded: &str, prefix: &str, change: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut bytes = bs58::decode(&encoded[prefix.len()..])
+            .with_check(None)
+            .into_vec()
+            .unwrap();
+        change(&mut bytes);
+        format!(
+            "{}{}",
+            prefix,
+            bs58::encode(bytes).with_check().into_string()
+        )
+    }
+
+    #[test]
+    fn test_unsigned_operation_roundtrip() {
+        let keypair = KeyPair::generate();
+        let unsigned_operation = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        };
+        let encoded = unsigned_operation.to_bs58_check().unwrap();
+        assert!(encoded.starts_with(UNSIGNED_OPERATION_PREFIX));
+        let decoded = UnsignedOperation::from_bs58_check(&encoded).unwrap();
+        assert_eq!(decoded.creator_address, unsigned_operation.creator_address);
+        assert_eq!(
+            serialized(&decoded.operation),
+            serialized(&unsigned_operation.operation)
+        );
+    }
+
+    #[test]
+    fn test_signed_operation_roundtrip() {
+        let keypair = KeyPair::generate();
+        let signed_operation = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        }
+        .sign(&keypair)
+        .unwrap();
+        let encoded = encode_signed_operation(&signed_operation).unwrap();
+        assert!(encoded.starts_with(SIGNED_OPERATION_PREFIX));
+        let decoded = decode_signed_operation(&encoded).unwrap();
+        assert_eq!(decoded.creator_public_key, keypair.get_public_key());
+        assert_eq!(decoded.signature, signed_operation.signature);
+        assert_eq!(decoded.serialized_content, signed_operation.serialized_data);
+        assert!(decoded.multisig.is_none());
+    }
+
+    #[test]
+    fn test_reject_wrong_prefix() {
+        let keypair = KeyPair::generate();
+        let encoded = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        }
+        .to_bs58_check()
+        .unwrap();
+        assert!(decode_signed_operation(&encoded).is_err());
+        assert!(PartiallySignedOperation::from_bs58_check(&encoded).is_err());
+        let renamed = format!("{}{}", SIGNED_OPERATION_PREFIX, &encoded[2..]);
+        assert!(UnsignedOperation::from_bs58_check(&renamed).is_err());
+    }
+
+    #[test]
+    fn test_reject_wrong_version() {
+        let keypair = KeyPair::generate();
+        let encoded = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        }
+        .to_bs58_check()
+        .unwrap();
+        let tampered = tamper(&encoded, UNSIGNED_OPERATION_PREFIX, |bytes| {
+            bytes[0] = OFFLINE_FORMAT_VERSION + 1
+        });
+        match UnsignedOperation::from_bs58_check(&tampered) {
+            Err(SdkError::OfflineFormatError(err)) => assert!(err.contains("version")),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reject_truncated() {
+        let keypair = KeyPair::generate();
+        let unsigned_operation = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        };
+        let encoded = unsigned_operation.to_bs58_check().unwrap();
+        // the checksum covers the whole payload
+        assert!(UnsignedOperation::from_bs58_check(&encoded[..encoded.len() - 1]).is_err());
+        for length in [1, ADDRESS_SIZE_BYTES, ADDRESS_SIZE_BYTES + 3] {
+            let truncated = tamper(&encoded, UNSIGNED_OPERATION_PREFIX, |bytes| {
+                bytes.truncate(length)
+            });
+            assert!(UnsignedOperation::from_bs58_check(&truncated).is_err());
+        }
+
+        let encoded = encode_signed_operation(&unsigned_operation.sign(&keypair).unwrap()).unwrap();
+        for length in [
+            1,
+            SIGNATURE_SIZE_BYTES,
+            SIGNATURE_SIZE_BYTES + PUBLIC_KEY_SIZE_BYTES,
+        ] {
+            let truncated = tamper(&encoded, SIGNED_OPERATION_PREFIX, |bytes| {
+                bytes.truncate(length)
+            });
+            assert!(decode_signed_operation(&truncated).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reject_wrong_signer_key() {
+        let keypair = KeyPair::generate();
+        let other_keypair = KeyPair::generate();
+        let unsigned_operation = UnsignedOperation {
+            creator_address: Address::from_public_key(&keypair.get_public_key()),
+            operation: operation(),
+        };
+        assert!(unsigned_operation.clone().sign(&other_keypair).is_err());
+
+        // a signed operation carrying another key than the one that signed it
+        let encoded = encode_signed_operation(&unsigned_operation.sign(&keypair).unwrap()).unwrap();
+        let tampered = tamper(&encoded, SIGNED_OPERATION_PREFIX, |bytes| {
+            let start = 1 + SIGNATURE_SIZE_BYTES;
+            bytes[start..start + PUBLIC_KEY_SIZE_BYTES]
+                .copy_from_slice(other_keypair.get_public_key().to_bytes());
+        });
+        assert!(decode_signed_operation(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_multisig_signature_checks() {
+        let keypairs = vec![KeyPair::generate(), KeyPair::generate()];
+        let policy =
+            MultisigPolicy::new(2, keypairs.iter().map(|k| k.get_public_key()).collect()).unwrap();
+        let unsigned_operation = UnsignedOperation {
+            creator_address: policy.address(),
+            operation: operation(),
+        };
+        match decode_operation_to_sign(&unsigned_operation.to_bs58_check().unwrap()).unwrap() {
+            OperationToSign::Unsigned(_) => {}
+            other => panic!("unexpected operation {:?}", other),
+        }
+        let multisig_operation = unsigned_operation.into_multisig(policy.clone()).unwrap();
+
+        let mut first = multisig_operation.clone();
+        first.sign(&keypairs[0]).unwrap();
+        match decode_operation_to_sign(&first.to_bs58_check().unwrap()).unwrap() {
+            OperationToSign::Multisig(decoded) => assert_eq!(decoded.signatures, first.signatures),
+            other => panic!("unexpected operation {:?}", other),
+        }
+
+        // a signature of the right key over other content
+        let index = policy.key_index(&keypairs[1].get_public_key()).unwrap();
+        let mut forged = multisig_operation.clone();
+        forged.signatures.signatures.insert(
+            index,
+            keypairs[1]
+                .sign(&Hash::compute_from(b"other content"))
+                .unwrap(),
+        );
+        let encoded = forged.to_bs58_check().unwrap();
+        assert!(PartiallySignedOperation::from_bs58_check(&encoded).is_err());
+        assert!(decode_operation_to_sign(&encoded).is_err());
+        assert!(first.clone().merge(forged).is_err());
+
+        let mut second = multisig_operation;
+        second.sign(&keypairs[1]).unwrap();
+        first.merge(second).unwrap();
+        assert!(first.is_complete());
+        first.into_wrapped().unwrap();
+
+        let signed = encode_signed_operation(
+            &UnsignedOperation {
+                creator_address: Address::from_public_key(&keypairs[0].get_public_key()),
+                operation: operation(),
+            }
+            .sign(&keypairs[0])
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(decode_operation_to_sign(&signed).is_err());
+    }
+}
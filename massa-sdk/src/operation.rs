@@ -0,0 +1,684 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! High-level creation of operations.
+//!
+//! `OperationBuilder` does what every client has to do before sending an operation: it computes
+//! the expiry period from the current slot, sizes the fee from a fee per byte chosen by the
+//! caller, estimates the gas, checks that the creator can pay, signs the operation and sends it,
+//! optionally waiting for its inclusion. The fee per byte is not estimated from the node.
+
+use crate::{RpcClient, SdkError};
+use massa_hash::Hash;
+use massa_models::api::{OperationInfo, OperationInput, ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::config::CompactConfig;
+use massa_models::datastore::{Datastore, DatastoreSerializer};
+use massa_models::error::ModelsError;
+use massa_models::execution::ReadOnlyResult;
+use massa_models::multisig::{MultisigPolicy, MultisigSignatures};
+use massa_models::operation::{
+    Operation, OperationId, OperationSerializer, OperationType, WrappedOperation,
+};
+use massa_models::timeslots::get_current_latest_block_slot;
+use massa_models::wrapped::WrappedContent;
+use massa_models::{address::Address, amount::Amount, slot::Slot};
+use massa_serialization::Serializer;
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+
+/// Status `OperationBuilder::send_and_wait` waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationStatus {
+    /// the operation is in a block
+    Included,
+    /// the operation is in a final block
+    Final,
+}
+
+/// Builds, signs and sends an operation
+#[derive(Debug, Clone)]
+pub struct OperationBuilder {
+    /// content of the operation
+    op: OperationType,
+    /// fixed fee, overrides `fee_per_byte`
+    fee: Option<Amount>,
+    /// fee paid for each byte of the signed operation
+    fee_per_byte: Amount,
+    /// validity of the operation in periods, the one of the node if `None`
+    validity_periods: Option<u64>,
+    /// margin added to the estimated gas, in percent. The gas is not estimated if `None`.
+    gas_margin_percent: Option<u64>,
+    /// check that the creator can pay for the operation before signing it
+    check_balance: bool,
+    /// delay between two status checks in `send_and_wait`
+    poll_interval: Option<MassaTime>,
+    /// policy of the creator if it is a multisig address
+    multisig_policy: Option<MultisigPolicy>,
+}
+
+impl OperationBuilder {
+    /// Builder of an operation of type `op`.
+    /// By default the fee is zero, the validity is the one of the node,
+    /// the gas is not estimated and the balance of the creator is checked.
+    pub fn new(op: OperationType) -> Self {
+        OperationBuilder {
+            op,
+            fee: None,
+            fee_per_byte: Amount::zero(),
+            validity_periods: None,
+            gas_margin_percent: None,
+            check_balance: true,
+            poll_interval: None,
+            multisig_policy: None,
+        }
+    }
+
+    /// Transfer `amount` coins to `recipient_address`
+    pub fn transaction(recipient_address: Address, amount: Amount) -> Self {
+        OperationBuilder::new(OperationType::Transaction {
+            recipient_address,
+            amount,
+        })
+    }
+
+    /// Buy `roll_count` rolls
+    pub fn buy_rolls(roll_count: u64) -> Self {
+        OperationBuilder::new(OperationType::RollBuy { roll_count })
+    }
+
+    /// Sell `roll_count` rolls
+    pub fn sell_rolls(roll_count: u64) -> Self {
+        OperationBuilder::new(OperationType::RollSell { roll_count })
+    }
+
+    /// Execute the bytecode `data`
+    pub fn execute_smart_contract(data: Vec<u8>, max_gas: u64, datastore: Datastore) -> Self {
+        OperationBuilder::new(OperationType::ExecuteSC {
+            data,
+            max_gas,
+            datastore,
+        })
+    }
+
+    /// Call the function `target_func` of the smart contract stored at `target_addr`
+    pub fn call_smart_contract(
+        target_addr: Address,
+        target_func: String,
+        param: Vec<u8>,
+        max_gas: u64,
+        coins: Amount,
+    ) -> Self {
+        OperationBuilder::new(OperationType::CallSC {
+            target_addr,
+            target_func,
+            param,
+            max_gas,
+            coins,
+        })
+    }
+
+    /// Pay exactly `fee`
+    pub fn fee(mut self, fee: Amount) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Pay `fee_per_byte` for each byte of the signed operation, unless a fixed fee is set.
+    /// The pool ranks the operations by fee per byte.
+    pub fn fee_per_byte(mut self, fee_per_byte: Amount) -> Self {
+        self.fee_per_byte = fee_per_byte;
+        self
+    }
+
+    /// Make the operation expire `validity_periods` periods after the current one
+    pub fn validity_periods(mut self, validity_periods: u64) -> Self {
+        self.validity_periods = Some(validity_periods);
+        self
+    }
+
+    /// Set the gas of smart contract operations to the gas used by a read-only execution
+    /// plus `margin_percent` percent. The gas given to the builder caps the estimation.
+    pub fn estimate_gas(mut self, margin_percent: u64) -> Self {
+        self.gas_margin_percent = Some(margin_percent);
+        self
+    }
+
+    /// Whether to check that the creator can pay for the operation before signing it
+    pub fn check_balance(mut self, check_balance: bool) -> Self {
+        self.check_balance = check_balance;
+        self
+    }
+
+    /// Delay between two status checks in `send_and_wait`, `t0 / thread_count` by default
+    pub fn poll_interval(mut self, poll_interval: MassaTime) -> Self {
+        self.poll_interval = Some(poll_interval);
+        self
+    }
+
+    /// The creator is the multisig address controlled by `policy`: the fee is sized for the
+    /// `threshold` signatures it needs. Such an operation can only be built by `build_unsigned`.
+    pub fn multisig_policy(mut self, policy: MultisigPolicy) -> Self {
+        self.multisig_policy = Some(policy);
+        self
+    }
+
+    /// Prepare the operation and sign it with `keypair`
+    pub async fn build(
+        self,
+        client: &RpcClient,
+        keypair: &KeyPair,
+    ) -> Result<WrappedOperation, SdkError> {
+        let config = client.get_status().await?.config;
+        let (operation, _) = self.build_signed(client, keypair, &config).await?;
+        Ok(operation)
+    }
+
+    /// Prepare the operation of `creator_address` without signing it,
+    /// so that it can be signed on another machine
+    pub async fn build_unsigned(
+        self,
+        client: &RpcClient,
+        creator_address: Address,
+    ) -> Result<Operation, SdkError> {
+        let config = client.get_status().await?.config;
+        let (operation, _) = self.prepare(client, creator_address, &config).await?;
+        Ok(operation)
+    }
+
+    /// Sign the operation with `keypair` and send it. Returns its id.
+    pub async fn send(
+        self,
+        client: &RpcClient,
+        keypair: &KeyPair,
+    ) -> Result<OperationId, SdkError> {
+        let config = client.get_status().await?.config;
+        let (operation, _) = self.build_signed(client, keypair, &config).await?;
+        send_operation(client, operation).await
+    }
+
+    /// Sign the operation with `keypair`, send it and wait until it reaches `status`.
+    /// Fails if the operation expires before being included.
+    pub async fn send_and_wait(
+        self,
+        client: &RpcClient,
+        keypair: &KeyPair,
+        status: OperationStatus,
+    ) -> Result<OperationInfo, SdkError> {
+        let config = client.get_status().await?.config;
+        let poll_interval = match self.poll_interval {
+            Some(poll_interval) => poll_interval,
+            None => config.t0.checked_div_u64(config.thread_count as u64)?,
+        };
+        let (operation, expire_period) = self.build_signed(client, keypair, &config).await?;
+        let operation_id = send_operation(client, operation).await?;
+        loop {
+            tokio::time::sleep(poll_interval.to_duration()).await;
+            if let Some(info) = client.get_operations(vec![operation_id]).await?.pop() {
+                let reached = match status {
+                    OperationStatus::Included => !info.in_blocks.is_empty(),
+                    OperationStatus::Final => info.is_final,
+                };
+                if reached {
+                    return Ok(info);
+                }
+                // an included operation can still become final after its expiry period
+                if !info.in_blocks.is_empty() {
+                    continue;
+                }
+            }
+            if current_slot(&config)?.period > expire_period {
+                return Err(SdkError::OperationExpired(operation_id));
+            }
+        }
+    }
+
+    /// Prepare and sign the operation. Returns it with its expiry period.
+    async fn build_signed(
+        self,
+        client: &RpcClient,
+        keypair: &KeyPair,
+        config: &CompactConfig,
+    ) -> Result<(WrappedOperation, u64), SdkError> {
+        let creator_address = Address::from_public_key(&keypair.get_public_key());
+        let (operation, expire_period) = self.prepare(client, creator_address, config).await?;
+        Ok((
+            Operation::new_wrapped(operation, OperationSerializer::new(), keypair)?,
+            expire_period,
+        ))
+    }
+
+    /// Prepare the content of the operation. Returns it with its expiry period.
+    async fn prepare(
+        mut self,
+        client: &RpcClient,
+        creator_address: Address,
+        config: &CompactConfig,
+    ) -> Result<(Operation, u64), SdkError> {
+        self.check_multisig_creator(creator_address)?;
+        if let Some(margin_percent) = self.gas_margin_percent {
+            self.estimate_max_gas(client, creator_address, margin_percent)
+                .await?;
+        }
+
+        let expire_period = expire_period(
+            current_slot(config)?,
+            self.validity_periods
+                .unwrap_or(config.operation_validity_periods),
+            creator_address.get_thread(config.thread_count),
+        );
+
+        let operation = self.content(expire_period)?;
+        if self.check_balance {
+            self.check_creator_funds(client, creator_address, config, operation.fee)
+                .await?;
+        }
+        Ok((operation, expire_period))
+    }
+
+    /// Check that the multisig policy, if any, controls `creator_address`
+    fn check_multisig_creator(&self, creator_address: Address) -> Result<(), SdkError> {
+        match &self.multisig_policy {
+            Some(policy) if policy.address() != creator_address => Err(
+                SdkError::MultisigPolicyMismatch(policy.address(), creator_address),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the gas of smart contract operations from a read-only execution
+    async fn estimate_max_gas(
+        &mut self,
+        client: &RpcClient,
+        caller_address: Address,
+        margin_percent: u64,
+    ) -> Result<(), SdkError> {
+        let response = match &self.op {
+            OperationType::ExecuteSC {
+                data,
+                max_gas,
+                datastore,
+            } => {
+                let operation_datastore = if datastore.is_empty() {
+                    None
+                } else {
+                    let mut serialized_datastore = Vec::new();
+                    DatastoreSerializer::new().serialize(datastore, &mut serialized_datastore)?;
+                    Some(serialized_datastore)
+                };
+                client
+                    .execute_read_only_bytecode(ReadOnlyBytecodeExecution {
+                        max_gas: *max_gas,
+                        bytecode: data.clone(),
+                        address: Some(caller_address),
+                        operation_datastore,
+                    })
+                    .await?
+            }
+            OperationType::CallSC {
+                target_addr,
+                target_func,
+                param,
+                max_gas,
+                ..
+            } => {
+                client
+                    .execute_read_only_call(ReadOnlyCall {
+                        max_gas: *max_gas,
+                        target_address: *target_addr,
+                        target_function: target_func.clone(),
+                        parameter: param.clone(),
+                        caller_address: Some(caller_address),
+                    })
+                    .await?
+            }
+            _ => return Ok(()),
+        };
+        if let ReadOnlyResult::Error(err) = response.result {
+            return Err(SdkError::GasEstimationError(err));
+        }
+        let estimated_gas = response
+            .gas_cost
+            .saturating_mul(margin_percent.saturating_add(100))
+            / 100;
+        if let OperationType::ExecuteSC { max_gas, .. } | OperationType::CallSC { max_gas, .. } =
+            &mut self.op
+        {
+            *max_gas = estimated_gas.min(*max_gas);
+        }
+        Ok(())
+    }
+
+    /// Content of the operation, with a fee proportional to its size unless the fee is fixed
+    fn content(&self, expire_period: u64) -> Result<Operation, SdkError> {
+        let mut operation = Operation {
+            fee: self.fee.unwrap_or_default(),
+            expire_period,
+            op: self.op.clone(),
+        };
+        if self.fee.is_some() {
+            return Ok(operation);
+        }
+        // the signed operation also holds the signature and the public key of its creator,
+        // or the policy and the signatures of a multisig creator
+        let signatures_size = match &self.multisig_policy {
+            Some(policy) => Operation::signatures_size(Some(&full_multisig_signatures(policy)?)),
+            None => Operation::signatures_size(None),
+        };
+        let serializer = OperationSerializer::new();
+        loop {
+            let mut serialized_content = Vec::new();
+            serializer.serialize(&operation, &mut serialized_content)?;
+            let size = serialized_content.len() + signatures_size;
+            let size_fee = self
+                .fee_per_byte
+                .checked_mul_u64(size as u64)
+                .ok_or(SdkError::AmountOverflow)?;
+            // the fee is serialized in the operation: a higher fee can make it longer
+            if size_fee <= operation.fee {
+                return Ok(operation);
+            }
+            operation.fee = size_fee;
+        }
+    }
+
+    /// Check that `creator_address` can pay for the operation
+    async fn check_creator_funds(
+        &self,
+        client: &RpcClient,
+        creator_address: Address,
+        config: &CompactConfig,
+        fee: Amount,
+    ) -> Result<(), SdkError> {
+        let info = client
+            .get_addresses(vec![creator_address])
+            .await?
+            .pop()
+            .ok_or(SdkError::AddressNotFound(creator_address))?;
+        self.check_funds(
+            info.candidate_balance,
+            info.candidate_roll_count,
+            config.roll_price,
+            fee,
+        )
+    }
+
+    /// Check that a creator owning `candidate_balance` coins and `candidate_roll_count` rolls
+    /// can pay for the operation
+    fn check_funds(
+        &self,
+        candidate_balance: Amount,
+        candidate_roll_count: u64,
+        roll_price: Amount,
+        fee: Amount,
+    ) -> Result<(), SdkError> {
+        let spent = match &self.op {
+            OperationType::Transaction { amount, .. } => *amount,
+            OperationType::RollBuy { roll_count } => roll_price
+                .checked_mul_u64(*roll_count)
+                .ok_or(SdkError::AmountOverflow)?,
+            OperationType::RollSell { roll_count } => {
+                if candidate_roll_count < *roll_count {
+                    return Err(SdkError::InsufficientRolls {
+                        needed: *roll_count,
+                        available: candidate_roll_count,
+                    });
+                }
+                Amount::zero()
+            }
+            OperationType::CallSC { coins, .. } => *coins,
+            OperationType::ExecuteSC { .. } | OperationType::SetStakingDelegate { .. } => {
+                Amount::zero()
+            }
+        };
+        let needed = spent.checked_add(fee).ok_or(SdkError::AmountOverflow)?;
+        if candidate_balance < needed {
+            return Err(SdkError::InsufficientBalance {
+                needed,
+                available: candidate_balance,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Placeholder signatures of the `threshold` keys of `policy` with the largest indexes,
+/// as long as the longest set of `threshold` signatures the operation can be sent with
+fn full_multisig_signatures(policy: &MultisigPolicy) -> Result<MultisigSignatures, SdkError> {
+    let signature = KeyPair::generate()
+        .sign(&Hash::compute_from(b"fee sizing"))
+        .map_err(ModelsError::from)?;
+    let key_count = policy.public_keys.len() as u32;
+    let mut signatures = MultisigSignatures::new(policy.clone());
+    for index in key_count.saturating_sub(policy.threshold)..key_count {
+        signatures.signatures.insert(index, signature);
+    }
+    Ok(signatures)
+}
+
+/// Last period in which an operation created at `slot` by an address of `creator_thread`
+/// can be included, for a validity of `validity_periods` periods.
+/// The operation cannot be included in the creator thread at the period of `slot` anymore
+/// if that slot is already reached.
+fn expire_period(slot: Slot, validity_periods: u64, creator_thread: u8) -> u64 {
+    let expire_period = slot.period.saturating_add(validity_periods);
+    if slot.thread >= creator_thread {
+        expire_period.saturating_add(1)
+    } else {
+        expire_period
+    }
+}
+
+/// Latest slot of the network described by `config`
+fn current_slot(config: &CompactConfig) -> Result<Slot, SdkError> {
+    // clock compensation is zero
+    Ok(
+        get_current_latest_block_slot(config.thread_count, config.t0, config.genesis_timestamp, 0)?
+            .unwrap_or_else(|| Slot::new(0, 0)),
+    )
+}
+
+/// Send a signed operation, returns its id
+async fn send_operation(
+    client: &RpcClient,
+    operation: WrappedOperation,
+) -> Result<OperationId, SdkError> {
+    client
+        .send_operations(vec![OperationInput {
+            creator_public_key: operation.creator_public_key,
+            serialized_content: operation.serialized_data,
+            signature: operation.signature,
+            multisig: operation.multisig,
+        }])
+        .await?
+        .pop()
+        .ok_or_else(|| SdkError::MissingReturnValue("send_operations".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expire_period, OperationBuilder};
+    use crate::SdkError;
+    use massa_models::address::Address;
+    use massa_models::amount::Amount;
+    use massa_models::multisig::{MultisigPolicy, MultisigSignatures};
+    use massa_models::operation::{Operation, OperationSerializer};
+    use massa_models::slot::Slot;
+    use massa_models::wrapped::WrappedContent;
+    use massa_serialization::Serializer;
+    use massa_signature::KeyPair;
+    use std::str::FromStr;
+
+    fn amount(amount: &str) -> Amount {
+        Amount::from_str(amount).unwrap()
+    }
+
+    fn recipient() -> Address {
+        Address::from_public_key(&KeyPair::generate().get_public_key())
+    }
+
+    /// Size of the operation once signed by a single key
+    fn signed_size(operation: &Operation) -> u64 {
+        let mut serialized_content = Vec::new();
+        OperationSerializer::new()
+            .serialize(operation, &mut serialized_content)
+            .unwrap();
+        (serialized_content.len() + Operation::signatures_size(None)) as u64
+    }
+
+    #[test]
+    fn test_content_fee_sizing() {
+        let fee_per_byte = amount("0.001");
+        for builder in [
+            OperationBuilder::transaction(recipient(), amount("1")),
+            OperationBuilder::buy_rolls(3),
+            OperationBuilder::execute_smart_contract(vec![0; 1000], 1_000_000, Default::default()),
+        ] {
+            let operation = builder.fee_per_byte(fee_per_byte).content(10).unwrap();
+            assert_eq!(operation.expire_period, 10);
+            // the fee pays for the operation serialized with that very fee
+            assert_eq!(
+                operation.fee,
+                fee_per_byte
+                    .checked_mul_u64(signed_size(&operation))
+                    .unwrap()
+            );
+        }
+        // a larger operation pays more
+        let small = OperationBuilder::execute_smart_contract(vec![0; 10], 0, Default::default())
+            .fee_per_byte(fee_per_byte)
+            .content(10)
+            .unwrap();
+        let large = OperationBuilder::execute_smart_contract(vec![0; 100], 0, Default::default())
+            .fee_per_byte(fee_per_byte)
+            .content(10)
+            .unwrap();
+        assert!(small.fee < large.fee);
+    }
+
+    #[test]
+    fn test_content_multisig_fee_sizing() {
+        let fee_per_byte = amount("0.001");
+        let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let policy = MultisigPolicy::new(
+            2,
+            keypairs
+                .iter()
+                .map(|keypair| keypair.get_public_key())
+                .collect(),
+        )
+        .unwrap();
+        let builder = OperationBuilder::transaction(recipient(), amount("1"))
+            .fee_per_byte(fee_per_byte)
+            .multisig_policy(policy.clone());
+        let operation = builder.content(10).unwrap();
+
+        // the fee pays for the operation sent with the signatures of the policy
+        let mut serialized_content = Vec::new();
+        OperationSerializer::new()
+            .serialize(&operation, &mut serialized_content)
+            .unwrap();
+        let hash = policy.content_hash(&serialized_content);
+        let mut signatures = MultisigSignatures::new(policy.clone());
+        for keypair in &keypairs[1..] {
+            signatures.sign(keypair, &hash).unwrap();
+        }
+        let size = serialized_content.len() + Operation::signatures_size(Some(&signatures));
+        assert_eq!(
+            operation.fee,
+            fee_per_byte.checked_mul_u64(size as u64).unwrap()
+        );
+        let single_key_operation = OperationBuilder::transaction(recipient(), amount("1"))
+            .fee_per_byte(fee_per_byte)
+            .content(10)
+            .unwrap();
+        assert!(single_key_operation.fee < operation.fee);
+
+        // the policy must control the creator address
+        assert!(builder.check_multisig_creator(policy.address()).is_ok());
+        let creator_address = Address::from_public_key(&keypairs[0].get_public_key());
+        assert!(matches!(
+            builder.check_multisig_creator(creator_address),
+            Err(SdkError::MultisigPolicyMismatch(policy_address, address))
+                if policy_address == policy.address() && address == creator_address
+        ));
+    }
+
+    #[test]
+    fn test_content_fixed_fee() {
+        let operation = OperationBuilder::buy_rolls(1)
+            .fee_per_byte(amount("1"))
+            .fee(amount("0.5"))
+            .content(7)
+            .unwrap();
+        assert_eq!(operation.fee, amount("0.5"));
+        let operation = OperationBuilder::buy_rolls(1).content(7).unwrap();
+        assert_eq!(operation.fee, Amount::zero());
+    }
+
+    #[test]
+    fn test_expire_period() {
+        // the creator thread is still ahead in the current period
+        assert_eq!(expire_period(Slot::new(100, 3), 10, 5), 110);
+        // the slot of the creator thread in the current period is reached
+        assert_eq!(expire_period(Slot::new(100, 5), 10, 5), 111);
+        assert_eq!(expire_period(Slot::new(100, 31), 10, 0), 111);
+        assert_eq!(expire_period(Slot::new(0, 0), 0, 1), 0);
+        assert_eq!(expire_period(Slot::new(u64::MAX, 2), 10, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_check_funds() {
+        let roll_price = amount("100");
+        let fee = amount("0.01");
+
+        let transaction = OperationBuilder::transaction(recipient(), amount("10"));
+        assert!(transaction
+            .check_funds(amount("10.01"), 0, roll_price, fee)
+            .is_ok());
+        match transaction.check_funds(amount("10"), 0, roll_price, fee) {
+            Err(SdkError::InsufficientBalance { needed, available }) => {
+                assert_eq!(needed, amount("10.01"));
+                assert_eq!(available, amount("10"));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let roll_buy = OperationBuilder::buy_rolls(2);
+        assert!(roll_buy
+            .check_funds(amount("200.01"), 0, roll_price, fee)
+            .is_ok());
+        assert!(matches!(
+            roll_buy.check_funds(amount("200"), 0, roll_price, fee),
+            Err(SdkError::InsufficientBalance { .. })
+        ));
+
+        let roll_sell = OperationBuilder::sell_rolls(2);
+        assert!(roll_sell.check_funds(fee, 2, roll_price, fee).is_ok());
+        assert!(matches!(
+            roll_sell.check_funds(fee, 1, roll_price, fee),
+            Err(SdkError::InsufficientRolls {
+                needed: 2,
+                available: 1
+            })
+        ));
+        // selling rolls still requires the fee
+        assert!(matches!(
+            roll_sell.check_funds(Amount::zero(), 2, roll_price, fee),
+            Err(SdkError::InsufficientBalance { .. })
+        ));
+
+        let call = OperationBuilder::call_smart_contract(
+            recipient(),
+            "main".to_string(),
+            Vec::new(),
+            1_000,
+            amount("5"),
+        );
+        assert!(call.check_funds(amount("5.01"), 0, roll_price, fee).is_ok());
+        assert!(call.check_funds(amount("5"), 0, roll_price, fee).is_err());
+
+        assert!(matches!(
+            OperationBuilder::buy_rolls(u64::MAX).check_funds(amount("1"), 0, roll_price, fee),
+            Err(SdkError::AmountOverflow)
+        ));
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Typed subscription streams.
+
+use crate::SdkError;
+use futures::Stream;
+use jsonrpsee::core::client::Subscription;
+use serde::de::DeserializeOwned;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Subscription offered by the node: the notifications it sends and the methods opening and closing it
+pub trait NodeSubscription {
+    /// notification sent by the node
+    type Item: DeserializeOwned + Unpin;
+    /// method opening the subscription
+    const SUBSCRIBE_METHOD: &'static str;
+    /// method closing the subscription
+    const UNSUBSCRIBE_METHOD: &'static str;
+}
+
+/// Notifications of a subscription, ending if the connection to the node is lost
+pub struct SubscriptionStream<T> {
+    subscription: Subscription<T>,
+}
+
+impl<T: DeserializeOwned> SubscriptionStream<T> {
+    pub(crate) fn new(subscription: Subscription<T>) -> Self {
+        SubscriptionStream { subscription }
+    }
+
+    /// Close the subscription on the node
+    pub async fn unsubscribe(self) -> Result<(), SdkError> {
+        Ok(self.subscription.unsubscribe().await?)
+    }
+}
+
+impl<T: DeserializeOwned + Unpin> Stream for SubscriptionStream<T> {
+    type Item = Result<T, SdkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.subscription)
+            .poll_next(cx)
+            .map(|notification| notification.map(|result| result.map_err(SdkError::from)))
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! TLS connection to the node API.
+//!
+//! The jsonrpsee clients cannot be told which certificates to trust, so when TLS is enabled
+//! the connections are opened by a connector configured with rustls, see `https.rs` and `ws.rs`.
+
+use hyper::{
+    client::connect::{Connected, Connection},
+    service::Service,
+    Uri,
+};
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName,
+};
+use std::{
+    convert::TryFrom,
+    fs::File,
+    future::Future,
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+
+/// TLS settings of the connection to the node
+#[derive(Debug, Clone, Default)]
+pub struct TlsClientConfig {
+    /// name the node certificate is checked against, the host of the node URL if `None`
+    pub server_name: Option<String>,
+    /// PEM files of the certificates the node may present.
+    /// If not empty, no other certificate is accepted and `certificate_store` is ignored.
+    pub pinned_certificates: Vec<PathBuf>,
+    /// PEM files of the certificate chain and of the private key presented to a node
+    /// that verifies client certificates
+    pub client_certificate: Option<(PathBuf, PathBuf)>,
+}
+
+/// Accepts only the end-entity certificates it was given
+struct PinnedCertificates(Vec<Certificate>);
+
+impl ServerCertVerifier for PinnedCertificates {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.0.contains(end_entity) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificateData(
+                "the node certificate is not pinned".to_string(),
+            ))
+        }
+    }
+}
+
+/// Load all the certificates of a PEM file
+pub(crate) fn load_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    if certificates.is_empty() {
+        return Err(format!("{}: no certificate found", path.display()));
+    }
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+/// Load the first private key of a PEM file
+pub(crate) fn load_private_key(path: &Path) -> Result<PrivateKey, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let mut reader = BufReader::new(file);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .map_err(|err| format!("{}: {}", path.display(), err))?
+        {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(format!("{}: no private key found", path.display())),
+        }
+    }
+}
+
+/// Trusted roots of the certificate store named in `HttpConfig`
+fn root_store(certificate_store: &str) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+    if certificate_store == "WebPki" {
+        roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+    } else {
+        let certificates = rustls_native_certs::load_native_certs()
+            .map_err(|err| format!("could not load the native certificates: {}", err))?;
+        for certificate in certificates {
+            // some system certificates may not be supported: skip them
+            let _ = roots.add(&Certificate(certificate.0));
+        }
+    }
+    Ok(roots)
+}
+
+/// Build the client TLS configuration
+fn client_config(
+    tls_config: &TlsClientConfig,
+    certificate_store: &str,
+) -> Result<ClientConfig, String> {
+    let builder = ClientConfig::builder().with_safe_defaults();
+    let builder = if tls_config.pinned_certificates.is_empty() {
+        builder.with_root_certificates(root_store(certificate_store)?)
+    } else {
+        let mut pinned = Vec::new();
+        for path in &tls_config.pinned_certificates {
+            pinned.extend(load_certificates(path)?);
+        }
+        builder.with_custom_certificate_verifier(Arc::new(PinnedCertificates(pinned)))
+    };
+    match &tls_config.client_certificate {
+        Some((cert_path, key_path)) => builder
+            .with_single_cert(load_certificates(cert_path)?, load_private_key(key_path)?)
+            .map_err(|err| format!("invalid client certificate or key: {}", err)),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Opens the TLS connections to a node API.
+/// It is also the connector of the HTTPS client, whatever the URI it is asked to connect to.
+#[derive(Clone)]
+pub(crate) struct NodeTlsConnector {
+    /// address of the node API
+    node_addr: SocketAddr,
+    /// name the node certificate is checked against
+    server_name: ServerName,
+    connector: TlsConnector,
+}
+
+impl NodeTlsConnector {
+    /// Prepare the connections to the node API listening on `node_addr`, known as `host`
+    pub(crate) fn new(
+        node_addr: SocketAddr,
+        host: &str,
+        tls_config: &TlsClientConfig,
+        certificate_store: &str,
+    ) -> Result<NodeTlsConnector, String> {
+        let name = match &tls_config.server_name {
+            Some(name) => name.clone(),
+            None => host.to_string(),
+        };
+        let server_name = ServerName::try_from(name.as_str())
+            .map_err(|err| format!("invalid TLS server name {}: {}", name, err))?;
+        Ok(NodeTlsConnector {
+            node_addr,
+            server_name,
+            connector: TlsConnector::from(Arc::new(client_config(tls_config, certificate_store)?)),
+        })
+    }
+
+    /// Open a TLS connection to the node
+    pub(crate) async fn connect(&self) -> std::io::Result<TlsStream<TcpStream>> {
+        let stream = TcpStream::connect(self.node_addr).await?;
+        self.connector
+            .connect(self.server_name.clone(), stream)
+            .await
+    }
+}
+
+impl Service<Uri> for NodeTlsConnector {
+    type Response = TlsConnection;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<TlsConnection, std::io::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move { connector.connect().await.map(TlsConnection) })
+    }
+}
+
+/// TLS connection to the node, as used by the HTTPS client
+pub(crate) struct TlsConnection(TlsStream<TcpStream>);
+
+impl Connection for TlsConnection {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::ServerConfig;
+    use std::net::Ipv4Addr;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use tokio_rustls::TlsAcceptor;
+
+    /// `server.pem` is a certificate for `localhost`, `other.pem` an unrelated self-signed one
+    fn cert_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/tests/certs")
+            .join(name)
+    }
+
+    /// Start an echo server presenting `server.pem`, answering a single connection
+    async fn start_echo_server() -> SocketAddr {
+        let server_config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                load_certificates(&cert_path("server.pem")).unwrap(),
+                load_private_key(&cert_path("server.key")).unwrap(),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let mut stream = acceptor.accept(stream).await?;
+            let mut message = [0u8; 4];
+            stream.read_exact(&mut message).await?;
+            stream.write_all(&message).await?;
+            stream.flush().await?;
+            Ok::<(), std::io::Error>(())
+        });
+        addr
+    }
+
+    /// Send a message to the echo server through a connection opened by `connector`
+    async fn round_trip(connector: &NodeTlsConnector) -> std::io::Result<Vec<u8>> {
+        let mut stream = connector.connect().await?;
+        stream.write_all(b"ping").await?;
+        stream.flush().await?;
+        let mut answer = vec![0u8; 4];
+        stream.read_exact(&mut answer).await?;
+        Ok(answer)
+    }
+
+    #[tokio::test]
+    async fn test_pinned_certificate_round_trip() {
+        let addr = start_echo_server().await;
+        let tls_config = TlsClientConfig {
+            pinned_certificates: vec![cert_path("other.pem"), cert_path("server.pem")],
+            ..Default::default()
+        };
+        // the pinned certificate is accepted whatever the name the node is reached with
+        let connector = NodeTlsConnector::new(addr, "127.0.0.1", &tls_config, "WebPki").unwrap();
+        assert_eq!(round_trip(&connector).await.unwrap(), b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_certificate_refused() {
+        let addr = start_echo_server().await;
+        let tls_config = TlsClientConfig {
+            pinned_certificates: vec![cert_path("other.pem")],
+            ..Default::default()
+        };
+        let connector = NodeTlsConnector::new(addr, "localhost", &tls_config, "WebPki").unwrap();
+        let err = round_trip(&connector).await.unwrap_err();
+        assert!(err.to_string().contains("not pinned"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_certificate_refused() {
+        // the test certificates are not signed by a public authority
+        let addr = start_echo_server().await;
+        let connector =
+            NodeTlsConnector::new(addr, "localhost", &TlsClientConfig::default(), "WebPki")
+                .unwrap();
+        assert!(round_trip(&connector).await.is_err());
+    }
+
+    #[test]
+    fn test_invalid_server_name() {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 33035));
+        let tls_config = TlsClientConfig {
+            server_name: Some("not a name!".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            NodeTlsConnector::new(addr, "localhost", &tls_config, "WebPki")
+                .err()
+                .unwrap()
+                .contains("invalid TLS server name")
+        );
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Websocket transport of the SDK.
+//!
+//! The connection is opened again, with an exponential backoff, by the first call made after
+//! it was lost. Calls are never retried on their own since some of them are not idempotent,
+//! and subscription streams end with the connection they were opened on.
+//! When TLS is enabled, the connections are opened by the connector configured with rustls
+//! and the websocket protocol is spoken over them by soketto.
+
+use crate::tls::NodeTlsConnector;
+use crate::HttpConfig;
+use async_trait::async_trait;
+use displaydoc::Display;
+use futures::io::{BufReader, BufWriter};
+use jsonrpsee::core::client::{
+    CertificateStore, ClientBuilder, ClientT, IdKind, ReceivedMessage, Subscription,
+    SubscriptionClientT, TransportReceiverT, TransportSenderT,
+};
+use jsonrpsee::core::traits::ToRpcParams;
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::ws_client::{HeaderMap, WsClient, WsClientBuilder};
+use serde::de::DeserializeOwned;
+use soketto::{
+    connection::{Receiver, Sender},
+    data::ByteSlice125,
+    handshake::{client::Header, Client as WsHandshake, ServerResponse},
+    Data, Incoming,
+};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::{net::TcpStream, sync::RwLock};
+use tokio_rustls::client::TlsStream;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+/// Node reached over TLS
+pub(crate) struct WsTlsTarget {
+    /// opens the TLS connections
+    pub connector: NodeTlsConnector,
+    /// authority sent in the `Host` header
+    pub authority: String,
+    /// path of the websocket endpoint
+    pub resource: String,
+}
+
+type TlsWsStream = BufReader<BufWriter<Compat<TlsStream<TcpStream>>>>;
+
+/// Error of the websocket transport over TLS
+#[derive(Display, Error, Debug)]
+pub(crate) enum TlsWsError {
+    /// websocket error: {0}
+    Ws(#[from] soketto::connection::Error),
+    /// invalid UTF-8 text: {0}
+    InvalidText(#[from] std::string::FromUtf8Error),
+    /// the node closed the connection
+    Closed,
+}
+
+struct TlsWsSender(Sender<TlsWsStream>);
+
+struct TlsWsReceiver(Receiver<TlsWsStream>);
+
+#[async_trait]
+impl TransportSenderT for TlsWsSender {
+    type Error = TlsWsError;
+
+    async fn send(&mut self, msg: String) -> Result<(), TlsWsError> {
+        self.0.send_text(msg).await?;
+        self.0.flush().await?;
+        Ok(())
+    }
+
+    async fn send_ping(&mut self) -> Result<(), TlsWsError> {
+        let empty: &[u8] = &[];
+        let data = ByteSlice125::try_from(empty).expect("an empty ping payload is valid");
+        self.0.send_ping(data).await?;
+        self.0.flush().await?;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<(), TlsWsError> {
+        self.0.close().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TransportReceiverT for TlsWsReceiver {
+    type Error = TlsWsError;
+
+    async fn receive(&mut self) -> Result<ReceivedMessage, TlsWsError> {
+        let mut message = Vec::new();
+        match self.0.receive(&mut message).await? {
+            Incoming::Data(Data::Text(_)) => Ok(ReceivedMessage::Text(String::from_utf8(message)?)),
+            Incoming::Data(Data::Binary(_)) => Ok(ReceivedMessage::Bytes(message)),
+            Incoming::Pong(_) => Ok(ReceivedMessage::Pong),
+            Incoming::Closed(_) => Err(TlsWsError::Closed),
+        }
+    }
+}
+
+/// How to open a websocket connection to the node
+struct WsConnector {
+    url: String,
+    config: HttpConfig,
+    headers: HeaderMap,
+    certificate_store: CertificateStore,
+    id_kind: IdKind,
+    /// reach the node over TLS with the configured connector
+    tls: Option<WsTlsTarget>,
+}
+
+impl WsConnector {
+    async fn connect(&self) -> Result<WsClient, JsonRpseeError> {
+        if let Some(target) = &self.tls {
+            return self.connect_tls(target).await;
+        }
+        WsClientBuilder::default()
+            .max_request_body_size(self.config.max_request_body_size)
+            .request_timeout(self.config.request_timeout.to_duration())
+            .max_concurrent_requests(self.config.max_concurrent_requests)
+            .certificate_store(self.certificate_store)
+            .id_format(self.id_kind)
+            .set_headers(self.headers.clone())
+            .build(&self.url)
+            .await
+    }
+
+    /// Open the websocket connection over a TLS connection of `target`
+    async fn connect_tls(&self, target: &WsTlsTarget) -> Result<WsClient, JsonRpseeError> {
+        let stream = target.connector.connect().await.map_err(|err| {
+            JsonRpseeError::Custom(format!("could not connect to the node: {}", err))
+        })?;
+        let mut handshake = WsHandshake::new(
+            BufReader::new(BufWriter::new(stream.compat())),
+            &target.authority,
+            &target.resource,
+        );
+        let headers: Vec<Header> = self
+            .headers
+            .iter()
+            .map(|(name, value)| Header {
+                name: name.as_str(),
+                value: value.as_bytes(),
+            })
+            .collect();
+        handshake.set_headers(&headers);
+        match handshake
+            .handshake()
+            .await
+            .map_err(|err| JsonRpseeError::Custom(format!("websocket handshake failed: {}", err)))?
+        {
+            ServerResponse::Accepted { .. } => {}
+            ServerResponse::Redirect { status_code, .. }
+            | ServerResponse::Rejected { status_code } => {
+                return Err(JsonRpseeError::Custom(format!(
+                    "websocket handshake refused with HTTP {}",
+                    status_code
+                )))
+            }
+        }
+        let mut builder = handshake.into_builder();
+        builder.set_max_message_size(self.config.max_request_body_size as usize);
+        let (sender, receiver) = builder.finish();
+        Ok(ClientBuilder::default()
+            .request_timeout(self.config.request_timeout.to_duration())
+            .max_concurrent_requests(self.config.max_concurrent_requests)
+            .id_format(self.id_kind)
+            .build_with_tokio(TlsWsSender(sender), TlsWsReceiver(receiver)))
+    }
+
+    /// Try to connect until it works or `reconnect_max_attempts` is reached,
+    /// doubling the delay between attempts up to `reconnect_max_delay`
+    async fn connect_with_backoff(&self) -> Result<WsClient, JsonRpseeError> {
+        let mut delay = self.config.reconnect_initial_delay.to_duration();
+        let max_delay = self.config.reconnect_max_delay.to_duration();
+        let mut attempt = 1;
+        loop {
+            match self.connect().await {
+                Ok(client) => return Ok(client),
+                Err(err) if attempt >= self.config.reconnect_max_attempts => return Err(err),
+                Err(_) => {
+                    tokio::time::sleep(delay).await;
+                    delay = std::cmp::min(delay.saturating_mul(2), max_delay);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Websocket client connecting again when its connection is lost
+pub(crate) struct ReconnectingWsClient {
+    connector: WsConnector,
+    client: RwLock<Arc<WsClient>>,
+}
+
+impl ReconnectingWsClient {
+    /// Connect to `url`, or to `tls` if set, retrying with backoff if the node cannot be reached
+    pub(crate) async fn connect(
+        url: &str,
+        config: &HttpConfig,
+        headers: HeaderMap,
+        certificate_store: CertificateStore,
+        id_kind: IdKind,
+        tls: Option<WsTlsTarget>,
+    ) -> Result<Self, JsonRpseeError> {
+        let connector = WsConnector {
+            url: url.to_string(),
+            config: config.clone(),
+            headers,
+            certificate_store,
+            id_kind,
+            tls,
+        };
+        let client = connector.connect_with_backoff().await?;
+        Ok(ReconnectingWsClient {
+            connector,
+            client: RwLock::new(Arc::new(client)),
+        })
+    }
+
+    /// Current connection, opened again if it was lost
+    async fn client(&self) -> Result<Arc<WsClient>, JsonRpseeError> {
+        let client = self.client.read().await.clone();
+        if client.is_connected() {
+            return Ok(client);
+        }
+        let mut client = self.client.write().await;
+        // another call may have reconnected in the meantime
+        if !client.is_connected() {
+            *client = Arc::new(self.connector.connect_with_backoff().await?);
+        }
+        Ok(client.clone())
+    }
+
+    /// Call `method`
+    pub(crate) async fn request<R, Params>(&self, method: &str, params: Params) -> RpcResult<R>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        self.client().await?.request(method, params).await
+    }
+
+    /// Subscribe to the notifications sent by the node for `subscribe_method`
+    pub(crate) async fn subscribe<N, Params>(
+        &self,
+        subscribe_method: &str,
+        params: Params,
+        unsubscribe_method: &str,
+    ) -> RpcResult<Subscription<N>>
+    where
+        N: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        self.client()
+            .await?
+            .subscribe(subscribe_method, params, unsubscribe_method)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::{load_certificates, load_private_key};
+    use crate::TlsClientConfig;
+    use jsonrpsee::rpc_params;
+    use jsonrpsee::server::{ServerBuilder, ServerHandle};
+    use jsonrpsee::RpcModule;
+    use massa_time::MassaTime;
+    use rustls::ServerConfig;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+
+    fn get_config(reconnect_max_attempts: u32) -> HttpConfig {
+        HttpConfig {
+            max_request_body_size: 52428800,
+            request_timeout: MassaTime::from_millis(5000),
+            max_concurrent_requests: 100,
+            certificate_store: "WebPki".to_string(),
+            id_kind: "Number".to_string(),
+            max_log_length: 1024,
+            headers: Vec::new(),
+            private_api_token: None,
+            tls: None,
+            reconnect_initial_delay: MassaTime::from_millis(50),
+            reconnect_max_delay: MassaTime::from_millis(80),
+            reconnect_max_attempts,
+        }
+    }
+
+    /// A local address that nothing listens on
+    fn get_free_addr() -> SocketAddr {
+        std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    /// Start a websocket node on `addr` answering `get_id` with `id`
+    async fn start_node(addr: SocketAddr, id: u32) -> ServerHandle {
+        let server = ServerBuilder::default().build(addr).await.unwrap();
+        let mut module = RpcModule::new(id);
+        module.register_method("get_id", |_, id| Ok(*id)).unwrap();
+        server.start(module).unwrap()
+    }
+
+    async fn stop_node(node: ServerHandle) {
+        node.stop().unwrap();
+        node.stopped().await;
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        config: &HttpConfig,
+        tls: Option<WsTlsTarget>,
+    ) -> Result<ReconnectingWsClient, JsonRpseeError> {
+        ReconnectingWsClient::connect(
+            &format!("ws://{}", addr),
+            config,
+            HeaderMap::new(),
+            CertificateStore::WebPki,
+            IdKind::Number,
+            tls,
+        )
+        .await
+    }
+
+    async fn get_id(client: &ReconnectingWsClient) -> RpcResult<u32> {
+        client.request("get_id", rpc_params![]).await
+    }
+
+    /// Wait for the client to notice that its connection was closed by the node
+    async fn wait_disconnected(client: &ReconnectingWsClient) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.client.read().await.is_connected() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the connection was not closed");
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_after_node_restart() {
+        let addr = get_free_addr();
+        let node = start_node(addr, 1).await;
+        let client = connect(addr, &get_config(3), None).await.unwrap();
+        assert_eq!(get_id(&client).await.unwrap(), 1);
+
+        stop_node(node).await;
+        wait_disconnected(&client).await;
+        let node = start_node(addr, 2).await;
+        assert_eq!(get_id(&client).await.unwrap(), 2);
+        assert_eq!(get_id(&client).await.unwrap(), 2);
+        stop_node(node).await;
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_backoff() {
+        // the node starts while the client is waiting between two attempts
+        let addr = get_free_addr();
+        let node = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            start_node(addr, 1).await
+        });
+        let client = connect(addr, &get_config(20), None).await.unwrap();
+        assert_eq!(get_id(&client).await.unwrap(), 1);
+        stop_node(node.await.unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn test_backoff_exhausted() {
+        // 3 attempts, waiting 50ms then 80ms (the maximum delay) between them
+        let addr = get_free_addr();
+        let start = Instant::now();
+        assert!(connect(addr, &get_config(3), None).await.is_err());
+        assert!(start.elapsed() >= Duration::from_millis(130));
+
+        // a lost connection is not opened again while the node is down,
+        // but the next call tries again
+        let node = start_node(addr, 1).await;
+        let client = connect(addr, &get_config(3), None).await.unwrap();
+        stop_node(node).await;
+        wait_disconnected(&client).await;
+        let start = Instant::now();
+        assert!(get_id(&client).await.is_err());
+        assert!(start.elapsed() >= Duration::from_millis(130));
+        let node = start_node(addr, 2).await;
+        assert_eq!(get_id(&client).await.unwrap(), 2);
+        stop_node(node).await;
+    }
+
+    /// Start a TLS endpoint presenting `server.pem` and forwarding its connections to `node_addr`
+    async fn start_tls_proxy(node_addr: SocketAddr) -> SocketAddr {
+        let cert_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/certs");
+        let server_config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                load_certificates(&cert_dir.join("server.pem")).unwrap(),
+                load_private_key(&cert_dir.join("server.key")).unwrap(),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let mut stream = acceptor.accept(stream).await?;
+                    let mut node_stream = TcpStream::connect(node_addr).await?;
+                    tokio::io::copy_bidirectional(&mut stream, &mut node_stream).await?;
+                    Ok::<(), std::io::Error>(())
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_tls_reconnect_after_node_restart() {
+        let node_addr = get_free_addr();
+        let node = start_node(node_addr, 1).await;
+        let proxy_addr = start_tls_proxy(node_addr).await;
+        let tls_config = TlsClientConfig {
+            pinned_certificates: vec![
+                Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/certs/server.pem")
+            ],
+            ..Default::default()
+        };
+        let target = WsTlsTarget {
+            connector: NodeTlsConnector::new(proxy_addr, "localhost", &tls_config, "WebPki")
+                .unwrap(),
+            authority: format!("localhost:{}", proxy_addr.port()),
+            resource: "/".to_string(),
+        };
+        let client = connect(proxy_addr, &get_config(3), Some(target))
+            .await
+            .unwrap();
+        assert_eq!(get_id(&client).await.unwrap(), 1);
+
+        stop_node(node).await;
+        wait_disconnected(&client).await;
+        let node = start_node(node_addr, 2).await;
+        assert_eq!(get_id(&client).await.unwrap(), 2);
+        stop_node(node).await;
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Hierarchical deterministic keys.
+//!
+//! A BIP-39 mnemonic phrase is turned into a seed, from which the keypair of each account is
+//! derived following SLIP-0010 for ed25519 under the path `m/44'/632'/account'/0'/0'`.
+//! All the derivation steps are hardened, as SLIP-0010 requires for ed25519.
+
+use crate::WalletError;
+use bip39::{Language, Mnemonic};
+use hmac::{Hmac, Mac};
+use massa_signature::{KeyPair, SECRET_KEY_BYTES_SIZE};
+use rand::RngCore;
+use sha2::Sha512;
+
+/// SLIP-0044 coin type of Massa
+pub const MASSA_COIN_TYPE: u32 = 632;
+
+/// Number of words of the generated mnemonic phrases when not specified
+pub const DEFAULT_MNEMONIC_WORD_COUNT: usize = 24;
+
+/// Flag of the hardened derivation indexes
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// HMAC key of the SLIP-0010 master key generation for ed25519
+const ED25519_CURVE_KEY: &[u8] = b"ed25519 seed";
+
+/// Generates a random English mnemonic phrase of `word_count` words (12, 15, 18, 21 or 24)
+pub fn generate_mnemonic(word_count: usize) -> Result<String, WalletError> {
+    if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+        return Err(WalletError::HdWalletError(format!(
+            "invalid mnemonic word count {}, expected 12, 15, 18, 21 or 24",
+            word_count
+        )));
+    }
+    // each group of 3 words encodes 32 bits of entropy
+    let mut entropy = vec![0u8; word_count / 3 * 4];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
+        .map_err(|err| WalletError::HdWalletError(err.to_string()))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Checks an English mnemonic phrase and computes its seed, salted with `passphrase`
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<Vec<u8>, WalletError> {
+    let mnemonic = Mnemonic::parse_in(Language::English, phrase)
+        .map_err(|err| WalletError::HdWalletError(format!("invalid mnemonic: {}", err)))?;
+    Ok(mnemonic.to_seed(passphrase).to_vec())
+}
+
+/// Derivation path of the account `index`, without the hardened flags
+pub fn derivation_path(index: u32) -> [u32; 5] {
+    [44, MASSA_COIN_TYPE, index, 0, 0]
+}
+
+/// Splits the output of HMAC-SHA512 into a key and a chain code
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for chunk in data {
+        mac.update(chunk);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut secret_key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    secret_key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    (secret_key, chain_code)
+}
+
+/// SLIP-0010 ed25519 derivation of the secret key at `path` (hardened indexes) from `seed`
+fn derive_secret_key(seed: &[u8], path: &[u32]) -> Result<[u8; 32], WalletError> {
+    let (mut secret_key, mut chain_code) = hmac_sha512(ED25519_CURVE_KEY, &[seed]);
+    for index in path {
+        if *index >= HARDENED_OFFSET {
+            return Err(WalletError::HdWalletError(format!(
+                "derivation index {} is too large",
+                index
+            )));
+        }
+        let hardened_index = (index | HARDENED_OFFSET).to_be_bytes();
+        (secret_key, chain_code) =
+            hmac_sha512(&chain_code, &[&[0u8], &secret_key, &hardened_index]);
+    }
+    Ok(secret_key)
+}
+
+/// Derives the keypair of the account `index` from `seed`
+pub fn derive_keypair(seed: &[u8], index: u32) -> Result<KeyPair, WalletError> {
+    let secret_key: [u8; SECRET_KEY_BYTES_SIZE] = derive_secret_key(seed, &derivation_path(index))?;
+    KeyPair::from_bytes(&secret_key).map_err(|err| WalletError::HdWalletError(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_ed25519_vector() {
+        // test vector 1 of SLIP-0010 for ed25519
+        let seed = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let master = derive_secret_key(&seed, &[]).unwrap();
+        assert_eq!(
+            master.to_vec(),
+            hex_decode("2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7")
+        );
+        let child = derive_secret_key(&seed, &[0]).unwrap();
+        assert_eq!(
+            child.to_vec(),
+            hex_decode("68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3")
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_roundtrip() {
+        let phrase = generate_mnemonic(DEFAULT_MNEMONIC_WORD_COUNT).unwrap();
+        assert_eq!(
+            phrase.split_whitespace().count(),
+            DEFAULT_MNEMONIC_WORD_COUNT
+        );
+        let seed = mnemonic_to_seed(&phrase, "").unwrap();
+        assert_eq!(
+            derive_keypair(&seed, 3).unwrap().get_public_key(),
+            derive_keypair(&mnemonic_to_seed(&phrase, "").unwrap(), 3)
+                .unwrap()
+                .get_public_key()
+        );
+        assert_ne!(
+            derive_keypair(&seed, 3).unwrap().get_public_key(),
+            derive_keypair(&mnemonic_to_seed(&phrase, "salt").unwrap(), 3)
+                .unwrap()
+                .get_public_key()
+        );
+        assert!(mnemonic_to_seed("not a mnemonic", "").is_err());
+    }
+
+    fn hex_decode(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
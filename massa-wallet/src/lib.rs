@@ -6,48 +6,103 @@
 #![feature(map_try_insert)]
 
 pub use error::WalletError;
+pub use massa_cipher::Argon2Params;
 
-use massa_cipher::{decrypt, encrypt};
+use massa_cipher::{decrypt, encrypt_with_params, read_header};
 use massa_hash::Hash;
 use massa_models::address::Address;
 use massa_models::composite::PubkeySig;
+use massa_models::multisig::MultisigPolicy;
 use massa_models::operation::{Operation, OperationSerializer, WrappedOperation};
 use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::wrapped::WrappedContent;
 use massa_signature::{KeyPair, PublicKey};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
 
 mod error;
+pub mod hd;
 
 /// Contains the keypairs created in the wallet.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Wallet {
     /// Keypairs and addresses
     pub keys: PreHashMap<Address, KeyPair>,
+    /// Seed of the mnemonic phrase the accounts are derived from, if any.
+    /// Only stored in the encrypted wallet file, never serialized with the wallet.
+    #[serde(skip_serializing, default)]
+    pub hd_seed: Option<Vec<u8>>,
+    /// Addresses of the derived accounts, by account index
+    pub hd_accounts: BTreeMap<u32, Address>,
+    /// Policies of the multisig addresses followed by the wallet
+    pub multisig_policies: PreHashMap<Address, MultisigPolicy>,
     /// Path to the file containing the keypairs (encrypted)
     pub wallet_path: PathBuf,
+    /// Key derivation parameters of the wallet file encryption
+    pub cipher_params: Argon2Params,
     /// Password
     pub password: String,
 }
 
+/// Content of the wallet file
+#[derive(Default, Deserialize, Serialize)]
+struct WalletContent {
+    keys: PreHashMap<Address, KeyPair>,
+    hd_seed: Option<Vec<u8>>,
+    hd_accounts: BTreeMap<u32, Address>,
+    #[serde(default)]
+    multisig_policies: PreHashMap<Address, MultisigPolicy>,
+}
+
+/// Content of the wallet file, in the current format or in the one of the wallets
+/// that only held keypairs
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredWalletContent {
+    Current(WalletContent),
+    KeysOnly(PreHashMap<Address, KeyPair>),
+}
+
 impl Wallet {
     /// Generates a new wallet initialized with the provided file content
     pub fn new(path: PathBuf, password: String) -> Result<Wallet, WalletError> {
         if path.is_file() {
             let content = &std::fs::read(&path)?[..];
-            let (_version, decrypted_content) = decrypt(&password, content)?;
-            let keys =
-                serde_json::from_slice::<PreHashMap<Address, KeyPair>>(&decrypted_content[..])?;
-            Ok(Wallet {
-                keys,
+            let (header, decrypted_content) = decrypt(&password, content)?;
+            let content =
+                match serde_json::from_slice::<StoredWalletContent>(&decrypted_content[..])? {
+                    StoredWalletContent::Current(content) => content,
+                    StoredWalletContent::KeysOnly(keys) => WalletContent {
+                        keys,
+                        ..Default::default()
+                    },
+                };
+            let wallet = Wallet {
+                keys: content.keys,
+                hd_seed: content.hd_seed,
+                hd_accounts: content.hd_accounts,
+                multisig_policies: content.multisig_policies,
                 wallet_path: path,
+                cipher_params: header.argon2_params.unwrap_or_default(),
                 password,
-            })
+            };
+            // the files of older cipher versions are encrypted again with the current one
+            if header.version < massa_cipher::VERSION {
+                wallet.backup(header.version)?;
+                wallet.save()?;
+            }
+            Ok(wallet)
         } else {
             let wallet = Wallet {
                 keys: PreHashMap::default(),
+                hd_seed: None,
+                hd_accounts: BTreeMap::new(),
+                multisig_policies: PreHashMap::default(),
                 wallet_path: path,
+                cipher_params: Argon2Params::default(),
                 password,
             };
             wallet.save()?;
@@ -55,6 +110,69 @@ impl Wallet {
         }
     }
 
+    /// Generates a mnemonic phrase of `word_count` words, uses it as the seed of the wallet
+    /// and derives its first account. Returns the phrase, which is not stored.
+    /// Fails if the wallet already has a seed.
+    pub fn generate_mnemonic(
+        &mut self,
+        word_count: usize,
+        passphrase: &str,
+    ) -> Result<String, WalletError> {
+        let phrase = hd::generate_mnemonic(word_count)?;
+        self.restore_mnemonic(&phrase, passphrase, 1)?;
+        Ok(phrase)
+    }
+
+    /// Uses a mnemonic phrase as the seed of the wallet and derives its first `account_count`
+    /// accounts. Returns their addresses. Fails if the wallet already has a seed.
+    pub fn restore_mnemonic(
+        &mut self,
+        phrase: &str,
+        passphrase: &str,
+        account_count: u32,
+    ) -> Result<Vec<Address>, WalletError> {
+        if self.hd_seed.is_some() {
+            return Err(WalletError::HdWalletError(
+                "the wallet already has a mnemonic seed".to_string(),
+            ));
+        }
+        let seed = hd::mnemonic_to_seed(phrase, passphrase)?;
+        let mut addresses = Vec::with_capacity(account_count as usize);
+        for index in 0..account_count {
+            let keypair = hd::derive_keypair(&seed, index)?;
+            let address = Address::from_public_key(&keypair.get_public_key());
+            self.keys.insert(address, keypair);
+            self.hd_accounts.insert(index, address);
+            addresses.push(address);
+        }
+        self.hd_seed = Some(seed);
+        self.save()?;
+        Ok(addresses)
+    }
+
+    /// Derives the account `index` from the seed of the wallet, or the account following
+    /// the last derived one if `index` is `None`. Returns the index and the address.
+    pub fn derive_address(&mut self, index: Option<u32>) -> Result<(u32, Address), WalletError> {
+        let seed = self.hd_seed.as_ref().ok_or_else(|| {
+            WalletError::HdWalletError("the wallet has no mnemonic seed".to_string())
+        })?;
+        let index = match index {
+            Some(index) => index,
+            None => match self.hd_accounts.keys().next_back() {
+                Some(last_index) => last_index.checked_add(1).ok_or_else(|| {
+                    WalletError::HdWalletError("no account index left".to_string())
+                })?,
+                None => 0,
+            },
+        };
+        let keypair = hd::derive_keypair(seed, index)?;
+        let address = Address::from_public_key(&keypair.get_public_key());
+        self.keys.insert(address, keypair);
+        self.hd_accounts.insert(index, address);
+        self.save()?;
+        Ok((index, address))
+    }
+
     /// Sign arbitrary message with the associated keypair
     /// returns none if the address isn't in the wallet or if an error occurred during the signature
     /// else returns the public key that signed the message and the signature
@@ -99,6 +217,9 @@ impl Wallet {
             if self.keys.remove(address).is_some() {
                 changed = true;
             }
+            if self.multisig_policies.remove(address).is_some() {
+                changed = true;
+            }
         }
         if changed {
             self.save()?;
@@ -123,15 +244,100 @@ impl Wallet {
         self.keys.keys().copied().collect()
     }
 
+    /// Follows the multisig address controlled by `policy`, returns the address.
+    /// The wallet file is updated.
+    pub fn add_multisig_policy(&mut self, policy: MultisigPolicy) -> Result<Address, WalletError> {
+        policy.check()?;
+        let address = policy.address();
+        if self.multisig_policies.insert(address, policy).is_none() {
+            self.save()?;
+        }
+        Ok(address)
+    }
+
+    /// Finds the policy of a followed multisig address
+    pub fn find_multisig_policy(&self, address: &Address) -> Option<&MultisigPolicy> {
+        self.multisig_policies.get(address)
+    }
+
+    /// Keypairs of the wallet allowed to sign for the multisig address of `policy`
+    pub fn multisig_signers(&self, policy: &MultisigPolicy) -> Vec<&KeyPair> {
+        policy
+            .public_keys
+            .iter()
+            .filter_map(|public_key| self.keys.get(&Address::from_public_key(public_key)))
+            .collect()
+    }
+
+    /// Encrypts the wallet file again with the current cipher version and `cipher_params`,
+    /// or the current parameters if `None`. Returns the previous cipher version of the file.
+    pub fn upgrade_encryption(
+        &mut self,
+        cipher_params: Option<Argon2Params>,
+    ) -> Result<u32, WalletError> {
+        let previous_version = read_header(&std::fs::read(&self.wallet_path)?)?.version;
+        if previous_version < massa_cipher::VERSION {
+            self.backup(previous_version)?;
+        }
+        let previous_params = self.cipher_params;
+        if let Some(cipher_params) = cipher_params {
+            self.cipher_params = cipher_params;
+        }
+        // invalid parameters are only detected on encryption, keep the ones that work
+        if let Err(err) = self.save() {
+            self.cipher_params = previous_params;
+            return Err(err);
+        }
+        Ok(previous_version)
+    }
+
     /// Save the wallet in json format in a file
-    /// Only the keypair is dumped
+    /// Only the keypairs, the derivation seed and accounts and the multisig policies are dumped
     fn save(&self) -> Result<(), WalletError> {
-        let ser_keys = serde_json::to_string(&self.keys)?;
-        let encrypted_content = encrypt(&self.password, ser_keys.as_bytes())?;
-        std::fs::write(&self.wallet_path, encrypted_content)?;
+        let ser_keys = serde_json::to_string(&WalletContent {
+            keys: self.keys.clone(),
+            hd_seed: self.hd_seed.clone(),
+            hd_accounts: self.hd_accounts.clone(),
+            multisig_policies: self.multisig_policies.clone(),
+        })?;
+        let encrypted_content =
+            encrypt_with_params(&self.password, ser_keys.as_bytes(), &self.cipher_params)?;
+        // an interrupted write must not leave a truncated wallet file behind:
+        // the content is written and synced to a temporary file that replaces the wallet file
+        let temp_path = self.sibling_path(".tmp");
+        let mut temp_file = File::create(&temp_path)?;
+        temp_file.write_all(&encrypted_content)?;
+        temp_file.sync_all()?;
+        drop(temp_file);
+        std::fs::rename(&temp_path, &self.wallet_path)?;
+        #[cfg(unix)]
+        {
+            // persist the rename itself
+            let directory = match self.wallet_path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => std::path::Path::new("."),
+            };
+            File::open(directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Copies the wallet file, encrypted with the cipher `version`, to `<wallet file>.v<version>.bak`
+    /// before it is encrypted again with the current version
+    fn backup(&self, version: u32) -> Result<(), WalletError> {
+        let backup_path = self.sibling_path(&format!(".v{}.bak", version));
+        std::fs::copy(&self.wallet_path, &backup_path)?;
+        File::open(&backup_path)?.sync_all()?;
         Ok(())
     }
 
+    /// Path of the wallet file followed by `suffix`
+    fn sibling_path(&self, suffix: &str) -> PathBuf {
+        let mut path = self.wallet_path.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+
     /// Export keys and addresses
     pub fn get_full_wallet(&self) -> &PreHashMap<Address, KeyPair> {
         &self.keys
@@ -150,6 +356,20 @@ impl Wallet {
     }
 }
 
+impl std::fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Wallet")
+            .field("keys", &self.keys)
+            .field("hd_seed", &self.hd_seed.as_ref().map(|_| "<redacted>"))
+            .field("hd_accounts", &self.hd_accounts)
+            .field("multisig_policies", &self.multisig_policies)
+            .field("wallet_path", &self.wallet_path)
+            .field("cipher_params", &self.cipher_params)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
 impl std::fmt::Display for Wallet {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f)?;
@@ -158,6 +378,12 @@ impl std::fmt::Display for Wallet {
             writeln!(f, "Public key: {}", keypair.get_public_key())?;
             writeln!(f, "Address: {}", addr)?;
         }
+        for (index, addr) in &self.hd_accounts {
+            writeln!(f, "Derived account {}: {}", index, addr)?;
+        }
+        for policy in self.multisig_policies.values() {
+            write!(f, "{}", policy)?;
+        }
         Ok(())
     }
 }
@@ -165,3 +391,90 @@ impl std::fmt::Display for Wallet {
 /// Test utils
 #[cfg(feature = "testing")]
 pub mod test_exports;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_cipher::encrypt;
+    use tempfile::TempDir;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Empty wallet file encrypted by the version 0 cipher with the password "test"
+    const V0_WALLET: [u8; 43] = [
+        0x00, 0x57, 0x34, 0x4c, 0x4c, 0x65, 0x74, 0x56, 0x30, 0x53, 0x61, 0x6c, 0x74, 0x0c, 0x0d,
+        0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x8f, 0x02, 0x2e, 0xed, 0x96,
+        0x38, 0xcd, 0x30, 0xab, 0x49, 0x37, 0x8e, 0xcb, 0xfd, 0x76, 0xb7, 0x5b, 0xf2,
+    ];
+
+    #[test]
+    fn test_upgrade_keeps_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.dat");
+        std::fs::write(&path, V0_WALLET).unwrap();
+
+        let wallet = Wallet::new(path.clone(), "test".to_string()).unwrap();
+        assert!(wallet.keys.is_empty());
+        assert_eq!(
+            read_header(&std::fs::read(&path).unwrap()).unwrap().version,
+            massa_cipher::VERSION
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("wallet.dat.v0.bak")).unwrap(),
+            V0_WALLET
+        );
+        assert!(!dir.path().join("wallet.dat.tmp").exists());
+        Wallet::new(path, "test".to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_load_keys_only_wallet() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.dat");
+        let keypair = KeyPair::generate();
+        let address = Address::from_public_key(&keypair.get_public_key());
+        let mut keys = PreHashMap::default();
+        keys.insert(address, keypair);
+        let content = serde_json::to_string(&keys).unwrap();
+        std::fs::write(&path, encrypt("test", content.as_bytes()).unwrap()).unwrap();
+
+        let wallet = Wallet::new(path, "test".to_string()).unwrap();
+        assert_eq!(wallet.keys.len(), 1);
+        assert!(wallet.keys.contains_key(&address));
+        assert!(wallet.hd_seed.is_none());
+        assert!(wallet.hd_accounts.is_empty());
+        assert!(wallet.multisig_policies.is_empty());
+    }
+
+    #[test]
+    fn test_hd_wallet_save_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.dat");
+        let mut wallet = Wallet::new(path.clone(), "test".to_string()).unwrap();
+        let addresses = wallet.restore_mnemonic(PHRASE, "", 2).unwrap();
+        let (index, address) = wallet.derive_address(Some(5)).unwrap();
+
+        let loaded = Wallet::new(path, "test".to_string()).unwrap();
+        assert_eq!(loaded.hd_seed, wallet.hd_seed);
+        assert!(loaded.hd_seed.is_some());
+        assert_eq!(loaded.hd_accounts, wallet.hd_accounts);
+        assert_eq!(loaded.hd_accounts.get(&index), Some(&address));
+        assert_eq!(loaded.hd_accounts.get(&0), Some(&addresses[0]));
+        assert_eq!(loaded.hd_accounts.get(&1), Some(&addresses[1]));
+        assert_eq!(loaded.keys.len(), 3);
+    }
+
+    #[test]
+    fn test_seed_not_exposed() {
+        let dir = TempDir::new().unwrap();
+        let mut wallet = Wallet::new(dir.path().join("wallet.dat"), "test".to_string()).unwrap();
+        wallet.restore_mnemonic(PHRASE, "", 1).unwrap();
+        let seed = wallet.hd_seed.clone().unwrap();
+
+        let json = serde_json::to_value(&wallet).unwrap();
+        assert!(json.get("hd_seed").is_none());
+        let debug = format!("{:?}", wallet);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains(&format!("{:?}", seed)));
+    }
+}